@@ -1,6 +1,5 @@
-use arbitrary::Arbitrary;
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use fxhash;
+use intmap_rs::bench::{self, Op};
 use intmap_rs::IntMap;
 use rand::distributions::Standard;
 use rand::Rng;
@@ -39,9 +38,9 @@ pub fn criterion_benchmark(c: &mut Criterion) {
     let mut intmap64 = IntMap::with_capacity(CAP as u32);
 
     let mut brown32: HashMap<K, V, Hashr> =
-        HashMap::with_capacity_and_hasher(CAP as usize, Default::default());
+        HashMap::with_capacity_and_hasher(CAP, Default::default());
     let mut brown64: HashMap<K64, V64, Hashr> =
-        HashMap::with_capacity_and_hasher(CAP as usize, Default::default());
+        HashMap::with_capacity_and_hasher(CAP, Default::default());
     //
     // Bench data
     //
@@ -58,22 +57,7 @@ pub fn criterion_benchmark(c: &mut Criterion) {
         .take(N)
         .collect::<Vec<_>>();
 
-    #[derive(Arbitrary, Debug)]
-    enum Op {
-        Insert(K64, V64),
-        Get(K64),
-        Delete(K64),
-        Contains(K64),
-    }
-
-    const OP_SIZE: usize = std::mem::size_of::<Op>();
-    let b = rand::thread_rng()
-        .sample_iter(rand::distributions::Standard)
-        .take(OP_SIZE * N)
-        .collect::<Vec<u8>>();
-    let ops: Vec<Op> =
-        Arbitrary::arbitrary_take_rest(arbitrary::Unstructured::new(&b[..])).unwrap();
-    drop(b);
+    let ops: Vec<Op<K64, V64>> = bench::arbitrary_ops(&mut rand::thread_rng(), N);
 
     // prefill maps
     for k in keys32.iter() {
@@ -150,7 +134,7 @@ pub fn criterion_benchmark(c: &mut Criterion) {
         "brown32" => |b|{
             b.iter_custom(|iters| {
                 let start = Instant::now();
-                for key in (0..N).into_iter().cycle().take(iters as usize) {
+                for key in (0..N).cycle().take(iters as usize) {
                     black_box(brown32.get(&(key as K)));
                 }
                 start.elapsed()
@@ -159,7 +143,7 @@ pub fn criterion_benchmark(c: &mut Criterion) {
         "intmap32" => |b|{
             b.iter_custom(|iters| {
                 let start = Instant::now();
-                for key in (0..N).into_iter().cycle().take(iters as usize) {
+                for key in (0..N).cycle().take(iters as usize) {
                     black_box(intmap32.get(key as K));
                 }
                 start.elapsed()
@@ -168,7 +152,7 @@ pub fn criterion_benchmark(c: &mut Criterion) {
         "brown64" => |b|{
             b.iter_custom(|iters| {
                 let start = Instant::now();
-                for key in (0..N).into_iter().cycle().take(iters as usize) {
+                for key in (0..N).cycle().take(iters as usize) {
                     black_box(brown64.get(&(key as K64)));
                 }
                 start.elapsed()
@@ -177,7 +161,7 @@ pub fn criterion_benchmark(c: &mut Criterion) {
         "intmap64" => |b|{
             b.iter_custom(|iters| {
                 let start = Instant::now();
-                for key in (0..N).into_iter().cycle().take(iters as usize) {
+                for key in (0..N).cycle().take(iters as usize) {
                     black_box(intmap64.get(key as K64));
                 }
                 start.elapsed()
@@ -309,6 +293,9 @@ pub fn criterion_benchmark(c: &mut Criterion) {
         brown64.insert(*k, *k as V64);
         intmap64.insert(*k, *k as V64);
     }
+    // the batch order 'remove_sorted' is meant for, keys ascending by home slot
+    let mut by_home64 = keys64.clone();
+    by_home64.sort_unstable_by_key(|k| k & (CAP as K64 - 1));
 
     bench!("Deletions random",
         "brown32" => |b|{
@@ -355,6 +342,24 @@ pub fn criterion_benchmark(c: &mut Criterion) {
                 criterion::BatchSize::LargeInput
             )
         }
+        "intmap64 home order" => |b|{
+            b.iter_batched(
+                || intmap64.clone(),
+                |mut map|{
+                    for k in by_home64.iter(){
+                        map.remove(*k);
+                    }
+                },
+                criterion::BatchSize::LargeInput
+            )
+        }
+        "intmap64 remove_sorted" => |b|{
+            b.iter_batched(
+                || intmap64.clone(),
+                |mut map| map.remove_sorted(&by_home64),
+                criterion::BatchSize::LargeInput
+            )
+        }
     );
 
     //
@@ -417,27 +422,61 @@ pub fn criterion_benchmark(c: &mut Criterion) {
         }
     );
 
+    //
+    // Bulk load of the whole batch into an empty map several times the size of the LLC, one
+    // load per iteration
+    //
+    bench!("Bulk load",
+        "insert loop" => |b|{
+            b.iter_custom(|iters| {
+                let mut elapsed = std::time::Duration::ZERO;
+                for _ in 0..iters {
+                    let mut intmap = IntMap::with_capacity(CAP as u32);
+                    let start = Instant::now();
+                    for k in keys64.iter() {
+                        intmap.insert(*k, *k as V64);
+                    }
+                    elapsed += start.elapsed();
+                    black_box(&intmap);
+                }
+                elapsed
+            })
+        }
+        "extend_from_slices" => |b|{
+            b.iter_custom(|iters| {
+                let mut elapsed = std::time::Duration::ZERO;
+                for _ in 0..iters {
+                    let mut intmap = IntMap::with_capacity(CAP as u32);
+                    let start = Instant::now();
+                    intmap.extend_from_slices(&keys64, &keys64);
+                    elapsed += start.elapsed();
+                    black_box(&intmap);
+                }
+                elapsed
+            })
+        }
+        "extend_from_slices nontemporal" => |b|{
+            b.iter_custom(|iters| {
+                let mut elapsed = std::time::Duration::ZERO;
+                for _ in 0..iters {
+                    let mut intmap = IntMap::with_capacity_nontemporal(CAP as u32);
+                    let start = Instant::now();
+                    intmap.extend_from_slices(&keys64, &keys64);
+                    elapsed += start.elapsed();
+                    black_box(&intmap);
+                }
+                elapsed
+            })
+        }
+    );
+
     bench!("Workload",
         "brown64" => |b|{
             b.iter_custom(|iters| {
                 brown64.clear();
                 brown64.reserve(iters as usize);
                 let start = Instant::now();
-                ops.iter().cycle().take(iters as usize).for_each(|op|
-                    match op {
-                        Op::Insert(key, value) => {
-                            black_box(brown64.insert(*key, *value));
-                        }
-                        Op::Get(key) => {
-                            black_box(brown64.get(key));
-                        }
-                        Op::Delete(key) => {
-                            black_box(brown64.remove(key));
-                        }
-                        Op::Contains(key) => {
-                            black_box(brown64.contains_key(key));
-                        }
-                    });
+                bench::run(&mut brown64, ops.iter().cycle().take(iters as usize));
                 start.elapsed()
             })
         }
@@ -445,21 +484,7 @@ pub fn criterion_benchmark(c: &mut Criterion) {
             b.iter_custom(|iters| {
                 intmap64.clear();
                 let start = Instant::now();
-                ops.iter().cycle().take(iters as usize).for_each(|op|
-                    match op {
-                        Op::Insert(key, value) => {
-                            black_box(intmap64.insert(*key, *value));
-                        }
-                        Op::Get(key) => {
-                            black_box(intmap64.get(*key));
-                        }
-                        Op::Delete(key) => {
-                            black_box(intmap64.remove(*key));
-                        }
-                        Op::Contains(key) => {
-                            black_box(intmap64.contains(*key));
-                        }
-                    });
+                bench::run(&mut intmap64, ops.iter().cycle().take(iters as usize));
                 start.elapsed()
             })
         }
@@ -0,0 +1,117 @@
+use criterion::measurement::{Measurement, ValueFormatter};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use intmap_rs::IntMap;
+use rand::distributions::Standard;
+use rand::Rng;
+use std::collections::HashMap;
+use std::mem::size_of;
+
+type Hashr = fxhash::FxBuildHasher;
+type K = i64;
+type V = i64;
+
+const CAP: u32 = 1 << 16;
+const LOAD_FACTORS: [f32; 5] = [0.1, 0.25, 0.5, 0.75, 0.9];
+
+//
+// Criterion measurement reporting bytes per stored entry instead of time,
+// 'iter_custom' closures return `bytes_per_entry * iters`
+//
+struct BytesPerEntry;
+
+impl Measurement for BytesPerEntry {
+    type Intermediate = ();
+    type Value = f64;
+
+    fn start(&self) -> Self::Intermediate {}
+    fn end(&self, _: Self::Intermediate) -> Self::Value {
+        0.0
+    }
+    fn add(&self, v1: &Self::Value, v2: &Self::Value) -> Self::Value {
+        v1 + v2
+    }
+    fn zero(&self) -> Self::Value {
+        0.0
+    }
+    fn to_f64(&self, value: &Self::Value) -> f64 {
+        *value
+    }
+    fn formatter(&self) -> &dyn ValueFormatter {
+        &BytesFormatter
+    }
+}
+
+struct BytesFormatter;
+
+impl ValueFormatter for BytesFormatter {
+    fn scale_values(&self, _typical_value: f64, _values: &mut [f64]) -> &'static str {
+        "B/entry"
+    }
+
+    fn scale_throughputs(&self, _: f64, _: &Throughput, _: &mut [f64]) -> &'static str {
+        "B/entry"
+    }
+
+    fn scale_for_machines(&self, _values: &mut [f64]) -> &'static str {
+        "B/entry"
+    }
+}
+
+// hashbrown doesn't expose its allocation size, mirror its layout instead:
+// power-of-two buckets at 7/8 max load, one control byte per bucket plus a trailing group
+fn hashbrown_memory_usage<K, V, S>(map: &HashMap<K, V, S>) -> usize {
+    const GROUP_WIDTH: usize = 16;
+    let buckets = match map.capacity() {
+        0 => return 0,
+        c if c < 8 => (c + 1).next_power_of_two(),
+        c => (c * 8 / 7).next_power_of_two(),
+    };
+    buckets * size_of::<(K, V)>() + buckets + GROUP_WIDTH
+}
+
+fn keys(n: usize) -> Vec<K> {
+    rand::thread_rng().sample_iter::<K, Standard>(Standard).take(n).collect()
+}
+
+fn memory_benchmark(c: &mut Criterion<BytesPerEntry>) {
+    let mut group = c.benchmark_group("Memory per entry");
+
+    println!("{:>6} {:>10} {:>12} {:>12}", "load", "entries", "intmap64", "brown64");
+    for load_factor in LOAD_FACTORS {
+        let n = (CAP as f32 * load_factor) as usize;
+        let keys = keys(n);
+
+        let mut intmap = IntMap::<K, V>::with_capacity(CAP);
+        let mut brown: HashMap<K, V, Hashr> =
+            HashMap::with_capacity_and_hasher(n, Default::default());
+        for k in keys.iter() {
+            intmap.insert(*k, *k);
+            brown.insert(*k, *k);
+        }
+
+        let intmap_bpe = intmap.memory_usage() as f64 / intmap.len() as f64;
+        let brown_bpe = hashbrown_memory_usage(&brown) as f64 / brown.len() as f64;
+        println!("{:>6.2} {:>10} {:>12.2} {:>12.2}", load_factor, n, intmap_bpe, brown_bpe);
+
+        group.bench_with_input(BenchmarkId::new("intmap64", load_factor), &intmap_bpe, |b, bpe| {
+            b.iter_custom(|iters| bpe * iters as f64)
+        });
+        group.bench_with_input(BenchmarkId::new("brown64", load_factor), &brown_bpe, |b, bpe| {
+            b.iter_custom(|iters| bpe * iters as f64)
+        });
+    }
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default()
+        .with_measurement(BytesPerEntry)
+        .without_plots()
+        .sample_size(10)
+        .warm_up_time(std::time::Duration::from_millis(1))
+        .measurement_time(std::time::Duration::from_millis(1));
+    targets = memory_benchmark
+}
+
+criterion_main!(benches);
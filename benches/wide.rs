@@ -0,0 +1,141 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use intmap_rs::IntMap;
+use rand::distributions::Standard;
+use rand::Rng;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::time::Instant;
+
+type Hashr = fxhash::FxBuildHasher;
+
+// u128 keys, e.g. UUIDs, against 64-bit keys of the same map and hashbrown
+fn wide_keys(c: &mut Criterion) {
+    type K = u128;
+    type V = u64;
+
+    // Batch size
+    const N: usize = 1_000_000;
+    // Map's capacity double the batch size to get 0.5 load factor
+    const CAP: usize = N.next_power_of_two() * 2;
+
+    // random keys, v4 UUIDs are 122 random bits
+    let keys = rand::thread_rng().sample_iter::<K, Standard>(Standard).take(N).collect::<Vec<_>>();
+    let keys64 = keys.iter().map(|k| *k as u64).collect::<Vec<_>>();
+    let misses =
+        rand::thread_rng().sample_iter::<K, Standard>(Standard).take(N).collect::<Vec<_>>();
+
+    let mut intmap = IntMap::<K, V>::with_capacity(CAP as u32);
+    let mut intmap_exact = IntMap::<K, V>::with_exact_capacity(CAP as u32);
+    let mut intmap64 = IntMap::<u64, V>::with_capacity(CAP as u32);
+    let mut brown: HashMap<K, V, Hashr> =
+        HashMap::with_capacity_and_hasher(CAP, Default::default());
+    for (i, (k, k64)) in keys.iter().zip(&keys64).enumerate() {
+        intmap.insert(*k, i as V);
+        intmap_exact.insert(*k, i as V);
+        intmap64.insert(*k64, i as V);
+        brown.insert(*k, i as V);
+    }
+
+    let mut group = c.benchmark_group("u128 keys");
+    group.bench_function("fxhash128", |b| {
+        b.iter_custom(|iters| {
+            let start = Instant::now();
+            for k in keys.iter().cycle().take(iters as usize) {
+                let mut h = fxhash::FxHasher::default();
+                k.hash(&mut h);
+                black_box(h.finish());
+            }
+            start.elapsed()
+        })
+    });
+    group.bench_function("hit brown128", |b| {
+        b.iter_custom(|iters| {
+            let start = Instant::now();
+            for key in keys.iter().cycle().take(iters as usize) {
+                black_box(brown.get(key));
+            }
+            start.elapsed()
+        })
+    });
+    group.bench_function("hit intmap128", |b| {
+        b.iter_custom(|iters| {
+            let start = Instant::now();
+            for key in keys.iter().cycle().take(iters as usize) {
+                black_box(intmap.get(*key));
+            }
+            start.elapsed()
+        })
+    });
+    group.bench_function("hit intmap128 exact", |b| {
+        b.iter_custom(|iters| {
+            let start = Instant::now();
+            for key in keys.iter().cycle().take(iters as usize) {
+                black_box(intmap_exact.get(*key));
+            }
+            start.elapsed()
+        })
+    });
+    group.bench_function("hit intmap64", |b| {
+        b.iter_custom(|iters| {
+            let start = Instant::now();
+            for key in keys64.iter().cycle().take(iters as usize) {
+                black_box(intmap64.get(*key));
+            }
+            start.elapsed()
+        })
+    });
+    group.bench_function("miss brown128", |b| {
+        b.iter_custom(|iters| {
+            let start = Instant::now();
+            for key in misses.iter().cycle().take(iters as usize) {
+                black_box(brown.get(key));
+            }
+            start.elapsed()
+        })
+    });
+    group.bench_function("miss intmap128", |b| {
+        b.iter_custom(|iters| {
+            let start = Instant::now();
+            for key in misses.iter().cycle().take(iters as usize) {
+                black_box(intmap.get(*key));
+            }
+            start.elapsed()
+        })
+    });
+    group.bench_function("insert brown128", |b| {
+        b.iter_batched(
+            || HashMap::<K, V, Hashr>::with_capacity_and_hasher(CAP, Default::default()),
+            |mut map| {
+                for (i, k) in keys.iter().enumerate() {
+                    map.insert(*k, i as V);
+                }
+                map
+            },
+            criterion::BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("insert intmap128", |b| {
+        b.iter_batched(
+            || IntMap::<K, V>::with_capacity(CAP as u32),
+            |mut map| {
+                for (i, k) in keys.iter().enumerate() {
+                    map.insert(*k, i as V);
+                }
+                map
+            },
+            criterion::BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default()
+        .without_plots()
+        .warm_up_time(std::time::Duration::from_secs(5))
+        .measurement_time(std::time::Duration::from_secs(10));
+    targets = wide_keys
+}
+
+criterion_main!(benches);
@@ -0,0 +1,40 @@
+// Entry-based aggregation: per-key statistics over a stream of orders, each built with a single
+// search per record through `probe`, `group_reduce` and `merge_insert`, then joined by key.
+use intmap_rs::IntMap;
+
+#[derive(Default, Debug)]
+struct Stats {
+    count: u32,
+    total: u64,
+    max: u64,
+}
+
+fn main() {
+    // (customer id, amount) orders
+    let orders = (0..10_000u64).map(|i| ((i * 31) % 500, (i * 17) % 1000)).collect::<Vec<_>>();
+
+    // probe: search once, then update in place or insert
+    let mut stats: IntMap<u64, Stats> = IntMap::with_capacity(1024);
+    for &(customer, amount) in &orders {
+        let entry = stats.probe(customer).or_insert_with(Stats::default);
+        entry.count += 1;
+        entry.total += amount;
+        entry.max = entry.max.max(amount);
+    }
+
+    // group_reduce: the same as a fold per key
+    let mut totals: IntMap<u64, u64> = IntMap::with_capacity(1024);
+    totals.group_reduce(orders.iter().copied(), |_| 0, |total, amount| *total += amount);
+
+    // merge_insert: combine with what's already stored
+    let mut big_orders: IntMap<u64, u32> = IntMap::with_capacity(1024);
+    for &(customer, _) in orders.iter().filter(|(_, amount)| *amount >= 900) {
+        big_orders.merge_insert(customer, 1, |n, one| *n += one);
+    }
+
+    for (customer, s, big) in stats.left_join(&big_orders).take(5) {
+        assert_eq!(totals.get(customer), Some(&s.total));
+        println!("customer {customer}: {s:?}, {} big orders", big.copied().unwrap_or(0));
+    }
+    println!("{} customers, {} with big orders", stats.len(), big_orders.len());
+}
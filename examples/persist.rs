@@ -0,0 +1,44 @@
+// Bulk load, persist and reload.
+//
+// Records are loaded with `IntMapLoader` rejecting duplicate ids, the map is written out as two
+// flat little-endian files (keys, values) in storage order and loaded back with
+// `extend_from_slices`, which inserts in home slot order and so writes the table front to back.
+// The table can't be mapped back in place: `BorrowedIntMap` formats the lanes it's given.
+use intmap_rs::{DuplicatePolicy, IntMap, IntMapLoader};
+use std::fs;
+use std::path::Path;
+
+const RECORDS: u64 = 100_000;
+
+fn write_lane(path: &Path, lane: &[u64]) {
+    let bytes = lane.iter().flat_map(|x| x.to_le_bytes()).collect::<Vec<_>>();
+    fs::write(path, bytes).unwrap();
+}
+
+fn read_lane(path: &Path) -> Vec<u64> {
+    let bytes = fs::read(path).unwrap();
+    bytes.chunks_exact(8).map(|c| u64::from_le_bytes(c.try_into().unwrap())).collect()
+}
+
+fn main() {
+    // (id, balance) records, ids scattered over the whole u64 range
+    let records = (0..RECORDS).map(|i| (i.wrapping_mul(0x9e3779b97f4a7c15), i * 100));
+    let map: IntMap<u64, u64> = IntMapLoader::new(RECORDS as u32 * 2)
+        .on_duplicate(DuplicatePolicy::Fail)
+        .load(records)
+        .expect("ids are unique");
+    println!("loaded {} records, load factor {:.2}", map.len(), map.load_factor());
+
+    let dir = std::env::temp_dir().join(format!("intmap-persist-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    write_lane(&dir.join("keys"), &map.keys_to_vec());
+    write_lane(&dir.join("values"), &map.values_to_vec());
+
+    let (keys, values) = (read_lane(&dir.join("keys")), read_lane(&dir.join("values")));
+    let mut reloaded = IntMap::with_capacity(map.capacity() as u32);
+    reloaded.extend_from_slices(&keys, &values);
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(reloaded == map, "reloaded map differs");
+    println!("reloaded {} records from {} bytes", reloaded.len(), keys.len() * 16);
+}
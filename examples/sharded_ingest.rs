@@ -0,0 +1,44 @@
+// Concurrent ingest into a sharded map.
+//
+// Producer threads count events per user id into `SyncIntMap` shards picked by the low bits
+// of the id, so writers to different shards never contend. Each shard is a plain map behind
+// its own lock, readers take the shard's shared lock only.
+use intmap_rs::SyncIntMap;
+use std::thread;
+
+const SHARDS: usize = 8;
+const PRODUCERS: u64 = 4;
+const EVENTS: u64 = 200_000;
+const USERS: u64 = 10_000;
+
+fn shard(user: u64) -> usize {
+    // the shards index by the low bits as well, so mix before picking one
+    (user.wrapping_mul(0x9e3779b97f4a7c15) >> 61) as usize % SHARDS
+}
+
+fn main() {
+    let shards = (0..SHARDS)
+        .map(|_| SyncIntMap::with_capacity((USERS / SHARDS as u64 * 2) as u32))
+        .collect::<Vec<_>>();
+
+    thread::scope(|s| {
+        for producer in 0..PRODUCERS {
+            let shards = &shards;
+            s.spawn(move || {
+                for event in 0..EVENTS {
+                    let user = (event * 7 + producer) % USERS;
+                    shards[shard(user)]
+                        .with_write(|m| m.merge_insert(user, 1u64, |n, one| *n += one));
+                }
+            });
+        }
+    });
+
+    let total: u64 = shards.iter().map(|s| s.with_read(|m| m.values().sum::<u64>())).sum();
+    assert_eq!(total, PRODUCERS * EVENTS);
+    let users: usize = shards.iter().map(SyncIntMap::len).sum();
+    println!("{total} events from {users} users over {SHARDS} shards");
+    for (i, s) in shards.iter().enumerate() {
+        println!("shard {i}: {} users", s.len());
+    }
+}
@@ -0,0 +1,155 @@
+// Long-running insert/remove churn at a fixed load factor, checked against a std HashMap.
+//
+//     cargo run --release --example soak -- --seconds 3600 --capacity 1048576 --load 0.8
+//
+// Options: --seconds, --capacity, --load, --seed, --keys uniform|sequential|clustered and
+// --report (seconds between progress lines). Every report compares the whole map with the
+// reference and prints the resident set size, which should stay flat for the whole run since
+// the table never reallocates. Debug builds check the structural invariants as well. Loads
+// close to 1 can hit the probe limit and panic, finding that out is what the run is for.
+use intmap_rs::IntMap;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+struct Config {
+    seconds: u64,
+    capacity: u32,
+    load: f64,
+    seed: u64,
+    keys: String,
+    report: u64,
+}
+
+fn parse_args() -> Config {
+    let mut config = Config {
+        seconds: 60,
+        capacity: 1 << 16,
+        load: 0.8,
+        seed: 0,
+        keys: "uniform".into(),
+        report: 10,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(flag) = args.next() {
+        let value = args.next().unwrap_or_else(|| panic!("{flag} expects a value"));
+        match flag.as_str() {
+            "--seconds" => config.seconds = parse(&flag, &value),
+            "--capacity" => config.capacity = parse(&flag, &value),
+            "--load" => config.load = parse(&flag, &value),
+            "--seed" => config.seed = parse(&flag, &value),
+            "--report" => config.report = parse(&flag, &value),
+            "--keys" => config.keys = value,
+            _ => panic!("unknown option {flag}"),
+        }
+    }
+    assert!(config.load > 0.0 && config.load <= 1.0, "--load must be in (0, 1]");
+    config
+}
+
+fn parse<T: std::str::FromStr>(flag: &str, value: &str) -> T {
+    value.parse().unwrap_or_else(|_| panic!("invalid value {value:?} for {flag}"))
+}
+
+// resident set size in KiB, None where /proc isn't available
+fn rss_kib() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|l| l.starts_with("VmRSS:"))?;
+    line.split_whitespace().nth(1)?.parse().ok()
+}
+
+fn check(map: &IntMap<u64, u64>, truth: &HashMap<u64, u64>) {
+    assert_eq!(map.len(), truth.len(), "length diverged");
+    for (key, value) in truth {
+        assert_eq!(map.get(*key), Some(value), "value of key {key} diverged");
+    }
+    #[cfg(debug_assertions)]
+    map.assert_invariants();
+}
+
+fn main() {
+    let config = parse_args();
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let mut map: IntMap<u64, u64> = IntMap::with_capacity(config.capacity);
+    let target = ((map.capacity() as f64 * config.load) as usize).max(1);
+    let mut truth: HashMap<u64, u64> = HashMap::with_capacity(target + 1);
+    // present keys, so removals can pick one at random; sequential keys retire the oldest one
+    // instead like a sliding window would, random removals leave holes that later keys overfill
+    let mut present: VecDeque<u64> = VecDeque::with_capacity(target + 1);
+    let fifo = config.keys == "sequential";
+    let mut sequence = 0u64;
+
+    let mut next_key = |rng: &mut StdRng| -> u64 {
+        match config.keys.as_str() {
+            "uniform" => rng.gen(),
+            "sequential" => {
+                sequence += 1;
+                sequence
+            }
+            // only a quarter of the low bit patterns, long probe clusters with `key & mask`
+            "clustered" => (rng.gen::<u64>() << 6) | rng.gen_range(0..16),
+            other => panic!("unknown key distribution {other}"),
+        }
+    };
+
+    println!(
+        "soak: capacity {} load {} keys {} for {}s, seed {}",
+        map.capacity(),
+        config.load,
+        config.keys,
+        config.seconds,
+        config.seed
+    );
+    let start = Instant::now();
+    let deadline = start + Duration::from_secs(config.seconds);
+    let mut next_report = start + Duration::from_secs(config.report);
+    let (mut ops, mut first_rss) = (0u64, None);
+
+    while Instant::now() < deadline {
+        for _ in 0..4096 {
+            // hover around the target: below it mostly insert, at it mostly remove
+            let insert =
+                if present.len() < target { rng.gen_bool(0.75) } else { rng.gen_bool(0.25) };
+            if insert || present.is_empty() {
+                let (key, value) = (next_key(&mut rng), rng.gen());
+                if map.insert(key, value).is_none() {
+                    present.push_back(key);
+                }
+                truth.insert(key, value);
+            } else {
+                let key = if fifo {
+                    present.pop_front()
+                } else {
+                    present.swap_remove_back(rng.gen_range(0..present.len()))
+                };
+                let key = key.unwrap();
+                assert_eq!(map.remove(key), truth.remove(&key), "removal of key {key} diverged");
+            }
+            if let Some(&key) = present.get(rng.gen_range(0..present.len().max(1))) {
+                assert_eq!(map.get(key), truth.get(&key), "lookup of key {key} diverged");
+            }
+            ops += 2;
+        }
+
+        if Instant::now() >= next_report {
+            check(&map, &truth);
+            let rss = rss_kib();
+            first_rss = first_rss.or(rss);
+            println!(
+                "{:>6}s  {:>12} ops  len {:>9}  load {:.3}  avg probes {:.2}  rss {} KiB (start {})",
+                start.elapsed().as_secs(),
+                ops,
+                map.len(),
+                map.load_factor(),
+                map.avg_probes_count(),
+                rss.map_or("?".into(), |r| r.to_string()),
+                first_rss.map_or("?".into(), |r| r.to_string()),
+            );
+            next_report += Duration::from_secs(config.report);
+        }
+    }
+
+    check(&map, &truth);
+    println!("soak: ok, {ops} ops in {:.1}s", start.elapsed().as_secs_f64());
+}
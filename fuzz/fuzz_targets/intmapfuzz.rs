@@ -25,6 +25,7 @@ fuzz_target!(|ops: Vec<Op>| {
                 let a = truth.insert(key, value);
                 let b = map.insert(key, value);
                 assert_eq!(a, b);
+                map.assert_invariants();
             }
             Op::Get(key) => {
                 let a = truth.get(&key);
@@ -35,6 +36,7 @@ fuzz_target!(|ops: Vec<Op>| {
                 let a = truth.remove(&key);
                 let b = map.remove(key);
                 assert_eq!(a, b);
+                map.assert_invariants();
             }
             Op::Contains(key) => {
                 let a = truth.contains_key(&key);
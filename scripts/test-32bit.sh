@@ -0,0 +1,11 @@
+#!/usr/bin/env sh
+# Runs the test suite with all features on a 32-bit target, where usize is narrower than the
+# u64/u128 keys and the capacity math differs. The musl target links with the bundled rust-lld,
+# so no 32-bit C toolchain is needed. Extra arguments go to `cargo test`.
+set -eu
+cd "$(dirname "$0")/.."
+
+target=i686-unknown-linux-musl
+rustup target add "$target"
+RUSTFLAGS="-C linker=rust-lld -C link-self-contained=yes" \
+    cargo test --target "$target" --all-features "$@"
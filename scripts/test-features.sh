@@ -0,0 +1,47 @@
+#!/usr/bin/env sh
+# Runs the test suite, doctests included, with no features, each feature on its own and all
+# features together; with --pairs also every pair of features. Extra arguments go to
+# `cargo test`, e.g. `scripts/test-features.sh --pairs -- --doc`.
+set -eu
+cd "$(dirname "$0")/.."
+
+pairs=false
+if [ "${1:-}" = "--pairs" ]; then
+    pairs=true
+    shift
+fi
+if [ "${1:-}" = "--" ]; then
+    shift
+fi
+
+# the names of the [features] table of Cargo.toml
+features=$(sed -n '/^\[features\]/,/^\[/{s/^\([a-z0-9-]*\) *=.*/\1/p}' Cargo.toml)
+
+# run <features> [cargo test args]
+run() {
+    echo "== features: ${1:-none}"
+    on=$1
+    shift
+    cargo test --no-default-features --features "$on" "$@"
+}
+
+run "" "$@"
+for f in $features; do
+    run "$f" "$@"
+done
+if $pairs; then
+    for f in $features; do
+        # only the features after 'f', so each pair runs once
+        after=false
+        for g in $features; do
+            if $after; then
+                run "$f,$g" "$@"
+            fi
+            if [ "$g" = "$f" ]; then
+                after=true
+            fi
+        done
+    done
+fi
+echo "== features: all"
+cargo test --all-features "$@"
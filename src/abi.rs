@@ -0,0 +1,265 @@
+use crate::{Capacity, IntMap};
+use std::ffi::c_void;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+type Map = IntMap<u64, u64>;
+
+/// Bumped whenever the layout of `IntMapVTable` changes.
+pub const ABI_VERSION: u32 = 2;
+
+/// Status of a vtable call: the key wasn't present; `insert` added it.
+pub const STATUS_ABSENT: i32 = 0;
+/// Status of a vtable call: the key was present, its value was written to the out-param.
+pub const STATUS_FOUND: i32 = 1;
+/// Status of `insert`: the map refused the key, it's full under its saturation policy or the
+/// key is the reserved one. The map is unchanged.
+pub const STATUS_REJECTED: i32 = -1;
+/// Status of a vtable call: the map panicked, e.g. `insert` overflowed the probe limit. The
+/// panic was caught at the boundary, unwinding out of an `extern "C"` function would abort the
+/// host; the map is left valid, see `IntMap`.
+pub const STATUS_PANICKED: i32 = -2;
+
+/// Function table of an `IntMapHandle`. Entries are `extern "C"` so a handle created by one
+/// component can be used from another built with a different compiler version.
+///
+/// `insert`, `get` and `remove` return one of the `STATUS_*` values and write the out-param
+/// only with `STATUS_FOUND`. No entry unwinds.
+#[repr(C)]
+pub struct IntMapVTable {
+    pub version: u32,
+    pub insert: unsafe extern "C" fn(map: *mut c_void, key: u64, value: u64, old: *mut u64) -> i32,
+    pub get: unsafe extern "C" fn(map: *const c_void, key: u64, out: *mut u64) -> i32,
+    pub remove: unsafe extern "C" fn(map: *mut c_void, key: u64, out: *mut u64) -> i32,
+    pub len: unsafe extern "C" fn(map: *const c_void) -> usize,
+    pub capacity: unsafe extern "C" fn(map: *const c_void) -> usize,
+    pub drop: unsafe extern "C" fn(map: *mut c_void),
+}
+
+/// Stable-layout owner of an `IntMap<u64, u64>`, safe to pass across a dylib boundary.
+///
+/// The map is only ever touched through the vtable of the component that created it, so its
+/// Rust layout never has to agree between both sides.
+#[repr(C)]
+pub struct IntMapHandle {
+    map: *mut c_void,
+    vtable: &'static IntMapVTable,
+}
+
+static VTABLE: IntMapVTable = IntMapVTable {
+    version: ABI_VERSION,
+    insert: abi_insert,
+    get: abi_get,
+    remove: abi_remove,
+    len: abi_len,
+    capacity: abi_capacity,
+    drop: abi_drop,
+};
+
+// runs a lookup at the boundary: a found value goes to 'out', a panic becomes a status
+unsafe fn found(out: *mut u64, f: impl FnOnce() -> Option<u64>) -> i32 {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Some(value)) => {
+            out.write(value);
+            STATUS_FOUND
+        }
+        Ok(None) => STATUS_ABSENT,
+        Err(_) => STATUS_PANICKED,
+    }
+}
+
+unsafe extern "C" fn abi_insert(map: *mut c_void, key: u64, value: u64, old: *mut u64) -> i32 {
+    match catch_unwind(AssertUnwindSafe(|| (*map.cast::<Map>()).try_insert(key, value))) {
+        Ok(Ok(Some(prev))) => {
+            old.write(prev);
+            STATUS_FOUND
+        }
+        Ok(Ok(None)) => STATUS_ABSENT,
+        Ok(Err(_)) => STATUS_REJECTED,
+        Err(_) => STATUS_PANICKED,
+    }
+}
+
+unsafe extern "C" fn abi_get(map: *const c_void, key: u64, out: *mut u64) -> i32 {
+    found(out, || (*map.cast::<Map>()).get(key).copied())
+}
+
+unsafe extern "C" fn abi_remove(map: *mut c_void, key: u64, out: *mut u64) -> i32 {
+    found(out, || (*map.cast::<Map>()).remove(key))
+}
+
+unsafe extern "C" fn abi_len(map: *const c_void) -> usize {
+    (*map.cast::<Map>()).len()
+}
+
+unsafe extern "C" fn abi_capacity(map: *const c_void) -> usize {
+    (*map.cast::<Map>()).capacity()
+}
+
+unsafe extern "C" fn abi_drop(map: *mut c_void) {
+    drop(Box::from_raw(map.cast::<Map>()));
+}
+
+impl IntMapHandle {
+    /// A handle owning a new `IntMap::with_capacity` map.
+    ///
+    /// ```
+    /// use intmap_rs::{IntMapHandle, ABI_VERSION};
+    ///
+    /// let h = IntMapHandle::with_capacity(16);
+    /// assert_eq!((h.capacity(), h.version()), (16, ABI_VERSION));
+    /// ```
+    pub fn with_capacity(capacity: impl Into<Capacity>) -> Self {
+        Self::from_map(IntMap::with_capacity(capacity))
+    }
+
+    /// A handle owning `map`, with whatever settings it was built with.
+    ///
+    /// ```
+    /// use intmap_rs::{IntMap, IntMapHandle};
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// m.insert(1, 10);
+    /// let h = IntMapHandle::from_map(m);
+    /// assert_eq!(h.get(1), Some(10));
+    /// ```
+    pub fn from_map(map: IntMap<u64, u64>) -> Self {
+        Self { map: Box::into_raw(Box::new(map)).cast(), vtable: &VTABLE }
+    }
+
+    /// Version of the vtable the handle was created with, see `ABI_VERSION`.
+    ///
+    /// ```
+    /// use intmap_rs::{IntMapHandle, ABI_VERSION};
+    ///
+    /// assert_eq!(IntMapHandle::with_capacity(4).version(), ABI_VERSION);
+    /// ```
+    pub fn version(&self) -> u32 {
+        self.vtable.version
+    }
+
+    /// Panics where `IntMap::insert` would, on this side of the boundary.
+    ///
+    /// ```
+    /// use intmap_rs::IntMapHandle;
+    ///
+    /// let mut h = IntMapHandle::with_capacity(8);
+    /// assert_eq!(h.insert(5, 50), None);
+    /// assert_eq!(h.insert(5, 51), Some(50));
+    /// ```
+    pub fn insert(&mut self, key: u64, value: u64) -> Option<u64> {
+        self.try_insert(key, value).unwrap_or_else(|status| panic!("{}", status_message(status)))
+    }
+
+    /// `Err` with `STATUS_REJECTED` or `STATUS_PANICKED`, see `IntMapVTable`.
+    ///
+    /// ```
+    /// use intmap_rs::{IntMap, IntMapHandle, SaturationPolicy, STATUS_REJECTED};
+    ///
+    /// let mut m = IntMap::with_capacity(1);
+    /// m.set_saturation_policy(SaturationPolicy::Reject);
+    /// let mut h = IntMapHandle::from_map(m);
+    /// assert_eq!(h.try_insert(1, 1), Ok(None));
+    /// assert_eq!(h.try_insert(2, 2), Err(STATUS_REJECTED));
+    /// ```
+    pub fn try_insert(&mut self, key: u64, value: u64) -> Result<Option<u64>, i32> {
+        let mut old = 0;
+        match unsafe { (self.vtable.insert)(self.map, key, value, &mut old) } {
+            STATUS_FOUND => Ok(Some(old)),
+            STATUS_ABSENT => Ok(None),
+            status => Err(status),
+        }
+    }
+
+    /// The value stored for `key`, if any.
+    ///
+    /// ```
+    /// use intmap_rs::IntMapHandle;
+    ///
+    /// let mut h = IntMapHandle::with_capacity(8);
+    /// h.insert(3, 30);
+    /// assert_eq!((h.get(3), h.get(4)), (Some(30), None));
+    /// ```
+    pub fn get(&self, key: u64) -> Option<u64> {
+        let mut out = 0;
+        lookup(unsafe { (self.vtable.get)(self.map, key, &mut out) }, out)
+    }
+
+    /// Removes the entry, returns its value if the key was present.
+    ///
+    /// ```
+    /// use intmap_rs::IntMapHandle;
+    ///
+    /// let mut h = IntMapHandle::with_capacity(8);
+    /// h.insert(3, 30);
+    /// assert_eq!(h.remove(3), Some(30));
+    /// assert_eq!(h.remove(3), None);
+    /// ```
+    pub fn remove(&mut self, key: u64) -> Option<u64> {
+        let mut out = 0;
+        lookup(unsafe { (self.vtable.remove)(self.map, key, &mut out) }, out)
+    }
+
+    /// Number of entries stored.
+    ///
+    /// ```
+    /// use intmap_rs::IntMapHandle;
+    ///
+    /// let mut h = IntMapHandle::with_capacity(8);
+    /// h.insert(1, 1);
+    /// h.insert(2, 2);
+    /// assert_eq!(h.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        unsafe { (self.vtable.len)(self.map) }
+    }
+
+    /// Whether the map holds no entries.
+    ///
+    /// ```
+    /// use intmap_rs::IntMapHandle;
+    ///
+    /// let mut h = IntMapHandle::with_capacity(8);
+    /// assert!(h.is_empty());
+    /// h.insert(1, 1);
+    /// assert!(!h.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of home slots of the map, see `IntMap::capacity`.
+    ///
+    /// ```
+    /// use intmap_rs::IntMapHandle;
+    ///
+    /// assert_eq!(IntMapHandle::with_capacity(100).capacity(), 128);
+    /// ```
+    pub fn capacity(&self) -> usize {
+        unsafe { (self.vtable.capacity)(self.map) }
+    }
+}
+
+fn status_message(status: i32) -> &'static str {
+    match status {
+        STATUS_REJECTED => "the map rejected the key",
+        STATUS_PANICKED => "the map panicked in a vtable call",
+        _ => "unknown vtable call status",
+    }
+}
+
+fn lookup(status: i32, out: u64) -> Option<u64> {
+    match status {
+        STATUS_FOUND => Some(out),
+        STATUS_ABSENT => None,
+        status => panic!("{}", status_message(status)),
+    }
+}
+
+impl Drop for IntMapHandle {
+    fn drop(&mut self) {
+        unsafe { (self.vtable.drop)(self.map) }
+    }
+}
+
+unsafe impl Send for IntMapHandle {}
+unsafe impl Sync for IntMapHandle {}
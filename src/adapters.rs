@@ -0,0 +1,356 @@
+use crate::{Capacity, IntKey, IntMap};
+
+/// A key type that converts losslessly to and from an integer key, so an `AdaptedIntMap` can
+/// be keyed by it directly. It's open for implementing on your own id types.
+pub trait KeyAdapter: Copy {
+    type Key: IntKey;
+
+    /// The integer key the map stores for `self`.
+    ///
+    /// ```
+    /// use intmap_rs::KeyAdapter;
+    ///
+    /// #[derive(Clone, Copy, Debug, PartialEq)]
+    /// struct UserId(u32);
+    ///
+    /// impl KeyAdapter for UserId {
+    ///     type Key = u32;
+    ///     fn to_key(self) -> u32 {
+    ///         self.0
+    ///     }
+    ///     fn from_key(key: u32) -> Self {
+    ///         Self(key)
+    ///     }
+    /// }
+    /// assert_eq!(UserId(7).to_key(), 7);
+    /// ```
+    fn to_key(self) -> Self::Key;
+
+    /// The value `to_key` converted into `key`, e.g. for the keys an iterator yields.
+    ///
+    /// ```
+    /// use intmap_rs::KeyAdapter;
+    ///
+    /// #[derive(Clone, Copy, Debug, PartialEq)]
+    /// struct UserId(u32);
+    ///
+    /// impl KeyAdapter for UserId {
+    ///     type Key = u32;
+    ///     fn to_key(self) -> u32 {
+    ///         self.0
+    ///     }
+    ///     fn from_key(key: u32) -> Self {
+    ///         Self(key)
+    ///     }
+    /// }
+    /// assert_eq!(UserId::from_key(UserId(7).to_key()), UserId(7));
+    /// ```
+    fn from_key(key: Self::Key) -> Self;
+}
+
+/// The address packed as `port << 32 | ip`, the address in the low bits: a server's peers
+/// mostly share a port and differ in their address.
+///
+/// ```
+/// use intmap_rs::AdaptedIntMap;
+/// use std::net::SocketAddrV4;
+///
+/// let mut peers = AdaptedIntMap::with_capacity(8);
+/// let addr: SocketAddrV4 = "10.0.0.1:8080".parse().unwrap();
+/// peers.insert(addr, "db");
+/// assert_eq!(peers.get(addr), Some(&"db"));
+/// ```
+#[cfg(feature = "net")]
+impl KeyAdapter for std::net::SocketAddrV4 {
+    type Key = u64;
+
+    #[inline]
+    fn to_key(self) -> u64 {
+        (u64::from(self.port()) << 32) | u64::from(u32::from(*self.ip()))
+    }
+
+    #[inline]
+    fn from_key(key: u64) -> Self {
+        Self::new((key as u32).into(), (key >> 32) as u16)
+    }
+}
+
+/// Nanosecond ticks, every duration fits.
+#[cfg(feature = "duration")]
+impl KeyAdapter for std::time::Duration {
+    type Key = u128;
+
+    #[inline]
+    fn to_key(self) -> u128 {
+        self.as_nanos()
+    }
+
+    #[inline]
+    fn from_key(key: u128) -> Self {
+        const NANOS_PER_SEC: u128 = 1_000_000_000;
+        Self::new((key / NANOS_PER_SEC) as u64, (key % NANOS_PER_SEC) as u32)
+    }
+}
+
+/// An `IntMap` keyed by a `KeyAdapter` type, converting keys on the way in and out.
+///
+/// Adapted keys are structured, e.g. addresses sharing a port or durations in whole
+/// milliseconds with their low bits always zero, so `with_capacity` builds a map that mixes
+/// keys, as `IntMap::with_exact_capacity` does, rather than indexing on their low bits.
+pub struct AdaptedIntMap<A: KeyAdapter, V> {
+    map: IntMap<A::Key, V>,
+}
+
+impl<A: KeyAdapter, V> AdaptedIntMap<A, V> {
+    /// A map of exactly `capacity` home slots over the mixed converted keys.
+    ///
+    /// ```
+    /// # use intmap_rs::{AdaptedIntMap, KeyAdapter};
+    /// # #[derive(Clone, Copy, Debug, PartialEq)]
+    /// # struct UserId(u32);
+    /// # impl KeyAdapter for UserId {
+    /// #     type Key = u32;
+    /// #     fn to_key(self) -> u32 { self.0 }
+    /// #     fn from_key(key: u32) -> Self { Self(key) }
+    /// # }
+    /// let m = AdaptedIntMap::<UserId, ()>::with_capacity(100);
+    /// assert_eq!((m.capacity(), m.len()), (100, 0));
+    /// ```
+    pub fn with_capacity(capacity: impl Into<Capacity>) -> Self {
+        Self::from_inner(IntMap::with_exact_capacity(capacity))
+    }
+
+    /// Wraps `map` as is; one built with `IntMap::with_capacity` indexes on the low bits of the
+    /// converted keys.
+    ///
+    /// ```
+    /// # use intmap_rs::{AdaptedIntMap, KeyAdapter};
+    /// # #[derive(Clone, Copy, Debug, PartialEq)]
+    /// # struct UserId(u32);
+    /// # impl KeyAdapter for UserId {
+    /// #     type Key = u32;
+    /// #     fn to_key(self) -> u32 { self.0 }
+    /// #     fn from_key(key: u32) -> Self { Self(key) }
+    /// # }
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut inner = IntMap::with_capacity(8);
+    /// inner.insert(7u32, "seven");
+    /// let m = AdaptedIntMap::<UserId, _>::from_inner(inner);
+    /// assert_eq!(m.get(UserId(7)), Some(&"seven"));
+    /// ```
+    pub fn from_inner(map: IntMap<A::Key, V>) -> Self {
+        Self { map }
+    }
+
+    /// Same as `IntMap::insert`, with the key converted.
+    ///
+    /// ```
+    /// # use intmap_rs::{AdaptedIntMap, KeyAdapter};
+    /// # #[derive(Clone, Copy, Debug, PartialEq)]
+    /// # struct UserId(u32);
+    /// # impl KeyAdapter for UserId {
+    /// #     type Key = u32;
+    /// #     fn to_key(self) -> u32 { self.0 }
+    /// #     fn from_key(key: u32) -> Self { Self(key) }
+    /// # }
+    /// let mut m = AdaptedIntMap::with_capacity(8);
+    /// assert_eq!(m.insert(UserId(1), "ada"), None);
+    /// assert_eq!(m.insert(UserId(1), "bob"), Some("ada"));
+    /// ```
+    pub fn insert(&mut self, key: A, value: V) -> Option<V> {
+        self.map.insert(key.to_key(), value)
+    }
+
+    /// Same as `IntMap::remove`, with the key converted.
+    ///
+    /// ```
+    /// # use intmap_rs::{AdaptedIntMap, KeyAdapter};
+    /// # #[derive(Clone, Copy, Debug, PartialEq)]
+    /// # struct UserId(u32);
+    /// # impl KeyAdapter for UserId {
+    /// #     type Key = u32;
+    /// #     fn to_key(self) -> u32 { self.0 }
+    /// #     fn from_key(key: u32) -> Self { Self(key) }
+    /// # }
+    /// let mut m = AdaptedIntMap::with_capacity(8);
+    /// m.insert(UserId(1), "ada");
+    /// assert_eq!((m.remove(UserId(1)), m.remove(UserId(1))), (Some("ada"), None));
+    /// ```
+    pub fn remove(&mut self, key: A) -> Option<V> {
+        self.map.remove(key.to_key())
+    }
+
+    /// Same as `IntMap::get`, with the key converted.
+    ///
+    /// ```
+    /// # use intmap_rs::{AdaptedIntMap, KeyAdapter};
+    /// # #[derive(Clone, Copy, Debug, PartialEq)]
+    /// # struct UserId(u32);
+    /// # impl KeyAdapter for UserId {
+    /// #     type Key = u32;
+    /// #     fn to_key(self) -> u32 { self.0 }
+    /// #     fn from_key(key: u32) -> Self { Self(key) }
+    /// # }
+    /// let mut m = AdaptedIntMap::with_capacity(8);
+    /// m.insert(UserId(1), "ada");
+    /// assert_eq!((m.get(UserId(1)), m.get(UserId(2))), (Some(&"ada"), None));
+    /// ```
+    pub fn get(&self, key: A) -> Option<&V> {
+        self.map.get(key.to_key())
+    }
+
+    /// Same as `IntMap::get_mut`, with the key converted.
+    ///
+    /// ```
+    /// # use intmap_rs::{AdaptedIntMap, KeyAdapter};
+    /// # #[derive(Clone, Copy, Debug, PartialEq)]
+    /// # struct UserId(u32);
+    /// # impl KeyAdapter for UserId {
+    /// #     type Key = u32;
+    /// #     fn to_key(self) -> u32 { self.0 }
+    /// #     fn from_key(key: u32) -> Self { Self(key) }
+    /// # }
+    /// let mut m = AdaptedIntMap::with_capacity(8);
+    /// m.insert(UserId(1), 0);
+    /// *m.get_mut(UserId(1)).unwrap() += 1;
+    /// assert_eq!(m.get(UserId(1)), Some(&1));
+    /// ```
+    pub fn get_mut(&mut self, key: A) -> Option<&mut V> {
+        self.map.get_mut(key.to_key())
+    }
+
+    /// Same as `IntMap::contains`, with the key converted.
+    ///
+    /// ```
+    /// # use intmap_rs::{AdaptedIntMap, KeyAdapter};
+    /// # #[derive(Clone, Copy, Debug, PartialEq)]
+    /// # struct UserId(u32);
+    /// # impl KeyAdapter for UserId {
+    /// #     type Key = u32;
+    /// #     fn to_key(self) -> u32 { self.0 }
+    /// #     fn from_key(key: u32) -> Self { Self(key) }
+    /// # }
+    /// let mut m = AdaptedIntMap::with_capacity(8);
+    /// m.insert(UserId(1), ());
+    /// assert!(m.contains(UserId(1)) && !m.contains(UserId(2)));
+    /// ```
+    pub fn contains(&self, key: A) -> bool {
+        self.map.contains(key.to_key())
+    }
+
+    /// Entries with their keys converted back, in the order of `IntMap::iter`.
+    ///
+    /// ```
+    /// # use intmap_rs::{AdaptedIntMap, KeyAdapter};
+    /// # #[derive(Clone, Copy, Debug, PartialEq)]
+    /// # struct UserId(u32);
+    /// # impl KeyAdapter for UserId {
+    /// #     type Key = u32;
+    /// #     fn to_key(self) -> u32 { self.0 }
+    /// #     fn from_key(key: u32) -> Self { Self(key) }
+    /// # }
+    /// let mut m = AdaptedIntMap::with_capacity(8);
+    /// m.insert(UserId(1), "ada");
+    /// assert_eq!(m.iter().collect::<Vec<_>>(), [(UserId(1), &"ada")]);
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = (A, &V)> + '_ {
+        self.map.iter().map(|(k, v)| (A::from_key(*k), v))
+    }
+
+    /// Number of entries stored.
+    ///
+    /// ```
+    /// # use intmap_rs::{AdaptedIntMap, KeyAdapter};
+    /// # #[derive(Clone, Copy, Debug, PartialEq)]
+    /// # struct UserId(u32);
+    /// # impl KeyAdapter for UserId {
+    /// #     type Key = u32;
+    /// #     fn to_key(self) -> u32 { self.0 }
+    /// #     fn from_key(key: u32) -> Self { Self(key) }
+    /// # }
+    /// let mut m = AdaptedIntMap::with_capacity(8);
+    /// m.insert(UserId(1), ());
+    /// m.insert(UserId(2), ());
+    /// assert_eq!(m.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Whether the map holds no entries.
+    ///
+    /// ```
+    /// # use intmap_rs::{AdaptedIntMap, KeyAdapter};
+    /// # #[derive(Clone, Copy, Debug, PartialEq)]
+    /// # struct UserId(u32);
+    /// # impl KeyAdapter for UserId {
+    /// #     type Key = u32;
+    /// #     fn to_key(self) -> u32 { self.0 }
+    /// #     fn from_key(key: u32) -> Self { Self(key) }
+    /// # }
+    /// let mut m = AdaptedIntMap::with_capacity(8);
+    /// assert!(m.is_empty());
+    /// m.insert(UserId(1), ());
+    /// assert!(!m.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Number of home slots, see `IntMap::capacity`.
+    ///
+    /// ```
+    /// # use intmap_rs::{AdaptedIntMap, KeyAdapter};
+    /// # #[derive(Clone, Copy, Debug, PartialEq)]
+    /// # struct UserId(u32);
+    /// # impl KeyAdapter for UserId {
+    /// #     type Key = u32;
+    /// #     fn to_key(self) -> u32 { self.0 }
+    /// #     fn from_key(key: u32) -> Self { Self(key) }
+    /// # }
+    /// assert_eq!(AdaptedIntMap::<UserId, ()>::with_capacity(50).capacity(), 50);
+    /// ```
+    pub fn capacity(&self) -> usize {
+        self.map.capacity()
+    }
+
+    /// The map of converted keys, for the APIs this wrapper doesn't forward.
+    ///
+    /// ```
+    /// # use intmap_rs::{AdaptedIntMap, KeyAdapter};
+    /// # #[derive(Clone, Copy, Debug, PartialEq)]
+    /// # struct UserId(u32);
+    /// # impl KeyAdapter for UserId {
+    /// #     type Key = u32;
+    /// #     fn to_key(self) -> u32 { self.0 }
+    /// #     fn from_key(key: u32) -> Self { Self(key) }
+    /// # }
+    /// let mut m = AdaptedIntMap::with_capacity(8);
+    /// m.insert(UserId(3), ());
+    /// assert!(m.as_inner().contains(3));
+    /// ```
+    pub fn as_inner(&self) -> &IntMap<A::Key, V> {
+        &self.map
+    }
+
+    /// Unwraps the map of converted keys.
+    ///
+    /// ```
+    /// # use intmap_rs::{AdaptedIntMap, KeyAdapter};
+    /// # #[derive(Clone, Copy, Debug, PartialEq)]
+    /// # struct UserId(u32);
+    /// # impl KeyAdapter for UserId {
+    /// #     type Key = u32;
+    /// #     fn to_key(self) -> u32 { self.0 }
+    /// #     fn from_key(key: u32) -> Self { Self(key) }
+    /// # }
+    /// let mut m = AdaptedIntMap::with_capacity(8);
+    /// m.insert(UserId(3), 'c');
+    /// let inner = m.into_inner();
+    /// assert_eq!(inner.get(3), Some(&'c'));
+    /// ```
+    pub fn into_inner(self) -> IntMap<A::Key, V> {
+        self.map
+    }
+}
@@ -0,0 +1,222 @@
+use crate::{Capacity, IntKey, IntMap};
+
+/// Clock-style approximation of LRU: every entry carries a small age that's reset on access
+/// and grows with each eviction round, `evict_cold` drops the oldest entries.
+///
+/// Ages are kept in a byte lane of the table next to the distances, moved along with their
+/// entries, so they cost one byte per slot whatever the alignment of `V`.
+pub struct AgingIntMap<K, V> {
+    map: IntMap<K, V>,
+}
+
+impl<K: IntKey, V> AgingIntMap<K, V> {
+    /// An empty map of `capacity` home slots rounded up to a power of two, see
+    /// `IntMap::with_capacity`.
+    ///
+    /// ```
+    /// use intmap_rs::AgingIntMap;
+    ///
+    /// let m = AgingIntMap::<u32, ()>::with_capacity(100);
+    /// assert_eq!((m.capacity(), m.len()), (128, 0));
+    /// ```
+    pub fn with_capacity(capacity: impl Into<Capacity>) -> Self {
+        let mut map = IntMap::with_capacity(capacity);
+        map.table.enable_ages();
+        Self { map }
+    }
+
+    /// Inserts a hot entry, returns the previous value.
+    ///
+    /// ```
+    /// use intmap_rs::AgingIntMap;
+    ///
+    /// let mut m = AgingIntMap::with_capacity(8);
+    /// assert_eq!(m.insert(1u32, 'a'), None);
+    /// m.evict_cold(0);
+    /// assert_eq!(m.insert(1, 'b'), Some('a'));
+    /// assert_eq!(m.age(1), Some(0));
+    /// ```
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        // a new entry starts at age 0, a replaced one is reset
+        let old = self.map.insert(key, value);
+        if old.is_some() {
+            self.touch(key);
+        }
+        old
+    }
+
+    /// Removes the entry, returns its value if the key was present.
+    ///
+    /// ```
+    /// use intmap_rs::AgingIntMap;
+    ///
+    /// let mut m = AgingIntMap::with_capacity(8);
+    /// m.insert(1u32, 'a');
+    /// assert_eq!((m.remove(1), m.remove(1)), (Some('a'), None));
+    /// ```
+    pub fn remove(&mut self, key: K) -> Option<V> {
+        self.map.remove(key)
+    }
+
+    /// Reads the value and marks the entry as recently used.
+    ///
+    /// ```
+    /// use intmap_rs::AgingIntMap;
+    ///
+    /// let mut m = AgingIntMap::with_capacity(8);
+    /// m.insert(1u32, 'a');
+    /// m.evict_cold(0);
+    /// assert_eq!(m.age(1), Some(1));
+    /// assert_eq!(m.get(1), Some(&'a'));
+    /// assert_eq!(m.age(1), Some(0));
+    /// ```
+    pub fn get(&mut self, key: K) -> Option<&V> {
+        self.get_mut(key).map(|v| &*v)
+    }
+
+    /// Same as `get`, mutably.
+    ///
+    /// ```
+    /// use intmap_rs::AgingIntMap;
+    ///
+    /// let mut m = AgingIntMap::with_capacity(8);
+    /// m.insert(1u32, 10);
+    /// m.evict_cold(0);
+    /// *m.get_mut(1).unwrap() += 1;
+    /// assert_eq!((m.peek(1), m.age(1)), (Some(&11), Some(0)));
+    /// ```
+    pub fn get_mut(&mut self, key: K) -> Option<&mut V> {
+        let index = self.map.find(key)?;
+        self.map.table.set_age(index, 0);
+        Some(&mut self.map.table[index])
+    }
+
+    /// Reads the value without refreshing its age.
+    ///
+    /// ```
+    /// use intmap_rs::AgingIntMap;
+    ///
+    /// let mut m = AgingIntMap::with_capacity(8);
+    /// m.insert(1u32, 'a');
+    /// m.evict_cold(0);
+    /// assert_eq!(m.peek(1), Some(&'a'));
+    /// assert_eq!(m.age(1), Some(1));
+    /// ```
+    pub fn peek(&self, key: K) -> Option<&V> {
+        self.map.get(key)
+    }
+
+    /// Marks the entry as recently used, returns false if the key is missing.
+    ///
+    /// ```
+    /// use intmap_rs::AgingIntMap;
+    ///
+    /// let mut m = AgingIntMap::with_capacity(8);
+    /// m.insert(1u32, ());
+    /// m.evict_cold(0);
+    /// assert!(m.touch(1) && !m.touch(2));
+    /// assert_eq!(m.age(1), Some(0));
+    /// ```
+    pub fn touch(&mut self, key: K) -> bool {
+        self.get_mut(key).is_some()
+    }
+
+    /// Eviction rounds since the entry was last used, none if the key is missing.
+    ///
+    /// ```
+    /// use intmap_rs::AgingIntMap;
+    ///
+    /// let mut m = AgingIntMap::with_capacity(8);
+    /// m.insert(1u32, ());
+    /// m.evict_cold(0);
+    /// m.evict_cold(0);
+    /// assert_eq!((m.age(1), m.age(2)), (Some(2), None));
+    /// ```
+    pub fn age(&self, key: K) -> Option<u8> {
+        self.map.find(key).map(|index| self.map.table.age(index))
+    }
+
+    /// Removes up to `n` entries with the highest age and ages the remaining ones by one round.
+    /// Returns the evicted entries.
+    ///
+    /// ```
+    /// use intmap_rs::AgingIntMap;
+    ///
+    /// let mut m = AgingIntMap::with_capacity(8);
+    /// for k in 1..=3u32 {
+    ///     m.insert(k, k * 10);
+    /// }
+    /// m.evict_cold(0);
+    /// m.get(1);
+    /// let mut evicted = m.evict_cold(2);
+    /// evicted.sort_unstable();
+    /// assert_eq!(evicted, [(2, 20), (3, 30)]);
+    /// assert_eq!((m.len(), m.age(1)), (1, Some(1)));
+    /// ```
+    pub fn evict_cold(&mut self, n: usize) -> Vec<(K, V)> {
+        let table = &self.map.table;
+        let mut ages = table.occupied().map(|(i, k)| (table.age(i), *k)).collect::<Vec<_>>();
+        let n = n.min(ages.len());
+        if n > 0 && n < ages.len() {
+            ages.select_nth_unstable_by(n - 1, |a, b| b.0.cmp(&a.0));
+        }
+        let evicted =
+            ages[..n].iter().filter_map(|&(_, k)| self.map.remove(k).map(|v| (k, v))).collect();
+        // free slots age too, the next entry stored there overwrites its byte
+        let ages = self.map.table.ages_mut().expect("aging maps have an ages lane");
+        ages.iter_mut().for_each(|age| *age = age.saturating_add(1));
+        evicted
+    }
+
+    /// Number of entries stored.
+    ///
+    /// ```
+    /// use intmap_rs::AgingIntMap;
+    ///
+    /// let mut m = AgingIntMap::with_capacity(8);
+    /// m.insert(1u32, ());
+    /// m.insert(2, ());
+    /// assert_eq!(m.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Whether the map holds no entries.
+    ///
+    /// ```
+    /// use intmap_rs::AgingIntMap;
+    ///
+    /// let mut m = AgingIntMap::with_capacity(8);
+    /// assert!(m.is_empty());
+    /// m.insert(1u32, ());
+    /// assert!(!m.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Number of home slots, see `IntMap::capacity`.
+    ///
+    /// ```
+    /// use intmap_rs::AgingIntMap;
+    ///
+    /// assert_eq!(AgingIntMap::<u32, ()>::with_capacity(8).capacity(), 8);
+    /// ```
+    pub fn capacity(&self) -> usize {
+        self.map.capacity()
+    }
+
+    /// As `IntMap::memory_usage`, the ages lane included.
+    ///
+    /// ```
+    /// use intmap_rs::{AgingIntMap, IntMap};
+    ///
+    /// let aging = AgingIntMap::<u32, u32>::with_capacity(64);
+    /// let plain = IntMap::<u32, u32>::with_capacity(64);
+    /// assert_eq!(aging.memory_usage(), plain.memory_usage() + plain.slots().get());
+    /// ```
+    pub fn memory_usage(&self) -> usize {
+        self.map.memory_usage()
+    }
+}
@@ -0,0 +1,51 @@
+use std::ops::{Deref, DerefMut};
+
+// Each lane is allocated with the alignment of its element type, so the way to get aligned
+// values is a value type with that alignment: storing `IntMap<K, Align64<V>>` puts every value
+// at a multiple of 64 bytes, its size padded up to the next multiple of the alignment.
+macro_rules! aligned {
+    ($($name:ident => $align:literal),+) => {$(
+        #[doc = concat!("`T` aligned to ", $align, " bytes, e.g. for SIMD loads straight from")]
+        /// the values lane. Dereferences to `T`.
+        #[repr(C, align($align))]
+        #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct $name<T>(pub T);
+
+        impl<T> $name<T> {
+            /// The wrapped value.
+            ///
+            /// ```
+            #[doc = concat!("use intmap_rs::", stringify!($name), ";")]
+            ///
+            #[doc = concat!("let v = ", stringify!($name), "([1.5f32; 4]);")]
+            #[doc = concat!("assert_eq!(std::mem::align_of_val(&v), ", $align, ");")]
+            /// assert_eq!(v.into_inner(), [1.5; 4]);
+            /// ```
+            pub fn into_inner(self) -> T {
+                self.0
+            }
+        }
+
+        impl<T> From<T> for $name<T> {
+            fn from(value: T) -> Self {
+                Self(value)
+            }
+        }
+
+        impl<T> Deref for $name<T> {
+            type Target = T;
+
+            fn deref(&self) -> &T {
+                &self.0
+            }
+        }
+
+        impl<T> DerefMut for $name<T> {
+            fn deref_mut(&mut self) -> &mut T {
+                &mut self.0
+            }
+        }
+    )+};
+}
+
+aligned!(Align16 => 16, Align32 => 32, Align64 => 64);
@@ -0,0 +1,107 @@
+use crate::{IntKey, IntMap};
+use std::sync::atomic::Ordering::{AcqRel, Acquire, Release};
+use std::sync::atomic::{AtomicI32, AtomicI64, AtomicU32, AtomicU64, AtomicUsize};
+
+// Atomic values: once the key set is built, a shared `&IntMap<K, AtomicU64>` can be updated
+// from many threads without a lock, the table itself is never written. Operations returning
+// an `Option` are `None` for a missing key.
+macro_rules! atomic_values {
+    ($($atomic:ty => $int:ty),+) => {$(
+        impl<K: IntKey> IntMap<K, $atomic> {
+            /// The value stored for `key`, if any.
+            ///
+            /// ```
+            #[doc = concat!("use std::sync::atomic::", stringify!($atomic), ";")]
+            /// use intmap_rs::IntMap;
+            ///
+            /// let mut m = IntMap::with_capacity(8);
+            #[doc = concat!("m.insert(1u32, ", stringify!($atomic), "::new(5));")]
+            /// assert_eq!((m.load(1), m.load(2)), (Some(5), None));
+            /// ```
+            pub fn load(&self, key: K) -> Option<$int> {
+                self.get(key).map(|v| v.load(Acquire))
+            }
+
+            /// Returns false if the key is missing.
+            ///
+            /// ```
+            #[doc = concat!("use std::sync::atomic::", stringify!($atomic), ";")]
+            /// use intmap_rs::IntMap;
+            ///
+            /// let mut m = IntMap::with_capacity(8);
+            #[doc = concat!("m.insert(1u32, ", stringify!($atomic), "::new(5));")]
+            /// assert!(m.store(1, 7) && !m.store(2, 7));
+            /// assert_eq!(m.load(1), Some(7));
+            /// ```
+            pub fn store(&self, key: K, value: $int) -> bool {
+                self.get(key).map(|v| v.store(value, Release)).is_some()
+            }
+
+            /// Replaces the value with `new` if it's `current`, returns the previous value: `Ok`
+            /// if it was replaced, `Err` with the actual value if not. Building block for
+            /// optimistic retry loops.
+            ///
+            /// ```
+            #[doc = concat!("use std::sync::atomic::", stringify!($atomic), ";")]
+            /// use intmap_rs::IntMap;
+            ///
+            /// let mut m = IntMap::with_capacity(8);
+            #[doc = concat!("m.insert(1u32, ", stringify!($atomic), "::new(5));")]
+            /// assert_eq!(m.compare_exchange(1, 5, 6), Some(Ok(5)));
+            /// assert_eq!(m.compare_exchange(1, 5, 7), Some(Err(6)));
+            /// assert_eq!(m.compare_exchange(2, 0, 1), None);
+            /// ```
+            pub fn compare_exchange(
+                &self,
+                key: K,
+                current: $int,
+                new: $int,
+            ) -> Option<Result<$int, $int>> {
+                self.get(key).map(|v| v.compare_exchange(current, new, AcqRel, Acquire))
+            }
+
+            /// Applies `f` until the exchange succeeds, returns the previous value; stops with
+            /// `Err` when `f` returns `None`. See `AtomicU64::fetch_update`.
+            ///
+            /// ```
+            #[doc = concat!("use std::sync::atomic::", stringify!($atomic), ";")]
+            /// use intmap_rs::IntMap;
+            ///
+            /// let mut m = IntMap::with_capacity(8);
+            #[doc = concat!("m.insert(1u32, ", stringify!($atomic), "::new(5));")]
+            /// assert_eq!(m.fetch_update(1, |v| (v < 10).then(|| v * 2)), Some(Ok(5)));
+            /// assert_eq!(m.fetch_update(1, |v| (v < 10).then(|| v * 2)), Some(Err(10)));
+            /// ```
+            pub fn fetch_update(
+                &self,
+                key: K,
+                f: impl FnMut($int) -> Option<$int>,
+            ) -> Option<Result<$int, $int>> {
+                self.get(key).map(|v| v.fetch_update(AcqRel, Acquire, f))
+            }
+
+            /// Wrapping add, returns the previous value.
+            ///
+            /// ```
+            #[doc = concat!("use std::sync::atomic::", stringify!($atomic), ";")]
+            /// use intmap_rs::IntMap;
+            ///
+            /// let mut m = IntMap::with_capacity(8);
+            #[doc = concat!("m.insert(1u32, ", stringify!($atomic), "::new(5));")]
+            /// assert_eq!(m.fetch_add(1, 2), Some(5));
+            /// assert_eq!((m.load(1), m.fetch_add(2, 2)), (Some(7), None));
+            /// ```
+            pub fn fetch_add(&self, key: K, delta: $int) -> Option<$int> {
+                self.get(key).map(|v| v.fetch_add(delta, AcqRel))
+            }
+        }
+    )+};
+}
+
+atomic_values!(
+    AtomicU32 => u32,
+    AtomicU64 => u64,
+    AtomicUsize => usize,
+    AtomicI32 => i32,
+    AtomicI64 => i64
+);
@@ -0,0 +1,217 @@
+use crate::{IntKey, IntMap};
+use arbitrary::{Arbitrary, Unstructured};
+use rand::distributions::{Distribution, Standard};
+use rand::Rng;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash};
+use std::hint::black_box;
+
+// Workload generation shared with the crate's own benchmarks, so a downstream comparison
+// against another map runs the exact same operation streams.
+
+/// One operation of a benchmark workload.
+#[derive(Arbitrary, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op<K, V> {
+    Insert(K, V),
+    Get(K),
+    Delete(K),
+    Contains(K),
+}
+
+/// `n` operations decoded with `Arbitrary` from random bytes, the mix of the `Workload`
+/// benchmark: about as many of each kind, over uniformly random keys.
+///
+/// ```
+/// use intmap_rs::bench::{arbitrary_ops, Op};
+/// use rand::{rngs::StdRng, SeedableRng};
+///
+/// let ops: Vec<Op<u32, u32>> = arbitrary_ops(&mut StdRng::seed_from_u64(1), 1000);
+/// assert_eq!(ops.len(), 1000);
+/// assert!(ops.iter().any(|op| matches!(op, Op::Delete(_))));
+/// ```
+pub fn arbitrary_ops<K, V>(rng: &mut impl Rng, n: usize) -> Vec<Op<K, V>>
+where
+    Op<K, V>: for<'a> Arbitrary<'a>,
+{
+    let mut bytes = vec![0u8; std::mem::size_of::<Op<K, V>>() * n];
+    let mut ops = Vec::with_capacity(n);
+    while ops.len() < n {
+        rng.fill(&mut bytes[..]);
+        let mut u = Unstructured::new(&bytes);
+        while !u.is_empty() && ops.len() < n {
+            ops.push(Op::arbitrary(&mut u).expect("any bytes decode"));
+        }
+    }
+    ops
+}
+
+/// How benchmark keys are spread over the key type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyDistribution {
+    /// Uniformly random over the whole key type.
+    Uniform,
+    /// `start, start + 1, ..`, e.g. ids from a counter.
+    Sequential { start: u64 },
+    /// Random high bits over only a quarter of the low bit patterns, which makes long probe
+    /// clusters with `key & mask` indexing.
+    Clustered,
+}
+
+/// `n` keys drawn from `distribution`, possibly with duplicates except for `Sequential`.
+///
+/// ```
+/// use intmap_rs::bench::{keys, KeyDistribution};
+/// use rand::{rngs::StdRng, SeedableRng};
+///
+/// let mut rng = StdRng::seed_from_u64(1);
+/// let ids: Vec<u64> = keys(KeyDistribution::Sequential { start: 10 }, &mut rng, 3);
+/// assert_eq!(ids, [10, 11, 12]);
+/// let clustered: Vec<u32> = keys(KeyDistribution::Clustered, &mut rng, 100);
+/// assert!(clustered.iter().all(|k| k & 0x3f < 16));
+/// ```
+pub fn keys<K: IntKey>(distribution: KeyDistribution, rng: &mut impl Rng, n: usize) -> Vec<K>
+where
+    Standard: Distribution<K>,
+{
+    (0..n as u64)
+        .map(|i| match distribution {
+            KeyDistribution::Uniform => rng.gen(),
+            KeyDistribution::Sequential { start } => {
+                K::from_u64(start + i).expect("sequential key out of the key type's range")
+            }
+            KeyDistribution::Clustered => {
+                (rng.gen::<K>() << 6) | K::from_u8(rng.gen_range(0..16)).unwrap()
+            }
+        })
+        .collect()
+}
+
+/// Random op streams as in `arbitrary_ops`, with keys drawn from `distribution` instead.
+///
+/// ```
+/// use intmap_rs::bench::{ops_with_keys, KeyDistribution, Op};
+/// use rand::{rngs::StdRng, SeedableRng};
+///
+/// let mut rng = StdRng::seed_from_u64(1);
+/// let ops: Vec<Op<u32, u8>> =
+///     ops_with_keys(KeyDistribution::Sequential { start: 0 }, &mut rng, 4);
+/// let keys: Vec<u32> = ops
+///     .iter()
+///     .map(|op| match *op {
+///         Op::Insert(k, _) | Op::Get(k) | Op::Delete(k) | Op::Contains(k) => k,
+///     })
+///     .collect();
+/// assert_eq!(keys, [0, 1, 2, 3]);
+/// ```
+pub fn ops_with_keys<K: IntKey, V>(
+    distribution: KeyDistribution,
+    rng: &mut impl Rng,
+    n: usize,
+) -> Vec<Op<K, V>>
+where
+    Standard: Distribution<K> + Distribution<V>,
+{
+    keys(distribution, rng, n)
+        .into_iter()
+        .map(|key| match rng.gen_range(0..4) {
+            0 => Op::Insert(key, rng.gen()),
+            1 => Op::Get(key),
+            2 => Op::Delete(key),
+            _ => Op::Contains(key),
+        })
+        .collect()
+}
+
+/// The map operations a workload runs. Implement it for another map to compare it with
+/// `IntMap` on the same ops.
+///
+/// ```
+/// use intmap_rs::bench::BenchMap;
+/// use intmap_rs::IntMap;
+///
+/// fn churn<M: BenchMap<u32, u32>>(map: &mut M) -> bool {
+///     map.insert(1, 10);
+///     map.remove(1) == Some(10) && map.get(1).is_none() && !map.contains(1)
+/// }
+/// assert!(churn(&mut IntMap::with_capacity(8)));
+/// ```
+pub trait BenchMap<K, V> {
+    fn insert(&mut self, key: K, value: V) -> Option<V>;
+    fn get(&self, key: K) -> Option<&V>;
+    fn remove(&mut self, key: K) -> Option<V>;
+    fn contains(&self, key: K) -> bool;
+}
+
+impl<K: IntKey, V> BenchMap<K, V> for IntMap<K, V> {
+    fn insert(&mut self, key: K, value: V) -> Option<V> {
+        IntMap::insert(self, key, value)
+    }
+
+    fn get(&self, key: K) -> Option<&V> {
+        IntMap::get(self, key)
+    }
+
+    fn remove(&mut self, key: K) -> Option<V> {
+        IntMap::remove(self, key)
+    }
+
+    fn contains(&self, key: K) -> bool {
+        IntMap::contains(self, key)
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> BenchMap<K, V> for HashMap<K, V, S> {
+    fn insert(&mut self, key: K, value: V) -> Option<V> {
+        HashMap::insert(self, key, value)
+    }
+
+    fn get(&self, key: K) -> Option<&V> {
+        HashMap::get(self, &key)
+    }
+
+    fn remove(&mut self, key: K) -> Option<V> {
+        HashMap::remove(self, &key)
+    }
+
+    fn contains(&self, key: K) -> bool {
+        HashMap::contains_key(self, &key)
+    }
+}
+
+/// Applies `ops` to `map` in order, keeping every result alive with `black_box`.
+///
+/// ```
+/// use intmap_rs::bench::{run, Op};
+/// use intmap_rs::IntMap;
+/// use std::collections::HashMap;
+///
+/// let ops = [Op::Insert(1u32, 10u32), Op::Insert(2, 20), Op::Delete(1), Op::Get(2)];
+/// let mut intmap = IntMap::with_capacity(8);
+/// let mut hashmap = HashMap::new();
+/// run(&mut intmap, &ops);
+/// run(&mut hashmap, &ops);
+/// assert_eq!((intmap.len(), hashmap.len()), (1, 1));
+/// ```
+pub fn run<'a, K, V, M>(map: &mut M, ops: impl IntoIterator<Item = &'a Op<K, V>>)
+where
+    K: Copy + 'a,
+    V: Copy + 'a,
+    M: BenchMap<K, V>,
+{
+    for op in ops {
+        match *op {
+            Op::Insert(key, value) => {
+                black_box(map.insert(key, value));
+            }
+            Op::Get(key) => {
+                black_box(map.get(key));
+            }
+            Op::Delete(key) => {
+                black_box(map.remove(key));
+            }
+            Op::Contains(key) => {
+                black_box(map.contains(key));
+            }
+        }
+    }
+}
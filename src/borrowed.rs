@@ -0,0 +1,153 @@
+use crate::map::{Distance, Table};
+use crate::{round_capacity, IntKey, IntMap, Probe};
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+use std::ops::Deref;
+
+/// An `IntMap` living in caller-provided lanes, e.g. frame-scoped scratch memory, so building
+/// one allocates nothing, except for the tag lane under the `tags` feature. Dereferences to `IntMap` for reading; mutation goes through the
+/// forwarding methods, so the borrowed table can't be swapped out of the wrapper.
+pub struct BorrowedIntMap<'a, K, V> {
+    map: IntMap<K, V>,
+    _storage: PhantomData<&'a mut [MaybeUninit<V>]>,
+}
+
+impl<'a, K: IntKey, V> BorrowedIntMap<'a, K, V> {
+    /// Builds an empty map over three lanes of equal length. The largest power-of-two capacity
+    /// that leaves room for a probe overflow region is used, see `IntMap::with_probe_limit`;
+    /// slots past `capacity + probe_limit` stay unused. With the `tags` feature the map still
+    /// allocates its tag lane, a byte per slot.
+    ///
+    /// Panics if the lanes differ in length or are shorter than 4 slots.
+    ///
+    /// ```
+    /// use intmap_rs::BorrowedIntMap;
+    /// use std::mem::MaybeUninit;
+    ///
+    /// let mut distances = [0i8; 40];
+    /// let mut keys = [MaybeUninit::uninit(); 40];
+    /// let mut values = [MaybeUninit::uninit(); 40];
+    /// let mut m = BorrowedIntMap::from_raw_buffers(&mut distances, &mut keys, &mut values);
+    /// assert_eq!((m.capacity(), m.probe_limit()), (16, 24));
+    /// m.insert(1u32, 10u64);
+    /// assert_eq!(m.get(1), Some(&10));
+    /// ```
+    pub fn from_raw_buffers(
+        distances: &'a mut [i8],
+        keys: &'a mut [MaybeUninit<K>],
+        values: &'a mut [MaybeUninit<V>],
+    ) -> Self {
+        let slots = distances.len();
+        assert!(keys.len() == slots && values.len() == slots, "lanes differ in length");
+        assert!(slots >= 4, "at least 4 slots are required");
+
+        let reserve = (slots / 2).min(Distance::MAX as usize);
+        let mut capacity = round_capacity((slots - reserve) as u32);
+        if capacity as usize > slots - reserve {
+            capacity /= 2;
+        }
+        let probe_limit = (slots - capacity as usize).min(Distance::MAX as usize) as Distance;
+        // SAFETY:
+        // the lanes are exclusively borrowed for 'a and cover 'capacity + probe_limit' slots
+        let table = unsafe {
+            Table::from_raw_parts(
+                distances.as_mut_ptr(),
+                keys.as_mut_ptr(),
+                values.as_mut_ptr(),
+                capacity as usize + probe_limit as usize,
+                probe_limit,
+            )
+        };
+        Self { map: IntMap::from_table(table, capacity), _storage: PhantomData }
+    }
+
+    /// Same as `IntMap::insert`.
+    ///
+    /// ```
+    /// use intmap_rs::BorrowedIntMap;
+    /// use std::mem::MaybeUninit;
+    ///
+    /// let mut distances = [0; 8];
+    /// let (mut keys, mut values) = ([MaybeUninit::uninit(); 8], [MaybeUninit::uninit(); 8]);
+    /// let mut m = BorrowedIntMap::from_raw_buffers(&mut distances, &mut keys, &mut values);
+    /// assert_eq!(m.insert(1u32, 'a'), None);
+    /// assert_eq!(m.insert(1, 'b'), Some('a'));
+    /// ```
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.map.insert(key, value)
+    }
+
+    /// Same as `IntMap::remove`.
+    ///
+    /// ```
+    /// use intmap_rs::BorrowedIntMap;
+    /// use std::mem::MaybeUninit;
+    ///
+    /// let mut distances = [0; 8];
+    /// let (mut keys, mut values) = ([MaybeUninit::uninit(); 8], [MaybeUninit::uninit(); 8]);
+    /// let mut m = BorrowedIntMap::from_raw_buffers(&mut distances, &mut keys, &mut values);
+    /// m.insert(1u32, 'a');
+    /// assert_eq!((m.remove(1), m.remove(1)), (Some('a'), None));
+    /// ```
+    pub fn remove(&mut self, key: K) -> Option<V> {
+        self.map.remove(key)
+    }
+
+    /// Same as `IntMap::get_mut`.
+    ///
+    /// ```
+    /// use intmap_rs::BorrowedIntMap;
+    /// use std::mem::MaybeUninit;
+    ///
+    /// let mut distances = [0; 8];
+    /// let (mut keys, mut values) = ([MaybeUninit::uninit(); 8], [MaybeUninit::uninit(); 8]);
+    /// let mut m = BorrowedIntMap::from_raw_buffers(&mut distances, &mut keys, &mut values);
+    /// m.insert(1u32, 1);
+    /// *m.get_mut(1).unwrap() += 1;
+    /// assert_eq!(m.get(1), Some(&2));
+    /// ```
+    pub fn get_mut(&mut self, key: K) -> Option<&mut V> {
+        self.map.get_mut(key)
+    }
+
+    /// Same as `IntMap::probe`.
+    ///
+    /// ```
+    /// use intmap_rs::BorrowedIntMap;
+    /// use std::mem::MaybeUninit;
+    ///
+    /// let mut distances = [0; 8];
+    /// let (mut keys, mut values) = ([MaybeUninit::uninit(); 8], [MaybeUninit::uninit(); 8]);
+    /// let mut m = BorrowedIntMap::from_raw_buffers(&mut distances, &mut keys, &mut values);
+    /// *m.probe(3u32).or_insert(0) += 5;
+    /// assert_eq!(m.get(3), Some(&5));
+    /// ```
+    pub fn probe(&mut self, key: K) -> Probe<'_, K, V> {
+        self.map.probe(key)
+    }
+
+    /// Same as `IntMap::clear`, the lanes stay borrowed.
+    ///
+    /// ```
+    /// use intmap_rs::BorrowedIntMap;
+    /// use std::mem::MaybeUninit;
+    ///
+    /// let mut distances = [0; 8];
+    /// let (mut keys, mut values) = ([MaybeUninit::uninit(); 8], [MaybeUninit::uninit(); 8]);
+    /// let mut m = BorrowedIntMap::from_raw_buffers(&mut distances, &mut keys, &mut values);
+    /// m.insert(1u32, "frame");
+    /// m.clear();
+    /// assert!(m.is_empty());
+    /// ```
+    pub fn clear(&mut self) {
+        self.map.clear();
+    }
+}
+
+impl<'a, K, V> Deref for BorrowedIntMap<'a, K, V> {
+    type Target = IntMap<K, V>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.map
+    }
+}
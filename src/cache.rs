@@ -0,0 +1,91 @@
+// Cache control intrinsics used by the table. Each one has a portable fallback, selected on
+// targets without the instruction and under Miri, which can't interpret most of 'std::arch'.
+
+#[cfg(all(target_arch = "x86_64", target_feature = "sse2", not(miri)))]
+mod imp {
+    use std::arch::asm;
+    use std::arch::x86_64::{
+        __m128i, _mm_prefetch, _mm_set1_epi8, _mm_sfence, _mm_stream_si128, _MM_HINT_T0,
+    };
+    use std::mem;
+
+    #[inline]
+    pub fn prefetch<T>(ptr: *const T) {
+        unsafe { _mm_prefetch::<_MM_HINT_T0>(ptr.cast()) };
+    }
+
+    // SAFETY: 'dst' must be valid for writes and aligned
+    #[inline]
+    pub unsafe fn stream_store<T: Copy>(dst: *mut T, value: &T) {
+        // the word is moved inside the asm block, so padding bytes of 'T' are copied like
+        // 'ptr::write' copies them instead of being read as an integer
+        match (mem::size_of::<T>(), mem::align_of::<T>()) {
+            (8, 8) => asm!(
+                "mov {word}, qword ptr [{src}]",
+                "movnti qword ptr [{dst}], {word}",
+                src = in(reg) value,
+                dst = in(reg) dst,
+                word = out(reg) _,
+                options(nostack, preserves_flags),
+            ),
+            (4, 4) => asm!(
+                "mov {word:e}, dword ptr [{src}]",
+                "movnti dword ptr [{dst}], {word:e}",
+                src = in(reg) value,
+                dst = in(reg) dst,
+                word = out(reg) _,
+                options(nostack, preserves_flags),
+            ),
+            _ => dst.write(*value),
+        }
+    }
+
+    #[inline]
+    pub fn store_fence() {
+        unsafe { _mm_sfence() };
+    }
+
+    pub fn stream_fill(bytes: &mut [i8], value: i8) {
+        unsafe {
+            let (head, body, tail) = bytes.align_to_mut::<__m128i>();
+            head.fill(value);
+            tail.fill(value);
+            let fill = _mm_set1_epi8(value);
+            for chunk in body {
+                _mm_stream_si128(chunk, fill);
+            }
+            // streaming stores are weakly ordered, publish them before the lane is read
+            _mm_sfence();
+        }
+    }
+}
+
+#[cfg(not(all(target_arch = "x86_64", target_feature = "sse2", not(miri))))]
+mod imp {
+    #[inline]
+    pub fn prefetch<T>(_ptr: *const T) {}
+
+    #[inline]
+    pub unsafe fn stream_store<T: Copy>(dst: *mut T, value: &T) {
+        dst.write(*value);
+    }
+
+    #[inline]
+    pub fn store_fence() {}
+
+    pub fn stream_fill(bytes: &mut [i8], value: i8) {
+        bytes.fill(value);
+    }
+}
+
+/// Hints the CPU to load the cache line at `ptr`. Never faults, any address is fine.
+pub use imp::prefetch;
+/// Orders the preceding streaming stores before any later store.
+pub use imp::store_fence;
+/// Fills `bytes` with stores that bypass the cache where supported, so a multi-GB fill doesn't
+/// evict everything else.
+pub use imp::stream_fill;
+/// Writes `*value` to `dst` bypassing the cache where supported: 4 and 8 byte types with a
+/// streaming store that is combined with its neighbours into a whole cache line, others with a
+/// regular one. Streaming stores are weakly ordered, `store_fence` publishes them.
+pub use imp::stream_store;
@@ -0,0 +1,95 @@
+/// A number of home slots, the entries a map holds at load factor 1. Constructors take it as
+/// requested and round or clamp it, `IntMap::capacity` is the result. A plain `u32` converts
+/// into it; a `Slots` count or an entry count doesn't, those are sized differently.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Capacity(u32);
+
+impl Capacity {
+    /// A capacity of `home_slots`, as requested.
+    ///
+    /// ```
+    /// use intmap_rs::{Capacity, IntMap};
+    ///
+    /// const SLOTS: Capacity = Capacity::new(1000);
+    /// assert_eq!(IntMap::<u32, ()>::with_exact_capacity(SLOTS).capacity(), 1000);
+    /// ```
+    pub const fn new(home_slots: u32) -> Self {
+        Self(home_slots)
+    }
+
+    /// Smallest capacity that holds `entries` at a load factor of at most `max_load`, e.g.
+    /// 1000 entries at 0.8 need 1250 home slots.
+    ///
+    /// Panics if `max_load` is not in `(0, 1]`.
+    ///
+    /// ```
+    /// use intmap_rs::Capacity;
+    ///
+    /// assert_eq!(Capacity::for_entries(1000, 0.8).get(), 1250);
+    /// ```
+    pub fn for_entries(entries: usize, max_load: f32) -> Self {
+        assert!(max_load > 0.0 && max_load <= 1.0, "load factor must be in (0, 1]");
+        let wanted = (entries as f64 / max_load as f64).ceil();
+        Self(wanted.min(u32::MAX as f64) as u32)
+    }
+
+    /// Entries that fit at a load factor of at most `max_load`, the inverse of `for_entries`.
+    ///
+    /// Panics if `max_load` is not in `(0, 1]`.
+    ///
+    /// ```
+    /// use intmap_rs::Capacity;
+    ///
+    /// assert_eq!(Capacity::new(1250).entries_at(0.8), 1000);
+    /// ```
+    pub fn entries_at(self, max_load: f32) -> usize {
+        assert!(max_load > 0.0 && max_load <= 1.0, "load factor must be in (0, 1]");
+        (self.0 as f64 * max_load as f64).floor() as usize
+    }
+
+    /// The number of home slots.
+    ///
+    /// ```
+    /// use intmap_rs::Capacity;
+    ///
+    /// assert_eq!(Capacity::from(64).get(), 64);
+    /// ```
+    pub const fn get(self) -> u32 {
+        self.0
+    }
+}
+
+impl From<u32> for Capacity {
+    fn from(home_slots: u32) -> Self {
+        Self(home_slots)
+    }
+}
+
+/// A number of table slots: the home slots plus the overflow region past the last one, see
+/// `IntMap::slots`. It's what the table allocates, not a capacity, and can't be passed as one.
+///
+/// ```compile_fail
+/// use intmap_rs::IntMap;
+///
+/// let m = IntMap::<u32, ()>::with_capacity(64);
+/// let copy = IntMap::<u32, ()>::with_capacity(m.slots());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Slots(usize);
+
+impl Slots {
+    pub(crate) const fn new(slots: usize) -> Self {
+        Self(slots)
+    }
+
+    /// The number of table slots.
+    ///
+    /// ```
+    /// use intmap_rs::{IntMap, OVERFLOW_SLOTS};
+    ///
+    /// assert_eq!(IntMap::<u32, ()>::with_capacity(16).slots().get(), 16 + OVERFLOW_SLOTS);
+    /// ```
+    pub const fn get(self) -> usize {
+        self.0
+    }
+}
@@ -0,0 +1,251 @@
+use crate::{Capacity, IntKey, IntMap};
+use std::cell::{Cell, UnsafeCell};
+
+/// An `IntMap` with `&self` insert and lookup for single-threaded code, e.g. a symbol table
+/// shared by the passes of a parser, where threading `&mut` through every call isn't worth
+/// the trouble. It's `!Sync`, like `RefCell`, wrap a `SyncIntMap` to share across threads.
+///
+/// No reference into the map leaves a call: lookups clone the value or run a closure on it.
+/// Closures and the values' `Clone` and `Drop` are the only user code that runs inside a
+/// call, and touching the same cell from there panics naming the call in progress, so the
+/// contract is checked rather than trusted. The check is a flag test per call and stays on in
+/// release builds, it's what keeps the `&self` mutation sound.
+///
+/// ```
+/// use intmap_rs::IntMapCell;
+///
+/// fn declare(symbols: &IntMapCell<u32, &'static str>, id: u32, name: &'static str) {
+///     symbols.insert(id, name);
+/// }
+///
+/// let symbols = IntMapCell::with_capacity(16);
+/// declare(&symbols, 1, "main");
+/// assert_eq!(symbols.get_cloned(1), Some("main"));
+/// ```
+///
+/// ```compile_fail
+/// fn shared<T: Sync>(_: &T) {}
+/// shared(&intmap_rs::IntMapCell::<u32, u32>::with_capacity(4));
+/// ```
+pub struct IntMapCell<K, V> {
+    map: UnsafeCell<IntMap<K, V>>,
+    // the call that currently has the map, if any
+    busy: Cell<Option<&'static str>>,
+}
+
+// Holds the map for one call, the drop releases it even if the call unwinds.
+struct Busy<'a>(&'a Cell<Option<&'static str>>);
+
+impl Drop for Busy<'_> {
+    fn drop(&mut self) {
+        self.0.set(None);
+    }
+}
+
+impl<K: IntKey, V> IntMapCell<K, V> {
+    /// Wraps `map`.
+    ///
+    /// ```
+    /// use intmap_rs::{IntMap, IntMapCell};
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// m.insert(1u32, "main");
+    /// let cell = IntMapCell::new(m);
+    /// assert_eq!(cell.get_cloned(1), Some("main"));
+    /// ```
+    pub fn new(map: IntMap<K, V>) -> Self {
+        Self { map: UnsafeCell::new(map), busy: Cell::new(None) }
+    }
+
+    /// A cell over a new `IntMap::with_capacity` map.
+    ///
+    /// ```
+    /// use intmap_rs::IntMapCell;
+    ///
+    /// let cell = IntMapCell::<u32, ()>::with_capacity(16);
+    /// assert!(cell.is_empty());
+    /// ```
+    pub fn with_capacity(capacity: impl Into<Capacity>) -> Self {
+        Self::new(IntMap::with_capacity(capacity))
+    }
+
+    // runs 'f' with exclusive access, the check is what makes handing out '&mut' sound
+    #[inline]
+    fn enter<R>(&self, call: &'static str, f: impl FnOnce(&mut IntMap<K, V>) -> R) -> R {
+        if let Some(running) = self.busy.replace(Some(call)) {
+            self.busy.set(Some(running));
+            panic!("IntMapCell::{call} called while IntMapCell::{running} is running");
+        }
+        let _busy = Busy(&self.busy);
+        // SAFETY:
+        // the cell is !Sync and 'busy' was free, so no other reference to the map exists
+        f(unsafe { &mut *self.map.get() })
+    }
+
+    /// Same as `IntMap::insert`, through a shared borrow.
+    ///
+    /// ```
+    /// use intmap_rs::IntMapCell;
+    ///
+    /// let cell = IntMapCell::with_capacity(8);
+    /// assert_eq!(cell.insert(1u32, 'a'), None);
+    /// assert_eq!(cell.insert(1, 'b'), Some('a'));
+    /// ```
+    pub fn insert(&self, key: K, value: V) -> Option<V> {
+        self.enter("insert", |map| map.insert(key, value))
+    }
+
+    /// Same as `IntMap::remove`, through a shared borrow.
+    ///
+    /// ```
+    /// use intmap_rs::IntMapCell;
+    ///
+    /// let cell = IntMapCell::with_capacity(8);
+    /// cell.insert(1u32, 'a');
+    /// assert_eq!((cell.remove(1), cell.remove(1)), (Some('a'), None));
+    /// ```
+    pub fn remove(&self, key: K) -> Option<V> {
+        self.enter("remove", |map| map.remove(key))
+    }
+
+    /// A clone of the value stored for `key`, if any.
+    ///
+    /// ```
+    /// use intmap_rs::IntMapCell;
+    ///
+    /// let cell = IntMapCell::with_capacity(8);
+    /// cell.insert(1u32, "main".to_string());
+    /// assert_eq!(cell.get_cloned(1).as_deref(), Some("main"));
+    /// assert_eq!(cell.get_cloned(2), None);
+    /// ```
+    pub fn get_cloned(&self, key: K) -> Option<V>
+    where
+        V: Clone,
+    {
+        self.enter("get_cloned", |map| map.get(key).cloned())
+    }
+
+    /// Whether an entry is stored for `key`.
+    ///
+    /// ```
+    /// use intmap_rs::IntMapCell;
+    ///
+    /// let cell = IntMapCell::with_capacity(8);
+    /// cell.insert(1u32, ());
+    /// assert!(cell.contains(1) && !cell.contains(2));
+    /// ```
+    pub fn contains(&self, key: K) -> bool {
+        self.enter("contains", |map| map.contains(key))
+    }
+
+    /// Runs `f` on the value, `None` if the key is missing.
+    ///
+    /// ```
+    /// use intmap_rs::IntMapCell;
+    ///
+    /// let cell = IntMapCell::with_capacity(8);
+    /// cell.insert(1u32, vec![1]);
+    /// assert_eq!(cell.update(1, |v| { v.push(2); v.len() }), Some(2));
+    /// assert_eq!(cell.update(2, |v| v.len()), None);
+    /// ```
+    pub fn update<R>(&self, key: K, f: impl FnOnce(&mut V) -> R) -> Option<R> {
+        self.enter("update", |map| map.get_mut(key).map(f))
+    }
+
+    /// Runs `f` on the whole map, for the APIs this cell doesn't forward.
+    ///
+    /// ```
+    /// use intmap_rs::IntMapCell;
+    ///
+    /// let cell = IntMapCell::with_capacity(8);
+    /// cell.insert(2u32, 20);
+    /// cell.insert(1, 10);
+    /// assert_eq!(cell.with(|map| map.values().sum::<i32>()), 30);
+    /// ```
+    pub fn with<R>(&self, f: impl FnOnce(&IntMap<K, V>) -> R) -> R {
+        self.enter("with", |map| f(map))
+    }
+
+    /// Runs `f` on the whole map, mutably.
+    ///
+    /// ```
+    /// use intmap_rs::IntMapCell;
+    ///
+    /// let cell = IntMapCell::with_capacity(8);
+    /// cell.insert(1u32, 10);
+    /// cell.insert(2, 20);
+    /// cell.with_mut(|map| map.retain(|k, _| k > 1));
+    /// assert_eq!(cell.len(), 1);
+    /// ```
+    pub fn with_mut<R>(&self, f: impl FnOnce(&mut IntMap<K, V>) -> R) -> R {
+        self.enter("with_mut", f)
+    }
+
+    /// Number of entries stored.
+    ///
+    /// ```
+    /// use intmap_rs::IntMapCell;
+    ///
+    /// let cell = IntMapCell::with_capacity(8);
+    /// cell.insert(1u32, ());
+    /// assert_eq!(cell.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.enter("len", |map| map.len())
+    }
+
+    /// Whether the map holds no entries.
+    ///
+    /// ```
+    /// use intmap_rs::IntMapCell;
+    ///
+    /// let cell = IntMapCell::with_capacity(8);
+    /// assert!(cell.is_empty());
+    /// cell.insert(1u32, ());
+    /// assert!(!cell.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drops the values inside the call, unlike `insert` and `remove` which hand the old
+    /// value back; a value whose drop uses the cell has to be removed instead.
+    ///
+    /// ```
+    /// use intmap_rs::IntMapCell;
+    ///
+    /// let cell = IntMapCell::with_capacity(8);
+    /// cell.insert(1u32, "x".to_string());
+    /// cell.clear();
+    /// assert!(cell.is_empty());
+    /// ```
+    pub fn clear(&self) {
+        self.enter("clear", |map| map.clear())
+    }
+
+    /// The map itself, no check needed with `&mut self`.
+    ///
+    /// ```
+    /// use intmap_rs::IntMapCell;
+    ///
+    /// let mut cell = IntMapCell::with_capacity(8);
+    /// cell.as_inner_mut().insert(1u32, 'a');
+    /// assert_eq!(cell.get_cloned(1), Some('a'));
+    /// ```
+    pub fn as_inner_mut(&mut self) -> &mut IntMap<K, V> {
+        self.map.get_mut()
+    }
+
+    /// Unwraps the map.
+    ///
+    /// ```
+    /// use intmap_rs::IntMapCell;
+    ///
+    /// let cell = IntMapCell::with_capacity(8);
+    /// cell.insert(1u32, 'a');
+    /// assert_eq!(cell.into_inner().get(1), Some(&'a'));
+    /// ```
+    pub fn into_inner(self) -> IntMap<K, V> {
+        self.map.into_inner()
+    }
+}
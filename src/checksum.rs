@@ -0,0 +1,67 @@
+use std::hash::Hasher;
+
+const P1: u64 = 0x9e37_79b1_85eb_ca87;
+const P2: u64 = 0xc2b2_ae3d_27d4_eb4f;
+const P3: u64 = 0x1656_67b1_9e37_79f9;
+
+/// xxhash64-style hasher behind `IntMap::checksum`, fixed unlike `DefaultHasher` whose
+/// algorithm may change between Rust releases. Integers are fed by value and `usize` as u64, so
+/// they hash the same on every platform; byte slices are read as they are, and std hashes
+/// integer slices as their native-endian bytes.
+pub(crate) struct Digest(u64);
+
+impl Digest {
+    pub(crate) fn new() -> Self {
+        Self(P3)
+    }
+
+    #[inline]
+    fn round(&mut self, word: u64) {
+        self.0 = (self.0 ^ word.wrapping_mul(P2)).rotate_left(31).wrapping_mul(P1);
+    }
+}
+
+impl Hasher for Digest {
+    fn write(&mut self, bytes: &[u8]) {
+        let mut chunks = bytes.chunks_exact(8);
+        for chunk in &mut chunks {
+            self.round(u64::from_le_bytes(chunk.try_into().unwrap()));
+        }
+        let mut tail = [0u8; 8];
+        tail[..chunks.remainder().len()].copy_from_slice(chunks.remainder());
+        self.round(u64::from_le_bytes(tail) ^ ((bytes.len() as u64) << 56));
+    }
+
+    fn write_u8(&mut self, i: u8) {
+        self.round(i.into())
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.round(i.into())
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.round(i.into())
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.round(i)
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.round(i as u64);
+        self.round((i >> 64) as u64);
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.round(i as u64)
+    }
+
+    // the xxhash64 avalanche
+    fn finish(&self) -> u64 {
+        let mut h = self.0;
+        h = (h ^ (h >> 33)).wrapping_mul(P2);
+        h = (h ^ (h >> 29)).wrapping_mul(P3);
+        h ^ (h >> 32)
+    }
+}
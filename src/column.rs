@@ -0,0 +1,229 @@
+use crate::{Capacity, IntKey, IntMap};
+
+/// A key → row index over a values column the caller owns, e.g. a component `Vec` of an ECS
+/// storage. The table holds only keys and row numbers; rows are dense, `0..len()`, and follow
+/// the column's own `push` and `swap_remove`, so the column stays a plain slice.
+///
+/// ```
+/// use intmap_rs::ColumnIndex;
+///
+/// let mut index = ColumnIndex::with_capacity(16);
+/// let mut positions = Vec::new();
+/// for (entity, x) in [(7u32, 1.0), (3, 2.0), (9, 3.0)] {
+///     if let Ok(row) = index.insert(entity) {
+///         debug_assert_eq!(row, positions.len());
+///         positions.push(x);
+///     }
+/// }
+/// let row = index.remove(7).unwrap();
+/// positions.swap_remove(row);
+/// assert_eq!(index.get(&positions, 9), Some(&3.0));
+/// assert_eq!(index.keys(), [9, 3]);
+/// ```
+pub struct ColumnIndex<K> {
+    rows: IntMap<K, u32>,
+    // key of every row, to find the key a 'swap_remove' moves
+    keys: Vec<K>,
+}
+
+impl<K: IntKey> ColumnIndex<K> {
+    /// An empty index of `capacity` home slots rounded up to a power of two.
+    ///
+    /// ```
+    /// use intmap_rs::ColumnIndex;
+    ///
+    /// let index = ColumnIndex::<u32>::with_capacity(100);
+    /// assert_eq!((index.capacity(), index.len()), (128, 0));
+    /// ```
+    pub fn with_capacity(capacity: impl Into<Capacity>) -> Self {
+        let rows = IntMap::with_capacity(capacity);
+        let keys = Vec::with_capacity(rows.capacity());
+        Self { rows, keys }
+    }
+
+    /// `Ok` with the row for a new key, `len() - 1`: the caller pushes its value so it lands
+    /// there. `Err` with the row of a key already present. Panics if the table is full.
+    ///
+    /// ```
+    /// use intmap_rs::ColumnIndex;
+    ///
+    /// let mut index = ColumnIndex::with_capacity(8);
+    /// assert_eq!(index.insert(7u32), Ok(0));
+    /// assert_eq!(index.insert(3), Ok(1));
+    /// assert_eq!(index.insert(7), Err(0));
+    /// ```
+    pub fn insert(&mut self, key: K) -> Result<usize, usize> {
+        if let Some(&row) = self.rows.get(key) {
+            return Err(row as usize);
+        }
+        let row = self.keys.len();
+        self.rows.insert(key, row as u32);
+        self.keys.push(key);
+        Ok(row)
+    }
+
+    /// Removes the key and returns its row, the caller does `column.swap_remove(row)`: the last
+    /// row's key moves to `row` here just as the last value moves there in the column.
+    ///
+    /// ```
+    /// use intmap_rs::ColumnIndex;
+    ///
+    /// let mut index = ColumnIndex::with_capacity(8);
+    /// let mut names = Vec::new();
+    /// for (id, name) in [(7u32, "a"), (3, "b"), (9, "c")] {
+    ///     index.insert(id).unwrap();
+    ///     names.push(name);
+    /// }
+    /// names.swap_remove(index.remove(7).unwrap());
+    /// assert_eq!((index.row(9), index.get(&names, 9)), (Some(0), Some(&"c")));
+    /// ```
+    pub fn remove(&mut self, key: K) -> Option<usize> {
+        let row = self.rows.remove(key)? as usize;
+        self.keys.swap_remove(row);
+        if let Some(&moved) = self.keys.get(row) {
+            *self.rows.get_mut(moved).unwrap() = row as u32;
+        }
+        Some(row)
+    }
+
+    /// The row of `key`, if it's present.
+    ///
+    /// ```
+    /// use intmap_rs::ColumnIndex;
+    ///
+    /// let mut index = ColumnIndex::with_capacity(8);
+    /// index.insert(7u32).unwrap();
+    /// assert_eq!((index.row(7), index.row(8)), (Some(0), None));
+    /// ```
+    pub fn row(&self, key: K) -> Option<usize> {
+        self.rows.get(key).map(|&row| row as usize)
+    }
+
+    /// Key stored at `row`.
+    ///
+    /// ```
+    /// use intmap_rs::ColumnIndex;
+    ///
+    /// let mut index = ColumnIndex::with_capacity(8);
+    /// index.insert(7u32).unwrap();
+    /// assert_eq!((index.key(0), index.key(1)), (Some(7), None));
+    /// ```
+    pub fn key(&self, row: usize) -> Option<K> {
+        self.keys.get(row).copied()
+    }
+
+    /// The keys in row order, parallel to the column.
+    ///
+    /// ```
+    /// use intmap_rs::ColumnIndex;
+    ///
+    /// let mut index = ColumnIndex::with_capacity(8);
+    /// for id in [7u32, 3, 9] {
+    ///     index.insert(id).unwrap();
+    /// }
+    /// assert_eq!(index.keys(), [7, 3, 9]);
+    /// ```
+    pub fn keys(&self) -> &[K] {
+        &self.keys
+    }
+
+    /// Value of `key` in `column`, which must be the column kept in step with this index.
+    ///
+    /// ```
+    /// use intmap_rs::ColumnIndex;
+    ///
+    /// let mut index = ColumnIndex::with_capacity(8);
+    /// let mut hp = Vec::new();
+    /// index.insert(7u32).unwrap();
+    /// hp.push(100);
+    /// assert_eq!((index.get(&hp, 7), index.get(&hp, 8)), (Some(&100), None));
+    /// ```
+    pub fn get<'a, V>(&self, column: &'a [V], key: K) -> Option<&'a V> {
+        column.get(self.row(key)?)
+    }
+
+    /// Same as `get`, mutably.
+    ///
+    /// ```
+    /// use intmap_rs::ColumnIndex;
+    ///
+    /// let mut index = ColumnIndex::with_capacity(8);
+    /// let mut hp = Vec::new();
+    /// index.insert(7u32).unwrap();
+    /// hp.push(100);
+    /// *index.get_mut(&mut hp, 7).unwrap() -= 30;
+    /// assert_eq!(hp, [70]);
+    /// ```
+    pub fn get_mut<'a, V>(&self, column: &'a mut [V], key: K) -> Option<&'a mut V> {
+        column.get_mut(self.row(key)?)
+    }
+
+    /// Whether `key` has a row.
+    ///
+    /// ```
+    /// use intmap_rs::ColumnIndex;
+    ///
+    /// let mut index = ColumnIndex::with_capacity(8);
+    /// index.insert(7u32).unwrap();
+    /// assert!(index.contains(7) && !index.contains(8));
+    /// ```
+    pub fn contains(&self, key: K) -> bool {
+        self.rows.contains(key)
+    }
+
+    /// Number of rows.
+    ///
+    /// ```
+    /// use intmap_rs::ColumnIndex;
+    ///
+    /// let mut index = ColumnIndex::with_capacity(8);
+    /// index.insert(7u32).unwrap();
+    /// index.insert(3).unwrap();
+    /// assert_eq!(index.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Whether there are no rows.
+    ///
+    /// ```
+    /// use intmap_rs::ColumnIndex;
+    ///
+    /// let mut index = ColumnIndex::with_capacity(8);
+    /// assert!(index.is_empty());
+    /// index.insert(7u32).unwrap();
+    /// assert!(!index.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Number of home slots of the table, see `IntMap::capacity`.
+    ///
+    /// ```
+    /// use intmap_rs::ColumnIndex;
+    ///
+    /// assert_eq!(ColumnIndex::<u64>::with_capacity(16).capacity(), 16);
+    /// ```
+    pub fn capacity(&self) -> usize {
+        self.rows.capacity()
+    }
+
+    /// Removes every key, the caller clears the column.
+    ///
+    /// ```
+    /// use intmap_rs::ColumnIndex;
+    ///
+    /// let mut index = ColumnIndex::with_capacity(8);
+    /// let mut column = vec!['a'];
+    /// index.insert(7u32).unwrap();
+    /// index.clear();
+    /// column.clear();
+    /// assert!(index.is_empty() && index.row(7).is_none());
+    /// ```
+    pub fn clear(&mut self) {
+        self.rows.clear();
+        self.keys.clear();
+    }
+}
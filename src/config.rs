@@ -0,0 +1,188 @@
+use crate::{Capacity, SaturationPolicy, MAX_PROBES};
+
+/// How `Config::capacity` becomes the number of home slots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CapacityPolicy {
+    /// Rounded up to a power of two, keys are indexed by their low bits as in `with_capacity`.
+    #[default]
+    PowerOfTwo,
+    /// Exactly the requested slots, keys are mixed with the seed as in `with_exact_capacity`.
+    Exact,
+}
+
+/// Construction parameters of an `IntMap`, see `IntMap::with_config`. The same config always
+/// yields the same table layout for the same inserts, so a tuned map can be reproduced.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    pub(crate) capacity: u32,
+    pub(crate) capacity_policy: CapacityPolicy,
+    pub(crate) seed: u64,
+    pub(crate) probe_limit: u8,
+    pub(crate) saturation: SaturationPolicy,
+}
+
+impl Config {
+    /// A config for `capacity` home slots with the defaults of `IntMap::with_capacity`.
+    ///
+    /// ```
+    /// use intmap_rs::{Config, IntMap, MAX_PROBES};
+    ///
+    /// let m = IntMap::<u32, ()>::with_config(&Config::new(100));
+    /// assert_eq!((m.capacity(), m.probe_limit()), (128, MAX_PROBES as usize));
+    /// ```
+    pub fn new(capacity: impl Into<Capacity>) -> Self {
+        Self {
+            capacity: capacity.into().get(),
+            capacity_policy: CapacityPolicy::default(),
+            seed: 0,
+            probe_limit: MAX_PROBES,
+            saturation: SaturationPolicy::default(),
+        }
+    }
+
+    /// Sets the requested number of home slots.
+    ///
+    /// ```
+    /// use intmap_rs::Config;
+    ///
+    /// assert_eq!(Config::new(8).capacity(64), Config::new(64));
+    /// ```
+    pub fn capacity(mut self, capacity: impl Into<Capacity>) -> Self {
+        self.capacity = capacity.into().get();
+        self
+    }
+
+    /// Sets how the capacity is rounded and keys are indexed.
+    ///
+    /// ```
+    /// use intmap_rs::{CapacityPolicy, Config, IntMap};
+    ///
+    /// let config = Config::new(100).capacity_policy(CapacityPolicy::Exact);
+    /// assert_eq!(IntMap::<u32, ()>::with_config(&config).capacity(), 100);
+    /// ```
+    pub fn capacity_policy(mut self, policy: CapacityPolicy) -> Self {
+        self.capacity_policy = policy;
+        self
+    }
+
+    /// Seed of the key mixer, only `CapacityPolicy::Exact` mixes keys. Changing it reshuffles
+    /// which keys share home slots, e.g. to break up a bad input pattern.
+    ///
+    /// ```
+    /// use intmap_rs::{CapacityPolicy, Config, IntMap};
+    ///
+    /// let exact = Config::new(64).capacity_policy(CapacityPolicy::Exact);
+    /// let (mut a, mut b) = (IntMap::with_config(&exact), IntMap::with_config(&exact.seed(7)));
+    /// for k in 0..32u32 {
+    ///     a.insert(k, ());
+    ///     b.insert(k, ());
+    /// }
+    /// assert!(a == b);
+    /// assert_ne!(a.keys_to_vec(), b.keys_to_vec());
+    /// ```
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// See `IntMap::with_probe_limit`, clamped to `2..=127`.
+    ///
+    /// ```
+    /// use intmap_rs::{Config, IntMap};
+    ///
+    /// let m = IntMap::<u32, ()>::with_config(&Config::new(16).probe_limit(8));
+    /// assert_eq!((m.probe_limit(), m.slots().get()), (8, 24));
+    /// let m = IntMap::<u32, ()>::with_config(&Config::new(16).probe_limit(0));
+    /// assert_eq!(m.probe_limit(), 2);
+    /// ```
+    pub fn probe_limit(mut self, probe_limit: u8) -> Self {
+        self.probe_limit = probe_limit;
+        self
+    }
+
+    /// What inserts do once the map is full; maps never grow, see `SaturationPolicy`.
+    ///
+    /// ```
+    /// use intmap_rs::{Config, IntMap, SaturationPolicy};
+    ///
+    /// let mut m = IntMap::with_config(&Config::new(1).saturation(SaturationPolicy::Reject));
+    /// m.insert(1u32, ());
+    /// assert!(m.try_insert(2, ()).is_err());
+    /// ```
+    pub fn saturation(mut self, policy: SaturationPolicy) -> Self {
+        self.saturation = policy;
+        self
+    }
+
+    /// Overrides the fields set in the environment as `{prefix}_CAPACITY`,
+    /// `{prefix}_CAPACITY_POLICY` (`pow2` or `exact`), `{prefix}_SEED` (decimal or `0x` hex),
+    /// `{prefix}_PROBE_LIMIT` and `{prefix}_SATURATION` (`overflow`, `reject`, `evict` or
+    /// `suggest:<max load percent>`), so a deployment can tune a map without rebuilding. Unset
+    /// variables keep the current value.
+    ///
+    /// ```
+    /// use intmap_rs::{Config, IntMap};
+    ///
+    /// std::env::set_var("DOC_SESSIONS_CAPACITY", "4096");
+    /// std::env::set_var("DOC_SESSIONS_SATURATION", "reject");
+    /// let config = Config::new(64).override_from_env("DOC_SESSIONS").unwrap();
+    /// assert_eq!(IntMap::<u64, ()>::with_config(&config).capacity(), 4096);
+    ///
+    /// std::env::set_var("DOC_SESSIONS_SEED", "0xzz");
+    /// let err = Config::new(64).override_from_env("DOC_SESSIONS").unwrap_err();
+    /// assert_eq!(err.name(), "DOC_SESSIONS_SEED");
+    /// ```
+    #[cfg(feature = "env")]
+    pub fn override_from_env(self, prefix: &str) -> Result<Self, crate::ConfigError> {
+        self.override_from(|suffix| {
+            let name = format!("{prefix}_{suffix}");
+            std::env::var(&name).ok().map(|value| (name, value))
+        })
+    }
+
+    // 'lookup' returns the variable's full name and its value if it's set
+    #[cfg(feature = "env")]
+    pub(crate) fn override_from(
+        mut self,
+        lookup: impl Fn(&str) -> Option<(String, String)>,
+    ) -> Result<Self, crate::ConfigError> {
+        fn parse<T>(
+            (name, value): (String, String),
+            f: impl FnOnce(&str) -> Option<T>,
+        ) -> Result<T, crate::ConfigError> {
+            f(value.trim()).ok_or(crate::ConfigError { name, value })
+        }
+
+        if let Some(var) = lookup("CAPACITY") {
+            self.capacity = parse(var, |v| v.parse().ok())?;
+        }
+        if let Some(var) = lookup("CAPACITY_POLICY") {
+            self.capacity_policy = parse(var, |v| match v {
+                "pow2" => Some(CapacityPolicy::PowerOfTwo),
+                "exact" => Some(CapacityPolicy::Exact),
+                _ => None,
+            })?;
+        }
+        if let Some(var) = lookup("SEED") {
+            self.seed = parse(var, |v| match v.strip_prefix("0x") {
+                Some(hex) => u64::from_str_radix(hex, 16).ok(),
+                None => v.parse().ok(),
+            })?;
+        }
+        if let Some(var) = lookup("PROBE_LIMIT") {
+            self.probe_limit = parse(var, |v| v.parse().ok())?;
+        }
+        if let Some(var) = lookup("SATURATION") {
+            self.saturation = parse(var, |v| match v {
+                "overflow" => Some(SaturationPolicy::Overflow),
+                "reject" => Some(SaturationPolicy::Reject),
+                "evict" => Some(SaturationPolicy::Evict),
+                _ => {
+                    let percent = v.strip_prefix("suggest:")?.parse().ok()?;
+                    Some(SaturationPolicy::SuggestEviction { max_load_percent: percent })
+                }
+            })?;
+        }
+        Ok(self)
+    }
+}
@@ -0,0 +1,296 @@
+use crate::{Capacity, IntKey, IntMap};
+
+/// An `IntMap` whose deletes can be rolled back: `remove_deferred` only marks the entry dead,
+/// hiding it from reads, until it's either `restore`d or dropped by `purge` together with all
+/// other dead entries, e.g. the abort and commit of a two-phase commit.
+///
+/// Dead entries keep their slots, so they still count against the capacity.
+pub struct DeferredIntMap<K, V> {
+    // (dead, value)
+    map: IntMap<K, (bool, V)>,
+    dead: usize,
+}
+
+impl<K: IntKey, V> DeferredIntMap<K, V> {
+    /// An empty map of `capacity` home slots rounded up to a power of two.
+    ///
+    /// ```
+    /// use intmap_rs::DeferredIntMap;
+    ///
+    /// let m = DeferredIntMap::<u32, ()>::with_capacity(8);
+    /// assert!(m.is_empty() && m.dead_count() == 0);
+    /// ```
+    pub fn with_capacity(capacity: impl Into<Capacity>) -> Self {
+        Self { map: IntMap::with_capacity(capacity), dead: 0 }
+    }
+
+    /// Inserts a live entry, returns the previous live value. A dead entry for the key is
+    /// dropped and can't be restored anymore.
+    ///
+    /// ```
+    /// use intmap_rs::DeferredIntMap;
+    ///
+    /// let mut m = DeferredIntMap::with_capacity(8);
+    /// assert_eq!(m.insert(1u32, 'a'), None);
+    /// m.remove_deferred(1);
+    /// assert_eq!(m.insert(1, 'b'), None);
+    /// assert!(!m.restore(1) && m.get(1) == Some(&'b'));
+    /// ```
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let (dead, old) = self.map.insert(key, (false, value))?;
+        if dead {
+            self.dead -= 1;
+            return None;
+        }
+        Some(old)
+    }
+
+    /// Marks the entry dead, returns false if there's no live entry for the key.
+    ///
+    /// ```
+    /// use intmap_rs::DeferredIntMap;
+    ///
+    /// let mut m = DeferredIntMap::with_capacity(8);
+    /// m.insert(1u32, 'a');
+    /// assert!(m.remove_deferred(1) && !m.remove_deferred(1));
+    /// assert!(m.get(1).is_none() && m.is_dead(1));
+    /// ```
+    pub fn remove_deferred(&mut self, key: K) -> bool {
+        match self.map.get_mut(key) {
+            Some((dead @ false, _)) => {
+                *dead = true;
+                self.dead += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Brings a dead entry back, returns false if the key has no dead entry.
+    ///
+    /// ```
+    /// use intmap_rs::DeferredIntMap;
+    ///
+    /// let mut m = DeferredIntMap::with_capacity(8);
+    /// m.insert(1u32, 'a');
+    /// m.remove_deferred(1);
+    /// assert!(m.restore(1) && !m.restore(1));
+    /// assert_eq!(m.get(1), Some(&'a'));
+    /// ```
+    pub fn restore(&mut self, key: K) -> bool {
+        match self.map.get_mut(key) {
+            Some((dead @ true, _)) => {
+                *dead = false;
+                self.dead -= 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Brings back every dead entry.
+    ///
+    /// ```
+    /// use intmap_rs::DeferredIntMap;
+    ///
+    /// let mut m = DeferredIntMap::with_capacity(8);
+    /// for k in 0..3u32 {
+    ///     m.insert(k, k);
+    ///     m.remove_deferred(k);
+    /// }
+    /// m.restore_all();
+    /// assert_eq!((m.len(), m.dead_count()), (3, 0));
+    /// ```
+    pub fn restore_all(&mut self) {
+        if self.dead > 0 {
+            self.map.retain(|_, (dead, _)| {
+                *dead = false;
+                true
+            });
+            self.dead = 0;
+        }
+    }
+
+    /// Drops all dead entries, returns how many there were.
+    ///
+    /// ```
+    /// use intmap_rs::DeferredIntMap;
+    ///
+    /// let mut m = DeferredIntMap::with_capacity(8);
+    /// m.insert(1u32, 'a');
+    /// m.insert(2, 'b');
+    /// m.remove_deferred(1);
+    /// assert_eq!(m.purge(), 1);
+    /// assert!(!m.is_dead(1) && !m.restore(1) && m.len() == 1);
+    /// ```
+    pub fn purge(&mut self) -> usize {
+        let purged = self.dead;
+        if purged > 0 {
+            self.map.retain(|_, (dead, _)| !*dead);
+            self.dead = 0;
+        }
+        purged
+    }
+
+    /// Removes the entry right away, dead or alive; returns the value only if it was alive.
+    ///
+    /// ```
+    /// use intmap_rs::DeferredIntMap;
+    ///
+    /// let mut m = DeferredIntMap::with_capacity(8);
+    /// m.insert(1u32, 'a');
+    /// m.insert(2, 'b');
+    /// m.remove_deferred(2);
+    /// assert_eq!((m.remove(1), m.remove(2)), (Some('a'), None));
+    /// assert_eq!(m.dead_count(), 0);
+    /// ```
+    pub fn remove(&mut self, key: K) -> Option<V> {
+        let (dead, value) = self.map.remove(key)?;
+        if dead {
+            self.dead -= 1;
+            return None;
+        }
+        Some(value)
+    }
+
+    /// The live value stored for `key`, if any.
+    ///
+    /// ```
+    /// use intmap_rs::DeferredIntMap;
+    ///
+    /// let mut m = DeferredIntMap::with_capacity(8);
+    /// m.insert(1u32, 'a');
+    /// assert_eq!(m.get(1), Some(&'a'));
+    /// m.remove_deferred(1);
+    /// assert_eq!(m.get(1), None);
+    /// ```
+    pub fn get(&self, key: K) -> Option<&V> {
+        match self.map.get(key) {
+            Some((false, value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// The live value stored for `key`, mutably, if any.
+    ///
+    /// ```
+    /// use intmap_rs::DeferredIntMap;
+    ///
+    /// let mut m = DeferredIntMap::with_capacity(8);
+    /// m.insert(1u32, 10);
+    /// *m.get_mut(1).unwrap() += 1;
+    /// m.remove_deferred(1);
+    /// assert!(m.get_mut(1).is_none());
+    /// m.restore(1);
+    /// assert_eq!(m.get(1), Some(&11));
+    /// ```
+    pub fn get_mut(&mut self, key: K) -> Option<&mut V> {
+        match self.map.get_mut(key) {
+            Some((false, value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Whether a live entry is stored for `key`.
+    ///
+    /// ```
+    /// use intmap_rs::DeferredIntMap;
+    ///
+    /// let mut m = DeferredIntMap::with_capacity(8);
+    /// m.insert(1u32, ());
+    /// m.insert(2, ());
+    /// m.remove_deferred(2);
+    /// assert!(m.contains(1) && !m.contains(2));
+    /// ```
+    pub fn contains(&self, key: K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Whether the key's entry is marked dead, see `remove_deferred`.
+    ///
+    /// ```
+    /// use intmap_rs::DeferredIntMap;
+    ///
+    /// let mut m = DeferredIntMap::with_capacity(8);
+    /// m.insert(1u32, ());
+    /// assert!(!m.is_dead(1));
+    /// m.remove_deferred(1);
+    /// assert!(m.is_dead(1) && !m.is_dead(2));
+    /// ```
+    pub fn is_dead(&self, key: K) -> bool {
+        matches!(self.map.get(key), Some((true, _)))
+    }
+
+    /// Number of live entries.
+    ///
+    /// ```
+    /// use intmap_rs::DeferredIntMap;
+    ///
+    /// let mut m = DeferredIntMap::with_capacity(8);
+    /// m.insert(1u32, ());
+    /// m.insert(2, ());
+    /// m.remove_deferred(2);
+    /// assert_eq!(m.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.map.len() - self.dead
+    }
+
+    /// Whether the map holds no live entries.
+    ///
+    /// ```
+    /// use intmap_rs::DeferredIntMap;
+    ///
+    /// let mut m = DeferredIntMap::with_capacity(8);
+    /// m.insert(1u32, ());
+    /// m.remove_deferred(1);
+    /// assert!(m.is_empty() && m.dead_count() == 1);
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of dead entries waiting for `purge` or `restore`.
+    ///
+    /// ```
+    /// use intmap_rs::DeferredIntMap;
+    ///
+    /// let mut m = DeferredIntMap::with_capacity(8);
+    /// m.insert(1u32, ());
+    /// m.remove_deferred(1);
+    /// assert_eq!(m.dead_count(), 1);
+    /// ```
+    pub fn dead_count(&self) -> usize {
+        self.dead
+    }
+
+    /// Live entries in storage order.
+    ///
+    /// ```
+    /// use intmap_rs::DeferredIntMap;
+    ///
+    /// let mut m = DeferredIntMap::with_capacity(8);
+    /// m.insert(1u32, 'a');
+    /// m.insert(2, 'b');
+    /// m.remove_deferred(1);
+    /// assert_eq!(m.iter().collect::<Vec<_>>(), [(2, &'b')]);
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = (K, &V)> {
+        self.map.iter().filter(|(_, (dead, _))| !dead).map(|(k, (_, v))| (*k, v))
+    }
+
+    /// Dead entries in storage order.
+    ///
+    /// ```
+    /// use intmap_rs::DeferredIntMap;
+    ///
+    /// let mut m = DeferredIntMap::with_capacity(8);
+    /// m.insert(1u32, 'a');
+    /// m.insert(2, 'b');
+    /// m.remove_deferred(1);
+    /// assert_eq!(m.iter_dead().collect::<Vec<_>>(), [(1, &'a')]);
+    /// ```
+    pub fn iter_dead(&self) -> impl Iterator<Item = (K, &V)> {
+        self.map.iter().filter(|(_, (dead, _))| *dead).map(|(k, (_, v))| (*k, v))
+    }
+}
@@ -0,0 +1,167 @@
+use crate::IntMap;
+use std::fmt::{self, Debug, Display};
+
+/// Reason an insertion was rejected, hands the rejected entry back to the caller.
+#[derive(PartialEq, Eq)]
+#[non_exhaustive]
+pub enum InsertError<K, V> {
+    /// The key is configured as reserved with `IntMap::set_reserved_key`.
+    ReservedKey(K, V),
+    /// The map is full and its policy is `SaturationPolicy::Reject`.
+    Full(K, V),
+    /// The map is at the load limit of `SaturationPolicy::SuggestEviction`, the keys are
+    /// candidates for making room, see `IntMap::eviction_candidates`.
+    EvictionNeeded(K, V, Vec<K>),
+}
+
+impl<K, V> InsertError<K, V> {
+    /// The rejected entry.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// m.set_reserved_key(Some(0u32));
+    /// let err = m.try_insert(0, "zero").unwrap_err();
+    /// assert_eq!(err.to_string(), "key 0 is reserved");
+    /// assert_eq!(err.into_entry(), (0, "zero"));
+    /// ```
+    pub fn into_entry(self) -> (K, V) {
+        match self {
+            InsertError::ReservedKey(key, value)
+            | InsertError::Full(key, value)
+            | InsertError::EvictionNeeded(key, value, _) => (key, value),
+        }
+    }
+}
+
+impl<K: Display, V> Display for InsertError<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InsertError::ReservedKey(key, _) => write!(f, "key {key} is reserved"),
+            InsertError::Full(key, _) => write!(f, "map is full, key {key} rejected"),
+            InsertError::EvictionNeeded(key, ..) => {
+                write!(f, "map is at its load limit, key {key} rejected")
+            }
+        }
+    }
+}
+
+impl<K: Display, V> Debug for InsertError<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InsertError::ReservedKey(key, _) => write!(f, "ReservedKey({key}, ..)"),
+            InsertError::Full(key, _) => write!(f, "Full({key}, ..)"),
+            InsertError::EvictionNeeded(key, _, candidates) => {
+                write!(f, "EvictionNeeded({key}, .., {} candidates)", candidates.len())
+            }
+        }
+    }
+}
+
+impl<K: Display, V> std::error::Error for InsertError<K, V> {}
+
+/// Reason `IntMapLoader::load` stopped.
+#[derive(Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LoadError<K> {
+    /// The input repeats the key and the policy is `DuplicatePolicy::Fail`.
+    DuplicateKey(K),
+}
+
+impl<K: Display> Display for LoadError<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::DuplicateKey(key) => write!(f, "duplicate key {key}"),
+        }
+    }
+}
+
+impl<K: Debug + Display> std::error::Error for LoadError<K> {}
+
+/// Reason `IntMap::convert_keys` failed: a key doesn't fit the target key type. Hands the
+/// unchanged map back.
+pub struct KeyOverflow<K, V> {
+    pub(crate) key: K,
+    // boxed, the shadow feature makes maps large
+    pub(crate) map: Box<IntMap<K, V>>,
+}
+
+impl<K: Copy, V> KeyOverflow<K, V> {
+    /// First key found not to fit.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// m.insert(1u64 << 40, ());
+    /// let err = m.convert_keys::<u32>().err().unwrap();
+    /// assert_eq!(err.key(), 1 << 40);
+    /// ```
+    pub fn key(&self) -> K {
+        self.key
+    }
+
+    /// The map `convert_keys` was called on, unchanged.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// m.insert(1u64 << 33, 'x');
+    /// let m = m.convert_keys::<u32>().err().unwrap().into_map();
+    /// assert_eq!(m.get(1 << 33), Some(&'x'));
+    /// ```
+    pub fn into_map(self) -> IntMap<K, V> {
+        *self.map
+    }
+}
+
+impl<K: Display, V> Display for KeyOverflow<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "key {} doesn't fit the target key type", self.key)
+    }
+}
+
+impl<K: Display, V> Debug for KeyOverflow<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "KeyOverflow({}, ..)", self.key)
+    }
+}
+
+impl<K: Display, V> std::error::Error for KeyOverflow<K, V> {}
+
+/// An environment variable read by `Config::override_from_env` holds an invalid value.
+#[cfg(feature = "env")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigError {
+    pub(crate) name: String,
+    pub(crate) value: String,
+}
+
+#[cfg(feature = "env")]
+impl ConfigError {
+    /// Name of the variable, prefix included.
+    ///
+    /// ```
+    /// use intmap_rs::Config;
+    ///
+    /// std::env::set_var("DOC_ERROR_PROBE_LIMIT", "many");
+    /// let err = Config::new(8).override_from_env("DOC_ERROR").unwrap_err();
+    /// assert_eq!(err.name(), "DOC_ERROR_PROBE_LIMIT");
+    /// assert_eq!(err.to_string(), r#"invalid value "many" for DOC_ERROR_PROBE_LIMIT"#);
+    /// ```
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[cfg(feature = "env")]
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid value {:?} for {}", self.value, self.name)
+    }
+}
+
+#[cfg(feature = "env")]
+impl std::error::Error for ConfigError {}
@@ -0,0 +1,313 @@
+use crate::{private, Capacity, IntMap};
+
+/// Wide and composite key types a `FingerprintIntMap` can hold: 128-bit integers, arrays of
+/// `u64` or `u32` and pairs of them, e.g. `[u64; 2]` or `(u64, u32)` ids.
+///
+/// ```
+/// use intmap_rs::FingerprintIntMap;
+///
+/// let mut components = FingerprintIntMap::with_capacity(64);
+/// components.insert((42u64, 3u32), "transform");
+/// assert_eq!(components.get((42, 3)), Some(&"transform"));
+/// assert_eq!(components.get((3, 42)), None);
+/// ```
+pub trait WideKey: Copy + Eq + private::SealedKey {
+    /// 64 bits each of which depends on all of the key's bits.
+    ///
+    /// ```
+    /// use intmap_rs::WideKey;
+    ///
+    /// assert_ne!((1u128 << 64).fingerprint(), (2u128 << 64).fingerprint());
+    /// assert_ne!([1u64, 2].fingerprint(), [2u64, 1].fingerprint());
+    /// ```
+    fn fingerprint(self) -> u64;
+}
+
+impl WideKey for u128 {
+    #[inline]
+    fn fingerprint(self) -> u64 {
+        // low half as is, so sequential keys get sequential fingerprints, and the high half
+        // spread over it
+        (self as u64) ^ ((self >> 64) as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15)
+    }
+}
+
+impl WideKey for i128 {
+    #[inline]
+    fn fingerprint(self) -> u64 {
+        (self as u128).fingerprint()
+    }
+}
+
+// The first word as is like the low half of a u128, the others folded together and run
+// through the splitmix64 finalizer, so keys whose later words differ only in their high bits
+// still get different home slots under the mask the table indexes by.
+#[inline]
+fn fold(first: u64, rest: impl IntoIterator<Item = u64>) -> u64 {
+    let mut acc = 0u64;
+    for word in rest {
+        acc = (acc ^ word).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    }
+    acc = (acc ^ (acc >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    acc = (acc ^ (acc >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    first ^ acc ^ (acc >> 31)
+}
+
+macro_rules! array_keys {
+    ($($word:ty)+) => {$(
+        impl<const N: usize> private::SealedKey for [$word; N] {}
+
+        impl<const N: usize> WideKey for [$word; N] {
+            #[inline]
+            fn fingerprint(self) -> u64 {
+                let first = self.first().map_or(0, |&word| word as u64);
+                fold(first, self.iter().skip(1).map(|&word| word as u64))
+            }
+        }
+    )+};
+}
+
+macro_rules! pair_keys {
+    ($(($a:ty, $b:ty))+) => {$(
+        impl private::SealedKey for ($a, $b) {}
+
+        impl WideKey for ($a, $b) {
+            #[inline]
+            fn fingerprint(self) -> u64 {
+                fold(self.0 as u64, [self.1 as u64])
+            }
+        }
+    )+};
+}
+
+array_keys!(u64 u32);
+pair_keys!((u64, u64)(u64, u32)(u32, u64)(u32, u32));
+
+const NONE: u32 = u32::MAX;
+
+struct Entry<K, V> {
+    key: K,
+    value: V,
+    // next entry with the same fingerprint
+    next: u32,
+}
+
+/// A map of 128-bit or composite keys, e.g. UUIDs or `(entity, component)` pairs, that probes
+/// 64-bit fingerprints instead of whole keys.
+///
+/// The table stores each key's fingerprint with the position of its entry in a dense side
+/// array holding the full key and the value. A lookup probes the fingerprints and compares
+/// one full key per match, so it reads half the key bytes of an `IntMap<u128, V>`. Keys with
+/// the same fingerprint are chained from a single table entry, so even then every key is
+/// told apart correctly.
+pub struct FingerprintIntMap<K, V> {
+    table: IntMap<u64, u32>,
+    entries: Vec<Entry<K, V>>,
+}
+
+impl<K: WideKey, V> FingerprintIntMap<K, V> {
+    /// An empty map of `capacity` home slots rounded up to a power of two, with room for as many
+    /// entries in the side array.
+    ///
+    /// ```
+    /// use intmap_rs::FingerprintIntMap;
+    ///
+    /// let m = FingerprintIntMap::<u128, ()>::with_capacity(64);
+    /// assert!(m.is_empty());
+    /// ```
+    pub fn with_capacity(capacity: impl Into<Capacity>) -> Self {
+        let capacity = capacity.into().get();
+        Self {
+            table: IntMap::with_capacity(capacity),
+            entries: Vec::with_capacity(capacity as usize),
+        }
+    }
+
+    // position of the key's entry in 'entries'
+    fn find(&self, key: K) -> Option<usize> {
+        let mut at = *self.table.get(key.fingerprint())?;
+        while at != NONE {
+            let entry = &self.entries[at as usize];
+            if entry.key == key {
+                return Some(at as usize);
+            }
+            at = entry.next;
+        }
+        None
+    }
+
+    /// Inserts the entry, returns the value it replaced, if any. Panics if the table is full or
+    /// the side array reaches `u32::MAX` entries.
+    ///
+    /// ```
+    /// use intmap_rs::FingerprintIntMap;
+    ///
+    /// let mut m = FingerprintIntMap::with_capacity(8);
+    /// let id = 0x6ba7_b810_9dad_11d1_80b4_00c0_4fd4_30c8u128;
+    /// assert_eq!(m.insert(id, "a"), None);
+    /// assert_eq!(m.insert(id, "b"), Some("a"));
+    /// ```
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(at) = self.find(key) {
+            return Some(std::mem::replace(&mut self.entries[at].value, value));
+        }
+        let at = u32::try_from(self.entries.len()).ok().filter(|&at| at != NONE);
+        let at = at.expect("too many entries");
+        // a new entry heads its fingerprint's chain
+        let next = self.table.insert(key.fingerprint(), at).unwrap_or(NONE);
+        self.entries.push(Entry { key, value, next });
+        None
+    }
+
+    /// The value stored for `key`, if any.
+    ///
+    /// ```
+    /// use intmap_rs::FingerprintIntMap;
+    ///
+    /// let mut m = FingerprintIntMap::with_capacity(8);
+    /// m.insert([1u64, 2], 'x');
+    /// assert_eq!((m.get([1, 2]), m.get([2, 1])), (Some(&'x'), None));
+    /// ```
+    pub fn get(&self, key: K) -> Option<&V> {
+        self.find(key).map(|at| &self.entries[at].value)
+    }
+
+    /// The value stored for `key`, mutably, if any.
+    ///
+    /// ```
+    /// use intmap_rs::FingerprintIntMap;
+    ///
+    /// let mut m = FingerprintIntMap::with_capacity(8);
+    /// m.insert((7u64, 1u32), 0);
+    /// *m.get_mut((7, 1)).unwrap() += 1;
+    /// assert_eq!(m.get((7, 1)), Some(&1));
+    /// ```
+    pub fn get_mut(&mut self, key: K) -> Option<&mut V> {
+        self.find(key).map(|at| &mut self.entries[at].value)
+    }
+
+    /// Whether an entry is stored for `key`.
+    ///
+    /// ```
+    /// use intmap_rs::FingerprintIntMap;
+    ///
+    /// let mut m = FingerprintIntMap::with_capacity(8);
+    /// m.insert(u128::MAX, ());
+    /// assert!(m.contains(u128::MAX) && !m.contains(0));
+    /// ```
+    pub fn contains(&self, key: K) -> bool {
+        self.find(key).is_some()
+    }
+
+    /// Removes the entry, returns its value if the key was present. The last entry of the side
+    /// array moves into its place.
+    ///
+    /// ```
+    /// use intmap_rs::FingerprintIntMap;
+    ///
+    /// let mut m = FingerprintIntMap::with_capacity(8);
+    /// m.insert(1u128, 'a');
+    /// m.insert(2, 'b');
+    /// assert_eq!((m.remove(1), m.remove(1)), (Some('a'), None));
+    /// assert_eq!(m.get(2), Some(&'b'));
+    /// ```
+    pub fn remove(&mut self, key: K) -> Option<V> {
+        let at = self.find(key)?;
+        let next = self.entries[at].next;
+        self.relink(key.fingerprint(), at as u32, next);
+        // the last entry moves into the hole
+        let last = (self.entries.len() - 1) as u32;
+        if at as u32 != last {
+            let moved = self.entries[last as usize].key;
+            self.relink(moved.fingerprint(), last, at as u32);
+        }
+        Some(self.entries.swap_remove(at).value)
+    }
+
+    // points the link that refers to entry 'from' in the chain of 'fingerprint' at 'to'
+    fn relink(&mut self, fingerprint: u64, from: u32, to: u32) {
+        let head = self.table.get_mut(fingerprint).unwrap();
+        if *head == from {
+            if to == NONE {
+                self.table.remove(fingerprint);
+            } else {
+                *head = to;
+            }
+            return;
+        }
+        let mut at = *head;
+        while self.entries[at as usize].next != from {
+            at = self.entries[at as usize].next;
+        }
+        self.entries[at as usize].next = to;
+    }
+
+    /// Number of entries stored.
+    ///
+    /// ```
+    /// use intmap_rs::FingerprintIntMap;
+    ///
+    /// let mut m = FingerprintIntMap::with_capacity(8);
+    /// m.insert(1u128, ());
+    /// m.insert(2, ());
+    /// assert_eq!(m.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the map holds no entries.
+    ///
+    /// ```
+    /// use intmap_rs::FingerprintIntMap;
+    ///
+    /// let mut m = FingerprintIntMap::with_capacity(8);
+    /// assert!(m.is_empty());
+    /// m.insert(1u128, ());
+    /// assert!(!m.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Removes every entry, keeping the capacity.
+    ///
+    /// ```
+    /// use intmap_rs::FingerprintIntMap;
+    ///
+    /// let mut m = FingerprintIntMap::with_capacity(8);
+    /// m.insert([1u32, 2, 3], ());
+    /// m.clear();
+    /// assert!(m.is_empty() && !m.contains([1, 2, 3]));
+    /// ```
+    pub fn clear(&mut self) {
+        self.table.clear();
+        self.entries.clear();
+    }
+
+    /// Entries in insertion order, as long as nothing was removed.
+    ///
+    /// ```
+    /// use intmap_rs::FingerprintIntMap;
+    ///
+    /// let mut m = FingerprintIntMap::with_capacity(8);
+    /// m.insert(20u128, 'b');
+    /// m.insert(10, 'a');
+    /// assert_eq!(m.iter().collect::<Vec<_>>(), [(20, &'b'), (10, &'a')]);
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = (K, &V)> {
+        self.entries.iter().map(|entry| (entry.key, &entry.value))
+    }
+
+    /// Bytes taken by the table and the side array.
+    ///
+    /// ```
+    /// use intmap_rs::{FingerprintIntMap, IntMap};
+    ///
+    /// let m = FingerprintIntMap::<u128, u64>::with_capacity(64);
+    /// assert!(m.memory_usage() > IntMap::<u64, u32>::with_capacity(64).memory_usage());
+    /// ```
+    pub fn memory_usage(&self) -> usize {
+        self.table.memory_usage() + self.entries.capacity() * std::mem::size_of::<Entry<K, V>>()
+    }
+}
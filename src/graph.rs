@@ -0,0 +1,262 @@
+use crate::{IntKey, IntMap};
+use std::collections::VecDeque;
+
+/// A directed graph stored as adjacency lists, node id → out-neighbors. Every node mentioned by
+/// an edge gets an entry, so the capacity bounds the number of distinct nodes.
+pub struct IntGraph<K> {
+    adjacency: IntMap<K, Vec<K>>,
+}
+
+impl<K: IntKey> IntGraph<K> {
+    /// An empty graph with room for `nodes` distinct nodes.
+    ///
+    /// ```
+    /// use intmap_rs::IntGraph;
+    ///
+    /// let g = IntGraph::<u32>::with_capacity(16);
+    /// assert_eq!((g.node_count(), g.edge_count()), (0, 0));
+    /// ```
+    pub fn with_capacity(nodes: u32) -> Self {
+        Self { adjacency: IntMap::with_capacity(nodes) }
+    }
+
+    /// Adds a node without edges, returns false if it's already present.
+    ///
+    /// ```
+    /// use intmap_rs::IntGraph;
+    ///
+    /// let mut g = IntGraph::with_capacity(16);
+    /// assert!(g.add_node(1u32) && !g.add_node(1));
+    /// assert_eq!(g.degree(1), 0);
+    /// ```
+    pub fn add_node(&mut self, node: K) -> bool {
+        let mut added = false;
+        self.adjacency.probe(node).or_insert_with(|| {
+            added = true;
+            Vec::new()
+        });
+        added
+    }
+
+    /// Adds the edge `from → to`, parallel edges are kept.
+    ///
+    /// ```
+    /// use intmap_rs::IntGraph;
+    ///
+    /// let mut g = IntGraph::with_capacity(16);
+    /// g.add_edge(1u32, 2);
+    /// g.add_edge(1, 2);
+    /// assert_eq!(g.neighbors(1), [2, 2]);
+    /// assert!(g.contains_node(2) && g.neighbors(2).is_empty());
+    /// ```
+    pub fn add_edge(&mut self, from: K, to: K) {
+        self.add_node(to);
+        self.adjacency.push_value(from, to);
+    }
+
+    /// Adds both `a → b` and `b → a`.
+    ///
+    /// ```
+    /// use intmap_rs::IntGraph;
+    ///
+    /// let mut g = IntGraph::with_capacity(16);
+    /// g.add_undirected_edge(1u32, 2);
+    /// g.add_undirected_edge(3, 3);
+    /// assert_eq!((g.neighbors(1), g.neighbors(2)), (&[2][..], &[1][..]));
+    /// assert_eq!(g.neighbors(3), [3]);
+    /// ```
+    pub fn add_undirected_edge(&mut self, a: K, b: K) {
+        self.add_edge(a, b);
+        if a != b {
+            self.add_edge(b, a);
+        }
+    }
+
+    /// Out-neighbors of `node` in insertion order, empty if the node is missing.
+    ///
+    /// ```
+    /// use intmap_rs::IntGraph;
+    ///
+    /// let mut g = IntGraph::with_capacity(16);
+    /// g.add_edge(1u32, 3);
+    /// g.add_edge(1, 2);
+    /// assert_eq!(g.neighbors(1), [3, 2]);
+    /// assert!(g.neighbors(9).is_empty());
+    /// ```
+    pub fn neighbors(&self, node: K) -> &[K] {
+        self.adjacency.values_of(node)
+    }
+
+    /// Out-degree of `node`.
+    ///
+    /// ```
+    /// use intmap_rs::IntGraph;
+    ///
+    /// let mut g = IntGraph::with_capacity(16);
+    /// g.add_edge(1u32, 2);
+    /// g.add_edge(1, 3);
+    /// assert_eq!((g.degree(1), g.degree(2)), (2, 0));
+    /// ```
+    pub fn degree(&self, node: K) -> usize {
+        self.neighbors(node).len()
+    }
+
+    /// Whether `node` was added, directly or as an edge endpoint.
+    ///
+    /// ```
+    /// use intmap_rs::IntGraph;
+    ///
+    /// let mut g = IntGraph::with_capacity(16);
+    /// g.add_edge(1u32, 2);
+    /// assert!(g.contains_node(1) && g.contains_node(2) && !g.contains_node(3));
+    /// ```
+    pub fn contains_node(&self, node: K) -> bool {
+        self.adjacency.contains(node)
+    }
+
+    /// Every node of the graph, in table order.
+    ///
+    /// ```
+    /// use intmap_rs::IntGraph;
+    ///
+    /// let mut g = IntGraph::with_capacity(16);
+    /// g.add_edge(1u32, 2);
+    /// g.add_node(5);
+    /// let mut nodes: Vec<_> = g.nodes().collect();
+    /// nodes.sort();
+    /// assert_eq!(nodes, [1, 2, 5]);
+    /// ```
+    pub fn nodes(&self) -> impl Iterator<Item = K> + '_ {
+        self.adjacency.keys().copied()
+    }
+
+    /// Number of distinct nodes.
+    ///
+    /// ```
+    /// use intmap_rs::IntGraph;
+    ///
+    /// let mut g = IntGraph::with_capacity(16);
+    /// g.add_edge(1u32, 2);
+    /// g.add_edge(2, 1);
+    /// assert_eq!(g.node_count(), 2);
+    /// ```
+    pub fn node_count(&self) -> usize {
+        self.adjacency.len()
+    }
+
+    /// Number of edges, parallel edges counted separately.
+    ///
+    /// ```
+    /// use intmap_rs::IntGraph;
+    ///
+    /// let mut g = IntGraph::with_capacity(16);
+    /// g.add_undirected_edge(1u32, 2);
+    /// g.add_edge(1, 2);
+    /// assert_eq!(g.edge_count(), 3);
+    /// ```
+    pub fn edge_count(&self) -> usize {
+        self.adjacency.values().map(Vec::len).sum()
+    }
+
+    /// Breadth-first traversal from `start`, yields every reachable node once, `start` first.
+    ///
+    /// ```
+    /// use intmap_rs::IntGraph;
+    ///
+    /// let mut g = IntGraph::with_capacity(16);
+    /// g.add_edge(1u32, 2);
+    /// g.add_edge(1, 3);
+    /// g.add_edge(2, 4);
+    /// g.add_edge(4, 1);
+    /// assert_eq!(g.bfs(1).collect::<Vec<_>>(), [1, 2, 3, 4]);
+    /// assert_eq!(g.bfs(9).count(), 0);
+    /// ```
+    pub fn bfs(&self, start: K) -> Bfs<'_, K> {
+        let mut bfs = Bfs { graph: self, queue: VecDeque::new(), visited: self.visited_set() };
+        if self.contains_node(start) {
+            bfs.visited.insert(start, ());
+            bfs.queue.push_back(start);
+        }
+        bfs
+    }
+
+    /// Depth-first preorder traversal from `start`, neighbors are visited in insertion order.
+    ///
+    /// ```
+    /// use intmap_rs::IntGraph;
+    ///
+    /// let mut g = IntGraph::with_capacity(16);
+    /// g.add_edge(1u32, 2);
+    /// g.add_edge(1, 3);
+    /// g.add_edge(2, 4);
+    /// assert_eq!(g.dfs(1).collect::<Vec<_>>(), [1, 2, 4, 3]);
+    /// ```
+    pub fn dfs(&self, start: K) -> Dfs<'_, K> {
+        let mut dfs = Dfs { graph: self, stack: Vec::new(), visited: self.visited_set() };
+        if self.contains_node(start) {
+            dfs.stack.push(start);
+        }
+        dfs
+    }
+
+    /// The adjacency map, node id → out-neighbors.
+    ///
+    /// ```
+    /// use intmap_rs::IntGraph;
+    ///
+    /// let mut g = IntGraph::with_capacity(16);
+    /// g.add_edge(1u32, 2);
+    /// let adjacency = g.into_inner();
+    /// assert_eq!((adjacency.get(1), adjacency.get(2)), (Some(&vec![2]), Some(&vec![])));
+    /// ```
+    pub fn into_inner(self) -> IntMap<K, Vec<K>> {
+        self.adjacency
+    }
+
+    // same capacity as the adjacency map, so every node fits
+    fn visited_set(&self) -> IntMap<K, ()> {
+        IntMap::with_capacity(self.adjacency.capacity() as u32)
+    }
+}
+
+pub struct Bfs<'a, K> {
+    graph: &'a IntGraph<K>,
+    queue: VecDeque<K>,
+    visited: IntMap<K, ()>,
+}
+
+impl<'a, K: IntKey> Iterator for Bfs<'a, K> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<K> {
+        let node = self.queue.pop_front()?;
+        for &next in self.graph.neighbors(node) {
+            if self.visited.insert(next, ()).is_none() {
+                self.queue.push_back(next);
+            }
+        }
+        Some(node)
+    }
+}
+
+pub struct Dfs<'a, K> {
+    graph: &'a IntGraph<K>,
+    stack: Vec<K>,
+    visited: IntMap<K, ()>,
+}
+
+impl<'a, K: IntKey> Iterator for Dfs<'a, K> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<K> {
+        while let Some(node) = self.stack.pop() {
+            if self.visited.insert(node, ()).is_some() {
+                continue;
+            }
+            let neighbors = self.graph.neighbors(node);
+            self.stack.extend(neighbors.iter().rev().filter(|&&n| !self.visited.contains(n)));
+            return Some(node);
+        }
+        None
+    }
+}
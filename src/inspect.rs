@@ -0,0 +1,92 @@
+use crate::{IntKey, IntMap};
+
+/// Snapshot of a single table slot, as seen by `IntMap::neighborhood`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlotView<K> {
+    /// Slot index in the table.
+    pub index: usize,
+    /// Key stored in the slot and its distance from the key's home slot, none if empty.
+    pub entry: Option<(K, u8)>,
+}
+
+impl<K> SlotView<K> {
+    /// Whether the slot holds no entry.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(16);
+    /// m.insert(1u32, ());
+    /// let views: Vec<_> = m.neighborhood(1, 1).collect();
+    /// assert!(views[0].is_empty() && !views[1].is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.entry.is_none()
+    }
+
+    /// Home slot of the stored key.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(16);
+    /// m.insert(1u32, ());
+    /// m.insert(17, ());
+    /// let homes: Vec<_> = m.neighborhood(1, 1).map(|view| view.home()).collect();
+    /// assert_eq!(homes, [None, Some(1), Some(1)]);
+    /// ```
+    pub fn home(&self) -> Option<usize> {
+        self.entry.as_ref().map(|&(_, distance)| self.index - distance as usize)
+    }
+}
+
+impl<K: IntKey, V> IntMap<K, V> {
+    /// Entries sharing the home slot of `key`, e.g. all keys with the same low bits under the
+    /// default indexing. They are stored next to each other, so this walks a single run.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(16);
+    /// for key in [1u32, 17, 2, 33] {
+    ///     m.insert(key, ());
+    /// }
+    /// let group: Vec<_> = m.collision_group(1).map(|(key, _)| key).collect();
+    /// assert_eq!(group, [1, 17, 33]);
+    /// ```
+    pub fn collision_group(&self, key: K) -> impl Iterator<Item = (K, &V)> + '_ {
+        let home = self.index_for_key(key);
+        (home..self.table.capacity())
+            .map_while(move |index| {
+                let (distance, key, value) = self.table.slot(index)?;
+                // the cluster is ordered by home slot, entries of later homes end the group
+                match (index - distance as usize).cmp(&home) {
+                    std::cmp::Ordering::Greater => None,
+                    std::cmp::Ordering::Equal => Some(Some((*key, value))),
+                    std::cmp::Ordering::Less => Some(None),
+                }
+            })
+            .flatten()
+    }
+
+    /// Slots within `radius` of the home slot of `key`, in table order. Meant for debugging
+    /// tools visualizing why a key's probe chain is long; the key doesn't have to be present.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(16);
+    /// m.insert(1u32, ());
+    /// m.insert(17, ());
+    /// let entries: Vec<_> = m.neighborhood(1, 1).map(|view| (view.index, view.entry)).collect();
+    /// assert_eq!(entries, [(0, None), (1, Some((1, 0))), (2, Some((17, 1)))]);
+    /// ```
+    pub fn neighborhood(&self, key: K, radius: usize) -> impl Iterator<Item = SlotView<K>> + '_ {
+        let home = self.index_for_key(key);
+        let end = home.saturating_add(radius).saturating_add(1).min(self.table.capacity());
+        (home.saturating_sub(radius)..end).map(|index| SlotView {
+            index,
+            entry: self.table.slot(index).map(|(distance, key, _)| (*key, distance as u8)),
+        })
+    }
+}
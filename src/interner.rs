@@ -0,0 +1,168 @@
+use crate::{Capacity, IntMap};
+
+/// Interns strings as dense `u32` symbols, `0, 1, 2, ...` in first-seen order.
+///
+/// Strings are appended to a single arena, the table maps string hashes to symbols. Two
+/// strings with the same hash are told apart by comparing against the arena and moving on to
+/// the next hash value, like linear probing in hash space.
+pub struct Interner {
+    pub(crate) symbols: IntMap<u64, u32>,
+    arena: String,
+    // end offset of every symbol's string in 'arena', the start is the previous end
+    ends: Vec<u32>,
+}
+
+impl Interner {
+    /// Room for about `capacity` distinct strings, `intern` panics once the table is full.
+    ///
+    /// ```
+    /// use intmap_rs::Interner;
+    ///
+    /// let interner = Interner::with_capacity(64);
+    /// assert!(interner.is_empty());
+    /// ```
+    pub fn with_capacity(capacity: impl Into<Capacity>) -> Self {
+        let capacity = capacity.into().get();
+        Self {
+            symbols: IntMap::with_capacity(capacity),
+            arena: String::new(),
+            ends: Vec::with_capacity(capacity as usize),
+        }
+    }
+
+    /// Returns the symbol of `s`, interning it first if it's new.
+    ///
+    /// ```
+    /// use intmap_rs::Interner;
+    ///
+    /// let mut interner = Interner::with_capacity(64);
+    /// assert_eq!((interner.intern("a"), interner.intern("b"), interner.intern("a")), (0, 1, 0));
+    /// ```
+    pub fn intern(&mut self, s: &str) -> u32 {
+        let mut hash = hash(s);
+        loop {
+            match self.symbols.get(hash) {
+                Some(&symbol) if self.resolve(symbol) == Some(s) => return symbol,
+                Some(_) => hash = hash.wrapping_add(1),
+                None => break,
+            }
+        }
+        let symbol = self.ends.len() as u32;
+        self.arena.push_str(s);
+        assert!(self.arena.len() <= u32::MAX as usize, "interner arena overflow");
+        self.ends.push(self.arena.len() as u32);
+        self.symbols.insert(hash, symbol);
+        symbol
+    }
+
+    /// Symbol of `s` if it has been interned.
+    ///
+    /// ```
+    /// use intmap_rs::Interner;
+    ///
+    /// let mut interner = Interner::with_capacity(64);
+    /// interner.intern("a");
+    /// assert_eq!((interner.get("a"), interner.get("b")), (Some(0), None));
+    /// ```
+    pub fn get(&self, s: &str) -> Option<u32> {
+        let mut hash = hash(s);
+        loop {
+            let symbol = *self.symbols.get(hash)?;
+            if self.resolve(symbol) == Some(s) {
+                return Some(symbol);
+            }
+            hash = hash.wrapping_add(1);
+        }
+    }
+
+    /// String of `symbol`, `None` if it wasn't handed out by this interner.
+    ///
+    /// ```
+    /// use intmap_rs::Interner;
+    ///
+    /// let mut interner = Interner::with_capacity(64);
+    /// let symbol = interner.intern("tag");
+    /// assert_eq!((interner.resolve(symbol), interner.resolve(7)), (Some("tag"), None));
+    /// ```
+    pub fn resolve(&self, symbol: u32) -> Option<&str> {
+        let symbol = symbol as usize;
+        let end = *self.ends.get(symbol)? as usize;
+        let start = symbol.checked_sub(1).map_or(0, |prev| self.ends[prev] as usize);
+        Some(&self.arena[start..end])
+    }
+
+    /// All interned strings in symbol order.
+    ///
+    /// ```
+    /// use intmap_rs::Interner;
+    ///
+    /// let mut interner = Interner::with_capacity(64);
+    /// interner.intern("x");
+    /// interner.intern("y");
+    /// assert_eq!(interner.iter().collect::<Vec<_>>(), [(0, "x"), (1, "y")]);
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &str)> {
+        (0..self.ends.len() as u32).map(|symbol| (symbol, self.resolve(symbol).unwrap()))
+    }
+
+    /// Number of distinct strings interned.
+    ///
+    /// ```
+    /// use intmap_rs::Interner;
+    ///
+    /// let mut interner = Interner::with_capacity(64);
+    /// interner.intern("x");
+    /// interner.intern("x");
+    /// assert_eq!(interner.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.ends.len()
+    }
+
+    /// Whether no string has been interned yet.
+    ///
+    /// ```
+    /// use intmap_rs::Interner;
+    ///
+    /// let mut interner = Interner::with_capacity(64);
+    /// assert!(interner.is_empty());
+    /// interner.intern("");
+    /// assert!(!interner.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.ends.is_empty()
+    }
+
+    /// Bytes of all interned strings.
+    ///
+    /// ```
+    /// use intmap_rs::Interner;
+    ///
+    /// let mut interner = Interner::with_capacity(64);
+    /// interner.intern("abc");
+    /// interner.intern("de");
+    /// interner.intern("abc");
+    /// assert_eq!(interner.arena_len(), 5);
+    /// ```
+    pub fn arena_len(&self) -> usize {
+        self.arena.len()
+    }
+}
+
+// fxhash over 8-byte words with the splitmix64 finalizer on top, the table indexes by the low bits
+pub(crate) fn hash(s: &str) -> u64 {
+    const SEED: u64 = 0x517cc1b727220a95;
+    let bytes = s.as_bytes();
+    let mut h = bytes.len() as u64;
+    let mut chunks = bytes.chunks_exact(8);
+    for chunk in &mut chunks {
+        let word = u64::from_le_bytes(chunk.try_into().unwrap());
+        h = (h.rotate_left(5) ^ word).wrapping_mul(SEED);
+    }
+    let mut tail = [0u8; 8];
+    tail[..chunks.remainder().len()].copy_from_slice(chunks.remainder());
+    h = (h.rotate_left(5) ^ u64::from_le_bytes(tail)).wrapping_mul(SEED);
+    h = (h ^ (h >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    h = (h ^ (h >> 27)).wrapping_mul(0x94d049bb133111eb);
+    h ^ (h >> 31)
+}
@@ -0,0 +1,187 @@
+use crate::map::Iter;
+use crate::{IntKey, IntMap};
+use std::iter::FusedIterator;
+
+enum Side<'a, K, V1, V2> {
+    // iterating the left map, probing the right one
+    Left(Iter<'a, K, V1>, &'a IntMap<K, V2>),
+    // iterating the right map, probing the left one
+    Right(Iter<'a, K, V2>, &'a IntMap<K, V1>),
+}
+
+pub struct InnerJoin<'a, K, V1, V2> {
+    side: Side<'a, K, V1, V2>,
+}
+
+impl<'a, K: IntKey, V1, V2> InnerJoin<'a, K, V1, V2> {
+    pub(crate) fn new(left: &'a IntMap<K, V1>, right: &'a IntMap<K, V2>) -> Self {
+        let side = if left.len() <= right.len() {
+            Side::Left(left.iter(), right)
+        } else {
+            Side::Right(right.iter(), left)
+        };
+        Self { side }
+    }
+}
+
+impl<'a, K: IntKey, V1, V2> Iterator for InnerJoin<'a, K, V1, V2> {
+    type Item = (K, &'a V1, &'a V2);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.side {
+            Side::Left(iter, right) => {
+                iter.find_map(|(k, v1)| right.get(*k).map(|v2| (*k, v1, v2)))
+            }
+            Side::Right(iter, left) => iter.find_map(|(k, v2)| left.get(*k).map(|v1| (*k, v1, v2))),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.side {
+            Side::Left(iter, _) => (0, Some(iter.len())),
+            Side::Right(iter, _) => (0, Some(iter.len())),
+        }
+    }
+}
+
+impl<'a, K: IntKey, V1, V2> DoubleEndedIterator for InnerJoin<'a, K, V1, V2> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        match &mut self.side {
+            Side::Left(iter, right) => {
+                iter.rev().find_map(|(k, v1)| right.get(*k).map(|v2| (*k, v1, v2)))
+            }
+            Side::Right(iter, left) => {
+                iter.rev().find_map(|(k, v2)| left.get(*k).map(|v1| (*k, v1, v2)))
+            }
+        }
+    }
+}
+
+impl<'a, K: IntKey, V1, V2> FusedIterator for InnerJoin<'a, K, V1, V2> {}
+
+pub struct LeftJoin<'a, K, V1, V2> {
+    iter: Iter<'a, K, V1>,
+    right: &'a IntMap<K, V2>,
+}
+
+impl<'a, K: IntKey, V1, V2> LeftJoin<'a, K, V1, V2> {
+    pub(crate) fn new(left: &'a IntMap<K, V1>, right: &'a IntMap<K, V2>) -> Self {
+        Self { iter: left.iter(), right }
+    }
+}
+
+impl<'a, K: IntKey, V1, V2> Iterator for LeftJoin<'a, K, V1, V2> {
+    type Item = (K, &'a V1, Option<&'a V2>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(k, v1)| (*k, v1, self.right.get(*k)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, K: IntKey, V1, V2> DoubleEndedIterator for LeftJoin<'a, K, V1, V2> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back().map(|(k, v1)| (*k, v1, self.right.get(*k)))
+    }
+}
+
+impl<'a, K: IntKey, V1, V2> ExactSizeIterator for LeftJoin<'a, K, V1, V2> {}
+impl<'a, K: IntKey, V1, V2> FusedIterator for LeftJoin<'a, K, V1, V2> {}
+
+// slots per block, small enough that a block of both maps stays in L1
+const MERGE_BLOCK: usize = 256;
+// how many slots ahead of the scan the other map's home slots are prefetched, when the maps
+// index keys differently and lookups land at random
+const MERGE_PREFETCH: usize = 16;
+
+pub struct MergeIter<'a, K, V1, V2> {
+    left: &'a IntMap<K, V1>,
+    right: &'a IntMap<K, V2>,
+    // slots per block, the whole table if the maps index keys differently
+    block: usize,
+    // the maps index keys differently, the other map's slots are prefetched
+    prefetch: bool,
+    end: usize,
+    start: usize,
+    index: usize,
+    // scanning the right map's slots of the current block
+    right_side: bool,
+    // entries not scanned yet
+    left_remaining: usize,
+    right_remaining: usize,
+}
+
+impl<'a, K: IntKey, V1, V2> MergeIter<'a, K, V1, V2> {
+    pub(crate) fn new(left: &'a IntMap<K, V1>, right: &'a IntMap<K, V2>) -> Self {
+        let end = left.table.capacity().max(right.table.capacity());
+        let same_layout = left.indexing == right.indexing;
+        Self {
+            left,
+            right,
+            block: if same_layout { MERGE_BLOCK } else { end },
+            prefetch: !same_layout,
+            end,
+            start: 0,
+            index: 0,
+            right_side: false,
+            left_remaining: left.len(),
+            right_remaining: right.len(),
+        }
+    }
+
+    // the other map's home slot of the key at 'index' of the map being scanned
+    #[inline]
+    fn prefetch(&self, index: usize) {
+        if !self.right_side {
+            if let Some((&k, _)) = self.left.table.get_by_slot(index) {
+                self.right.prefetch(k);
+            }
+        } else if let Some((&k, _)) = self.right.table.get_by_slot(index) {
+            self.left.prefetch(k);
+        }
+    }
+}
+
+impl<'a, K: IntKey, V1, V2> Iterator for MergeIter<'a, K, V1, V2> {
+    type Item = (K, Option<&'a V1>, Option<&'a V2>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.start < self.end {
+            let block_end = (self.start + self.block).min(self.end);
+            while self.index < block_end {
+                let index = self.index;
+                self.index += 1;
+                if self.prefetch {
+                    self.prefetch(index + MERGE_PREFETCH);
+                }
+                if !self.right_side {
+                    if let Some((&k, v1)) = self.left.table.get_by_slot(index) {
+                        self.left_remaining -= 1;
+                        return Some((k, Some(v1), self.right.get(k)));
+                    }
+                } else if let Some((&k, v2)) = self.right.table.get_by_slot(index) {
+                    self.right_remaining -= 1;
+                    // keys of both maps came with the left block
+                    if !self.left.contains(k) {
+                        return Some((k, None, Some(v2)));
+                    }
+                }
+            }
+            if self.right_side {
+                self.start = block_end;
+            }
+            self.right_side = !self.right_side;
+            self.index = self.start;
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.left_remaining, Some(self.left_remaining + self.right_remaining))
+    }
+}
+
+impl<'a, K: IntKey, V1, V2> FusedIterator for MergeIter<'a, K, V1, V2> {}
@@ -0,0 +1,73 @@
+use crate::{IntKey, IntMap};
+use std::sync::OnceLock;
+
+/// A map built once on first use and read-only afterwards, meant for `static` lookup tables.
+///
+/// ```
+/// use intmap_rs::{IntMap, StaticIntMap};
+///
+/// static OPCODES: StaticIntMap<u32, &str> = StaticIntMap::new();
+///
+/// let table = OPCODES.get_or_init(|| {
+///     let mut m = IntMap::with_capacity(4);
+///     m.insert(0x01, "nop");
+///     m
+/// });
+/// assert_eq!(table.get(0x01), Some(&"nop"));
+/// ```
+pub struct StaticIntMap<K, V> {
+    cell: OnceLock<IntMap<K, V>>,
+}
+
+impl<K: IntKey, V> StaticIntMap<K, V> {
+    /// An empty cell, the map is built by the first `get_or_init`.
+    ///
+    /// ```
+    /// use intmap_rs::StaticIntMap;
+    ///
+    /// static TABLE: StaticIntMap<u32, u32> = StaticIntMap::new();
+    /// assert!(TABLE.get().is_none());
+    /// ```
+    pub const fn new() -> Self {
+        Self { cell: OnceLock::new() }
+    }
+
+    /// Returns the map, building it with `init` if this is the first call. Concurrent callers
+    /// block until the winning `init` completes, and only one `init` ever runs.
+    ///
+    /// ```
+    /// use intmap_rs::{IntMap, StaticIntMap};
+    ///
+    /// let cell = StaticIntMap::new();
+    /// cell.get_or_init(|| {
+    ///     let mut m = IntMap::with_capacity(4);
+    ///     m.insert(1u32, 'a');
+    ///     m
+    /// });
+    /// let again = cell.get_or_init(|| unreachable!());
+    /// assert_eq!(again.get(1), Some(&'a'));
+    /// ```
+    pub fn get_or_init(&self, init: impl FnOnce() -> IntMap<K, V>) -> &IntMap<K, V> {
+        self.cell.get_or_init(init)
+    }
+
+    /// The map, if it has been built already.
+    ///
+    /// ```
+    /// use intmap_rs::{IntMap, StaticIntMap};
+    ///
+    /// let cell = StaticIntMap::<u32, ()>::new();
+    /// assert!(cell.get().is_none());
+    /// cell.get_or_init(|| IntMap::with_capacity(4));
+    /// assert!(cell.get().is_some());
+    /// ```
+    pub fn get(&self) -> Option<&IntMap<K, V>> {
+        self.cell.get()
+    }
+}
+
+impl<K: IntKey, V> Default for StaticIntMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,7 +1,99 @@
+mod abi;
+mod adapters;
+mod aging;
+mod align;
+mod atomic;
+#[cfg(feature = "bench-utils")]
+pub mod bench;
+mod borrowed;
+mod cache;
+mod capacity;
+mod cell;
+mod checksum;
+mod column;
+mod config;
+mod deferred;
+mod error;
+mod fingerprint;
+mod graph;
+mod inspect;
+mod interner;
+mod join;
+mod lazy;
+mod loader;
 mod map;
-use map::{Distance, Keys, SearchResult, Table};
-use num_traits::{AsPrimitive, FromPrimitive, PrimInt};
+mod migrating;
+mod monitor;
+mod partition;
+mod pool;
+mod probe;
+mod profile;
+mod registry;
+mod replication;
+mod sets;
+mod shadow;
+mod shard;
+mod sparse;
+mod sync;
+mod typed;
+mod weighted;
+mod windowed;
+use checksum::Digest;
+use map::{Distance, SearchResult, Table};
+use num_traits::{AsPrimitive, FromPrimitive, NumCast, PrimInt};
+#[cfg(not(feature = "profiling"))]
+use profile::OpClass;
+#[cfg(feature = "profiling")]
+pub use profile::OpClass;
+use profile::Profile;
+use shadow::Shadow;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{hash_map, HashMap};
 use std::fmt::{Debug, Display};
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Weak};
+
+pub use abi::{
+    IntMapHandle, IntMapVTable, ABI_VERSION, STATUS_ABSENT, STATUS_FOUND, STATUS_PANICKED,
+    STATUS_REJECTED,
+};
+pub use adapters::{AdaptedIntMap, KeyAdapter};
+pub use aging::AgingIntMap;
+pub use align::{Align16, Align32, Align64};
+pub use borrowed::BorrowedIntMap;
+pub use capacity::{Capacity, Slots};
+pub use cell::IntMapCell;
+pub use column::ColumnIndex;
+pub use config::{CapacityPolicy, Config};
+pub use deferred::DeferredIntMap;
+#[cfg(feature = "env")]
+pub use error::ConfigError;
+pub use error::{InsertError, KeyOverflow, LoadError};
+pub use fingerprint::{FingerprintIntMap, WideKey};
+pub use graph::{Bfs, Dfs, IntGraph};
+pub use inspect::SlotView;
+pub use interner::Interner;
+pub use join::{InnerJoin, LeftJoin, MergeIter};
+pub use lazy::StaticIntMap;
+pub use loader::{DuplicatePolicy, IntMapLoader};
+pub use map::{Iter, Keys, SlotIter, Values};
+pub use migrating::MigratingMap;
+pub use monitor::{MonitoredIntMap, StatsHandle, TableStats};
+pub use partition::Partition;
+pub use pool::IntMapPool;
+pub use probe::Probe;
+#[cfg(feature = "profiling")]
+pub use profile::OpProfile;
+pub use registry::IdRegistry;
+pub use replication::MapOp;
+#[cfg(feature = "oplog")]
+pub use replication::RecordingIntMap;
+pub use shard::{shard_for, ShardedBuilder};
+pub use sparse::SparseVec;
+pub use sync::SyncIntMap;
+pub use typed::{Id, TypedIntMap};
+pub use weighted::WeightedIntMap;
+pub use windowed::WindowedIntMap;
 
 mod private {
     pub trait SealedKey {}
@@ -9,353 +101,5234 @@ mod private {
 
 macro_rules! sealed_set {
     ($name:ident [$($type_set:ty)+] $seal:path$(: $($bounds:path)*)?) => {
-        pub trait $name: $($( $bounds +)*)? where Self: Sized { }
+        pub trait $name: $seal $($(+ $bounds)*)? where Self: Sized { }
         $(impl $seal for $type_set{})+
         $(impl $name for $type_set {})+
     };
 }
 
-sealed_set!(IntKey [i32 u32 i64 usize u64 i128 u128] private::SealedKey: 
-    Debug Display PrimInt FromPrimitive Default
-    AsPrimitive::<u32> AsPrimitive::<usize>);
+// Keys are reduced to a slot index with `key & mask` in the key's own width and only then
+// narrowed to u32, so the high bits of 64/128-bit keys never take part in the usize conversion.
+sealed_set!(IntKey [i32 u32 i64 usize u64 i128 u128] private::SealedKey:
+    Debug Display PrimInt FromPrimitive Default Hash
+    AsPrimitive::<u32>);
+
+/// Largest number of home slots a map can have, larger requested capacities are clamped.
+// the table has to address `capacity + MAX_PROBES` slots, on 16-bit targets that's far less than 2^30
+pub const MAX_CAPACITY: u32 = if usize::BITS < 32 { 1 << (usize::BITS - 2) } else { 1 << 30 };
+
+/// Smallest number of home slots a map can have, smaller requested capacities are raised.
+pub const MIN_CAPACITY: u32 = 1;
+
+/// Default and largest probe limit: entries are stored fewer than `MAX_PROBES` slots past their
+/// home slot, an insert that would go further panics.
+pub const MAX_PROBES: u8 = Distance::MAX as u8;
+
+/// Smallest probe limit accepted by `IntMap::with_probe_limit`.
+pub const MIN_PROBES: u8 = 2;
+
+/// Slots reserved past the last home slot for entries displaced from the end of the table, for
+/// maps with the default probe limit. `with_probe_limit` maps reserve `probe_limit` slots.
+pub const OVERFLOW_SLOTS: usize = MAX_PROBES as usize;
+
+const _: () = {
+    assert!(MIN_CAPACITY.is_power_of_two() && MAX_CAPACITY.is_power_of_two());
+    assert!(MIN_CAPACITY <= MAX_CAPACITY);
+    assert!(MIN_PROBES <= MAX_PROBES);
+    // every slot index of the largest table fits a usize
+    assert!((MAX_CAPACITY as usize).checked_add(OVERFLOW_SLOTS).is_some());
+};
+
+#[inline]
+pub(crate) fn round_capacity(capacity: u32) -> u32 {
+    capacity.min(MAX_CAPACITY).next_power_of_two()
+}
+
+/// Outcome of `IntMap::remove_if`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemoveIf<V> {
+    /// The predicate held, the entry is removed.
+    Removed(V),
+    /// The predicate didn't hold, the entry is left in place.
+    Kept,
+    /// There's no entry for the key.
+    Missing,
+}
+
+/// What inserting a new key does once the map holds `capacity` entries, see
+/// `IntMap::set_saturation_policy`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SaturationPolicy {
+    /// Keep storing entries in the overflow region past the last home slot until an insert
+    /// exceeds the probe limit and panics.
+    #[default]
+    Overflow,
+    /// Reject the key: `try_insert` returns `InsertError::Full`, other inserts panic.
+    Reject,
+    /// Drop the first entry stored at or after the new key's home slot to make room, so the
+    /// victim shares the new key's cluster if there's one. Updates of present keys never evict.
+    Evict,
+    /// Reject keys that would take the load factor over `max_load_percent`, at most 100:
+    /// `try_insert` returns `InsertError::EvictionNeeded` with the entries stored farthest from
+    /// their home slots as candidates, other inserts panic. Which entry to drop is the
+    /// application's call, e.g. on a device with a fixed memory budget.
+    SuggestEviction { max_load_percent: u8 },
+}
+
+/// Number of keys suggested by `InsertError::EvictionNeeded`.
+pub const EVICTION_CANDIDATES: usize = 8;
+
+// how many pairs ahead of the insert 'extend_from_slices' prefetches
+const BULK_PREFETCH: usize = 8;
+// home slots per bucket of the batch sort of 'extend_from_slices', a bucket of pairs stays in
+// the cache while it's sorted
+const BULK_BUCKET_SLOTS: usize = 1 << 11;
+
+// how keys are reduced to their home slot
+#[derive(Clone, Copy, PartialEq)]
+enum Indexing<K> {
+    // `key & mask`, power-of-two capacities
+    Mask(K),
+    // Lemire's multiply-shift reduction of the key mixed with a seed, any capacity
+    FastRange(u32, u64),
+}
+
+impl<K: IntKey> Indexing<K> {
+    #[inline]
+    fn home(self, key: K) -> usize {
+        match self {
+            Indexing::Mask(mask) => AsPrimitive::<u32>::as_(key & mask) as usize,
+            Indexing::FastRange(capacity, seed) => {
+                ((mix(key, seed) as u64 * capacity as u64) >> 32) as usize
+            }
+        }
+    }
+}
+
+// folds every 32-bit chunk of the key into a u64, then applies the splitmix64 finalizer so the
+// high bits fastrange relies on depend on all key bits
+#[inline]
+fn mix<K: IntKey>(key: K, seed: u64) -> u32 {
+    let bits = K::zero().count_zeros();
+    let mut h = AsPrimitive::<u32>::as_(key) as u64;
+    let mut shift = 32;
+    while shift < bits {
+        h = h.rotate_left(32) ^ AsPrimitive::<u32>::as_(key.unsigned_shr(shift)) as u64;
+        shift += 32;
+    }
+    h ^= seed;
+    h = (h ^ (h >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    h = (h ^ (h >> 27)).wrapping_mul(0x94d049bb133111eb);
+    ((h ^ (h >> 31)) >> 32) as u32
+}
 
+/// A fixed-capacity map of integer keys: its table is allocated once, up front, and never
+/// grows or rehashes.
+///
+/// ```
+/// use intmap_rs::IntMap;
+///
+/// let mut m = IntMap::with_capacity(16);
+/// assert_eq!(m.insert(7u64, "seven"), None);
+/// assert_eq!(m.insert(7, "sieben"), Some("seven"));
+/// assert_eq!(m.get(7), Some(&"sieben"));
+/// assert!(m.contains(7) && !m.contains(8));
+/// assert_eq!(m.remove(7), Some("sieben"));
+/// assert!(m.is_empty());
+/// ```
+///
+/// A panic in a closure passed to the map, or in a value's `Clone` or `Drop`, leaves the table
+/// valid with every entry either in it or dropped once, so the map can be used again after
+/// `catch_unwind`. It's `UnwindSafe` and `RefUnwindSafe` when `K` and `V` are.
 pub struct IntMap<K, V> {
     table: Table<K, V>,
-    index_mask: K,
+    indexing: Indexing<K>,
+    reserved: Option<K>,
+    saturation: SaturationPolicy,
+    // applied to every key before it's indexed, compared or stored
+    canonicalize: Option<fn(K) -> K>,
+    shadow: Shadow<K>,
+    profile: Profile,
 }
 
 impl<K: IntKey, V> IntMap<K, V> {
-    pub fn with_capacity(capacity: u32) -> Self {
-        let capacity = capacity.min(1 << 30).next_power_of_two();
-        let table_cap = capacity as usize + Distance::MAX as usize;
+    /// A map of `capacity` home slots rounded up to the next power of two, keys are reduced to
+    /// their home slot with `key & mask`.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let m = IntMap::<u32, u32>::with_capacity(100);
+    /// assert_eq!((m.capacity(), m.len()), (128, 0));
+    /// ```
+    pub fn with_capacity(capacity: impl Into<Capacity>) -> Self {
+        let capacity = round_capacity(capacity.into().get());
+        let table_cap = capacity as usize + OVERFLOW_SLOTS;
+        let table = Table::with_capacity(table_cap);
+        let index_mask = K::from_u32(capacity - 1).unwrap();
+
+        Self {
+            indexing: Indexing::Mask(index_mask),
+            table,
+            reserved: None,
+            saturation: SaturationPolicy::Overflow,
+            canonicalize: None,
+            shadow: Shadow::default(),
+            profile: Profile::new(),
+        }
+    }
+
+    /// A map of exactly `capacity` home slots instead of the next power of two. Keys are mixed
+    /// and mapped onto the slots with a multiply-shift (fastrange) reduction, which costs a few
+    /// multiplications per lookup over the `key & mask` of `with_capacity`.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let m = IntMap::<u64, ()>::with_exact_capacity(100);
+    /// assert_eq!(m.capacity(), 100);
+    /// ```
+    pub fn with_exact_capacity(capacity: impl Into<Capacity>) -> Self {
+        let capacity = capacity.into().get().clamp(MIN_CAPACITY, MAX_CAPACITY);
+        let table_cap = capacity as usize + OVERFLOW_SLOTS;
         let table = Table::with_capacity(table_cap);
+
+        Self {
+            indexing: Indexing::FastRange(capacity, 0),
+            table,
+            reserved: None,
+            saturation: SaturationPolicy::Overflow,
+            canonicalize: None,
+            shadow: Shadow::default(),
+            profile: Profile::new(),
+        }
+    }
+
+    // an empty map of 'capacity' home slots indexed like this one, with the same probe limit and
+    // lazy clear, nontemporal clear and constant time lookup settings
+    fn empty_like_with<K2: IntKey, U>(&self, capacity: u32) -> IntMap<K2, U> {
+        let (policy, seed) = match self.indexing {
+            Indexing::Mask(_) => (CapacityPolicy::PowerOfTwo, 0),
+            Indexing::FastRange(_, seed) => (CapacityPolicy::Exact, seed),
+        };
+        let config = Config::new(capacity)
+            .capacity_policy(policy)
+            .seed(seed)
+            .probe_limit(self.probe_limit() as u8);
+        let mut map = IntMap::with_config(&config);
+        map.set_lazy_clear(self.is_lazy_clear());
+        map.set_nontemporal_clear(self.is_nontemporal_clear());
+        map.set_constant_time_lookup(self.is_constant_time_lookup());
+        map
+    }
+
+    // wraps a table of 'capacity' home slots plus its overflow region, capacity is a power of two
+    pub(crate) fn from_table(table: Table<K, V>, capacity: u32) -> Self {
+        let index_mask = K::from_u32(capacity - 1).unwrap();
+        Self {
+            indexing: Indexing::Mask(index_mask),
+            table,
+            reserved: None,
+            saturation: SaturationPolicy::Overflow,
+            canonicalize: None,
+            shadow: Shadow::default(),
+            profile: Profile::new(),
+        }
+    }
+
+    /// A map whose entries are stored at most `probe_limit - 1` slots past their home slot, the
+    /// limit is clamped to `2..=127`. The table only reserves `probe_limit` overflow slots after
+    /// the last home slot instead of 127, which is most of the footprint of tiny maps; inserts
+    /// exceeding the limit panic like they do with the default one.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_probe_limit(8, 4);
+    /// assert_eq!((m.probe_limit(), m.slots().get()), (4, 12));
+    /// // keys 0, 8 and 16 share home slot 0 and take the slots 0 to 2
+    /// for k in [0u32, 8, 16] {
+    ///     m.insert(k, ());
+    /// }
+    /// assert_eq!(m.get_with_distance(16), Some((&(), 2)));
+    /// ```
+    pub fn with_probe_limit(capacity: impl Into<Capacity>, probe_limit: u8) -> Self {
+        let probe_limit = probe_limit.clamp(MIN_PROBES, MAX_PROBES) as Distance;
+        let capacity = round_capacity(capacity.into().get());
+        let table_cap = capacity as usize + probe_limit as usize;
+        let mut table = Table::with_capacity(table_cap);
+        table.set_probe_limit(probe_limit);
+        let index_mask = K::from_u32(capacity - 1).unwrap();
+
+        Self {
+            indexing: Indexing::Mask(index_mask),
+            table,
+            reserved: None,
+            saturation: SaturationPolicy::Overflow,
+            canonicalize: None,
+            shadow: Shadow::default(),
+            profile: Profile::new(),
+        }
+    }
+
+    /// A map built from `config`: capacity policy, mixer seed, probe limit and saturation
+    /// policy, see `Config`.
+    ///
+    /// ```
+    /// use intmap_rs::{CapacityPolicy, Config, IntMap};
+    ///
+    /// let config = Config::new(100).capacity_policy(CapacityPolicy::Exact).seed(42);
+    /// let m = IntMap::<u32, ()>::with_config(&config);
+    /// assert_eq!(m.capacity(), 100);
+    /// ```
+    pub fn with_config(config: &Config) -> Self {
+        let probe_limit = config.probe_limit.clamp(MIN_PROBES, MAX_PROBES) as Distance;
+        let (capacity, indexing) = match config.capacity_policy {
+            CapacityPolicy::PowerOfTwo => {
+                let capacity = round_capacity(config.capacity);
+                (capacity, Indexing::Mask(K::from_u32(capacity - 1).unwrap()))
+            }
+            CapacityPolicy::Exact => {
+                let capacity = config.capacity.clamp(MIN_CAPACITY, MAX_CAPACITY);
+                (capacity, Indexing::FastRange(capacity, config.seed))
+            }
+        };
+        let mut table = Table::with_capacity(capacity as usize + probe_limit as usize);
+        table.set_probe_limit(probe_limit);
+
+        Self {
+            indexing,
+            table,
+            reserved: None,
+            saturation: config.saturation,
+            canonicalize: None,
+            shadow: Shadow::default(),
+            profile: Profile::new(),
+        }
+    }
+
+    /// Same as `with_capacity`, but every key goes through `canonicalize` before it's indexed,
+    /// compared or stored, e.g. to mask off version bits: keys with the same canonical form name
+    /// the same entry and the map yields canonical keys. `canonicalize` must be idempotent.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// // the high 16 bits hold a version
+    /// let mut m = IntMap::with_canonicalizer(8, |k: u32| k & 0xffff);
+    /// m.insert(0x0001_0007, "v1");
+    /// assert_eq!(m.insert(0x0002_0007, "v2"), Some("v1"));
+    /// assert_eq!(m.keys().copied().collect::<Vec<_>>(), [7]);
+    /// ```
+    pub fn with_canonicalizer(capacity: impl Into<Capacity>, canonicalize: fn(K) -> K) -> Self {
+        let mut map = Self::with_capacity(capacity);
+        map.canonicalize = Some(canonicalize);
+        map
+    }
+
+    /// Same as `with_capacity`, but the table is initialized with nontemporal stores and
+    /// `set_nontemporal_clear` is on, see there.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let m = IntMap::<u64, u64>::with_capacity_nontemporal(1 << 12);
+    /// assert!(m.is_nontemporal_clear() && m.is_empty());
+    /// ```
+    pub fn with_capacity_nontemporal(capacity: impl Into<Capacity>) -> Self {
+        let capacity = round_capacity(capacity.into().get());
+        let table_cap = capacity as usize + OVERFLOW_SLOTS;
+        let table = Table::with_capacity_nontemporal(table_cap);
         let index_mask = K::from_u32(capacity - 1).unwrap();
 
-        Self { index_mask, table }
+        Self {
+            indexing: Indexing::Mask(index_mask),
+            table,
+            reserved: None,
+            saturation: SaturationPolicy::Overflow,
+            canonicalize: None,
+            shadow: Shadow::default(),
+            profile: Profile::new(),
+        }
     }
 
+    /// Removes every entry, keeping the capacity.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// m.insert(1u32, "one".to_string());
+    /// m.clear();
+    /// assert!(m.is_empty() && m.get(1).is_none());
+    /// assert_eq!(m.capacity(), 8);
+    /// ```
     #[inline]
     pub fn clear(&mut self) {
+        let timer = self.profile.start(OpClass::Clear);
+        self.shadow.clear();
         self.table.clear();
+        self.profile.stop(timer);
+    }
+
+    /// `clear` for huge maps run from a cooperative scheduler: slots are released in runs of
+    /// `chunk` and `yield_now` is called between runs, e.g. to check a budget or park the task.
+    /// The map stays consistent at every call, the entries not cleared yet are still readable
+    /// if `yield_now` unwinds. A lazy clear map holding values that don't need dropping is
+    /// cleared in one step.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(1 << 10);
+    /// for k in 0..100u32 {
+    ///     m.insert(k, k.to_string());
+    /// }
+    /// let mut yields = 0;
+    /// m.clear_yielding(256, || yields += 1);
+    /// assert!(m.is_empty() && yields > 0);
+    /// ```
+    pub fn clear_yielding(&mut self, chunk: usize, yield_now: impl FnMut()) {
+        if self.is_lazy_clear() && !std::mem::needs_drop::<V>() {
+            return self.clear();
+        }
+        let shadow = &mut self.shadow;
+        self.table.clear_chunked(chunk, |key| shadow.remove(*key), yield_now);
+    }
+
+    /// Makes `clear` O(1) by keeping a generation stamp per slot, at the cost of one extra byte
+    /// per slot and a stamp check on every probe. Values that need dropping are still dropped
+    /// one by one.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(1 << 16);
+    /// m.set_lazy_clear(true);
+    /// m.insert(1u32, 1u64);
+    /// m.clear();
+    /// assert!(m.is_lazy_clear() && m.is_empty());
+    /// ```
+    pub fn set_lazy_clear(&mut self, enabled: bool) {
+        if enabled {
+            self.table.enable_stamps();
+        } else {
+            self.table.disable_stamps();
+        }
+    }
+
+    /// Whether `clear` is O(1), see `set_lazy_clear`.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::<u32, ()>::with_capacity(8);
+    /// assert!(!m.is_lazy_clear());
+    /// m.set_lazy_clear(true);
+    /// assert!(m.is_lazy_clear());
+    /// ```
+    #[inline]
+    pub fn is_lazy_clear(&self) -> bool {
+        self.table.has_stamps()
+    }
+
+    /// Makes `clear` mark slots free with nontemporal (streaming) stores that bypass the cache,
+    /// so clearing a table much larger than the LLC doesn't evict the working set of co-running
+    /// code. Only the x86_64 SSE2 path streams, other targets fall back to regular stores.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::<u32, u32>::with_capacity(1 << 16);
+    /// m.set_nontemporal_clear(true);
+    /// m.insert(3, 3);
+    /// m.clear();
+    /// assert!(m.is_empty());
+    /// ```
+    pub fn set_nontemporal_clear(&mut self, enabled: bool) {
+        self.table.set_nontemporal(enabled);
+    }
+
+    /// Whether `clear` uses streaming stores, see `set_nontemporal_clear`.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let m = IntMap::<u32, ()>::with_capacity(8);
+    /// assert!(!m.is_nontemporal_clear());
+    /// assert!(IntMap::<u32, ()>::with_capacity_nontemporal(8).is_nontemporal_clear());
+    /// ```
+    #[inline]
+    pub fn is_nontemporal_clear(&self) -> bool {
+        self.table.is_nontemporal()
+    }
+
+    /// Makes `get`, `get_mut` and `contains` compare every slot of the key's probe window,
+    /// `probe_limit` slots, and pick the match with masks instead of stopping at the first hit or
+    /// the end of the cluster, so their timing says little about whether or where a key is
+    /// stored, e.g. for maps keyed by secret tokens. Lookups get slower by the length of the
+    /// window; inserts and removes still take data-dependent time.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(64);
+    /// m.set_constant_time_lookup(true);
+    /// m.insert(0xdead_beefu64, "session");
+    /// assert_eq!(m.get(0xdead_beef), Some(&"session"));
+    /// assert!(!m.contains(0xbeef));
+    /// ```
+    pub fn set_constant_time_lookup(&mut self, enabled: bool) {
+        if enabled {
+            self.table.enable_constant_time();
+        } else {
+            self.table.disable_constant_time();
+        }
+    }
+
+    /// Whether lookups scan whole probe windows, see `set_constant_time_lookup`.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::<u32, ()>::with_capacity(8);
+    /// m.set_constant_time_lookup(true);
+    /// assert!(m.is_constant_time_lookup());
+    /// ```
+    #[inline]
+    pub fn is_constant_time_lookup(&self) -> bool {
+        self.table.is_constant_time()
+    }
+
+    /// Configures a sentinel key that is never stored: `try_insert` rejects it with an error and
+    /// `insert` panics. An entry already stored under the key stays readable and removable.
+    ///
+    /// ```
+    /// use intmap_rs::{InsertError, IntMap};
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// m.set_reserved_key(Some(u32::MAX));
+    /// assert!(matches!(m.try_insert(u32::MAX, ()), Err(InsertError::ReservedKey(..))));
+    /// assert!(m.is_empty());
+    /// ```
+    pub fn set_reserved_key(&mut self, key: Option<K>) {
+        self.reserved = key;
+    }
+
+    /// The sentinel key, see `set_reserved_key`.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::<u32, ()>::with_capacity(8);
+    /// assert_eq!(m.reserved_key(), None);
+    /// m.set_reserved_key(Some(0));
+    /// assert_eq!(m.reserved_key(), Some(0));
+    /// ```
+    #[inline]
+    pub fn reserved_key(&self) -> Option<K> {
+        self.reserved
+    }
+
+    /// Sets what inserting a new key into a full map does, `SaturationPolicy::Overflow` by
+    /// default.
+    ///
+    /// ```
+    /// use intmap_rs::{IntMap, SaturationPolicy};
+    ///
+    /// let mut m = IntMap::with_capacity(2);
+    /// m.set_saturation_policy(SaturationPolicy::Evict);
+    /// for k in 0..4u32 {
+    ///     m.insert(k, k);
+    /// }
+    /// assert_eq!(m.len(), 2);
+    /// ```
+    pub fn set_saturation_policy(&mut self, policy: SaturationPolicy) {
+        self.saturation = policy;
+    }
+
+    /// What inserting a new key into a full map does, see `set_saturation_policy`.
+    ///
+    /// ```
+    /// use intmap_rs::{IntMap, SaturationPolicy};
+    ///
+    /// let mut m = IntMap::<u32, ()>::with_capacity(8);
+    /// assert_eq!(m.saturation_policy(), SaturationPolicy::Overflow);
+    /// m.set_saturation_policy(SaturationPolicy::Reject);
+    /// assert_eq!(m.saturation_policy(), SaturationPolicy::Reject);
+    /// ```
+    #[inline]
+    pub fn saturation_policy(&self) -> SaturationPolicy {
+        self.saturation
     }
 }
 
 impl<K: IntKey, V> IntMap<K, V> {
+    #[inline]
+    fn canonical(&self, key: K) -> K {
+        match self.canonicalize {
+            Some(canonicalize) => canonicalize(key),
+            None => key,
+        }
+    }
+
     #[inline]
     fn index_for_key(&self, key: K) -> usize {
-        (key & self.index_mask).as_()
+        self.indexing.home(self.canonical(key))
+    }
+
+    #[inline]
+    fn is_reserved(&self, key: K) -> bool {
+        self.reserved.is_some_and(|reserved| self.canonical(reserved) == self.canonical(key))
+    }
+
+    #[inline]
+    fn assert_not_reserved(&self, key: K) {
+        if self.is_reserved(key) {
+            panic!("{}", InsertError::ReservedKey(key, ()));
+        }
+    }
+
+    #[inline]
+    fn search(&self, key: K) -> SearchResult {
+        let key = self.canonical(key);
+        let result = self.table.search(&key, self.index_for_key(key));
+        self.shadow.check(key, result.is_found(), self.len());
+        result
+    }
+
+    // 'find' of the public lookups, the ones the profile counts
+    #[inline]
+    fn find_timed(&self, key: K) -> Option<usize> {
+        let timer = self.profile.start(OpClass::Lookup);
+        let found = self.find(key);
+        self.profile.stop(timer);
+        found
+    }
+
+    // 'search' for pure lookups, taking the constant time path when it's on
+    #[inline]
+    fn find(&self, key: K) -> Option<usize> {
+        if !self.table.is_constant_time() {
+            return match self.search(key) {
+                SearchResult::Found(index) => Some(index),
+                SearchResult::NotFound(..) => None,
+            };
+        }
+        let key = self.canonical(key);
+        let found = self.table.find_constant_time(&key, self.index_for_key(key));
+        self.shadow.check(key, found.is_some(), self.len());
+        found
+    }
+
+    // applies the saturation policy to a new key about to be stored at its insertion point,
+    // returns the insertion point to use or None if the key is rejected
+    #[inline]
+    fn admit(&mut self, key: K, index: usize, distance: Distance) -> Option<(usize, Distance)> {
+        if !self.is_saturated() {
+            return Some((index, distance));
+        }
+        match self.saturation {
+            SaturationPolicy::Overflow => Some((index, distance)),
+            SaturationPolicy::Reject | SaturationPolicy::SuggestEviction { .. } => None,
+            SaturationPolicy::Evict => {
+                let (home, slots) = (self.index_for_key(key), self.table.capacity());
+                let victim = (home..slots).chain(0..home).find(|&i| self.table.slot(i).is_some());
+                if let Some(victim) = victim {
+                    let victim_key = *self.table.slot(victim).unwrap().1;
+                    self.remove_at(victim, victim_key);
+                }
+                match self.search(key) {
+                    SearchResult::NotFound(index, distance) => Some((index, distance)),
+                    SearchResult::Found(_) => unreachable!("evicted key {key} is still present"),
+                }
+            }
+        }
+    }
+
+    // whether one more entry is more than the saturation policy allows for
+    #[inline]
+    fn is_saturated(&self) -> bool {
+        match self.saturation {
+            SaturationPolicy::SuggestEviction { max_load_percent } => {
+                (self.len() + 1) * 100 > self.capacity() * max_load_percent.min(100) as usize
+            }
+            _ => self.is_full(),
+        }
+    }
+
+    #[inline]
+    fn admit_or_panic(&mut self, key: K, index: usize, distance: Distance) -> (usize, Distance) {
+        match self.admit(key, index, distance) {
+            Some(point) => point,
+            None if matches!(self.saturation, SaturationPolicy::SuggestEviction { .. }) => {
+                panic!("{}", InsertError::EvictionNeeded(key, (), Vec::new()))
+            }
+            None => panic!("{}", InsertError::Full(key, ())),
+        }
+    }
+
+    // every new entry of the map is stored through here
+    #[inline]
+    fn insert_at(&mut self, index: usize, key: K, value: V, distance: Distance) {
+        let key = self.canonical(key);
+        // the table insert may panic on a probe overflow, leaving the table unchanged
+        self.table.insert(index, key, value, distance);
+        self.shadow.insert(key);
+    }
+
+    // re-inserts an entry moved out of a table that's being rebuilt, keeping its tag
+    fn insert_tagged(&mut self, key: K, value: V, tag: u8) {
+        self.insert(key, value);
+        if cfg!(feature = "tags") {
+            if let Some(index) = self.find(key) {
+                self.table.set_tag(index, tag);
+            }
+        }
+    }
+
+    // every entry of the map is removed through here, except for 'clear'
+    #[inline]
+    fn remove_at(&mut self, index: usize, key: K) -> V {
+        let key = self.canonical(key);
+        self.shadow.remove(key);
+        self.table.remove(index)
     }
 }
 
 impl<K: IntKey, V> IntMap<K, V> {
+    /// Inserts the entry, returns the value it replaced, if any. Panics if the key is reserved or
+    /// the saturation policy rejects it, see `try_insert`.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// assert_eq!(m.insert(5u32, 'a'), None);
+    /// assert_eq!(m.insert(5, 'b'), Some('a'));
+    /// assert_eq!(m.get(5), Some(&'b'));
+    /// ```
     pub fn insert(&mut self, key: K, value: V) -> Option<V> {
-        match self.table.search(&key, self.index_for_key(key)) {
+        match self.try_insert(key, value) {
+            Ok(old) => old,
+            Err(err) => panic!("{err}"),
+        }
+    }
+
+    /// Same as `insert`, but hands the entry back instead of panicking if the key is reserved
+    /// or the saturation policy rejects it.
+    ///
+    /// ```
+    /// use intmap_rs::{IntMap, SaturationPolicy};
+    ///
+    /// let mut m = IntMap::with_capacity(1);
+    /// m.set_saturation_policy(SaturationPolicy::Reject);
+    /// assert_eq!(m.try_insert(1u32, 'a').ok(), Some(None));
+    /// assert_eq!(m.try_insert(2, 'b').unwrap_err().into_entry(), (2, 'b'));
+    /// ```
+    pub fn try_insert(&mut self, key: K, value: V) -> Result<Option<V>, InsertError<K, V>> {
+        let timer = self.profile.start(OpClass::Insert);
+        let result = self.insert_entry(key, value);
+        self.profile.stop(timer);
+        result
+    }
+
+    fn insert_entry(&mut self, key: K, value: V) -> Result<Option<V>, InsertError<K, V>> {
+        if self.is_reserved(key) {
+            return Err(InsertError::ReservedKey(key, value));
+        }
+        Ok(match self.search(key) {
             SearchResult::Found(index) => Some(std::mem::replace(&mut self.table[index], value)),
             SearchResult::NotFound(index, distance) => {
-                self.table.insert(index, key, value, distance);
+                let Some((index, distance)) = self.admit(key, index, distance) else {
+                    return Err(match self.saturation {
+                        SaturationPolicy::SuggestEviction { .. } => {
+                            let candidates = self.eviction_candidates(EVICTION_CANDIDATES);
+                            InsertError::EvictionNeeded(key, value, candidates)
+                        }
+                        _ => InsertError::Full(key, value),
+                    });
+                };
+                self.insert_at(index, key, value, distance);
                 None
             }
+        })
+    }
+
+    /// Bulk insert of `keys[i] => values[i]` pairs, later duplicates overwrite earlier ones.
+    /// Panics if the slices differ in length.
+    ///
+    /// The pairs are copied and sorted by home slot up front, in a few linear passes, then
+    /// inserted in that order, so the table is written front to back instead of at random, with
+    /// the home slots a few inserts ahead prefetched. Into an empty map they're written in one
+    /// sweep without any displacement, and a map in nontemporal mode, see
+    /// `set_nontemporal_clear`, writes them with streaming stores in whole cache lines, so a
+    /// bulk load into a table much larger than the LLC doesn't evict everything else.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity_nontemporal(1 << 10);
+    /// m.extend_from_slices(&[3u32, 9, 3], &[30u64, 90, 31]);
+    /// assert_eq!((m.len(), m.get(3), m.get(9)), (2, Some(&31), Some(&90)));
+    /// ```
+    pub fn extend_from_slices(&mut self, keys: &[K], values: &[V])
+    where
+        V: Copy,
+    {
+        assert_eq!(keys.len(), values.len(), "keys and values differ in length");
+        let order = self.sort_by_home(keys, values);
+        let indexing = self.indexing;
+        let home = |j: usize| indexing.home(order[j].0);
+        // the home slot of the pair a few inserts ahead; streamed slots aren't prefetched, their
+        // lines aren't read
+        let (prefetch_slot, stream) = (self.table.prefetcher(), self.is_nontemporal_clear());
+        let prefetch = |j: usize| {
+            if !stream && j + BULK_PREFETCH < order.len() {
+                prefetch_slot(home(j + BULK_PREFETCH));
+            }
+        };
+        // an empty map with no reserved key and no insert the saturation policy could turn
+        // down has nothing to check between the writes
+        let admits_all = match self.saturation {
+            SaturationPolicy::Overflow => true,
+            SaturationPolicy::Reject | SaturationPolicy::Evict => keys.len() <= self.capacity(),
+            SaturationPolicy::SuggestEviction { .. } => false,
+        };
+        if self.is_empty() && self.reserved.is_none() && admits_all {
+            // a key repeated later in its run of pairs sharing a home slot is overwritten there
+            let overwritten = |j: usize| {
+                let (key, run) = (order[j].0, home(j));
+                (j + 1..order.len()).take_while(|&l| home(l) == run).any(|l| order[l].0 == key)
+            };
+            let entries = (0..order.len()).filter(|&j| !overwritten(j)).map(|j| {
+                prefetch(j);
+                (home(j), order[j].0, order[j].1)
+            });
+            let shadow = &mut self.shadow;
+            self.table.fill_sorted(entries, |key| shadow.insert(key));
+        } else {
+            for (j, &(key, value)) in order.iter().enumerate() {
+                prefetch(j);
+                self.insert(key, value);
+            }
+        }
+    }
+
+    // the pairs with canonical keys, stably sorted by home slot, so a key's last pair is the
+    // last of them in its run of pairs sharing a home slot. A comparison sort of a large batch
+    // costs more than the inserts it orders, this takes one counting sort pass over the batch
+    // into buckets of 'BULK_BUCKET_SLOTS' home slots, then a counting sort of each bucket while
+    // it's in the cache. The values move along, so they're read in order, not at random.
+    fn sort_by_home(&self, keys: &[K], values: &[V]) -> Vec<(K, V)>
+    where
+        V: Copy,
+    {
+        let home = |key: K| self.index_for_key(key);
+        let bucket_slots = BULK_BUCKET_SLOTS.min(self.capacity().next_power_of_two());
+        let shift = bucket_slots.trailing_zeros();
+        let buckets = (self.capacity() >> shift) + 1;
+        let mut starts = vec![0usize; buckets + 1];
+        for &key in keys {
+            starts[(home(self.canonical(key)) >> shift) + 1] += 1;
+        }
+        for b in 1..=buckets {
+            starts[b] += starts[b - 1];
+        }
+        let mut order = Vec::with_capacity(keys.len());
+        let mut next = starts.clone();
+        let spare = order.spare_capacity_mut();
+        for (&key, &value) in keys.iter().zip(values) {
+            let key = self.canonical(key);
+            let b = home(key) >> shift;
+            spare[next[b]].write((key, value));
+            next[b] += 1;
+        }
+        // SAFETY: the buckets partition the batch, every pair was written once
+        unsafe { order.set_len(keys.len()) };
+
+        let mut scratch = Vec::new();
+        let mut counts = vec![0usize; bucket_slots + 1];
+        let slot = |key: K| home(key) & (bucket_slots - 1);
+        for b in 0..buckets {
+            let bucket = &mut order[starts[b]..starts[b + 1]];
+            counts.fill(0);
+            for &(key, _) in &*bucket {
+                counts[slot(key) + 1] += 1;
+            }
+            for s in 1..=bucket_slots {
+                counts[s] += counts[s - 1];
+            }
+            scratch.clear();
+            scratch.extend_from_slice(bucket);
+            for &(key, value) in &scratch {
+                bucket[counts[slot(key)]] = (key, value);
+                counts[slot(key)] += 1;
+            }
         }
+        order
     }
 
+    /// Removes the entry, returns its value if the key was present.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// m.insert(5u32, 'a');
+    /// assert_eq!(m.remove(5), Some('a'));
+    /// assert_eq!(m.remove(5), None);
+    /// ```
     pub fn remove(&mut self, key: K) -> Option<V> {
-        match self.table.search(&key, self.index_for_key(key)) {
-            SearchResult::Found(index) => Some(self.table.remove(index)),
+        let timer = self.profile.start(OpClass::Remove);
+        let old = match self.search(key) {
+            SearchResult::Found(index) => Some(self.remove_at(index, key)),
             SearchResult::NotFound(..) => None,
+        };
+        self.profile.stop(timer);
+        old
+    }
+
+    /// Removes all `keys` in one sweep over the table, returns how many were present. Instead
+    /// of a backward shift per key, entries behind removed ones move back once, which pays off
+    /// for large batches in a well-filled map, e.g. a log compaction: at load 0.9 and above
+    /// it's several times cheaper than `remove` per key. Below about half load the clusters
+    /// are short and per-key removal is faster. Keys sorted by home slot, such as ascending
+    /// keys under `with_capacity`, keep the lookups sequential; any order and duplicates are
+    /// handled.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(64);
+    /// for k in 0..60u32 {
+    ///     m.insert(k, k);
+    /// }
+    /// assert_eq!(m.remove_sorted(&[3, 4, 5, 5, 100]), 3);
+    /// assert_eq!(m.len(), 57);
+    /// assert!(!m.contains(4) && m.contains(6));
+    /// ```
+    pub fn remove_sorted(&mut self, keys: &[K]) -> usize {
+        // a bitmap of the slots to remove rather than a sorted index list: keys sharing a home
+        // slot come in any order, and sorting their slots cost more than the sweep saves
+        let mut marked = vec![0u64; self.table.capacity().div_ceil(64)];
+        for &key in keys {
+            if let SearchResult::Found(index) = self.search(key) {
+                marked[index / 64] |= 1 << (index % 64);
+            }
+        }
+        // only after the lookups, the shadow checks them against the map as it was
+        if cfg!(feature = "shadow") {
+            for (word, &bits) in marked.iter().enumerate() {
+                let mut bits = bits;
+                while bits != 0 {
+                    let index = word * 64 + bits.trailing_zeros() as usize;
+                    self.shadow.remove(*self.table.slot(index).unwrap().1);
+                    bits &= bits - 1;
+                }
+            }
         }
+        self.table.remove_marked(&marked, &mut Vec::new())
     }
 
-    pub fn get(&self, key: K) -> Option<&V> {
-        match self.table.search(&key, self.index_for_key(key)) {
-            SearchResult::Found(index) => Some(&self.table[index]),
-            SearchResult::NotFound(..) => None,
+    /// Inserts `value`, or if the key is already present combines it into the stored value with
+    /// `merge(old, new)`. Returns whether a merge happened.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut totals = IntMap::with_capacity(8);
+    /// assert!(!totals.merge_insert(1u32, 10, |old, new| *old += new));
+    /// assert!(totals.merge_insert(1, 5, |old, new| *old += new));
+    /// assert_eq!(totals.get(1), Some(&15));
+    /// ```
+    pub fn merge_insert(&mut self, key: K, value: V, merge: impl FnOnce(&mut V, V)) -> bool {
+        match self.search(key) {
+            SearchResult::Found(index) => {
+                merge(&mut self.table[index], value);
+                true
+            }
+            SearchResult::NotFound(index, distance) => {
+                self.assert_not_reserved(key);
+                let (index, distance) = self.admit_or_panic(key, index, distance);
+                self.insert_at(index, key, value, distance);
+                false
+            }
         }
     }
 
-    pub fn get_mut(&mut self, key: K) -> Option<&mut V> {
-        match self.table.search(&key, self.index_for_key(key)) {
-            SearchResult::Found(index) => Some(&mut self.table[index]),
-            SearchResult::NotFound(..) => None,
+    /// Removes the entry only if `predicate` holds for its current value, a compare-and-delete
+    /// in a single probe.
+    ///
+    /// ```
+    /// use intmap_rs::{IntMap, RemoveIf};
+    ///
+    /// let mut leases = IntMap::with_capacity(8);
+    /// leases.insert(1u32, "worker-a");
+    /// assert_eq!(leases.remove_if(1, |owner| *owner == "worker-b"), RemoveIf::Kept);
+    /// let removed = leases.remove_if(1, |owner| *owner == "worker-a");
+    /// assert_eq!(removed, RemoveIf::Removed("worker-a"));
+    /// assert_eq!(leases.remove_if(1, |_| true), RemoveIf::Missing);
+    /// ```
+    pub fn remove_if(&mut self, key: K, predicate: impl FnOnce(&V) -> bool) -> RemoveIf<V> {
+        match self.search(key) {
+            SearchResult::Found(index) if predicate(&self.table[index]) => {
+                RemoveIf::Removed(self.remove_at(index, key))
+            }
+            SearchResult::Found(_) => RemoveIf::Kept,
+            SearchResult::NotFound(..) => RemoveIf::Missing,
         }
     }
 
-    pub fn contains(&self, key: K) -> bool {
-        self.table.search(&key, self.index_for_key(key)).is_found()
+    /// The value stored for `key`, if any.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// m.insert(2u64, "two");
+    /// assert_eq!((m.get(2), m.get(3)), (Some(&"two"), None));
+    /// ```
+    pub fn get(&self, key: K) -> Option<&V> {
+        self.find_timed(key).map(|index| &self.table[index])
     }
 
-    pub fn keys(&self) -> Keys<K> {
-        self.table.keys()
+    /// Same as `get`, also returning how far from its home slot the entry is stored, i.e. how
+    /// many extra slots a lookup of the key walks.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// // both keys have home slot 1
+    /// m.insert(1u32, 'a');
+    /// m.insert(9, 'b');
+    /// assert_eq!(m.get_with_distance(1), Some((&'a', 0)));
+    /// assert_eq!(m.get_with_distance(9), Some((&'b', 1)));
+    /// assert_eq!(m.get_with_distance(17), None);
+    /// ```
+    pub fn get_with_distance(&self, key: K) -> Option<(&V, usize)> {
+        match self.search(key) {
+            SearchResult::Found(index) => {
+                Some((&self.table[index], index - self.index_for_key(key)))
+            }
+            SearchResult::NotFound(..) => None,
+        }
     }
-}
 
-impl<K: IntKey, V> IntMap<K, V> {
-    pub fn len(&self) -> usize {
-        self.table.len()
+    /// The value stored for `key`, mutably, if any.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// m.insert(2u32, vec![1]);
+    /// m.get_mut(2).unwrap().push(2);
+    /// assert_eq!(m.get(2).map(Vec::as_slice), Some(&[1, 2][..]));
+    /// assert!(m.get_mut(3).is_none());
+    /// ```
+    pub fn get_mut(&mut self, key: K) -> Option<&mut V> {
+        self.find_timed(key).map(|index| &mut self.table[index])
     }
 
+    /// Starts loading the key's home slot into the cache without waiting for it. Issued a few
+    /// keys ahead of the lookups in a batch, it overlaps their cache misses. A no-op where the
+    /// target has no prefetch instruction.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(1 << 10);
+    /// for k in 0..500u32 {
+    ///     m.insert(k * 7, k);
+    /// }
+    /// let batch = [7, 70, 700, 3];
+    /// let mut found = 0;
+    /// for (i, &key) in batch.iter().enumerate() {
+    ///     if let Some(&next) = batch.get(i + 2) {
+    ///         m.prefetch(next);
+    ///     }
+    ///     found += m.contains(key) as usize;
+    /// }
+    /// assert_eq!(found, 3);
+    /// ```
     #[inline]
-    pub fn capacity(&self) -> usize {
-        1 + AsPrimitive::<usize>::as_(self.index_mask)
+    pub fn prefetch(&self, key: K) {
+        self.table.prefetch(self.index_for_key(key));
+    }
+
+    /// Walks the probe paths of `keys` only to bring their slots into the cache and their pages
+    /// into the TLB, e.g. right before a traffic spike, so the first real lookups don't miss.
+    /// The values of present keys are prefetched too. Nothing is returned or changed.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(1 << 10);
+    /// for k in 0..100u32 {
+    ///     m.insert(k, k);
+    /// }
+    /// m.warm(0..200);
+    /// assert_eq!(m.len(), 100);
+    /// ```
+    pub fn warm(&self, keys: impl IntoIterator<Item = K>) {
+        for key in keys {
+            if let Some(index) = std::hint::black_box(self.find(key)) {
+                self.table.prefetch(index);
+            }
+        }
     }
 
+    /// # Safety
+    ///
+    /// `key` must be present in the map, calling this with a missing key is undefined behavior.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// m.insert(4u32, "four");
+    /// assert!(m.contains(4));
+    /// // SAFETY: the key was just checked to be present
+    /// assert_eq!(unsafe { *m.get_unchecked(4) }, "four");
+    /// ```
     #[inline]
-    pub fn is_empty(&self) -> bool {
-        self.len() == 0
+    pub unsafe fn get_unchecked(&self, key: K) -> &V {
+        debug_assert!(self.contains(key), "get_unchecked: key {} is not in the map", key);
+        let key = self.canonical(key);
+        &self.table[self.table.search_unchecked(&key, self.index_for_key(key))]
     }
 
+    /// # Safety
+    ///
+    /// `key` must be present in the map, calling this with a missing key is undefined behavior.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// m.insert(4u32, 40);
+    /// // SAFETY: the key was just inserted
+    /// unsafe { *m.get_unchecked_mut(4) += 2 };
+    /// assert_eq!(m.get(4), Some(&42));
+    /// ```
     #[inline]
-    pub fn is_full(&self) -> bool {
-        self.len() == self.capacity()
+    pub unsafe fn get_unchecked_mut(&mut self, key: K) -> &mut V {
+        debug_assert!(self.contains(key), "get_unchecked_mut: key {} is not in the map", key);
+        let key = self.canonical(key);
+        let index = self.table.search_unchecked(&key, self.index_for_key(key));
+        &mut self.table[index]
     }
 
-    pub fn probes(&self) -> Vec<usize> {
-        self.keys().enumerate().map(|(i, k)| i - self.index_for_key(*k)).collect()
+    /// Whether an entry is stored for `key`.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// m.insert(-1i64, ());
+    /// assert!(m.contains(-1) && !m.contains(1));
+    /// ```
+    pub fn contains(&self, key: K) -> bool {
+        self.find_timed(key).is_some()
     }
 
+    /// Searches for `key` once, the returned [`Probe`] reads, updates or inserts at the found
+    /// position without searching again.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut counts = IntMap::with_capacity(8);
+    /// for word in [3u32, 1, 3] {
+    ///     *counts.probe(word).or_insert(0) += 1;
+    /// }
+    /// assert_eq!((counts.get(3), counts.get(1)), (Some(&2), Some(&1)));
+    /// ```
     #[inline]
-    pub fn avg_probes_count(&self) -> f32 {
-        (self.probes().into_iter().sum::<usize>() as f32) / self.len() as f32
+    pub fn probe(&mut self, key: K) -> Probe<'_, K, V> {
+        let result = self.search(key);
+        Probe::new(self, key, result)
     }
 
-    #[inline]
-    pub fn load_factor(&self) -> f32 {
-        self.len() as f32 / self.capacity() as f32
+    /// Returns the value for `key`, loading it with `f` on a miss; the slot found by the lookup
+    /// is reused for the insert. A failed load is returned as is and leaves the map unchanged.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut cache = IntMap::with_capacity(8);
+    /// let load = |id: u32| if id < 100 { Ok(format!("user {id}")) } else { Err("no such user") };
+    /// assert_eq!(cache.get_or_try_insert_with(7, || load(7)).unwrap().as_str(), "user 7");
+    /// assert_eq!(cache.get_or_try_insert_with(700, || load(700)), Err("no such user"));
+    /// assert_eq!(cache.len(), 1);
+    /// ```
+    pub fn get_or_try_insert_with<E>(
+        &mut self,
+        key: K,
+        f: impl FnOnce() -> Result<V, E>,
+    ) -> Result<&mut V, E> {
+        self.probe(key).or_try_insert_with(f)
     }
-}
 
-impl<K: Clone, V: Clone> Clone for IntMap<K, V> {
-    #[inline]
-    fn clone(&self) -> Self {
-        Self { table: self.table.clone(), index_mask: self.index_mask.clone() }
+    /// A map with the same keys and `f(value)` for values, built by copying the key layout
+    /// instead of inserting every key again.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// m.insert(1u32, 10u32);
+    /// m.insert(2, 20);
+    /// let labels = m.map_values_to(|v| format!("#{v}"));
+    /// assert_eq!(labels.get(2).map(String::as_str), Some("#20"));
+    /// assert_eq!(labels.len(), 2);
+    /// ```
+    pub fn map_values_to<U>(&self, mut f: impl FnMut(&V) -> U) -> IntMap<K, U> {
+        IntMap {
+            table: self.table.map_values_to(|_, v| f(v)),
+            indexing: self.indexing,
+            reserved: self.reserved,
+            saturation: self.saturation,
+            canonicalize: self.canonicalize,
+            shadow: self.shadow.clone(),
+            profile: Profile::new(),
+        }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// A map with the same keys and fresh `fill(key)` values, e.g. per-epoch state over a fixed
+    /// key set; like `map_values_to`, no key is probed again.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// m.insert(1u32, "config");
+    /// m.insert(2, "config");
+    /// let hits = m.clone_structure(|_| 0u64);
+    /// assert_eq!((hits.len(), hits.get(1), hits.get(3)), (2, Some(&0), None));
+    /// ```
+    pub fn clone_structure<U>(&self, mut fill: impl FnMut(K) -> U) -> IntMap<K, U> {
+        IntMap {
+            table: self.table.map_values_to(|k, _| fill(*k)),
+            indexing: self.indexing,
+            reserved: self.reserved,
+            saturation: self.saturation,
+            canonicalize: self.canonicalize,
+            shadow: self.shadow.clone(),
+            profile: Profile::new(),
+        }
+    }
 
-    #[test]
-    fn empty() {
-        let m = IntMap::<i32, u32>::with_capacity(10);
-        assert!(m.is_empty());
-        assert!(!m.is_full());
-        assert_eq!(m.len(), 0);
-        // power of two capacity
-        assert_eq!(m.capacity(), 16);
+    /// Converts the map to another key type, e.g. `u64` keys to `u32` once they're known to fit;
+    /// fails with the map handed back if some key doesn't. Maps with power-of-two capacities keep
+    /// every entry in place and only reallocate the keys lane, `with_exact_capacity` maps are
+    /// rebuilt. A reserved key that doesn't fit the new type is dropped, so is the canonicalizer.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// m.insert(3u64, 'c');
+    /// let narrow = m.convert_keys::<u32>().ok().unwrap();
+    /// assert_eq!(narrow.get(3u32), Some(&'c'));
+    ///
+    /// let mut wide = IntMap::with_capacity(8);
+    /// wide.insert(1u64 << 40, 'x');
+    /// let err = wide.convert_keys::<u32>().err().unwrap();
+    /// assert_eq!(err.key(), 1 << 40);
+    /// assert_eq!(err.into_map().len(), 1);
+    /// ```
+    pub fn convert_keys<K2: IntKey>(self) -> Result<IntMap<K2, V>, KeyOverflow<K, V>> {
+        if let Some(key) = self.keys().copied().find(|&k| <K2 as NumCast>::from(k).is_none()) {
+            return Err(KeyOverflow { key, map: Box::new(self) });
+        }
+        let convert = |key: K| <K2 as NumCast>::from(key).unwrap();
+        let reserved = self.reserved.and_then(<K2 as NumCast>::from);
+        match self.indexing {
+            Indexing::Mask(mask) => {
+                // numerically equal keys share their low bits, so home slots and the order
+                // within clusters don't change
+                let mut shadow = Shadow::default();
+                self.keys().for_each(|&key| shadow.insert(convert(key)));
+                Ok(IntMap {
+                    indexing: Indexing::Mask(convert(mask)),
+                    table: self.table.convert_keys(|&key| convert(key)),
+                    reserved,
+                    saturation: self.saturation,
+                    canonicalize: None,
+                    shadow,
+                    profile: Profile::new(),
+                })
+            }
+            Indexing::FastRange(capacity, _) => {
+                let mut map = self.empty_like_with(capacity);
+                let mut table = self.table;
+                table.drain_with(|key, value, tag| map.insert_tagged(convert(key), value, tag));
+                map.reserved = reserved;
+                map.saturation = self.saturation;
+                Ok(map)
+            }
+        }
     }
 
-    #[test]
-    fn insert() {
-        let mut m: IntMap<i32, i32> = IntMap::with_capacity(2);
-        assert!(m.insert(1, 2).is_none());
-        assert!(m.contains(1));
-        assert!(!m.contains(0));
-        assert_eq!(*m.get(1).unwrap(), 2);
-        *m.get_mut(1).unwrap() = 4;
-        assert!(m.contains(1));
-        assert_eq!(*m.get(1).unwrap(), 4);
+    /// Replaces every key with `f(key)`, rebuilding the table in a single pass. If `f` maps
+    /// several keys to the same key, the entry visited last in storage order wins.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// m.insert(1u32, 'a');
+    /// m.insert(2, 'b');
+    /// m.rekey_all(|k| k + 100);
+    /// assert_eq!((m.get(101), m.get(102), m.get(1)), (Some(&'a'), Some(&'b'), None));
+    /// ```
+    pub fn rekey_all(&mut self, f: impl Fn(K) -> K) {
+        let table = self.table.empty_like();
+        let mut old = std::mem::replace(&mut self.table, table);
+        self.shadow.clear();
+        old.drain_with(|key, value, tag| self.insert_tagged(f(key), value, tag));
     }
 
-    #[test]
-    fn insert_overwrite() {
-        let mut m = IntMap::with_capacity(2);
-        m.insert(1, 2);
-        assert_eq!(m.insert(1, 3), Some(2));
-        assert!(m.contains(1));
-        assert_eq!(m.len(), 1);
-        assert_eq!(*m.get(1).unwrap(), 3);
+    /// Keeps only the entries for which `f` returns true, in a single pass over the table.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// for k in 0..8u32 {
+    ///     m.insert(k, k * k);
+    /// }
+    /// m.retain(|k, v| {
+    ///     *v += 1;
+    ///     k % 2 == 0
+    /// });
+    /// assert_eq!((m.len(), m.get(2), m.get(3)), (4, Some(&5), None));
+    /// ```
+    pub fn retain(&mut self, mut f: impl FnMut(K, &mut V) -> bool) {
+        let mut index = 0;
+        while index < self.table.capacity() {
+            let removed = match self.table.get_by_slot_mut(index) {
+                Some((&key, value)) => (!f(key, value)).then_some(key),
+                None => None,
+            };
+            match removed {
+                // the backward shift refills 'index' with an entry that's not visited yet
+                Some(key) => drop(self.remove_at(index, key)),
+                None => index += 1,
+            }
+        }
     }
 
-    #[test]
-    fn insert_some() {
-        const N: u32 = 128;
-        let mut m = IntMap::with_capacity(N as u32);
-        (0..N).for_each(|i| {
-            m.insert(i, i);
-        });
-        assert_eq!(m.len(), N as usize);
-        for i in 0..N {
-            assert!(m.contains(i));
-            assert_eq!(*m.get(i).unwrap(), i);
+    /// `retain` pushing the removed entries onto `removed` instead of dropping them, in storage
+    /// order. Reusing the buffer across calls saves an allocation per prune.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// for k in 0..6u32 {
+    ///     m.insert(k, k * 10);
+    /// }
+    /// let mut removed = Vec::new();
+    /// m.retain_into(|k, _| k >= 4, &mut removed);
+    /// removed.sort_unstable();
+    /// assert_eq!(removed, [(0, 0), (1, 10), (2, 20), (3, 30)]);
+    /// assert_eq!(m.len(), 2);
+    /// ```
+    pub fn retain_into(&mut self, mut f: impl FnMut(K, &mut V) -> bool, removed: &mut Vec<(K, V)>) {
+        let mut index = 0;
+        while index < self.table.capacity() {
+            let key = match self.table.get_by_slot_mut(index) {
+                Some((&key, value)) => (!f(key, value)).then_some(key),
+                None => None,
+            };
+            match key {
+                Some(key) => removed.push((key, self.remove_at(index, key))),
+                None => index += 1,
+            }
         }
     }
 
-    #[test]
-    fn insert_sparse_keys() {
-        let mut m = IntMap::with_capacity(4);
-        m.insert(0, 1);
-        m.insert(3, 1);
-        assert_eq!(m.keys().cloned().collect::<Vec<u32>>(), &[0, 3]);
+    /// Moves every entry onto the end of `out` in storage order, leaving the map empty with
+    /// its capacity.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// m.insert(2u32, 'b');
+    /// m.insert(1, 'a');
+    /// let mut out = vec![(0, '-')];
+    /// m.drain_into(&mut out);
+    /// out[1..].sort_unstable();
+    /// assert_eq!(out, [(0, '-'), (1, 'a'), (2, 'b')]);
+    /// assert!(m.is_empty() && m.capacity() == 8);
+    /// ```
+    pub fn drain_into(&mut self, out: &mut Vec<(K, V)>) {
+        out.reserve(self.len());
+        self.shadow.clear();
+        self.table.drain_with(|key, value, _| out.push((key, value)));
+    }
+
+    /// `retain` calling `yield_now` after every `chunk` visited slots, see `clear_yielding`. The
+    /// map is consistent at every call.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(1 << 10);
+    /// for k in 0..1000u32 {
+    ///     m.insert(k, ());
+    /// }
+    /// let mut yields = 0;
+    /// m.retain_yielding(128, |k, _| k % 10 == 0, || yields += 1);
+    /// assert_eq!(m.len(), 100);
+    /// assert!(yields > 0);
+    /// ```
+    pub fn retain_yielding(
+        &mut self,
+        chunk: usize,
+        mut f: impl FnMut(K, &mut V) -> bool,
+        mut yield_now: impl FnMut(),
+    ) {
+        let chunk = chunk.max(1);
+        let mut index = 0;
+        let mut visited = 0;
+        while index < self.table.capacity() {
+            let removed = match self.table.get_by_slot_mut(index) {
+                Some((&key, value)) => (!f(key, value)).then_some(key),
+                None => None,
+            };
+            match removed {
+                Some(key) => drop(self.remove_at(index, key)),
+                None => index += 1,
+            }
+            visited += 1;
+            if visited % chunk == 0 && index < self.table.capacity() {
+                yield_now();
+            }
+        }
+    }
+
+    /// Aggregates `(key, item)` pairs per key in a single pass: missing keys are initialized
+    /// with `init(key)`, then `f` folds the item into the key's accumulator.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let orders = [(1u32, 30u64), (2, 5), (1, 12)];
+    /// let mut totals = IntMap::with_capacity(8);
+    /// totals.group_reduce(orders, |_| 0, |total, amount| *total += amount);
+    /// assert_eq!((totals.get(1), totals.get(2)), (Some(&42), Some(&5)));
+    /// ```
+    pub fn group_reduce<T, I, F, R>(&mut self, items: I, mut init: F, mut f: R)
+    where
+        I: IntoIterator<Item = (K, T)>,
+        F: FnMut(K) -> V,
+        R: FnMut(&mut V, T),
+    {
+        for (key, item) in items {
+            f(self.probe(key).or_insert_with(|| init(key)), item);
+        }
+    }
+
+    /// Per-group aggregates of the values in a single pass over the table: every entry is
+    /// folded by `f` into the accumulator of `group(key)`, which starts as `A::default()`. The
+    /// groups map mixes its keys like `with_exact_capacity`, so any group keys fit.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// // keys are a tenant in the high bits and an id in the low ones
+    /// let mut sizes = IntMap::with_capacity(16);
+    /// sizes.insert((1u64 << 32) | 1, 100u64);
+    /// sizes.insert((1 << 32) | 2, 50);
+    /// sizes.insert((2 << 32) | 1, 7);
+    /// let per_tenant = sizes.reduce_by(|k| k >> 32, |total: &mut u64, size| *total += size);
+    /// assert_eq!((per_tenant.get(1), per_tenant.get(2)), (Some(&150), Some(&7)));
+    /// ```
+    pub fn reduce_by<G: IntKey, A: Default>(
+        &self,
+        mut group: impl FnMut(K) -> G,
+        f: impl FnMut(&mut A, &V),
+    ) -> IntMap<G, A> {
+        // never more groups than entries, all of them if every key is its own group; group keys
+        // are mixed, the caller picks them and they may share their low bits
+        let mut groups = IntMap::with_exact_capacity(Capacity::for_entries(self.len(), 0.8));
+        groups.group_reduce(self.iter().map(|(k, v)| (group(*k), v)), |_| A::default(), f);
+        groups
+    }
+
+    /// Keys in an unspecified order, the order of `iter`.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// m.insert(2u32, 'b');
+    /// m.insert(1, 'a');
+    /// let mut keys: Vec<_> = m.keys().copied().collect();
+    /// keys.sort_unstable();
+    /// assert_eq!(keys, [1, 2]);
+    /// ```
+    pub fn keys(&self) -> Keys<'_, K, V> {
+        self.table.keys()
+    }
+
+    /// Values in an unspecified order, the order of `iter`.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// m.insert(2u32, 20);
+    /// m.insert(1, 10);
+    /// assert_eq!(m.values().sum::<i32>(), 30);
+    /// ```
+    pub fn values(&self) -> Values<'_, K, V> {
+        self.table.values()
+    }
+
+    /// Entries in an unspecified order, currently `iter_storage_order`.
+    ///
+    /// Like all the map's iterators it checks `version` on every step: if the map was changed
+    /// behind the borrow, through raw pointers or foreign code, a debug build panics and a
+    /// release build ends the iteration.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(4);
+    /// m.insert(2u32, 'b');
+    /// m.insert(1, 'a');
+    /// let mut entries: Vec<_> = m.iter().map(|(k, v)| (*k, *v)).collect();
+    /// entries.sort_unstable();
+    /// assert_eq!(entries, [(1, 'a'), (2, 'b')]);
+    /// ```
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        self.table.iter()
+    }
+
+    /// Entries in slot (memory) order, the fastest way to visit every entry. The order follows
+    /// the home slots and so depends on the capacity and indexing, not on the keys' values.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// for k in [5u32, 1, 3] {
+    ///     m.insert(k, ());
+    /// }
+    /// // under `with_capacity` small keys are their own home slots
+    /// let keys: Vec<_> = m.iter_storage_order().map(|(k, _)| *k).collect();
+    /// assert_eq!(keys, [1, 3, 5]);
+    /// ```
+    pub fn iter_storage_order(&self) -> Iter<'_, K, V> {
+        self.table.iter()
+    }
+
+    /// Entries sorted by key. Collects and sorts the entries upfront, `O(n log n)` and one
+    /// allocation every call.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// for k in [9u32, 1, 17] {
+    ///     m.insert(k, ());
+    /// }
+    /// let keys: Vec<_> = m.iter_key_order().map(|(k, _)| *k).collect();
+    /// assert_eq!(keys, [1, 9, 17]);
+    /// ```
+    pub fn iter_key_order(&self) -> std::vec::IntoIter<(&K, &V)> {
+        let mut entries = self.table.iter().collect::<Vec<_>>();
+        entries.sort_unstable_by_key(|(k, _)| **k);
+        entries.into_iter()
+    }
+
+    /// Entries along with the index of the slot holding them. Slot indices stay valid for as
+    /// long as the map isn't modified, see `get_by_slot`.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// m.insert(1u32, 'a');
+    /// m.insert(9, 'b');
+    /// let slots: Vec<_> = m.iter_slots().collect();
+    /// assert_eq!(slots, [(1, 1, &'a'), (2, 9, &'b')]);
+    /// assert_eq!(m.get_by_slot(2), Some((9, &'b')));
+    /// ```
+    pub fn iter_slots(&self) -> SlotIter<'_, K, V> {
+        self.table.iter_slots()
+    }
+
+    /// Entry stored in the slot `index`, none if the slot is empty or out of range.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// m.insert(3u32, 'c');
+    /// assert_eq!(m.get_by_slot(3), Some((3, &'c')));
+    /// assert_eq!((m.get_by_slot(4), m.get_by_slot(1000)), (None, None));
+    /// ```
+    pub fn get_by_slot(&self, index: usize) -> Option<(K, &V)> {
+        self.table.get_by_slot(index).map(|(k, v)| (*k, v))
+    }
+
+    /// The entry's tag byte, a small piece of per-entry state such as a connection state kept
+    /// next to the value instead of inside it. Entries start with tag 0.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// m.insert(1u32, "conn");
+    /// assert_eq!(m.tag(1), Some(0));
+    /// m.set_tag(1, 2);
+    /// assert_eq!((m.tag(1), m.tag(2)), (Some(2), None));
+    /// ```
+    #[cfg(feature = "tags")]
+    pub fn tag(&self, key: K) -> Option<u8> {
+        self.find(key).map(|index| self.table.tag(index))
+    }
+
+    /// Sets the entry's tag, returns false if the key is missing.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(4);
+    /// m.insert(1u32, ());
+    /// assert!(m.set_tag(1, 3) && !m.set_tag(2, 3));
+    /// assert_eq!((m.tag(1), m.tag(2)), (Some(3), None));
+    /// ```
+    #[cfg(feature = "tags")]
+    pub fn set_tag(&mut self, key: K, tag: u8) -> bool {
+        self.find(key).map(|index| self.table.set_tag(index, tag)).is_some()
+    }
+
+    /// Entries with the given tag, in storage order.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// for k in 0..4u32 {
+    ///     m.insert(k, ());
+    ///     m.set_tag(k, (k % 2) as u8);
+    /// }
+    /// let mut odd: Vec<_> = m.iter_tagged(1).map(|(k, _)| *k).collect();
+    /// odd.sort_unstable();
+    /// assert_eq!(odd, [1, 3]);
+    /// ```
+    #[cfg(feature = "tags")]
+    pub fn iter_tagged(&self, tag: u8) -> impl Iterator<Item = (&K, &V)> {
+        self.table
+            .slots()
+            .filter(move |&(index, _, _)| self.table.tag(index) == tag)
+            .map(|(_, key, value)| (key, value))
+    }
+
+    /// Number of values matching `f`.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// for k in 0..5u32 {
+    ///     m.insert(k, k * k);
+    /// }
+    /// assert_eq!(m.count_values(|v| *v > 3), 3);
+    /// ```
+    pub fn count_values(&self, f: impl FnMut(&V) -> bool) -> usize {
+        self.table.count_values(f)
+    }
+
+    /// Whether some value matches `f`, stops at the first match.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// m.insert(1u32, "idle");
+    /// m.insert(2, "busy");
+    /// assert!(m.any_value(|v| *v == "busy") && !m.any_value(|v| v.is_empty()));
+    /// ```
+    pub fn any_value(&self, f: impl FnMut(&V) -> bool) -> bool {
+        self.table.any_value(f)
+    }
+
+    /// Whether every value matches `f`, stops at the first mismatch.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// m.insert(1u32, 2);
+    /// m.insert(2, 4);
+    /// assert!(m.all_values(|v| v % 2 == 0) && !m.all_values(|v| *v > 2));
+    /// ```
+    pub fn all_values(&self, mut f: impl FnMut(&V) -> bool) -> bool {
+        !self.table.any_value(|v| !f(v))
+    }
+
+    /// Keys in iteration order, copied into an exactly sized `Vec`.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// m.insert(2u32, ());
+    /// m.insert(1, ());
+    /// let mut keys = m.keys_to_vec();
+    /// keys.sort_unstable();
+    /// assert_eq!((keys, m.keys_to_vec().capacity()), (vec![1, 2], 2));
+    /// ```
+    pub fn keys_to_vec(&self) -> Vec<K> {
+        self.table.keys_to_vec()
+    }
+
+    /// Values in iteration order, copied into an exactly sized `Vec`.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// m.insert(2u32, 20u8);
+    /// m.insert(1, 10);
+    /// let mut values = m.values_to_vec();
+    /// values.sort_unstable();
+    /// assert_eq!(values, [10, 20]);
+    /// ```
+    pub fn values_to_vec(&self) -> Vec<V>
+    where
+        V: Copy,
+    {
+        self.table.values_to_vec()
+    }
+
+    /// Entries present in both maps, iterates the smaller map and probes the larger one.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let (mut names, mut ages) = (IntMap::with_capacity(8), IntMap::with_capacity(8));
+    /// names.insert(1u32, "ada");
+    /// names.insert(2, "bob");
+    /// ages.insert(2, 41);
+    /// ages.insert(3, 29);
+    /// let joined: Vec<_> = names.inner_join(&ages).collect();
+    /// assert_eq!(joined, [(2, &"bob", &41)]);
+    /// ```
+    pub fn inner_join<'a, V2>(&'a self, other: &'a IntMap<K, V2>) -> InnerJoin<'a, K, V, V2> {
+        InnerJoin::new(self, other)
+    }
+
+    /// Every entry of `self` paired with the value for the same key in `other`, if any.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let (mut names, mut ages) = (IntMap::with_capacity(8), IntMap::with_capacity(8));
+    /// names.insert(1u32, "ada");
+    /// names.insert(2, "bob");
+    /// ages.insert(2, 41);
+    /// let mut joined: Vec<_> = names.left_join(&ages).collect();
+    /// joined.sort_unstable();
+    /// assert_eq!(joined, [(1, &"ada", None), (2, &"bob", Some(&41))]);
+    /// ```
+    pub fn left_join<'a, V2>(&'a self, other: &'a IntMap<K, V2>) -> LeftJoin<'a, K, V, V2> {
+        LeftJoin::new(self, other)
+    }
+
+    /// Every key of either map once, with its value in each, e.g. to build a merged map. Both
+    /// tables are scanned in alternating blocks of slots and keys are looked up in the other
+    /// map's block just scanned, so neither map is probed at random. That needs maps built the
+    /// same way, e.g. with the same capacity: otherwise their blocks don't line up, `self` is
+    /// scanned whole before `other` and the random lookups are prefetched a few slots ahead.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let (mut a, mut b) = (IntMap::with_capacity(8), IntMap::with_capacity(8));
+    /// a.insert(1u32, 'a');
+    /// a.insert(2, 'b');
+    /// b.insert(2, 'B');
+    /// b.insert(3, 'C');
+    /// let mut merged: Vec<_> = a.merge_iter(&b).collect();
+    /// merged.sort_unstable();
+    /// assert_eq!(
+    ///     merged,
+    ///     [(1, Some(&'a'), None), (2, Some(&'b'), Some(&'B')), (3, None, Some(&'C'))]
+    /// );
+    /// ```
+    pub fn merge_iter<'a, V2>(&'a self, other: &'a IntMap<K, V2>) -> MergeIter<'a, K, V, V2> {
+        MergeIter::new(self, other)
+    }
+}
+
+impl<K: IntKey, V> IntMap<K, V> {
+    /// Number of entries stored.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// m.insert(1u32, ());
+    /// m.insert(1, ());
+    /// m.insert(2, ());
+    /// assert_eq!(m.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+
+    /// Changes whenever a key is added or removed, or the map is cleared; replacing or
+    /// updating a value in place leaves it alone. Iterators record it and check it on every step, see
+    /// `iter`. A clone starts over at its own count.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// let v0 = m.version();
+    /// m.insert(1u32, 'a');
+    /// let v1 = m.version();
+    /// m.insert(1, 'b');
+    /// assert!(v0 != v1 && m.version() == v1);
+    /// ```
+    pub fn version(&self) -> u64 {
+        self.table.version()
+    }
+
+    /// Ticks spent in the `class` operations since the map was made or `reset_profile`. A
+    /// clone starts with an empty profile.
+    ///
+    /// ```
+    /// use intmap_rs::{IntMap, OpClass};
+    ///
+    /// let mut m = IntMap::with_capacity(64);
+    /// for k in 0..32u32 {
+    ///     m.insert(k, k);
+    /// }
+    /// let inserts = m.profile(OpClass::Insert);
+    /// assert_eq!(inserts.count(), 32);
+    /// println!("insert: {inserts}");
+    /// ```
+    #[cfg(feature = "profiling")]
+    pub fn profile(&self, class: OpClass) -> OpProfile {
+        self.profile.get(class)
+    }
+
+    /// Empties the profile, see `profile`.
+    ///
+    /// ```
+    /// use intmap_rs::{IntMap, OpClass};
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// m.insert(1u32, ());
+    /// m.reset_profile();
+    /// assert_eq!(m.profile(OpClass::Insert).count(), 0);
+    /// ```
+    #[cfg(feature = "profiling")]
+    pub fn reset_profile(&self) {
+        self.profile.reset();
+    }
+
+    /// Number of home slots, the entries the map holds at load factor 1.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// assert_eq!(IntMap::<u32, ()>::with_capacity(1000).capacity(), 1024);
+    /// assert_eq!(IntMap::<u32, ()>::with_exact_capacity(1000).capacity(), 1000);
+    /// ```
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        match self.indexing {
+            Indexing::Mask(mask) => 1 + AsPrimitive::<u32>::as_(mask) as usize,
+            Indexing::FastRange(capacity, _) => capacity as usize,
+        }
+    }
+
+    /// Whether the map holds no entries.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// assert!(m.is_empty());
+    /// m.insert(1u32, ());
+    /// assert!(!m.is_empty());
+    /// ```
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Entries are stored less than this many slots away from their home slot.
+    ///
+    /// ```
+    /// use intmap_rs::{IntMap, MAX_PROBES};
+    ///
+    /// assert_eq!(IntMap::<u32, ()>::with_capacity(8).probe_limit(), MAX_PROBES as usize);
+    /// assert_eq!(IntMap::<u32, ()>::with_probe_limit(8, 16).probe_limit(), 16);
+    /// ```
+    #[inline]
+    pub fn probe_limit(&self) -> usize {
+        self.table.probe_limit() as usize
+    }
+
+    /// Slots between the farthest entry and its home slot, a scan of the metadata lane.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// for k in [0u32, 8, 16, 3] {
+    ///     m.insert(k, ());
+    /// }
+    /// // 0, 8 and 16 share home slot 0
+    /// assert_eq!(m.max_displacement(), 2);
+    /// ```
+    pub fn max_displacement(&self) -> usize {
+        self.table.max_distance().max(0) as usize
+    }
+
+    /// Whether the map holds `capacity` entries; more fit into the overflow region unless the
+    /// saturation policy says otherwise.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(2);
+    /// m.insert(1u32, ());
+    /// assert!(!m.is_full());
+    /// m.insert(2, ());
+    /// assert!(m.is_full());
+    /// ```
+    #[inline]
+    pub fn is_full(&self) -> bool {
+        self.len() == self.capacity()
+    }
+
+    /// Slots of the table, the home slots plus the overflow region past the last one.
+    ///
+    /// ```
+    /// use intmap_rs::{IntMap, OVERFLOW_SLOTS};
+    ///
+    /// let m = IntMap::<u32, ()>::with_capacity(64);
+    /// assert_eq!(m.slots().get(), 64 + OVERFLOW_SLOTS);
+    /// ```
+    #[inline]
+    pub fn slots(&self) -> Slots {
+        Slots::new(self.table.capacity())
+    }
+
+    /// Bytes allocated by the table, including the probe overflow region. The whole table is
+    /// allocated upfront, so this doesn't depend on the number of stored elements.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::<u32, u64>::with_capacity(1 << 10);
+    /// let empty = m.memory_usage();
+    /// m.insert(1, 1);
+    /// assert!(empty >= m.slots().get() * (4 + 8) && m.memory_usage() == empty);
+    /// ```
+    #[inline]
+    pub fn memory_usage(&self) -> usize {
+        self.table.memory_usage()
+    }
+
+    /// How far each entry is stored from its home slot, in storage order.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// for k in [0u32, 8, 3] {
+    ///     m.insert(k, ());
+    /// }
+    /// assert_eq!(m.probes(), [0, 1, 0]);
+    /// ```
+    pub fn probes(&self) -> Vec<usize> {
+        self.table.occupied().map(|(i, k)| i - self.index_for_key(*k)).collect()
+    }
+
+    /// Keys of the `n` entries stored farthest from their home slots, farthest first. Removing
+    /// them shortens the longest probe sequences the most, see
+    /// `SaturationPolicy::SuggestEviction`.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// for k in [0u32, 8, 16, 5] {
+    ///     m.insert(k, ());
+    /// }
+    /// assert_eq!(m.eviction_candidates(2), [16, 8]);
+    /// ```
+    pub fn eviction_candidates(&self, n: usize) -> Vec<K> {
+        let mut entries: Vec<(usize, K)> =
+            self.table.occupied().map(|(i, k)| (i - self.index_for_key(*k), *k)).collect();
+        if n < entries.len() {
+            entries.select_nth_unstable_by(n, |a, b| b.0.cmp(&a.0));
+            entries.truncate(n);
+        }
+        entries.sort_unstable_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        entries.into_iter().map(|(_, k)| k).collect()
+    }
+
+    /// Number of occupied slots in each 64-byte cache line of the metadata lane, in memory order.
+    /// Lines close to 64 next to empty ones point at clustering caused by structured keys.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(64);
+    /// for k in 0..10u32 {
+    ///     m.insert(k, ());
+    /// }
+    /// // 191 slots, a line holds 64 of them and the lane may start mid-line
+    /// let profile = m.occupancy_profile();
+    /// assert!(profile.len() >= 3 && profile.iter().map(|&n| n as usize).sum::<usize>() == 10);
+    /// ```
+    pub fn occupancy_profile(&self) -> Vec<u8> {
+        self.table.occupancy_profile()
+    }
+
+    /// Mean distance of the entries from their home slots, see `probes`.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// for k in [0u32, 8, 16, 3] {
+    ///     m.insert(k, ());
+    /// }
+    /// assert_eq!(m.avg_probes_count(), 0.75);
+    /// ```
+    #[inline]
+    pub fn avg_probes_count(&self) -> f32 {
+        (self.probes().into_iter().sum::<usize>() as f32) / self.len() as f32
+    }
+
+    /// Entries per home slot.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// for k in 0..6u32 {
+    ///     m.insert(k, ());
+    /// }
+    /// assert_eq!(m.load_factor(), 0.75);
+    /// ```
+    #[inline]
+    pub fn load_factor(&self) -> f32 {
+        self.len() as f32 / self.capacity() as f32
+    }
+}
+
+impl<K: IntKey, V> IntMap<K, Arc<V>> {
+    /// Makes entries whose values share a fingerprint point to a single shared `Arc`, so identical
+    /// large values are stored once. Returns the number of values replaced by a shared one.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    /// use std::sync::Arc;
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// for k in 0..3u32 {
+    ///     m.insert(k, Arc::new("default config".to_string()));
+    /// }
+    /// assert_eq!(m.dedup_values_by(|v| v.clone()), 2);
+    /// assert!(Arc::ptr_eq(m.get(0).unwrap(), m.get(2).unwrap()));
+    /// ```
+    pub fn dedup_values_by<F: Hash + Eq>(&mut self, mut fingerprint: impl FnMut(&V) -> F) -> usize {
+        let mut pool: HashMap<F, Arc<V>> = HashMap::new();
+        let mut replaced = 0;
+        self.table.for_each_mut(|_, value| match pool.entry(fingerprint(value)) {
+            hash_map::Entry::Occupied(shared) => {
+                if !Arc::ptr_eq(shared.get(), value) {
+                    *value = Arc::clone(shared.get());
+                    replaced += 1;
+                }
+            }
+            hash_map::Entry::Vacant(slot) => {
+                slot.insert(Arc::clone(value));
+            }
+        });
+        replaced
+    }
+}
+
+impl<K: IntKey, T> IntMap<K, Weak<T>> {
+    /// The value behind the `Weak` stored for `key`, if both exist.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    /// use std::sync::Arc;
+    ///
+    /// let session = Arc::new("alice");
+    /// let mut m = IntMap::with_capacity(8);
+    /// m.insert(1u32, Arc::downgrade(&session));
+    /// assert_eq!(m.get_upgraded(1).as_deref(), Some(&"alice"));
+    /// drop(session);
+    /// assert_eq!(m.get_upgraded(1), None);
+    /// ```
+    pub fn get_upgraded(&self, key: K) -> Option<Arc<T>> {
+        self.get(key).and_then(Weak::upgrade)
+    }
+
+    /// Removes entries whose referents were dropped, returns the number of entries removed.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    /// use std::sync::Arc;
+    ///
+    /// let (alive, gone) = (Arc::new(1), Arc::new(2));
+    /// let mut m = IntMap::with_capacity(8);
+    /// m.insert(1u32, Arc::downgrade(&alive));
+    /// m.insert(2, Arc::downgrade(&gone));
+    /// drop(gone);
+    /// assert_eq!(m.prune_dead(), 1);
+    /// assert!(m.contains(1) && !m.contains(2));
+    /// ```
+    pub fn prune_dead(&mut self) -> usize {
+        let len = self.len();
+        self.retain(|_, weak| weak.strong_count() > 0);
+        len - self.len()
+    }
+}
+
+impl<K: IntKey, V> IntMap<K, Vec<V>> {
+    /// Appends `value` to the bucket stored under `key`, creating an empty bucket first if needed.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// m.push_value(1u32, 'a');
+    /// m.push_value(1, 'b');
+    /// assert_eq!(m.values_of(1), ['a', 'b']);
+    /// ```
+    pub fn push_value(&mut self, key: K, value: V) {
+        self.probe(key).or_insert_with(Vec::new).push(value);
+    }
+
+    /// Returns the bucket stored under `key`, empty if there's none.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// m.push_value(1u32, "x");
+    /// assert_eq!((m.values_of(1), m.values_of(2)), (&["x"][..], &[][..]));
+    /// ```
+    pub fn values_of(&self, key: K) -> &[V] {
+        self.get(key).map_or(&[], Vec::as_slice)
+    }
+}
+
+impl<K: IntKey, V> IntMap<K, V> {
+    /// Panics if the table is structurally corrupted: slot distances disagree with the keys' home
+    /// slots, a probe cluster has a hole or is out of order, or the length is off.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(8);
+    /// for k in [0u32, 8, 1, 16] {
+    ///     m.insert(k, ());
+    /// }
+    /// m.remove(8);
+    /// m.assert_invariants();
+    /// ```
+    #[cfg(any(test, debug_assertions))]
+    pub fn assert_invariants(&self) {
+        self.table.assert_invariants(|k| self.index_for_key(*k));
+    }
+}
+
+impl<K: IntKey, V: PartialEq> PartialEq for IntMap<K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().all(|(k, v)| other.get(*k) == Some(v))
+    }
+}
+
+impl<K: IntKey, V: Eq> Eq for IntMap<K, V> {}
+
+impl<K: IntKey, V: Hash> Hash for IntMap<K, V> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // entries are hashed independently and summed up, so maps with equal contents hash
+        // equally regardless of their capacity and slot order
+        let sum = self.iter().fold(0u64, |sum, entry| {
+            let mut hasher = DefaultHasher::new();
+            entry.hash(&mut hasher);
+            sum.wrapping_add(hasher.finish())
+        });
+        state.write_usize(self.len());
+        state.write_u64(sum);
+    }
+}
+
+impl<K: IntKey, V: Hash> IntMap<K, V> {
+    /// Digest of the entries, for comparing the contents of maps on different nodes or
+    /// checking a persisted map. Like `Hash`, it only depends on the entries, not on the
+    /// capacity or slot order, but it's computed with a fixed algorithm so it doesn't change
+    /// between Rust releases as long as `V`'s `Hash` impl doesn't.
+    ///
+    /// It's the same across platforms for values hashed as fixed-width integers, `bool`,
+    /// `char`, `str` and tuples of them. Slices, arrays and `Vec`s of integers hash as one
+    /// native-endian byte slice, so their checksum differs between little- and big-endian
+    /// hosts.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let (mut a, mut b) = (IntMap::with_capacity(4), IntMap::with_exact_capacity(50));
+    /// a.insert(1u64, 10u32);
+    /// a.insert(2, 20);
+    /// b.insert(2, 20);
+    /// b.insert(1, 10);
+    /// assert_eq!(a.checksum(), b.checksum());
+    /// ```
+    pub fn checksum(&self) -> u64 {
+        let sum = self.iter().fold(0u64, |sum, entry| {
+            let mut digest = Digest::new();
+            entry.hash(&mut digest);
+            sum.wrapping_add(digest.finish())
+        });
+        let mut digest = Digest::new();
+        digest.write_usize(self.len());
+        digest.write_u64(sum);
+        digest.finish()
+    }
+}
+
+impl<K: IntKey, V: Clone> IntMap<K, V> {
+    /// Clones into the smallest capacity that keeps the load factor at or below `target_load`,
+    /// never larger than the source capacity. Meant for long-lived snapshots of a map that was
+    /// over-provisioned for a peak. Keys crowding the home slots of the smaller table past the
+    /// probe limit double its capacity until they fit, up to the source capacity. Settings like
+    /// the probe limit, lazy clear or the reserved key carry over.
+    ///
+    /// Panics if `target_load` is not in `(0, 1]`.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(1 << 12);
+    /// for k in 0..100u32 {
+    ///     m.insert(k, k);
+    /// }
+    /// let snapshot = m.clone_compact(0.5);
+    /// assert_eq!(snapshot.capacity(), 256);
+    /// assert!(snapshot == m);
+    /// ```
+    pub fn clone_compact(&self, target_load: f32) -> Self {
+        assert!(target_load > 0.0 && target_load <= 1.0, "target load factor must be in (0, 1]");
+        let wanted = (self.len() as f64 / target_load as f64).ceil() as usize;
+        let mut capacity = wanted.clamp(1, self.capacity());
+        // keys spread out over the home slots here may crowd a smaller table past the probe
+        // limit, each retry doubles it up to the source capacity, where the entries fit as is
+        'retry: while capacity < self.capacity() {
+            let mut map = self.empty_like_with(capacity as u32);
+            map.canonicalize = self.canonicalize;
+            for (slot, &key, value) in self.table.slots() {
+                match map.search(key) {
+                    SearchResult::NotFound(index, distance)
+                        if map.table.can_insert(index, distance) =>
+                    {
+                        // the new entry lands in its search slot
+                        map.insert_at(index, key, value.clone(), distance);
+                        map.table.set_tag(index, self.table.tag(slot));
+                    }
+                    _ => {
+                        capacity = (capacity * 2).min(self.capacity());
+                        continue 'retry;
+                    }
+                }
+            }
+            map.reserved = self.reserved;
+            map.saturation = self.saturation;
+            return map;
+        }
+        self.clone()
+    }
+}
+
+impl<K: Clone, V: Clone> Clone for IntMap<K, V> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            table: self.table.clone(),
+            indexing: self.indexing.clone(),
+            reserved: self.reserved.clone(),
+            saturation: self.saturation,
+            canonicalize: self.canonicalize,
+            shadow: self.shadow.clone(),
+            profile: Profile::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty() {
+        let m = IntMap::<i32, u32>::with_capacity(10);
+        assert!(m.is_empty());
+        assert!(!m.is_full());
+        assert_eq!(m.len(), 0);
+        // power of two capacity
+        assert_eq!(m.capacity(), 16);
+    }
+
+    #[test]
+    fn insert() {
+        let mut m: IntMap<i32, i32> = IntMap::with_capacity(2);
+        assert!(m.insert(1, 2).is_none());
+        assert!(m.contains(1));
+        assert!(!m.contains(0));
+        assert_eq!(*m.get(1).unwrap(), 2);
+        *m.get_mut(1).unwrap() = 4;
+        assert!(m.contains(1));
+        assert_eq!(*m.get(1).unwrap(), 4);
+    }
+
+    #[test]
+    fn insert_overwrite() {
+        let mut m = IntMap::with_capacity(2);
+        m.insert(1, 2);
+        assert_eq!(m.insert(1, 3), Some(2));
+        assert!(m.contains(1));
+        assert_eq!(m.len(), 1);
+        assert_eq!(*m.get(1).unwrap(), 3);
+    }
+
+    #[test]
+    fn insert_some() {
+        const N: u32 = 128;
+        let mut m = IntMap::with_capacity(N);
+        (0..N).for_each(|i| {
+            m.insert(i, i);
+        });
+        assert_eq!(m.len(), N as usize);
+        for i in 0..N {
+            assert!(m.contains(i));
+            assert_eq!(*m.get(i).unwrap(), i);
+        }
+    }
+
+    #[test]
+    fn get_unchecked() {
+        let mut m: IntMap<u32, u32> = IntMap::with_capacity(4);
+        m.insert(0, 0);
+        m.insert(4, 1);
+        m.insert(8, 2);
+        m.insert(1, 3);
+        unsafe {
+            assert_eq!(*m.get_unchecked(0), 0);
+            assert_eq!(*m.get_unchecked(8), 2);
+            assert_eq!(*m.get_unchecked(1), 3);
+            *m.get_unchecked_mut(4) = 5;
+        }
+        assert_eq!(m.get(4), Some(&5));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "not in the map")]
+    fn get_unchecked_missing() {
+        let mut m: IntMap<u32, u32> = IntMap::with_capacity(4);
+        m.insert(0, 0);
+        unsafe { m.get_unchecked(4) };
+    }
+
+    #[test]
+    fn probe() {
+        let mut m: IntMap<u32, u32> = IntMap::with_capacity(4);
+        let p = m.probe(4);
+        assert!(!p.is_found());
+        assert_eq!(p.get(), None);
+        assert_eq!(p.insert(1), None);
+
+        *m.probe(0).or_insert(2) += 1;
+        *m.probe(0).or_insert(2) += 1;
+        assert_eq!(m.get(0), Some(&4));
+
+        let mut p = m.probe(4);
+        assert_eq!(p.key(), 4);
+        assert_eq!(p.get(), Some(&1));
+        *p.get_mut().unwrap() = 5;
+        assert_eq!(p.insert(6), Some(5));
+
+        assert_eq!(*m.probe(8).or_insert_with(|| 7), 7);
+        assert_eq!(m.probe(4).remove(), Some(6));
+        assert_eq!(m.probe(4).remove(), None);
+        assert_eq!(m.len(), 2);
+        m.assert_invariants();
+    }
+
+    #[test]
+    fn rekey_all() {
+        let mut m: IntMap<u64, u64> = IntMap::with_capacity(16);
+        for i in 0..10 {
+            m.insert(i, i * 10);
+        }
+        m.rekey_all(|k| k + (1 << 40));
+        assert_eq!(m.len(), 10);
+        for i in 0..10 {
+            assert!(!m.contains(i));
+            assert_eq!(m.get(i + (1 << 40)), Some(&(i * 10)));
+        }
+        m.assert_invariants();
+
+        m.rekey_all(|k| k & 1);
+        assert_eq!(m.len(), 2);
+        m.assert_invariants();
+    }
+
+    #[test]
+    fn group_reduce() {
+        let mut m: IntMap<u32, (u32, u32)> = IntMap::with_capacity(8);
+        let items = (0..100u32).map(|i| (i % 3, i));
+        m.group_reduce(
+            items,
+            |_| (0, 0),
+            |(count, sum), i| {
+                *count += 1;
+                *sum += i;
+            },
+        );
+        assert_eq!(m.len(), 3);
+        assert_eq!(m.get(0), Some(&(34, (0..100).step_by(3).sum())));
+        assert_eq!(m.get(2), Some(&(33, (2..100).step_by(3).sum())));
+
+        m.group_reduce([(1, 1000)], |_| (0, 0), |(_, sum), i| *sum += i);
+        assert_eq!(m.get(1).unwrap().1, (1..100).step_by(3).sum::<u32>() + 1000);
+    }
+
+    #[test]
+    fn insert_sparse_keys() {
+        let mut m = IntMap::with_capacity(4);
+        m.insert(0, 1);
+        m.insert(3, 1);
+        assert_eq!(m.keys().cloned().collect::<Vec<u32>>(), &[0, 3]);
+    }
+
+    #[test]
+    fn wide_keys_high_bits() {
+        // keys differing only above bit 32 share a home slot regardless of usize width
+        let mut m: IntMap<u64, u64> = IntMap::with_capacity(8);
+        for i in 0..4 {
+            m.insert(i << 40, i);
+        }
+        assert_eq!(m.probes(), &[0, 1, 2, 3]);
+        for i in 0..4 {
+            assert_eq!(m.get(i << 40), Some(&i));
+        }
+
+        let mut m: IntMap<i128, u32> = IntMap::with_capacity(8);
+        m.insert(i128::MIN, 0);
+        m.insert(i128::MAX, 1);
+        m.insert(1 << 100, 2);
+        m.insert(-1, 3);
+        assert_eq!(m.get(i128::MIN), Some(&0));
+        assert_eq!(m.get(i128::MAX), Some(&1));
+        assert_eq!(m.get(1 << 100), Some(&2));
+        assert_eq!(m.get(-1), Some(&3));
+        assert!(!m.contains(0));
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "32")]
+    fn capacity_clamp_32bit() {
+        assert_eq!(round_capacity(u32::MAX), MAX_CAPACITY);
+        assert_eq!(Capacity::from(u32::MAX).entries_at(1.0), u32::MAX as usize);
+        let m = IntMap::<u64, u8>::with_capacity(1 << 20);
+        assert_eq!(m.capacity(), 1 << 20);
+        assert_eq!(m.table.capacity(), (1 << 20) + OVERFLOW_SLOTS);
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "32")]
+    #[should_panic(expected = "capacity overflow")]
+    fn max_capacity_wide_keys_32bit() {
+        // 2^30 u64 keys take more bytes than a 32-bit address space, the allocation is refused
+        // before anything is written rather than wrapping to a smaller table
+        IntMap::<u64, u8>::with_capacity(MAX_CAPACITY);
+    }
+
+    #[test]
+    fn iter() {
+        let mut m = IntMap::with_capacity(8);
+        m.insert(3u32, 30u32);
+        m.insert(11, 110);
+        m.insert(1, 10);
+        assert_eq!(
+            m.iter().map(|(k, v)| (*k, *v)).collect::<Vec<_>>(),
+            &[(1, 10), (3, 30), (11, 110)]
+        );
+        assert_eq!(m.values().copied().collect::<Vec<_>>(), &[10, 30, 110]);
+    }
+
+    #[test]
+    fn joins() {
+        let mut a: IntMap<u32, u32> = IntMap::with_capacity(16);
+        let mut b: IntMap<u32, &str> = IntMap::with_capacity(16);
+        for i in 0..10 {
+            a.insert(i, i * 10);
+        }
+        b.insert(2, "two");
+        b.insert(7, "seven");
+        b.insert(20, "twenty");
+
+        let mut inner = a.inner_join(&b).map(|(k, x, y)| (k, *x, *y)).collect::<Vec<_>>();
+        inner.sort_unstable();
+        assert_eq!(inner, &[(2, 20, "two"), (7, 70, "seven")]);
+
+        // the smaller side is iterated, the tuple order stays (key, self, other)
+        let mut inner = b.inner_join(&a).map(|(k, x, y)| (k, *x, *y)).collect::<Vec<_>>();
+        inner.sort_unstable();
+        assert_eq!(inner, &[(2, "two", 20), (7, "seven", 70)]);
+
+        let left = b.left_join(&a).map(|(k, x, y)| (k, *x, y.copied())).collect::<Vec<_>>();
+        assert_eq!(left.len(), 3);
+        assert!(left.contains(&(20, "twenty", None)));
+        assert!(left.contains(&(7, "seven", Some(70))));
+    }
+
+    #[test]
+    fn insert_collide() {
+        let mut m: IntMap<u32, u32> = IntMap::with_capacity(4);
+        m.insert(0, 0);
+        m.insert(1, 1);
+        /*
+        index key distance
+        0     0   0
+        1     1   0
+        */
+        m.insert(4, 2);
+        /*
+        index key distance
+        0     0   0
+        1     4   1
+        2     1   1
+        */
+        assert_eq!(m.len(), 3);
+        m.insert(8, 3);
+        /*
+        index key distance
+        0     0   0
+        1     4   1
+        2     8   2
+        3     1   2
+        */
+        assert_eq!(m.keys().cloned().collect::<Vec<u32>>(), &[0, 4, 8, 1]);
+    }
+
+    #[test]
+    fn insert_collide_inside() {
+        let mut m: IntMap<u32, u32> = IntMap::with_capacity(8);
+        m.insert(0, 0);
+        m.insert(8, 1);
+        m.insert(1, 2);
+        m.insert(2, 3);
+        /*
+        index key distance
+        0     0   0
+        1     8   1
+        2     1   1
+        3     2   1
+        */
+        m.insert(16, 4);
+        m.assert_invariants();
+        /*
+        index key distance
+        0     0   0
+        1     8   1
+        2     16  2
+        3     1   2
+        4     2   2
+        */
+        assert_eq!(m.len(), 5);
+        assert_eq!(m.keys().cloned().collect::<Vec<u32>>(), &[0, 8, 16, 1, 2]);
+    }
+
+    #[test]
+    fn insert_signed_remove() {
+        let mut m = IntMap::with_capacity(4);
+        m.insert(-10, 1);
+        m.insert(-20, 2);
+        m.insert(-30, 3);
+        m.insert(-40, 4);
+        m.insert(-20, 22);
+        assert!(m.is_full());
+        assert_eq!(m.remove(-40), Some(4));
+        assert_eq!(m.remove(-10), Some(1));
+        assert_eq!(m.remove(-30), Some(3));
+        assert_eq!(m.remove(-20), Some(22));
+        assert!(m.is_empty());
+    }
+
+    #[test]
+    fn remove() {
+        let mut m = IntMap::with_capacity(4);
+        m.insert(1, 1);
+        assert_eq!(m.len(), 1);
+        assert_eq!(m.remove(1), Some(1));
+        assert!(m.is_empty());
+        m.insert(1, 1);
+        m.insert(2, 2);
+        m.insert(3, 3);
+        m.insert(4, 4);
+        assert!(m.is_full());
+        assert_eq!(m.remove(2), Some(2));
+        assert_eq!(m.remove(4), Some(4));
+        assert_eq!(m.remove(1), Some(1));
+        assert_eq!(m.remove(3), Some(3));
+        assert!(m.is_empty());
+    }
+
+    #[test]
+    fn remove_conflict() {
+        let mut m = IntMap::with_capacity(4);
+        m.insert(1, 2);
+        assert_eq!(*m.get(1).unwrap(), 2);
+        m.insert(5, 3);
+        assert_eq!(*m.get(1).unwrap(), 2);
+        assert_eq!(*m.get(5).unwrap(), 3);
+        m.insert(9, 4);
+        assert_eq!(*m.get(1).unwrap(), 2);
+        assert_eq!(*m.get(5).unwrap(), 3);
+        assert_eq!(*m.get(9).unwrap(), 4);
+        assert!(m.remove(1).is_some());
+        assert_eq!(*m.get(9).unwrap(), 4);
+        assert_eq!(*m.get(5).unwrap(), 3);
+    }
+
+    #[test]
+    fn insert_remove_seq() {
+        // quadratic in N, keep it small enough for Miri
+        const N: u32 = if cfg!(miri) { 64 } else { 1000 };
+        let mut m = IntMap::with_capacity(2 * N);
+
+        for _ in 0..10 {
+            assert!(m.is_empty());
+            for i in 1..=N {
+                m.insert(i, i);
+
+                for j in 1..=i {
+                    let r = m.get(j);
+                    assert_eq!(r, Some(&j));
+                }
+
+                for j in i + 1..=N {
+                    let r = m.get(j);
+                    assert_eq!(r, None);
+                }
+            }
+
+            for i in N + 1..=2 * N {
+                assert!(!m.contains(i));
+            }
+
+            // remove forwards
+            for i in 1..=N {
+                assert!(m.remove(i).is_some());
+
+                for j in 1..=i {
+                    assert!(!m.contains(j));
+                }
+
+                for j in i + 1..=N {
+                    assert!(m.contains(j));
+                }
+            }
+
+            for i in 1..=N {
+                assert!(!m.contains(i));
+            }
+
+            for i in 1..=N {
+                m.insert(i, i);
+            }
+
+            // remove backwards
+            for i in (1..=N).rev() {
+                assert!(m.remove(i).is_some());
+
+                for j in i..=N {
+                    assert!(!m.contains(j));
+                }
+
+                for j in 1..i {
+                    assert!(m.contains(j));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn invariants_after_churn() {
+        let mut m = IntMap::with_capacity(64);
+        for i in 0..64u32 {
+            m.insert(i.wrapping_mul(0x9e37_79b9) % 256, i);
+            m.assert_invariants();
+        }
+        for i in (0..256).step_by(3) {
+            m.remove(i);
+            m.assert_invariants();
+        }
+    }
+
+    #[test]
+    fn probes_sparse() {
+        let mut m = IntMap::with_capacity(8);
+        m.insert(3u32, 0);
+        m.insert(11, 1);
+        m.insert(6, 2);
+        assert_eq!(m.probes(), &[0, 1, 0]);
+    }
+
+    #[test]
+    fn memory_usage() {
+        let mut m = IntMap::<u32, u64>::with_capacity(16);
+        // distance, key, value and the tag byte with the 'tags' feature
+        let expected =
+            (16 + Distance::MAX as usize) * (1 + 4 + 8 + cfg!(feature = "tags") as usize);
+        assert_eq!(m.memory_usage(), expected);
+        m.insert(1, 1);
+        assert_eq!(m.memory_usage(), expected);
+    }
+
+    #[test]
+    fn dedup_values_by() {
+        let mut m = IntMap::<u32, Arc<String>>::with_capacity(8);
+        m.insert(0, Arc::new("a".repeat(64)));
+        m.insert(1, Arc::new("b".repeat(64)));
+        m.insert(2, Arc::new("a".repeat(64)));
+        m.insert(3, Arc::new("a".repeat(64)));
+        assert_eq!(m.dedup_values_by(|v| v.clone()), 2);
+        assert!(Arc::ptr_eq(m.get(0).unwrap(), m.get(2).unwrap()));
+        assert!(Arc::ptr_eq(m.get(0).unwrap(), m.get(3).unwrap()));
+        assert_eq!(Arc::strong_count(m.get(0).unwrap()), 3);
+        assert_eq!(Arc::strong_count(m.get(1).unwrap()), 1);
+        assert_eq!(m.dedup_values_by(|v| v.clone()), 0);
+    }
+
+    #[test]
+    fn pool() {
+        let mut pool = IntMapPool::<u32, String>::new(1);
+        let mut a = pool.acquire(10);
+        assert_eq!(a.capacity(), 16);
+        a.insert(1, "a".to_string());
+        let slot = a.get(1).unwrap() as *const String;
+        pool.release(a);
+        // over the class limit
+        pool.release(IntMap::with_capacity(16));
+        pool.release(IntMap::with_capacity(64));
+        assert_eq!(pool.len(), 2);
+
+        let mut b = pool.acquire(16);
+        assert!(b.is_empty());
+        b.insert(1, "b".to_string());
+        assert_eq!(b.get(1).unwrap() as *const String, slot);
+        assert_eq!(pool.acquire(33).capacity(), 64);
+        assert_eq!(pool.acquire(16).capacity(), 16);
+        assert!(pool.is_empty());
+
+        // a configured map comes back with the settings of a fresh one
+        let mut pool = IntMapPool::<u32, u32>::new(4);
+        let mut m = IntMap::with_canonicalizer(16, |k| k & 0xff);
+        m.set_reserved_key(Some(7));
+        m.set_saturation_policy(SaturationPolicy::Reject);
+        m.set_lazy_clear(true);
+        m.set_nontemporal_clear(true);
+        m.set_constant_time_lookup(true);
+        m.insert(0x101, 1);
+        pool.release(m);
+        pool.release(IntMap::with_probe_limit(16, 4));
+        pool.release(IntMap::with_exact_capacity(16));
+        assert_eq!(pool.len(), 1);
+        let mut m = pool.acquire(16);
+        let fresh = IntMap::<u32, u32>::with_capacity(16);
+        assert_eq!(m.reserved_key(), None);
+        assert_eq!(m.saturation_policy(), fresh.saturation_policy());
+        assert!(!m.is_lazy_clear() && !m.is_nontemporal_clear() && !m.is_constant_time_lookup());
+        assert_eq!(m.probe_limit(), fresh.probe_limit());
+        for k in 0..24 {
+            m.insert(k * 0x100 + 7, k);
+        }
+        assert!(m.len() == 24 && m.get(0x107) == Some(&1) && !m.contains(7 + 0x1800));
+        assert!(m.keys().all(|&k| k % 0x100 == 7));
+        m.assert_invariants();
+    }
+
+    #[test]
+    fn lazy_clear() {
+        let mut m: IntMap<u32, u32> = IntMap::with_capacity(16);
+        m.insert(1, 1);
+        m.set_lazy_clear(true);
+        assert!(m.is_lazy_clear());
+        assert_eq!(m.get(1), Some(&1));
+
+        // enough rounds to wrap the epoch counter
+        for round in 0..600 {
+            for k in 0..12 {
+                m.insert(k * 3 + round % 5, k);
+            }
+            m.remove(round % 5);
+            m.assert_invariants();
+            m.clear();
+            assert!(m.is_empty());
+            assert_eq!(m.keys().count(), 0);
+            assert!(!m.contains(3 + round % 5));
+        }
+
+        m.insert(7, 7);
+        m.clear();
+        m.insert(5, 5);
+        m.set_lazy_clear(false);
+        assert_eq!(m.keys().copied().collect::<Vec<_>>(), &[5]);
+        m.assert_invariants();
+    }
+
+    #[test]
+    fn clone_owned_values() {
+        let mut m = IntMap::<u32, String>::with_capacity(4);
+        m.insert(0, String::from("cloned"));
+        m.insert(4, String::from("not copied"));
+        let mut c = m.clone();
+        drop(m);
+        c.get_mut(0).unwrap().push('!');
+        assert_eq!(c.get(0).map(String::as_str), Some("cloned!"));
+        assert_eq!(c.get(4).map(String::as_str), Some("not copied"));
+
+        c.set_lazy_clear(true);
+        c.clear();
+        c.insert(1, String::new());
+        assert_eq!(c.clone().keys().copied().collect::<Vec<_>>(), &[1]);
+    }
+
+    #[test]
+    // the profile's counters are atomics, but they take no part in Eq or Hash
+    #[cfg_attr(feature = "profiling", allow(clippy::mutable_key_type))]
+    fn eq_hash() {
+        fn hash_of<T: Hash>(t: &T) -> u64 {
+            let mut h = DefaultHasher::new();
+            t.hash(&mut h);
+            h.finish()
+        }
+
+        let mut a: IntMap<u32, &str> = IntMap::with_capacity(4);
+        let mut b: IntMap<u32, &str> = IntMap::with_capacity(64);
+        for (k, v) in [(0, "a"), (4, "b"), (8, "c"), (1, "d")] {
+            a.insert(k, v);
+        }
+        for (k, v) in [(1, "d"), (8, "c"), (4, "b"), (0, "a")] {
+            b.insert(k, v);
+        }
+        assert!(a == b);
+        assert_eq!(hash_of(&a), hash_of(&b));
+
+        let mut memo = HashMap::new();
+        memo.insert(a.clone(), 1);
+        assert_eq!(memo.get(&b), Some(&1));
+
+        b.insert(4, "x");
+        assert!(a != b);
+        assert_ne!(hash_of(&a), hash_of(&b));
+        b.insert(4, "b");
+        b.remove(1);
+        assert!(a != b);
+    }
+
+    #[test]
+    fn occupancy_profile() {
+        let mut m = IntMap::with_capacity(1024);
+        // keys sharing the low bits pile up behind a single home slot
+        for i in 0..100u32 {
+            m.insert(i << 10, i);
+        }
+        let profile = m.occupancy_profile();
+        assert!(profile.len() >= (1024 + Distance::MAX as usize) / 64);
+        assert_eq!(profile.iter().map(|&n| n as usize).sum::<usize>(), 100);
+        assert!(profile.iter().filter(|&&n| n > 0).count() <= 3);
+        assert!(profile.iter().all(|&n| n <= 64));
+    }
+
+    #[test]
+    fn aging() {
+        let mut m = AgingIntMap::<u32, u32>::with_capacity(16);
+        for i in 0..8 {
+            m.insert(i, i);
+        }
+        assert!(m.evict_cold(0).is_empty());
+        // 0..4 stay hot, 4..8 are left to age
+        for i in 0..4 {
+            assert!(m.touch(i));
+        }
+        assert_eq!(m.get(2), Some(&2));
+        m.insert(8, 8);
+        assert_eq!(m.age(5), Some(1));
+        assert_eq!(m.age(8), Some(0));
+
+        let mut evicted = m.evict_cold(4).into_iter().map(|(k, _)| k).collect::<Vec<_>>();
+        evicted.sort_unstable();
+        assert_eq!(evicted, &[4, 5, 6, 7]);
+        assert_eq!(m.len(), 5);
+        assert_eq!(m.peek(0), Some(&0));
+        assert_eq!(m.age(0), Some(1));
+        assert_eq!(m.evict_cold(10).len(), 5);
+        assert!(m.is_empty());
+
+        // ages move with their entries through displacements and backward shifts, in a lane of
+        // their own: one byte per slot, no padding next to the values
+        let mut m = AgingIntMap::<u32, u64>::with_capacity(16);
+        let plain = IntMap::<u32, u64>::with_capacity(16);
+        assert_eq!(m.memory_usage(), plain.memory_usage() + plain.slots().get());
+        for k in [1, 17, 33] {
+            m.insert(k, k as u64);
+        }
+        m.evict_cold(0);
+        m.touch(33);
+        m.insert(0, 0);
+        m.insert(16, 16);
+        m.remove(1);
+        assert_eq!([0, 16, 17, 33].map(|k| m.age(k)), [Some(0), Some(0), Some(1), Some(0)]);
+        assert_eq!(m.insert(17, 170), Some(17));
+        assert_eq!((m.age(17), m.peek(17)), (Some(0), Some(&170)));
+    }
+
+    #[test]
+    fn push_value() {
+        let mut m = IntMap::<u32, Vec<&str>>::with_capacity(8);
+        assert!(m.values_of(3).is_empty());
+        m.push_value(3, "a");
+        m.push_value(11, "c");
+        m.push_value(3, "b");
+        assert_eq!(m.values_of(3), ["a", "b"]);
+        assert_eq!(m.values_of(11), ["c"]);
+        assert_eq!(m.len(), 2);
+        m.assert_invariants();
+    }
+
+    #[test]
+    fn abi_handle() {
+        let mut h = IntMapHandle::with_capacity(16);
+        assert_eq!(h.version(), ABI_VERSION);
+        assert_eq!(h.capacity(), 16);
+        assert_eq!(h.insert(5, 50), None);
+        assert_eq!(h.insert(5, 51), Some(50));
+        assert_eq!(h.get(5), Some(51));
+        assert_eq!(h.get(6), None);
+        assert_eq!(h.len(), 1);
+        assert_eq!(h.remove(5), Some(51));
+        assert_eq!(h.remove(5), None);
+        assert!(h.is_empty());
+
+        // saturation and probe overflow come back as statuses instead of unwinding
+        let mut m = IntMap::with_capacity(16);
+        m.set_saturation_policy(SaturationPolicy::Reject);
+        let mut h = IntMapHandle::from_map(m);
+        for k in 0..16 {
+            assert_eq!(h.try_insert(k, k), Ok(None));
+        }
+        assert_eq!(h.try_insert(16, 16), Err(STATUS_REJECTED));
+        assert_eq!(h.try_insert(3, 30), Ok(Some(3)));
+        let mut h = IntMapHandle::from_map(IntMap::with_probe_limit(16, 4));
+        for k in 0..4 {
+            assert_eq!(h.try_insert(k * 16, k), Ok(None));
+        }
+        assert_eq!(h.try_insert(64, 4), Err(STATUS_PANICKED));
+        assert_eq!((h.len(), h.get(48)), (4, Some(3)));
+    }
+
+    #[test]
+    fn static_map() {
+        static TABLE: StaticIntMap<u32, u32> = StaticIntMap::new();
+        assert!(TABLE.get().is_none());
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                std::thread::spawn(|| {
+                    TABLE
+                        .get_or_init(|| {
+                            let mut m = IntMap::with_capacity(8);
+                            m.insert(1, 10);
+                            m
+                        })
+                        .get(1)
+                        .copied()
+                })
+            })
+            .collect();
+        for t in threads {
+            assert_eq!(t.join().unwrap(), Some(10));
+        }
+        assert_eq!(TABLE.get_or_init(|| unreachable!()).len(), 1);
+    }
+
+    #[test]
+    fn neighborhood() {
+        let mut m = IntMap::<u32, ()>::with_capacity(8);
+        for k in [2, 10, 18, 4] {
+            m.insert(k, ());
+        }
+        let view: Vec<_> = m.neighborhood(2, 1).collect();
+        assert_eq!(view.len(), 3);
+        assert_eq!(view[0], SlotView { index: 1, entry: None });
+        assert_eq!(view[1].entry, Some((2, 0)));
+        assert_eq!(view[2].entry, Some((10, 1)));
+        assert_eq!(view[2].home(), Some(2));
+
+        // 4 is displaced by the cluster homed at 2
+        let view: Vec<_> = m.neighborhood(4, 1).collect();
+        assert_eq!(view[1].entry, Some((18, 2)));
+        assert_eq!(view[2].entry, Some((4, 1)));
+        assert!(m.neighborhood(0, 0).all(|s| s.is_empty()));
+        assert_eq!(m.neighborhood(0, usize::MAX).count(), m.table.capacity());
+    }
+
+    #[test]
+    fn extend_from_slices() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+        let mut rng = StdRng::seed_from_u64(7);
+        let keys: Vec<u64> = (0..600).map(|_| rng.gen_range(0..2048)).collect();
+        let values: Vec<u32> = (0..600).collect();
+
+        let mut one_by_one = IntMap::with_capacity(1024);
+        for (&k, &v) in keys.iter().zip(&values) {
+            one_by_one.insert(k, v);
+        }
+        // the sweep into an empty map, with regular and streaming stores, after a lazy clear,
+        // and the insert loop into a map holding entries
+        let mut plain = IntMap::with_capacity(1024);
+        let mut streamed = IntMap::with_capacity_nontemporal(1024);
+        let mut lazy = IntMap::with_capacity(1024);
+        lazy.set_lazy_clear(true);
+        lazy.extend_from_slices(&[5, 6, 7], &[0, 0, 0]);
+        lazy.clear();
+        let mut filled = IntMap::with_capacity(1024);
+        filled.insert(keys[0], 7);
+        for bulk in [&mut plain, &mut streamed, &mut lazy, &mut filled] {
+            bulk.extend_from_slices(&keys, &values);
+            assert!(*bulk == one_by_one);
+            bulk.assert_invariants();
+        }
+        let mut exact = IntMap::with_exact_capacity(1000);
+        exact.extend_from_slices(&keys, &values);
+        assert!(exact == one_by_one);
+        exact.assert_invariants();
+
+        // canonical keys, and a sweep panicking past the probe limit keeps what it stored
+        let mut m = IntMap::with_canonicalizer(64, |k: u32| k & 0xFF);
+        m.extend_from_slices(&[0x101, 1, 0x202], &['a', 'b', 'c']);
+        assert_eq!((m.len(), m.get(1), m.get(2)), (2, Some(&'b'), Some(&'c')));
+        let mut m = IntMap::<u32, u8>::with_probe_limit(8, 2);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            m.extend_from_slices(&[0, 8, 16], &[0, 1, 2])
+        }));
+        assert!(result.is_err());
+        assert_eq!(m.len(), 2);
+        m.assert_invariants();
+    }
+
+    #[test]
+    fn nontemporal_clear() {
+        let mut m = IntMap::<u32, String>::with_capacity_nontemporal(1000);
+        assert!(m.is_nontemporal_clear());
+        assert_eq!(m.capacity(), 1024);
+        for k in 0..600 {
+            m.insert(k * 3, k.to_string());
+        }
+        m.assert_invariants();
+        let copy = m.clone();
+        assert!(copy.is_nontemporal_clear());
+        m.clear();
+        assert!(m.is_empty() && m.iter().next().is_none());
+        m.insert(1, "1".into());
+        m.assert_invariants();
+        assert_eq!(copy.len(), 600);
+
+        let mut m = IntMap::<u32, u32>::with_capacity(16);
+        m.set_nontemporal_clear(true);
+        m.insert(1, 1);
+        m.clear();
+        assert_eq!(m.get(1), None);
+    }
+
+    #[test]
+    fn merge_insert() {
+        let mut m = IntMap::<u64, u64>::with_capacity(8);
+        let sum = |old: &mut u64, new| *old += new;
+        assert!(!m.merge_insert(3, 1, sum));
+        assert!(m.merge_insert(3, 2, sum));
+        assert!(m.merge_insert(3, 1, |old, new| *old = (*old).max(new)));
+        assert!(!m.merge_insert(11, 9, sum));
+        assert_eq!(m.get(3), Some(&3));
+        assert_eq!(m.get(11), Some(&9));
+        m.assert_invariants();
+    }
+
+    #[test]
+    fn get_with_distance() {
+        let mut m = IntMap::<u32, char>::with_capacity(8);
+        m.insert(1, 'a');
+        m.insert(9, 'b');
+        m.insert(17, 'c');
+        assert_eq!(m.get_with_distance(1), Some((&'a', 0)));
+        assert_eq!(m.get_with_distance(17), Some((&'c', 2)));
+        assert_eq!(m.get_with_distance(25), None);
+        m.remove(1);
+        assert_eq!(m.get_with_distance(17), Some((&'c', 1)));
+    }
+
+    #[test]
+    fn iter_exact_double_ended() {
+        let mut m = IntMap::<u32, u32>::with_capacity(16);
+        for k in [3, 19, 35, 7, 12] {
+            m.insert(k, k * 2);
+        }
+        let mut keys = m.keys();
+        assert_eq!(keys.len(), 5);
+        let first = *keys.next().unwrap();
+        let last = *keys.next_back().unwrap();
+        assert_eq!(keys.len(), 3);
+        assert_eq!(keys.by_ref().count(), 3);
+        assert_eq!(keys.next(), None);
+        assert_eq!(keys.next_back(), None);
+
+        let forward: Vec<_> = m.iter().map(|(k, _)| *k).collect();
+        let mut backward: Vec<_> = m.iter().rev().map(|(k, _)| *k).collect();
+        backward.reverse();
+        assert_eq!(forward, backward);
+        assert_eq!((forward[0], forward[4]), (first, last));
+        assert_eq!(m.values().rev().len(), 5);
+
+        let other: IntMap<u32, ()> = IntMap::with_capacity(1);
+        assert_eq!(m.left_join(&other).len(), 5);
+        assert_eq!(m.inner_join(&other).size_hint(), (0, Some(0)));
+        assert_eq!(m.inner_join(&m).rev().count(), 5);
+    }
+
+    #[test]
+    fn to_vec() {
+        let mut m = IntMap::<i64, f32>::with_capacity(64);
+        for k in (-40..40).step_by(3) {
+            m.insert(k, k as f32);
+        }
+        m.set_lazy_clear(true);
+        let keys = m.keys_to_vec();
+        assert_eq!(keys.len(), m.len());
+        assert_eq!(keys.capacity(), m.len());
+        assert_eq!(keys, m.keys().copied().collect::<Vec<_>>());
+        assert_eq!(m.values_to_vec(), m.values().copied().collect::<Vec<_>>());
+        m.clear();
+        assert!(m.keys_to_vec().is_empty());
+    }
+
+    #[test]
+    fn exact_capacity() {
+        let mut m = IntMap::<u64, u64>::with_exact_capacity(1000);
+        assert_eq!(m.capacity(), 1000);
+        assert!(m.memory_usage() < IntMap::<u64, u64>::with_capacity(1000).memory_usage());
+        for k in 0..900 {
+            m.insert(k << 20, k);
+        }
+        m.assert_invariants();
+        assert!(m.avg_probes_count() < 4.0);
+        assert!((0..900).all(|k| m.get(k << 20) == Some(&k)));
+        assert_eq!(m.remove(5 << 20), Some(5));
+        assert_eq!(m.clone().len(), 899);
+
+        let mut m = IntMap::<i128, ()>::with_exact_capacity(3);
+        m.insert(i128::MIN, ());
+        m.insert(-1, ());
+        m.insert(i128::MAX, ());
+        m.assert_invariants();
+        assert_eq!(IntMap::<u32, ()>::with_exact_capacity(0).capacity(), 1);
+    }
+
+    #[test]
+    fn probe_limit() {
+        let m = IntMap::<u32, u64>::with_probe_limit(8, 4);
+        assert_eq!(m.probe_limit(), 4);
+        assert_eq!(m.memory_usage(), (8 + 4) * (1 + 4 + 8 + cfg!(feature = "tags") as usize));
+        assert_eq!(IntMap::<u32, u64>::with_capacity(8).probe_limit(), Distance::MAX as usize);
+        assert_eq!(IntMap::<u32, u64>::with_probe_limit(8, 0).probe_limit(), 2);
+
+        let mut m = IntMap::<u32, u32>::with_probe_limit(8, 4);
+        // home slot 7 is the last one, the overflow region takes distances 1..=3
+        for k in [7, 15, 23, 31] {
+            m.insert(k, k);
+        }
+        m.assert_invariants();
+        assert_eq!(m.get_with_distance(31), Some((&31, 3)));
+        let mut copy = m.clone();
+        copy.rekey_all(|k| k + 8);
+        assert_eq!(copy.probe_limit(), 4);
+        assert!(copy.contains(39));
+    }
+
+    #[test]
+    #[should_panic(expected = "maximum probes count reached")]
+    fn probe_limit_overflow() {
+        let mut m = IntMap::<u32, u32>::with_probe_limit(8, 3);
+        for k in [1, 9, 17, 25] {
+            m.insert(k, k);
+        }
+    }
+
+    #[test]
+    fn borrowed_buffers() {
+        use std::mem::MaybeUninit;
+        let mut distances = [0i8; 40];
+        let mut keys = [MaybeUninit::uninit(); 40];
+        let mut values: Vec<MaybeUninit<String>> = (0..40).map(|_| MaybeUninit::uninit()).collect();
+        for _frame in 0..3 {
+            let mut m = BorrowedIntMap::from_raw_buffers(&mut distances, &mut keys, &mut values);
+            assert_eq!(m.capacity(), 16);
+            assert_eq!(m.probe_limit(), 24);
+            for k in 0..16u32 {
+                m.insert(k * 16, k.to_string());
+            }
+            m.assert_invariants();
+            assert_eq!(m.get(15 * 16).map(String::as_str), Some("15"));
+            assert_eq!(m.remove(0), Some("0".into()));
+            *m.probe(7).or_insert_with(String::new) += "x";
+            assert_eq!(m.len(), 16);
+            // values left in the map are dropped here, the lanes stay with the caller
+        }
+        assert_eq!(
+            BorrowedIntMap::<u64, ()>::from_raw_buffers(
+                &mut [0; 300],
+                &mut [MaybeUninit::uninit(); 300],
+                &mut [MaybeUninit::uninit(); 300]
+            )
+            .capacity(),
+            128
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "bench-utils")]
+    fn bench_utils() {
+        use crate::bench::{self, KeyDistribution, Op};
+        use rand::SeedableRng;
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let keys: Vec<u32> = bench::keys(KeyDistribution::Sequential { start: 5 }, &mut rng, 3);
+        assert_eq!(keys, [5, 6, 7]);
+        let clustered: Vec<u64> = bench::keys(KeyDistribution::Clustered, &mut rng, 100);
+        assert!(clustered.iter().all(|k| k & 0x3f < 16));
+
+        // the same ops leave both maps with the same entries
+        for ops in [
+            bench::arbitrary_ops::<u32, u32>(&mut rng, 1000),
+            bench::ops_with_keys(KeyDistribution::Clustered, &mut rng, 1000),
+        ] {
+            assert_eq!(ops.len(), 1000);
+            let (mut m, mut truth) = (IntMap::with_capacity(2048), HashMap::new());
+            // arbitrary keys are spread over all of u32, keep inserting into the same 256
+            let ops = ops.iter().map(|&op| match op {
+                Op::Insert(k, v) => Op::Insert(k % 256, v),
+                Op::Get(k) => Op::Get(k % 256),
+                Op::Delete(k) => Op::Delete(k % 256),
+                Op::Contains(k) => Op::Contains(k % 256),
+            });
+            let ops = ops.collect::<Vec<_>>();
+            bench::run(&mut m, &ops);
+            bench::run(&mut truth, &ops);
+            assert_eq!(m.len(), truth.len());
+            assert!(truth.iter().all(|(k, v)| m.get(*k) == Some(v)));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "tags")]
+    fn tags() {
+        let mut m = IntMap::with_capacity(16);
+        // keys sharing home slots, so inserts and removes shift tagged entries around
+        for k in 0..12u32 {
+            m.insert(k * 16 + k % 4, k);
+            m.set_tag(k * 16 + k % 4, k as u8 % 3);
+        }
+        assert!(!m.set_tag(7, 1));
+        assert_eq!((m.tag(7), m.tag(16 + 1)), (None, Some(1)));
+        let check = |m: &IntMap<u32, u32>| {
+            for (k, v) in m.iter() {
+                assert_eq!(m.tag(*k), Some(*v as u8 % 3), "tag of key {k}");
+            }
+        };
+        for k in (0..12).step_by(3) {
+            m.remove(k * 16 + k % 4);
+            check(&m);
+        }
+        m.insert(1000, 5);
+        assert_eq!(m.tag(1000), Some(0));
+        m.remove(1000);
+        check(&m);
+        check(&m.clone());
+        let wide = m.clone().convert_keys::<u64>().unwrap();
+        assert!(wide.iter().all(|(k, v)| wide.tag(*k) == Some(*v as u8 % 3)));
+
+        let mut tagged = m.iter_tagged(2).map(|(k, _)| *k).collect::<Vec<_>>();
+        tagged.sort_unstable();
+        assert_eq!(tagged, [2 * 16 + 2, 5 * 16 + 1, 8 * 16, 11 * 16 + 3]);
+    }
+
+    #[test]
+    #[cfg(feature = "tags")]
+    fn tags_survive_rebuilds() {
+        let tagged = |m: &IntMap<u32, u32>| m.iter().all(|(k, v)| m.tag(*k) == Some(*v as u8));
+        let mut m = IntMap::with_capacity(1024);
+        for k in 0..40 {
+            m.insert(k * 64, k);
+            m.set_tag(k * 64, k as u8);
+        }
+        let compact = m.clone_compact(0.5);
+        assert!(compact.capacity() < m.capacity() && tagged(&compact));
+
+        let mut rekeyed = m.clone();
+        rekeyed.rekey_all(|k| k + 1);
+        assert!(rekeyed.contains(1) && tagged(&rekeyed));
+
+        let mut exact = IntMap::<u64, u32>::with_exact_capacity(100);
+        for k in 0..40 {
+            exact.insert(k as u64 * 7, k);
+            exact.set_tag(k as u64 * 7, k as u8);
+        }
+        let narrow = exact.convert_keys::<u32>().unwrap();
+        assert_eq!(narrow.len(), 40);
+        assert!(narrow.iter().all(|(k, v)| narrow.tag(*k) == Some(*v as u8)));
+    }
+
+    #[test]
+    #[cfg(feature = "net")]
+    fn socket_addr_keys() {
+        use std::net::{Ipv4Addr, SocketAddrV4};
+
+        let addrs = [
+            SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 1), 80),
+            SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 1), 443),
+            SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 2), 80),
+            SocketAddrV4::new(Ipv4Addr::BROADCAST, u16::MAX),
+            SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0),
+        ];
+        let mut m = AdaptedIntMap::with_capacity(8);
+        for (i, addr) in addrs.into_iter().enumerate() {
+            assert_eq!(SocketAddrV4::from_key(addr.to_key()), addr);
+            m.insert(addr, i);
+        }
+        assert_eq!(m.len(), addrs.len());
+        assert!(addrs.iter().enumerate().all(|(i, &addr)| m.get(addr) == Some(&i)));
+        assert_eq!(addrs[0].to_key(), 0x0050_0a00_0001);
+
+        // peers sharing one port, with the mixing map and with plain low-bit indexing
+        let peers =
+            |n: u32| (0..n).map(|i| SocketAddrV4::new(Ipv4Addr::from(0x0a00_0000 + i), 443));
+        let mut m = AdaptedIntMap::with_capacity(1 << 16);
+        let mut masked = AdaptedIntMap::from_inner(IntMap::with_capacity(1 << 16));
+        for (i, addr) in peers(10_000).enumerate() {
+            m.insert(addr, i);
+            masked.insert(addr, i);
+        }
+        assert!(m.len() == 10_000 && masked.len() == 10_000);
+        assert!(peers(10_000).enumerate().all(|(i, addr)| m.get(addr) == Some(&i)));
+        m.as_inner().assert_invariants();
+        masked.as_inner().assert_invariants();
+    }
+
+    #[test]
+    #[cfg(feature = "duration")]
+    fn duration_keys() {
+        use std::time::Duration;
+
+        let durations = [
+            Duration::ZERO,
+            Duration::from_nanos(1),
+            Duration::from_millis(1500),
+            Duration::new(u64::MAX, 999_999_999),
+        ];
+        let mut m = AdaptedIntMap::with_capacity(8);
+        for (i, d) in durations.into_iter().enumerate() {
+            assert_eq!(Duration::from_key(d.to_key()), d);
+            m.insert(d, i);
+        }
+        assert!(durations.iter().enumerate().all(|(i, &d)| m.get(d) == Some(&i)));
+        assert_eq!(m.iter().filter(|(d, _)| d.as_secs() == 1).count(), 1);
+
+        // whole milliseconds leave the low 6 bits of the nanoseconds zero
+        let mut m = AdaptedIntMap::with_capacity(1 << 14);
+        for ms in 0..12_000 {
+            m.insert(Duration::from_millis(ms), ms);
+        }
+        assert!(m.len() == 12_000 && m.get(Duration::from_secs(11)) == Some(&11_000));
+        assert!(m.as_inner().probe_limit() > m.as_inner().probes().into_iter().max().unwrap());
+        m.as_inner().assert_invariants();
+    }
+
+    #[test]
+    #[cfg(feature = "oplog")]
+    fn recording_int_map() {
+        let mut primary = RecordingIntMap::with_capacity(16);
+        let mut replica = IntMap::with_capacity(16);
+        for k in 0..10u32 {
+            primary.insert(k * 16, k);
+        }
+        primary.insert(0, 100);
+        assert_eq!(primary.remove(7), None);
+        primary.remove(16);
+        primary.retain(|k, _| k % 32 != 0);
+        assert_eq!(primary.ops().len(), 10 + 1 + 1 + 5);
+        for op in primary.take_ops() {
+            replica.apply(op);
+        }
+        assert!(primary.ops().is_empty());
+        assert!(primary.as_inner() == &replica);
+
+        primary.clear();
+        primary.insert(3, 3);
+        assert_eq!(primary.ops(), [MapOp::Clear, MapOp::Insert(3, 3)]);
+        for op in primary.take_ops() {
+            replica.apply(op);
+        }
+        assert!(primary.into_inner() == replica);
     }
 
     #[test]
-    fn insert_collide() {
+    #[cfg(feature = "profiling")]
+    fn profiling() {
+        let mut m = IntMap::with_capacity(64);
+        for k in 0..40u32 {
+            m.insert(k, k);
+        }
+        m.insert(0, 1);
+        assert!(m.try_insert(0, 2).is_ok());
+        for k in 0..50 {
+            m.get(k);
+        }
+        m.contains(1);
+        m.remove(3);
+        m.remove(100);
+        m.clear();
+        let counts = [OpClass::Lookup, OpClass::Insert, OpClass::Remove, OpClass::Clear]
+            .map(|c| m.profile(c).count());
+        assert_eq!(counts, [51, 42, 2, 1]);
+        let lookups = m.profile(OpClass::Lookup);
+        assert!(lookups.percentile(0.0) <= lookups.percentile(0.5));
+        assert!(lookups.percentile(0.5) <= lookups.percentile(1.0));
+        assert!(lookups.percentile(1.0) as f64 >= lookups.mean_ticks());
+        assert!(lookups.to_string().starts_with("51 ops, mean"));
+        assert_eq!(m.clone().profile(OpClass::Insert).count(), 0);
+        m.reset_profile();
+        assert_eq!(
+            m.profile(OpClass::Lookup),
+            IntMap::<u32, u32>::with_capacity(4).profile(OpClass::Lookup)
+        );
+        assert_eq!(m.profile(OpClass::Remove).percentile(0.5), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "secure-wipe")]
+    fn secure_wipe() {
+        const SECRET: [u8; 16] = [0xa5; 16];
+        let leaked = |m: &IntMap<u32, [u8; 16]>| {
+            m.table.value_bytes().chunks(16).filter(|c| *c == SECRET).count()
+        };
+        let mut m = IntMap::<u32, [u8; 16]>::with_capacity(16);
+        for k in [1, 17, 33, 2] {
+            m.insert(k, SECRET);
+        }
+        assert_eq!(m.remove(1), Some(SECRET));
+        assert_eq!(leaked(&m), 3);
+        m.clear();
+        assert_eq!(leaked(&m), 0);
+
+        m.insert(5, SECRET);
+        m.rekey_all(|k| k + 1);
+        assert_eq!(leaked(&m), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "shadow")]
+    #[should_panic(expected = "shadow: presence of key 3 diverged")]
+    fn shadow_divergence() {
+        let mut m = IntMap::<u32, ()>::with_capacity(8);
+        m.insert(3, ());
+        // bypassing the map leaves the shadow set behind
+        m.table.remove(3);
+        m.get(3);
+    }
+
+    #[test]
+    fn loader_duplicates() {
+        let input = [(1u32, 'a'), (2, 'b'), (1, 'c')];
+        let load = |policy| IntMapLoader::new(8).on_duplicate(policy).load(input);
+        assert_eq!(load(DuplicatePolicy::LastWins).unwrap().get(1), Some(&'c'));
+        assert_eq!(load(DuplicatePolicy::FirstWins).unwrap().get(1), Some(&'a'));
+        let err = load(DuplicatePolicy::Fail).err().unwrap();
+        assert_eq!(err, LoadError::DuplicateKey(1));
+        assert_eq!(err.to_string(), "duplicate key 1");
+        assert_eq!(IntMapLoader::new(8).load(input).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn iter_slots() {
+        let mut m = IntMap::<u64, &str>::with_capacity(8);
+        m.insert(1, "a");
+        m.insert(9, "b");
+        m.insert(4, "c");
+        let slots: Vec<_> = m.iter_slots().collect();
+        assert_eq!(slots, [(1, 1, &"a"), (2, 9, &"b"), (4, 4, &"c")]);
+        for (slot, key, value) in slots {
+            assert_eq!(m.get_by_slot(slot), Some((key, value)));
+        }
+        assert_eq!(m.get_by_slot(3), None);
+        assert_eq!(m.get_by_slot(usize::MAX), None);
+    }
+
+    #[test]
+    fn partitions() {
+        let mut m = IntMap::<u32, u64>::with_capacity(256);
+        for k in 0..200 {
+            m.insert(k * 7, k as u64);
+        }
+        assert_eq!(m.partitions(0).len(), 1);
+        let parts: Vec<_> = m.partitions(3).collect();
+        assert_eq!(parts[0].slots().start, 0);
+        assert_eq!(parts[2].slots().end, m.table.capacity());
+        assert_eq!(parts[0].slots().end, parts[1].slots().start);
+
+        let sums: Vec<u64> = std::thread::scope(|scope| {
+            let workers: Vec<_> =
+                m.partitions(4).map(|p| scope.spawn(move || p.values().sum::<u64>())).collect();
+            workers.into_iter().map(|w| w.join().unwrap()).collect()
+        });
+        assert_eq!(sums.iter().sum::<u64>(), (0..200).sum());
+        let len: usize = m.partitions(5).map(|p| p.iter().len()).sum();
+        assert_eq!(len, m.len());
+        assert_eq!(m.partitions(2).map(|p| p.keys().count()).sum::<usize>(), 200);
+    }
+
+    #[test]
+    fn retain() {
+        let mut m = IntMap::<u32, u32>::with_capacity(16);
+        // a single long cluster, every removal shifts the rest
+        for k in 0..12 {
+            m.insert(k * 16 + 3, k);
+        }
+        m.insert(4, 100);
+        m.retain(|_, v| {
+            *v += 1;
+            *v % 2 == 0
+        });
+        m.assert_invariants();
+        let mut values = m.values_to_vec();
+        values.sort_unstable();
+        assert_eq!(values, [2, 4, 6, 8, 10, 12]);
+        assert!(!m.contains(4));
+    }
+
+    #[test]
+    fn weak_values() {
+        let alive = Arc::new("alive");
+        let dead = Arc::new("dead");
+        let mut m = IntMap::<u64, Weak<&str>>::with_capacity(8);
+        m.insert(1, Arc::downgrade(&alive));
+        m.insert(9, Arc::downgrade(&dead));
+        m.insert(17, Weak::new());
+        assert_eq!(m.get_upgraded(9).as_deref(), Some(&"dead"));
+        drop(dead);
+        assert_eq!(m.get_upgraded(9), None);
+        assert_eq!(m.prune_dead(), 2);
+        assert_eq!(m.len(), 1);
+        assert_eq!(m.get_upgraded(1).as_deref(), Some(&"alive"));
+        m.assert_invariants();
+    }
+
+    #[test]
+    fn get_or_try_insert_with() {
+        let mut m = IntMap::<u32, String>::with_capacity(8);
+        let loaded = m.get_or_try_insert_with(1, || Ok::<_, ()>("one".to_string()));
+        assert_eq!(loaded.map(|v| v.as_str()), Ok("one"));
+        let cached = m.get_or_try_insert_with(1, || Err("not called"));
+        assert_eq!(cached.map(|v| v.clone()), Ok("one".to_string()));
+        let failed = m.get_or_try_insert_with(9, || Err("io error"));
+        assert_eq!(failed.map(|v| v.clone()), Err("io error"));
+        assert_eq!(m.len(), 1);
+        m.assert_invariants();
+
+        // a full evicting map evicts only once the value is there
+        let mut m = IntMap::<u32, u32>::with_capacity(16);
+        m.set_saturation_policy(SaturationPolicy::Evict);
+        for k in 0..16 {
+            m.insert(k, k);
+        }
+        let before = m.clone();
+        assert_eq!(m.get_or_try_insert_with(16, || Err("io error")).err(), Some("io error"));
+        assert!(m.len() == 16 && m == before);
+        assert_eq!(m.get_or_try_insert_with(16, || Ok::<_, ()>(160)).copied(), Ok(160));
+        assert_eq!(m.len(), 16);
+        m.assert_invariants();
+    }
+
+    #[test]
+    fn sync_map() {
+        let m = SyncIntMap::<u32, String>::with_capacity(64);
+        std::thread::scope(|scope| {
+            for t in 0..4 {
+                let m = &m;
+                scope.spawn(move || {
+                    for k in 0..8 {
+                        m.insert(t * 8 + k, k.to_string());
+                    }
+                });
+            }
+        });
+        assert_eq!(m.len(), 32);
+        assert_eq!(m.get_cloned(25).as_deref(), Some("1"));
+        assert_eq!(m.with_read(|map| map.values().filter(|v| *v == "0").count()), 4);
+
+        let poisoned = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            m.with_write(|map| {
+                map.remove(0);
+                panic!("writer died");
+            })
+        }));
+        assert!(poisoned.is_err() && m.is_poisoned());
+        assert!(!m.contains(0));
+        assert_eq!(m.remove(1).as_deref(), Some("1"));
+        m.clear_poison();
+        assert!(!m.is_poisoned());
+        assert_eq!(m.into_inner().len(), 30);
+    }
+
+    #[test]
+    fn typed_map() {
+        struct Order;
+        struct Trade;
+        let mut orders = TypedIntMap::<Order, u64, &str>::with_capacity(8);
+        let order = Id::<Order, _>::new(7);
+        orders.insert(order, "buy");
+        assert_eq!(orders.get(order), Some(&"buy"));
+        assert_eq!(orders.iter().next(), Some((order, &"buy")));
+        // `orders.get(Id::<Trade, _>::new(7))` doesn't compile
+        let trade = Id::<Trade, u64>::new(7);
+        assert_eq!(trade.raw(), order.raw());
+        assert_eq!(std::mem::size_of::<Id<Order, u64>>(), 8);
+        assert_eq!(orders.remove(order), Some("buy"));
+        assert!(orders.is_empty());
+    }
+
+    #[test]
+    fn collision_group() {
+        let mut m = IntMap::<u32, u32>::with_capacity(8);
+        for k in [1, 2, 9, 10, 17, 3] {
+            m.insert(k, k * 10);
+        }
+        let mut group: Vec<_> = m.collision_group(25).collect();
+        group.sort_unstable();
+        assert_eq!(group, [(1, &10), (9, &90), (17, &170)]);
+        let mut group: Vec<_> = m.collision_group(2).map(|(k, _)| k).collect();
+        group.sort_unstable();
+        assert_eq!(group, [2, 10]);
+        assert_eq!(m.collision_group(3).count(), 1);
+        assert_eq!(m.collision_group(4).count(), 0);
+    }
+
+    #[test]
+    fn disjoint() {
+        let mut a = IntMap::<u32, ()>::with_capacity(16);
+        let mut b = IntMap::<u32, char>::with_capacity(64);
+        for k in 0..10 {
+            a.insert(k, ());
+        }
+        for k in 10..50 {
+            b.insert(k, 'b');
+        }
+        assert!(a.is_disjoint(&b) && b.is_disjoint(&a));
+        b.insert(3, 'x');
+        assert!(a.intersects(&b) && b.intersects(&a));
+        assert!(IntMap::<u32, ()>::with_capacity(1).is_disjoint(&a));
+
+        // lookups into a map indexed differently are prefetched
+        let mut small = IntMap::<u32, ()>::with_capacity(1 << 8);
+        let mut large = IntMap::<u32, ()>::with_exact_capacity(5000);
+        for k in 0..4000 {
+            large.insert(k * 2, ());
+        }
+        for k in 0..200 {
+            small.insert(k * 2 + 1, ());
+        }
+        assert!(small.is_disjoint(&large) && large.is_disjoint(&small));
+        small.insert(7998, ());
+        assert!(small.intersects(&large) && large.intersects(&small));
+    }
+
+    #[test]
+    fn subset() {
+        let mut parent = IntMap::<i64, ()>::with_capacity(32);
+        let mut child = IntMap::<i64, u8>::with_capacity(8);
+        for k in -10..10 {
+            parent.insert(k, ());
+        }
+        for k in [-3, 0, 9] {
+            child.insert(k, 0);
+        }
+        assert!(child.keys_subset_of(&parent) && parent.keys_superset_of(&child));
+        assert!(!parent.keys_subset_of(&child) && !child.keys_equal(&parent));
+        child.insert(10, 0);
+        assert!(!child.keys_subset_of(&parent));
+        assert!(child.keys_equal(&child.clone()));
+
+        let mut large = IntMap::<i64, ()>::with_exact_capacity(3000);
+        for k in -1000..1000 {
+            large.insert(k, ());
+        }
+        assert!(child.keys_subset_of(&large) && !large.keys_subset_of(&child));
+        child.insert(1000, 0);
+        assert!(!child.keys_subset_of(&large));
+    }
+
+    #[test]
+    fn value_predicates() {
+        let mut m = IntMap::<u32, i32>::with_capacity(32);
+        for k in 0..20 {
+            m.insert(k * 3, k as i32 - 5);
+        }
+        m.set_lazy_clear(true);
+        assert_eq!(m.count_values(|v| *v < 0), 5);
+        assert!(m.any_value(|v| *v == 14));
+        assert!(!m.any_value(|v| *v == 15));
+        assert!(m.all_values(|v| *v >= -5));
+        assert!(!m.all_values(|v| *v >= 0));
+        m.clear();
+        assert_eq!(m.count_values(|_| true), 0);
+        assert!(m.all_values(|_| false));
+    }
+
+    #[test]
+    fn clone_compact() {
+        let mut m = IntMap::<u64, String>::with_capacity(4096);
+        for k in 0..100 {
+            m.insert(k * 1000, k.to_string());
+        }
+        m.set_reserved_key(Some(u64::MAX));
+        let snapshot = m.clone_compact(0.75);
+        assert_eq!(snapshot.capacity(), 256);
+        assert!(snapshot == m);
+        assert_eq!(snapshot.reserved_key(), Some(u64::MAX));
+        snapshot.assert_invariants();
+        assert_eq!(m.clone_compact(1e-6).capacity(), 4096);
+
+        let exact = IntMap::<u64, ()>::with_exact_capacity(1000);
+        assert_eq!(exact.clone_compact(0.5).capacity(), 1);
+
+        // strided keys share home slots in smaller tables, the clone grows until they fit
+        let mut strided = IntMap::<u64, u64>::with_capacity(1 << 20);
+        for i in 0..1000 {
+            strided.insert(i * 1024, i);
+        }
+        let snapshot = strided.clone_compact(0.8);
+        assert!(snapshot == strided);
+        // 8 home slots of 125 keys each
+        assert_eq!(snapshot.capacity(), 8192);
+        snapshot.assert_invariants();
+        let mut crowded = IntMap::<u32, ()>::with_probe_limit(1 << 10, 2);
+        for k in [0, 1, 512, 513] {
+            crowded.insert(k, ());
+        }
+        let snapshot = crowded.clone_compact(1.0);
+        assert_eq!((snapshot.capacity(), snapshot.probe_limit()), (1 << 10, 2));
+        assert!(snapshot == crowded);
+
+        // the probe limit carries over to exact capacity clones as well
+        let config = Config::new(100).capacity_policy(CapacityPolicy::Exact).probe_limit(8);
+        let mut exact = IntMap::<u32, u32>::with_config(&config);
+        for k in 0..10 {
+            exact.insert(k, k);
+        }
+        let snapshot = exact.clone_compact(0.5);
+        assert_eq!((snapshot.capacity(), snapshot.probe_limit()), (20, 8));
+        assert!(snapshot == exact);
+        snapshot.assert_invariants();
+    }
+
+    #[test]
+    fn map_values_to() {
+        let mut m = IntMap::<u32, u32>::with_probe_limit(16, 8);
+        for k in [1, 17, 33, 2, 5] {
+            m.insert(k, k);
+        }
+        m.set_lazy_clear(true);
+        let strings = m.map_values_to(|v| format!("#{v}"));
+        strings.assert_invariants();
+        assert_eq!(strings.len(), 5);
+        assert_eq!(strings.get(33).map(String::as_str), Some("#33"));
+        assert_eq!(strings.probe_limit(), 8);
+        assert!(strings.is_lazy_clear());
+        assert!(m
+            .iter_slots()
+            .map(|(i, k, _)| (i, k))
+            .eq(strings.iter_slots().map(|(i, k, _)| (i, k))));
+
+        m.clear();
+        m.insert(3, 3);
+        assert_eq!(m.map_values_to(|v| *v as u64).get(3), Some(&3));
+    }
+
+    #[test]
+    fn clone_structure() {
+        let mut m = IntMap::<i32, String>::with_capacity(8);
+        for k in [-1, 7, 15] {
+            m.insert(k, k.to_string());
+        }
+        let epoch: IntMap<i32, i64> = m.clone_structure(|k| k as i64 * 2);
+        epoch.assert_invariants();
+        assert!(epoch.keys_equal(&m));
+        assert_eq!(epoch.get(15), Some(&30));
+        assert_eq!(epoch.get(-1), Some(&-2));
+    }
+
+    #[test]
+    #[cfg(feature = "poison")]
+    #[should_panic(expected = "read of released (poisoned) slot 2")]
+    fn poison_released_slots() {
+        let mut m = IntMap::<u32, u32>::with_capacity(8);
+        m.insert(1, 1);
+        m.insert(9, 9);
+        m.remove(1);
+        // 9 shifted back into slot 1, slot 2 is released
+        assert_eq!(m.table.value_bytes()[8..12], [0xDE; 4]);
+        let _ = m.table[2];
+    }
+
+    #[test]
+    fn sparse_vec() {
+        let mut x = SparseVec::with_capacity(16);
+        x.scatter_add(&[1, 5, 5, 9], &[1.0, 2.0, 0.5, 3.0]);
+        assert_eq!(x.nnz(), 3);
+        assert_eq!(x.get(5), 2.5);
+        assert_eq!(x.get(7), 0.0);
+
+        let mut y = SparseVec::with_capacity(16);
+        y.set(5, 2.0);
+        y.set(11, 4.0);
+        assert_eq!(x.dot(&y), 5.0);
+        assert_eq!(y.dot(&x), 5.0);
+        assert_eq!(x.dot_dense(&[0.0, 2.0, 0.0, 0.0, 0.0, 1.0]), 4.5);
+
+        y.axpy(2.0, &x);
+        assert_eq!((y.get(1), y.get(5), y.get(9), y.get(11)), (2.0, 7.0, 6.0, 4.0));
+        y.scale(0.5);
+        assert_eq!(y.get(5), 3.5);
+
+        let mut dense = vec![1.0; 12];
+        x.scatter_into(&mut dense);
+        assert_eq!(&dense[..6], &[1.0, 2.0, 1.0, 1.0, 1.0, 3.5]);
+        let g = SparseVec::gather(&[0.0, 2.0, 0.0, 3.0], &[0, 1, 3]);
+        assert_eq!(g.nnz(), 2);
+        assert_eq!(g.get(3), 3.0);
+    }
+
+    #[test]
+    fn int_graph() {
+        let mut g = IntGraph::with_capacity(16);
+        g.add_edge(1u32, 2);
+        g.add_edge(1, 3);
+        g.add_edge(2, 4);
+        g.add_edge(3, 4);
+        g.add_edge(4, 1);
+        g.add_undirected_edge(7, 8);
+        assert!(!g.add_node(8));
+        assert!(g.add_node(9));
+        assert_eq!(g.node_count(), 7);
+        assert_eq!(g.edge_count(), 7);
+        assert_eq!(g.neighbors(1), &[2, 3]);
+        assert_eq!((g.degree(4), g.degree(9), g.degree(42)), (1, 0, 0));
+
+        assert_eq!(g.bfs(1).collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+        assert_eq!(g.dfs(1).collect::<Vec<_>>(), vec![1, 2, 4, 3]);
+        assert_eq!(g.dfs(7).collect::<Vec<_>>(), vec![7, 8]);
+        assert_eq!(g.bfs(9).collect::<Vec<_>>(), vec![9]);
+        assert_eq!(g.bfs(42).count(), 0);
+    }
+
+    #[test]
+    fn interner() {
+        let mut interner = Interner::with_capacity(64);
+        let words = ["", "a", "ab", "a longer string spanning words", "ünïcödé", "a"];
+        let symbols = words.map(|w| interner.intern(w));
+        assert_eq!(symbols, [0, 1, 2, 3, 4, 1]);
+        assert_eq!(interner.len(), 5);
+        for (w, s) in words.iter().zip(symbols) {
+            assert_eq!(interner.resolve(s), Some(*w));
+            assert_eq!(interner.get(w), Some(s));
+        }
+        assert_eq!(interner.get("b"), None);
+        assert_eq!(interner.resolve(5), None);
+        assert_eq!(interner.iter().map(|(_, s)| s).collect::<Vec<_>>(), &words[..5]);
+
+        // a colliding hash is resolved by comparing against the arena
+        let mut interner = Interner::with_capacity(8);
+        let a = interner.intern("a");
+        interner.symbols.insert(interner::hash("b"), a);
+        let b = interner.intern("b");
+        assert_ne!(a, b);
+        assert_eq!((interner.get("a"), interner.get("b")), (Some(a), Some(b)));
+    }
+
+    #[test]
+    fn id_registry() {
+        let mut r = IdRegistry::<u32, &str>::with_capacity(4);
+        assert_eq!([r.insert_new("a"), r.insert_new("b"), r.insert_new("c")], [0, 1, 2]);
+        assert_eq!(r.remove(1), Some("b"));
+        assert_eq!(r.remove(1), None);
+        assert_eq!(r.insert_new("d"), 1);
+        assert_eq!(r.insert_new("e"), 3);
+        assert!(r.is_full());
+        assert_eq!(r.try_insert_new("f"), Err("f"));
+        assert_eq!(r.get(1), Some(&"d"));
+        // ids below the capacity never probe
+        assert!(r.as_inner().probes().iter().all(|&p| p == 0));
+        r.as_inner().assert_invariants();
+
+        r.clear();
+        assert_eq!(r.insert_new("g"), 0);
+    }
+
+    #[test]
+    fn windowed_int_map() {
+        let mut m = WindowedIntMap::with_capacity(16, 4u64);
+        for k in 0..5 {
+            assert_eq!(m.insert(k, k), Ok(None));
+        }
+        assert_eq!(m.len(), 5);
+        assert_eq!(m.insert(6, 6), Ok(None));
+        assert_eq!((m.window_start(), m.len()), (2, 4));
+        assert!(!m.contains(1) && m.contains(2));
+        assert_eq!(m.insert(1, 1), Err(1));
+        assert_eq!(m.insert(3, 30), Ok(Some(3)));
+
+        // a jump past the whole window evicts everything by a scan
+        assert_eq!(m.insert(1_000_000, 0), Ok(None));
+        assert_eq!(m.iter().map(|(k, _)| k).collect::<Vec<_>>(), vec![1_000_000]);
+        assert_eq!(m.max_key(), Some(1_000_000));
+
+        let mut m = WindowedIntMap::with_capacity(8, 2i32);
+        assert_eq!(m.insert(i32::MIN, 0), Ok(None));
+        assert_eq!(m.insert(i32::MIN + 1, 0), Ok(None));
+        assert_eq!(m.insert(i32::MAX, 0), Ok(None));
+        assert_eq!((m.len(), m.window_start()), (1, i32::MAX - 2));
+    }
+
+    #[test]
+    fn saturation_policy() {
+        let mut m: IntMap<u32, u32> = IntMap::with_capacity(4);
+        assert_eq!(m.saturation_policy(), SaturationPolicy::Overflow);
+        for k in 0..4 {
+            m.insert(k, k);
+        }
+        assert!(m.is_full());
+        assert_eq!(m.insert(4, 4), None);
+        assert_eq!(m.len(), 5);
+
+        let mut m: IntMap<u32, u32> = IntMap::with_capacity(4);
+        m.set_saturation_policy(SaturationPolicy::Reject);
+        for k in 0..4 {
+            m.insert(k, k);
+        }
+        assert_eq!(m.try_insert(8, 8).err().unwrap().into_entry(), (8, 8));
+        // updates of present keys still go through
+        assert_eq!(m.try_insert(1, 10).ok(), Some(Some(1)));
+        assert_eq!(m.len(), 4);
+        assert_eq!(m.clone().saturation_policy(), SaturationPolicy::Reject);
+
+        let mut m: IntMap<u32, u32> = IntMap::with_capacity(4);
+        m.set_saturation_policy(SaturationPolicy::Evict);
+        for k in 0..4 {
+            m.insert(k, k);
+        }
+        // 6 lands in home slot 2 and evicts its resident
+        assert_eq!(m.insert(6, 6), None);
+        assert_eq!(m.len(), 4);
+        assert!(!m.contains(2) && m.contains(6));
+        *m.probe(7).or_insert(0) += 7;
+        assert_eq!((m.len(), m.get(7)), (4, Some(&7)));
+        assert!(!m.merge_insert(9, 9, |_, _| unreachable!()));
+        assert_eq!(m.len(), 4);
+        m.assert_invariants();
+    }
+
+    #[test]
+    fn saturation_suggest_eviction() {
+        let mut m: IntMap<u32, u32> = IntMap::with_capacity(16);
+        m.set_saturation_policy(SaturationPolicy::SuggestEviction { max_load_percent: 75 });
+        // 12 entries fit, keys sharing home slot 3 are displaced the farthest
+        for k in [3, 19, 35, 51, 8, 9, 10, 11, 12, 13, 14, 15] {
+            m.insert(k, k);
+        }
+        assert_eq!(m.eviction_candidates(3), [51, 35, 19]);
+        assert_eq!(m.eviction_candidates(100).len(), 12);
+        let Err(InsertError::EvictionNeeded(0, 0, candidates)) = m.try_insert(0, 0) else {
+            panic!("insert past the load limit went through");
+        };
+        assert_eq!(candidates.len(), EVICTION_CANDIDATES);
+        assert_eq!(candidates[0], 51);
+        assert_eq!(m.try_insert(3, 30).ok(), Some(Some(3)));
+        m.remove(candidates[0]);
+        assert_eq!(m.try_insert(0, 0).ok(), Some(None));
+        assert_eq!(m.len(), 12);
+        m.assert_invariants();
+    }
+
+    #[test]
+    #[should_panic(expected = "map is at its load limit, key 1 rejected")]
+    fn saturation_suggest_eviction_panics_on_insert() {
         let mut m: IntMap<u32, u32> = IntMap::with_capacity(4);
+        m.set_saturation_policy(SaturationPolicy::SuggestEviction { max_load_percent: 25 });
         m.insert(0, 0);
         m.insert(1, 1);
-        /*
-        index key distance
-        0     0   0
-        1     1   0
-        */
-        m.insert(4, 2);
-        /*
-        index key distance
-        0     0   0
-        1     4   1
-        2     1   1
-        */
-        assert_eq!(m.len(), 3);
-        m.insert(8, 3);
-        /*
-        index key distance
-        0     0   0
-        1     4   1
-        2     8   2
-        3     1   2
-        */
-        assert_eq!(m.keys().cloned().collect::<Vec<u32>>(), &[0, 4, 8, 1]);
     }
 
     #[test]
-    fn insert_collide_inside() {
-        let mut m: IntMap<u32, u32> = IntMap::with_capacity(8);
-        m.insert(0, 0);
-        m.insert(8, 1);
-        m.insert(1, 2);
-        m.insert(2, 3);
-        /*
-        index key distance
-        0     0   0
-        1     8   1
-        2     1   1
-        3     2   1
-        */
-        m.insert(16, 4);
-        /*
-        index key distance
-        0     0   0
-        1     8   1
-        2     16  2
-        3     1   2
-        4     2   2
-        */
-        assert_eq!(m.len(), 5);
-        assert_eq!(m.keys().cloned().collect::<Vec<u32>>(), &[0, 8, 16, 1, 2]);
+    #[should_panic(expected = "map is full, key 4 rejected")]
+    fn saturation_reject_panics_on_insert() {
+        let mut m: IntMap<u32, u32> = IntMap::with_capacity(4);
+        m.set_saturation_policy(SaturationPolicy::Reject);
+        for k in 0..5 {
+            m.insert(k, k);
+        }
+    }
+
+    #[test]
+    fn iter_orders() {
+        let mut m: IntMap<i64, i64> = IntMap::with_capacity(8);
+        for k in [5, -3, 8, 1, 16] {
+            m.insert(k, k * 10);
+        }
+        let storage = m.iter_storage_order().map(|(k, _)| *k).collect::<Vec<_>>();
+        assert_eq!(storage, m.iter().map(|(k, _)| *k).collect::<Vec<_>>());
+        assert_eq!(storage, vec![8, 16, 1, 5, -3]);
+        let sorted = m.iter_key_order().map(|(k, v)| (*k, *v)).collect::<Vec<_>>();
+        assert_eq!(sorted, vec![(-3, -30), (1, 10), (5, 50), (8, 80), (16, 160)]);
+    }
+
+    #[test]
+    fn convert_keys() {
+        let mut m: IntMap<u64, String> = IntMap::with_capacity(16);
+        for k in [0, 3, 19, 35, 7, u32::MAX as u64] {
+            m.insert(k, k.to_string());
+        }
+        m.set_lazy_clear(true);
+        m.set_reserved_key(Some(1 << 40));
+        let slots = m.iter_slots().map(|(i, k, _)| (i, k)).collect::<Vec<_>>();
+        let n: IntMap<u32, String> = m.convert_keys().unwrap();
+        n.assert_invariants();
+        assert_eq!(n.iter_slots().map(|(i, k, _)| (i, k as u64)).collect::<Vec<_>>(), slots);
+        assert_eq!(n.get(19).map(String::as_str), Some("19"));
+        assert_eq!(n.reserved_key(), None);
+        assert!(n.is_lazy_clear());
+
+        let err = n.clone().convert_keys::<i32>().err().unwrap();
+        assert_eq!(err.key(), u32::MAX);
+        assert_eq!(err.to_string(), "key 4294967295 doesn't fit the target key type");
+        assert_eq!(err.into_map().len(), 6);
+
+        let mut m: IntMap<i64, u8> = IntMap::with_exact_capacity(10);
+        for k in -4..4 {
+            m.insert(k, k as u8);
+        }
+        let n = m.convert_keys::<i32>().unwrap();
+        n.assert_invariants();
+        assert_eq!(n.len(), 8);
+        assert_eq!(n.get(-3), Some(&253));
+        // rebuilt maps keep the probe limit
+        let config = Config::new(100).capacity_policy(CapacityPolicy::Exact).probe_limit(8);
+        let mut m = IntMap::<u64, u64>::with_config(&config);
+        for k in 0..50 {
+            m.insert(k * 3, k);
+        }
+        let n = m.clone().convert_keys::<u32>().unwrap();
+        n.assert_invariants();
+        assert_eq!((n.capacity(), n.probe_limit()), (100, 8));
+        assert!(m.iter().all(|(&k, v)| n.get(k as u32) == Some(v)));
+
+        // negative keys keep their home slots too
+        let m: IntMap<i64, u8> = (-4..4).fold(IntMap::with_capacity(4), |mut m, k| {
+            m.insert(k * 4, 0);
+            m
+        });
+        let n = m.convert_keys::<i32>().unwrap();
+        n.assert_invariants();
+        assert!((-4..4).all(|k| n.contains(k * 4)));
+    }
+
+    #[test]
+    fn yielding_variants() {
+        use std::rc::Rc;
+
+        let mut m: IntMap<u32, Rc<()>> = IntMap::with_capacity(64);
+        let value = Rc::new(());
+        for k in 0..48 {
+            m.insert(k * 3, value.clone());
+        }
+        let mut yields = 0;
+        m.retain_yielding(16, |k, _| k % 2 == 0, || yields += 1);
+        assert_eq!((m.len(), Rc::strong_count(&value)), (24, 25));
+        assert!(yields >= m.table.capacity() / 16 - 1);
+        m.assert_invariants();
+
+        // a yield hook unwinding leaves the entries not cleared yet readable
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let mut yields = 0;
+            m.clear_yielding(8, || {
+                yields += 1;
+                assert!(yields < 18, "budget exhausted");
+            });
+        }));
+        assert!(result.is_err());
+        m.assert_invariants();
+        assert!(!m.is_empty() && m.len() < 24);
+        assert_eq!(Rc::strong_count(&value), m.len() + 1);
+        assert!(m.keys().all(|&k| m.get(k).is_some()));
+
+        let mut yields = 0;
+        m.clear_yielding(32, || yields += 1);
+        assert!(m.is_empty());
+        assert_eq!(Rc::strong_count(&value), 1);
+        m.insert(3, value.clone());
+        assert_eq!(m.len(), 1);
+    }
+
+    #[test]
+    fn canonicalizer() {
+        // the high byte holds a version that doesn't take part in the identity
+        let mut m: IntMap<u32, &str> = IntMap::with_canonicalizer(16, |k| k & 0x00ff_ffff);
+        assert_eq!(m.insert(0x0100_0007, "v1"), None);
+        assert_eq!(m.insert(0x0200_0007, "v2"), Some("v1"));
+        assert_eq!(m.len(), 1);
+        assert_eq!(m.get(7), Some(&"v2"));
+        assert_eq!(m.keys().copied().collect::<Vec<_>>(), vec![7]);
+        assert!(m.contains(0xff00_0007));
+        assert_eq!(unsafe { *m.get_unchecked(0x0300_0007) }, "v2");
+        *m.probe(0x0500_0009).or_insert("a") = "b";
+        assert_eq!(m.get(9), Some(&"b"));
+        m.set_reserved_key(Some(0x0100_0011));
+        assert!(m.try_insert(0x11, "r").is_err());
+        let c = m.clone();
+        assert_eq!(c.get(0x0400_0009), Some(&"b"));
+        assert_eq!(m.remove(0x0900_0007), Some("v2"));
+        assert_eq!(m.len(), 1);
+        m.assert_invariants();
+    }
+
+    #[test]
+    fn limits() {
+        assert_eq!(IntMap::<u32, u8>::with_capacity(0).capacity(), MIN_CAPACITY as usize);
+        assert_eq!(IntMap::<u32, u8>::with_capacity(8).probe_limit(), MAX_PROBES as usize);
+        assert_eq!(IntMap::<u32, u8>::with_probe_limit(8, 0).probe_limit(), MIN_PROBES as usize);
+        assert_eq!(IntMap::<u32, u8>::with_capacity(8).table.capacity(), 8 + OVERFLOW_SLOTS);
+    }
+
+    #[test]
+    fn atomic_values() {
+        use std::sync::atomic::AtomicU64;
+
+        const ROUNDS: u64 = if cfg!(miri) { 20 } else { 1000 };
+        let mut m: IntMap<u32, AtomicU64> = IntMap::with_capacity(16);
+        for k in 0..4 {
+            m.insert(k, AtomicU64::new(0));
+        }
+        let m = m;
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for _ in 0..ROUNDS {
+                        // optimistic increment of every key
+                        for k in 0..4 {
+                            let mut current = m.load(k).unwrap();
+                            while let Err(actual) =
+                                m.compare_exchange(k, current, current + 1).unwrap()
+                            {
+                                current = actual;
+                            }
+                        }
+                    }
+                });
+            }
+        });
+        assert!((0..4).all(|k| m.load(k) == Some(4 * ROUNDS)));
+        assert_eq!(m.compare_exchange(9, 0, 1), None);
+        assert_eq!(m.compare_exchange(1, 7, 8), Some(Err(4000)));
+        assert_eq!(m.fetch_add(1, 5), Some(4000));
+        assert_eq!(m.fetch_update(1, |v| (v < 5000).then_some(v * 2)), Some(Ok(4005)));
+        assert_eq!(m.load(1), Some(8010));
+        assert!(m.store(2, 3) && !m.store(9, 3));
+
+        let sync = SyncIntMap::with_capacity(8);
+        sync.insert(1u32, "a".to_string());
+        assert_eq!(sync.compare_exchange(1, &"a".to_string(), "b".into()), Ok("a".into()));
+        assert_eq!(sync.compare_exchange(1, &"a".to_string(), "c".into()), Err(Some("b".into())));
+        assert_eq!(sync.compare_exchange(2, &"a".to_string(), "c".into()), Err(None));
+    }
+
+    #[test]
+    fn weighted_int_map() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        let mut m: WeightedIntMap<u32, String> = WeightedIntMap::with_budget(16, 10, String::len);
+        let evicted = Rc::new(RefCell::new(Vec::new()));
+        let sink = evicted.clone();
+        m.set_eviction_hook(move |k, v| sink.borrow_mut().push((k, v)));
+
+        m.insert(0, "aaaa".into());
+        m.insert(1, "bbbb".into());
+        assert_eq!(m.total_weight(), 8);
+        assert_eq!(m.insert(1, "bb".into()), Some("bbbb".into()));
+        assert_eq!(m.total_weight(), 6);
+        m.insert(2, "cccccc".into());
+        // 12 > 10, the clock hand starts at slot 0
+        assert_eq!(*evicted.borrow(), vec![(0, "aaaa".to_string())]);
+        assert_eq!((m.len(), m.total_weight()), (2, 8));
+
+        assert!(m.update(1, |v| v.push_str("bbbbbb")));
+        assert_eq!(evicted.borrow().last(), Some(&(2, "cccccc".to_string())));
+        assert_eq!((m.len(), m.total_weight()), (1, 8));
+
+        // an entry heavier than the budget stays alone
+        m.insert(3, "x".repeat(20));
+        assert_eq!((m.len(), m.total_weight()), (1, 20));
+        assert_eq!(m.remove(3).map(|v| v.len()), Some(20));
+        assert_eq!(m.total_weight(), 0);
+
+        for k in 0..5 {
+            m.insert(k, "zz".into());
+        }
+        m.set_budget(4);
+        assert_eq!((m.len(), m.total_weight()), (2, 4));
+
+        // an insert that panics on a full table doesn't count its value
+        let mut m: WeightedIntMap<u32, u8> = WeightedIntMap::with_budget(1, usize::MAX, |_| 1);
+        let mut k = 0;
+        while std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| m.insert(k, 0))).is_ok() {
+            k += 1;
+        }
+        assert_eq!((m.len(), m.total_weight()), (k as usize, k as usize));
+    }
+
+    #[test]
+    fn panic_safety() {
+        use std::cell::Cell;
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        thread_local!(static DROPS: Cell<usize> = const { Cell::new(0) });
+        struct Bomb(bool);
+        impl Drop for Bomb {
+            fn drop(&mut self) {
+                DROPS.with(|d| d.set(d.get() + 1));
+                if self.0 {
+                    panic!("bomb");
+                }
+            }
+        }
+
+        // a panicking destructor during clear still drops every other value exactly once
+        let mut m: IntMap<u32, Bomb> = IntMap::with_capacity(16);
+        for k in 0..10 {
+            m.insert(k, Bomb(k == 3));
+        }
+        assert!(catch_unwind(AssertUnwindSafe(|| m.clear())).is_err());
+        assert_eq!(DROPS.with(Cell::get), 10);
+        assert!(m.is_empty());
+        m.assert_invariants();
+        m.insert(1, Bomb(false));
+        drop(m);
+        assert_eq!(DROPS.with(Cell::get), 11);
+
+        // a displacement chain overflowing the probe limit panics before moving anything
+        let mut m: IntMap<u32, String> = IntMap::with_probe_limit(8, 4);
+        for k in [0, 8, 1, 9, 17] {
+            m.insert(k, k.to_string());
+        }
+        let before = m.clone();
+        let result = catch_unwind(AssertUnwindSafe(|| m.insert(16, "16".into())));
+        assert!(result.is_err());
+        m.assert_invariants();
+        assert!(m == before);
+        assert!(!m.contains(16));
+    }
+
+    #[test]
+    fn unwind_safety() {
+        use std::panic::{catch_unwind, AssertUnwindSafe, RefUnwindSafe, UnwindSafe};
+        use std::rc::Rc;
+
+        fn unwind_safe<T: UnwindSafe + RefUnwindSafe>() {}
+        unwind_safe::<IntMap<u64, String>>();
+        unwind_safe::<SyncIntMap<u32, Vec<u8>>>();
+        unwind_safe::<StatsHandle>();
+        // owned like a Vec's, interior mutable values don't keep the map from being UnwindSafe
+        fn owned_unwind_safe<T: UnwindSafe>() {}
+        owned_unwind_safe::<IntMap<u32, std::cell::Cell<u8>>>();
+
+        let value = Rc::new(());
+        let filled = || {
+            let mut m = IntMap::with_capacity(64);
+            for k in 0..48u32 {
+                m.insert(k * 5, value.clone());
+            }
+            m
+        };
+        // every entry is either still in the map or was dropped once
+        let check = |m: &IntMap<u32, Rc<()>>, held: usize| {
+            m.assert_invariants();
+            assert_eq!(Rc::strong_count(&value), m.len() + held + 1);
+            assert!(m.keys().all(|&k| m.get(k).is_some()));
+        };
+        fn fails<R>(f: impl FnOnce() -> R) {
+            assert!(catch_unwind(AssertUnwindSafe(f)).is_err());
+        }
+
+        let mut m = filled();
+        fails(|| {
+            m.retain(|k, _| {
+                assert!(k < 150);
+                k % 2 == 0
+            })
+        });
+        assert!(m.len() > 24 && m.len() < 48);
+        check(&m, 0);
+        m.insert(1, value.clone());
+        check(&m, 0);
+
+        let mut removed = Vec::new();
+        m = filled();
+        fails(|| {
+            m.retain_into(
+                |k, _| {
+                    assert!(k < 150);
+                    k % 3 == 0
+                },
+                &mut removed,
+            )
+        });
+        assert!(!removed.is_empty());
+        check(&m, removed.len());
+        removed.clear();
+
+        m = filled();
+        fails(|| {
+            m.probe(1000).or_insert_with(|| panic!("init"));
+        });
+        fails(|| m.merge_insert(5, value.clone(), |_, _| panic!("merge")));
+        fails(|| m.rekey_all(|k| if k < 100 { k + 1 } else { panic!("rekey") }));
+        assert!(!m.contains(1000) && m.len() < 48);
+        check(&m, 0);
+
+        // 'f' runs before an evicting admit, its panic evicts nothing
+        m = IntMap::with_capacity(16);
+        m.set_saturation_policy(SaturationPolicy::Evict);
+        for k in 0..16u32 {
+            m.insert(k, value.clone());
+        }
+        fails(|| {
+            m.probe(16).or_insert_with(|| panic!("init"));
+        });
+        assert!(m.len() == 16 && m.contains(0));
+        check(&m, 0);
+        drop(m);
+
+        // a partial clone drops what it had cloned
+        struct Flaky(Rc<()>, u32);
+        impl Clone for Flaky {
+            fn clone(&self) -> Self {
+                assert!(self.1 != 20, "clone");
+                Flaky(self.0.clone(), self.1)
+            }
+        }
+        let mut m = IntMap::with_capacity(64);
+        for k in 0..48u32 {
+            m.insert(k, Flaky(value.clone(), k));
+        }
+        fails(|| m.clone());
+        fails(|| m.clone_structure(|k| if k < 20 { value.clone() } else { panic!() }));
+        m.assert_invariants();
+        assert_eq!(Rc::strong_count(&value), 49);
+        drop(m);
+
+        // a destructor unwinding out of 'retain' runs after its entry is unlinked
+        thread_local!(static ARMED: std::cell::Cell<bool> = const { std::cell::Cell::new(false) });
+        struct Bomb {
+            _count: Rc<()>,
+        }
+        impl Drop for Bomb {
+            fn drop(&mut self) {
+                if ARMED.with(|armed| armed.replace(false)) {
+                    panic!("bomb");
+                }
+            }
+        }
+        let mut m = IntMap::with_capacity(64);
+        for k in 0..48u32 {
+            m.insert(k * 5, Bomb { _count: value.clone() });
+        }
+        ARMED.with(|armed| armed.set(true));
+        fails(|| m.retain(|k, _| k != 100));
+        m.assert_invariants();
+        assert!(m.len() == 47 && !m.contains(100));
+        assert_eq!(Rc::strong_count(&value), 48);
+        drop(m);
+
+        let mut w = WeightedIntMap::with_budget(64, 10, |v: &Vec<u8>| v.len());
+        w.set_eviction_hook(|_, _| panic!("hook"));
+        w.insert(1, vec![0; 6]);
+        fails(|| w.insert(2, vec![0; 6]));
+        fails(|| {
+            w.update(2, |v| {
+                v.push(0);
+                panic!("update")
+            })
+        });
+        assert_eq!(w.total_weight(), w.iter().map(|(_, v)| v.len()).sum::<usize>());
+        assert!(w.get(1).is_none() && w.get(2).map(Vec::len) == Some(7));
+
+        let cell = IntMapCell::with_capacity(16);
+        cell.insert(1u32, 1);
+        fails(|| cell.update(1, |_| panic!("update")));
+        assert_eq!((cell.insert(2, 2), cell.len()), (None, 2));
+
+        let mut old = IntMap::with_capacity(16);
+        old.insert(1u32, 0u8);
+        old.insert(2, 1);
+        let mut migrating = MigratingMap::new(old, |_, v| 1 / v);
+        fails(|| migrating.get(1));
+        assert_eq!((migrating.len(), migrating.remaining()), (1, 1));
+        assert!(migrating.finish().get(2) == Some(&1));
+    }
+
+    #[test]
+    fn prefetch() {
+        let mut m = IntMap::with_capacity(16);
+        m.prefetch(1u32);
+        m.insert(1, 1);
+        m.prefetch(1);
+        m.prefetch(u32::MAX);
+        assert_eq!(m.get(1), Some(&1));
+
+        // present and missing keys, with both lookup modes
+        for k in 0..8 {
+            m.insert(k * 16, k);
+        }
+        m.warm([0, 16, 1, 2, 3, 1000, u32::MAX]);
+        m.set_constant_time_lookup(true);
+        m.warm(0..64);
+        assert_eq!((m.len(), m.get(16)), (9, Some(&1)));
     }
 
     #[test]
-    fn insert_signed_remove() {
-        let mut m = IntMap::with_capacity(4);
-        m.insert(-10, 1);
-        m.insert(-20, 2);
-        m.insert(-30, 3);
-        m.insert(-40, 4);
-        m.insert(-20, 22);
-        assert!(m.is_full());
-        assert_eq!(m.remove(-40), Some(4));
-        assert_eq!(m.remove(-10), Some(1));
-        assert_eq!(m.remove(-30), Some(3));
-        assert_eq!(m.remove(-20), Some(22));
+    fn retain_into_drain_into() {
+        let mut m = IntMap::with_capacity(64);
+        let mut buffer = Vec::new();
+        for round in 0..3u32 {
+            for k in 0..40 {
+                m.insert(k * 7, k + round);
+            }
+            buffer.clear();
+            m.retain_into(|k, _| k % 2 == 0, &mut buffer);
+            buffer.sort_unstable();
+            assert_eq!(
+                buffer,
+                (0..40).filter(|k| k * 7 % 2 == 1).map(|k| (k * 7, k + round)).collect::<Vec<_>>()
+            );
+            assert_eq!(m.len(), 20);
+            m.assert_invariants();
+        }
+        let capacity = buffer.capacity();
+        buffer.clear();
+        m.drain_into(&mut buffer);
         assert!(m.is_empty());
+        assert_eq!(buffer.len(), 20);
+        assert!(buffer.iter().all(|&(k, v)| k % 2 == 0 && v == k / 7 + 2));
+        assert_eq!(buffer.capacity(), capacity);
+        m.insert(1, 1);
+        assert_eq!(m.get(1), Some(&1));
     }
 
     #[test]
-    fn remove() {
-        let mut m = IntMap::with_capacity(4);
-        m.insert(1, 1);
-        assert_eq!(m.len(), 1);
-        assert_eq!(m.remove(1), Some(1));
-        assert!(m.is_empty());
-        m.insert(1, 1);
-        m.insert(2, 2);
-        m.insert(3, 3);
-        m.insert(4, 4);
-        assert!(m.is_full());
-        assert_eq!(m.remove(2), Some(2));
-        assert_eq!(m.remove(4), Some(4));
-        assert_eq!(m.remove(1), Some(1));
-        assert_eq!(m.remove(3), Some(3));
-        assert!(m.is_empty());
+    fn constant_time_lookup() {
+        let mut m = IntMap::with_capacity(64);
+        for k in 0..40u32 {
+            m.insert(k * 64 + k % 3, k);
+        }
+        m.set_constant_time_lookup(true);
+        let check = |m: &IntMap<u32, u32>| {
+            for k in 0..4096 {
+                let expected = m.iter().find(|(key, _)| **key == k).map(|(_, v)| v);
+                assert_eq!(m.get(k), expected);
+                assert_eq!(m.contains(k), expected.is_some());
+            }
+        };
+        check(&m);
+
+        // churn, removals shift entries and leave stale keys in free slots
+        for k in (0..40).step_by(2) {
+            m.remove(k * 64 + k % 3);
+        }
+        *m.get_mut(64 + 1).unwrap() += 100;
+        assert_eq!(m.get(64 + 1), Some(&101));
+        check(&m);
+
+        // stale keys of a lazy clear must not match
+        m.set_lazy_clear(true);
+        m.clear();
+        assert!(m.get(64 + 1).is_none());
+        m.insert(5, 5);
+        check(&m);
+
+        // the mode carries over to copies
+        let clone = m.clone();
+        assert!(clone.is_constant_time_lookup() && m.clone_compact(0.5).is_constant_time_lookup());
+        check(&clone);
+        let wide = m.clone().convert_keys::<u64>().unwrap();
+        assert!(wide.is_constant_time_lookup());
+        assert_eq!(wide.get(5), Some(&5));
+
+        m.set_constant_time_lookup(false);
+        check(&m);
     }
 
     #[test]
-    fn remove_conflict() {
-        let mut m = IntMap::with_capacity(4);
-        m.insert(1, 2);
-        assert_eq!(*m.get(1).unwrap(), 2);
-        m.insert(5, 3);
-        assert_eq!(*m.get(1).unwrap(), 2);
-        assert_eq!(*m.get(5).unwrap(), 3);
-        m.insert(9, 4);
-        assert_eq!(*m.get(1).unwrap(), 2);
-        assert_eq!(*m.get(5).unwrap(), 3);
-        assert_eq!(*m.get(9).unwrap(), 4);
-        assert!(m.remove(1).is_some());
-        assert_eq!(*m.get(9).unwrap(), 4);
-        assert_eq!(*m.get(5).unwrap(), 3);
+    fn aligned_values() {
+        let mut m: IntMap<u32, Align64<[f32; 4]>> = IntMap::with_capacity(16);
+        for k in 0..12 {
+            m.insert(k * 16, Align64([k as f32; 4]));
+        }
+        m.remove(16);
+        assert!(m.values().all(|v| (v as *const _ as usize).is_multiple_of(64)));
+        assert_eq!(m.get(32).map(|v| v[0]), Some(2.0));
+        *m.get_mut(32).unwrap() = [0.5; 4].into();
+        assert_eq!(m.get(32).unwrap().into_inner(), [0.5; 4]);
+        assert_eq!(std::mem::size_of::<Align32<[f32; 12]>>(), 64);
     }
 
     #[test]
-    #[cfg_attr(miri, ignore)]
-    fn insert_remove_seq() {
-        const N: u32 = 1 << 11;
-        let mut m = IntMap::with_capacity(N);
+    fn with_config() {
+        let m = IntMap::<u32, ()>::with_config(&Config::new(100).probe_limit(8));
+        assert_eq!((m.capacity(), m.probe_limit()), (128, 8));
 
-        for _ in 0..10 {
-            assert!(m.is_empty());
-            for i in 1..1001 {
-                m.insert(i, i);
+        let config = Config::new(100).capacity_policy(CapacityPolicy::Exact);
+        let build = |config: &Config| {
+            let mut m = IntMap::with_config(config);
+            for k in 0..80u64 {
+                m.insert(k * 1000, k);
+            }
+            m.assert_invariants();
+            m
+        };
+        let (a, b, seeded) = (build(&config), build(&config), build(&config.clone().seed(7)));
+        assert_eq!(a.capacity(), 100);
+        // deterministic for the same config, the seed moves keys around
+        assert!(a.iter().eq(b.iter()));
+        assert!(!a.iter().eq(seeded.iter()) && seeded == a);
+        // copies keep the seed
+        let mut expected = IntMap::with_config(&config.clone().capacity(80).seed(7));
+        for (&k, &v) in seeded.iter() {
+            expected.insert(k, v);
+        }
+        assert!(seeded.clone_compact(1.0).iter().eq(expected.iter()));
 
-                for j in 1..=i {
-                    let r = m.get(j);
-                    assert_eq!(r, Some(&j));
-                }
+        let mut full = IntMap::with_config(&Config::new(2).saturation(SaturationPolicy::Reject));
+        full.insert(1u32, ());
+        full.insert(2, ());
+        assert!(matches!(full.try_insert(3, ()), Err(InsertError::Full(3, ()))));
+    }
 
-                for j in i + 1..1001 {
-                    let r = m.get(j);
-                    assert_eq!(r, None);
-                }
+    #[test]
+    #[cfg(feature = "env")]
+    fn config_from_env() {
+        fn lookup(vars: &'static [(&str, &str)]) -> impl Fn(&str) -> Option<(String, String)> {
+            move |suffix| {
+                let (_, value) = vars.iter().find(|(name, _)| *name == suffix)?;
+                Some((format!("MAP_{suffix}"), value.to_string()))
             }
+        }
 
-            for i in 1001..2001 {
-                assert!(!m.contains(i));
+        let vars = &[
+            ("CAPACITY", "1000"),
+            ("CAPACITY_POLICY", "exact"),
+            ("SEED", "0xff"),
+            ("SATURATION", "suggest:90"),
+        ];
+        let config = Config::new(16).probe_limit(32).override_from(lookup(vars)).unwrap();
+        assert_eq!(
+            config,
+            Config::new(1000)
+                .capacity_policy(CapacityPolicy::Exact)
+                .seed(255)
+                .probe_limit(32)
+                .saturation(SaturationPolicy::SuggestEviction { max_load_percent: 90 })
+        );
+
+        let err = Config::new(16).override_from(lookup(&[("PROBE_LIMIT", "lots")])).unwrap_err();
+        assert_eq!(err.name(), "MAP_PROBE_LIMIT");
+        assert_eq!(err.to_string(), r#"invalid value "lots" for MAP_PROBE_LIMIT"#);
+
+        std::env::set_var("INTMAP_TEST_CONFIG_CAPACITY", "64");
+        let config = Config::new(16).override_from_env("INTMAP_TEST_CONFIG").unwrap();
+        assert_eq!(config, Config::new(64));
+    }
+
+    #[test]
+    fn deferred_int_map() {
+        let mut m = DeferredIntMap::with_capacity(16);
+        for k in 0..6u32 {
+            m.insert(k, k * 10);
+        }
+        assert!(m.remove_deferred(1) && m.remove_deferred(2) && !m.remove_deferred(2));
+        assert!(!m.remove_deferred(9));
+        assert_eq!((m.get(1), m.contains(2), m.is_dead(2)), (None, false, true));
+        assert_eq!((m.len(), m.dead_count()), (4, 2));
+        assert_eq!(m.iter_dead().map(|(k, _)| k).collect::<Vec<_>>(), [1, 2]);
+
+        // rollback of one delete, commit of the other
+        assert!(m.restore(1) && !m.restore(1) && !m.restore(3));
+        assert_eq!(m.get(1), Some(&10));
+        assert_eq!(m.purge(), 1);
+        assert!(!m.is_dead(2) && !m.restore(2));
+        assert_eq!(m.iter().count(), 5);
+
+        // a new insert supersedes a pending delete
+        m.remove_deferred(3);
+        assert_eq!(m.insert(3, 33), None);
+        assert_eq!((m.get(3), m.dead_count()), (Some(&33), 0));
+
+        m.remove_deferred(4);
+        m.remove_deferred(5);
+        assert_eq!(m.remove(4), None);
+        m.restore_all();
+        assert_eq!((m.get(5), m.len(), m.dead_count()), (Some(&50), 4, 0));
+    }
+
+    #[test]
+    fn reduce_by() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        let mut m = IntMap::with_capacity(64);
+        for k in 0..50u64 {
+            m.insert(k, k as u32);
+        }
+        // (count, sum) per bucket of 10 keys
+        let sums = m.reduce_by(
+            |k| (k / 10) as u32,
+            |(n, sum): &mut (u32, u64), v| {
+                *n += 1;
+                *sum += *v as u64;
+            },
+        );
+        assert_eq!(sums.len(), 5);
+        for bucket in 0..5u64 {
+            assert_eq!(sums.get(bucket as u32), Some(&(10, (bucket * 10..bucket * 10 + 10).sum())));
+        }
+        let max = m.reduce_by(|k| k % 2, |max: &mut u32, v| *max = (*max).max(*v));
+        assert_eq!((max.get(0), max.get(1)), (Some(&48), Some(&49)));
+        assert!(IntMap::<u32, u32>::with_capacity(4)
+            .reduce_by(|k| k, |n: &mut u32, _| *n += 1)
+            .is_empty());
+
+        // as many groups as entries, in a map that's exactly full
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut m = IntMap::with_capacity(1024);
+        while !m.is_full() {
+            m.insert(rng.gen::<u64>(), ());
+        }
+        let counts = m.reduce_by(|k| k, |n: &mut u32, _| *n += 1);
+        assert!(counts.len() == m.len() && counts.load_factor() <= 0.8);
+        assert!(m.keys().all(|&k| counts.get(k) == Some(&1)));
+        counts.assert_invariants();
+
+        // group keys sharing their low bits, a tenant id in the high half
+        let mut m = IntMap::with_exact_capacity(512);
+        for t in 0..300u64 {
+            m.insert((t << 32) | 7, 1u32);
+        }
+        let tenants = m.reduce_by(|k| k & 0xFFFF_FFFF_0000_0000, |n: &mut u32, v| *n += v);
+        assert_eq!(tenants.len(), 300);
+        assert!((0..300u64).all(|t| tenants.get(t << 32) == Some(&1)));
+        tenants.assert_invariants();
+    }
+
+    #[test]
+    fn full_probe_window() {
+        for limit in [2, 3, 8, MAX_PROBES] {
+            let mut m = IntMap::<u32, u32>::with_probe_limit(8, limit);
+            // home slot 7 is the last one, fill its whole window up to the table's end
+            let keys = (0..limit as u32).map(|i| 7 + 8 * i).collect::<Vec<_>>();
+            for &k in &keys {
+                m.insert(k, k);
             }
+            m.assert_invariants();
+            assert_eq!(
+                m.get_with_distance(*keys.last().unwrap()),
+                Some((&keys[keys.len() - 1], limit as usize - 1))
+            );
 
-            // remove forwards
-            for i in 1..1001 {
-                assert!(m.remove(i).is_some());
+            // a missing key probes the full window and stops at the table's end
+            let missing = 7 + 8 * limit as u32;
+            assert_eq!(m.get(missing), None);
+            assert!(!m.contains(missing) && m.get_mut(missing).is_none());
+            assert!(m.remove(missing).is_none());
+            m.set_constant_time_lookup(true);
+            assert_eq!(m.get(missing), None);
+            assert_eq!(m.get(keys[0]), Some(&keys[0]));
+            m.set_constant_time_lookup(false);
 
-                for j in 1..=i {
-                    assert!(!m.contains(j));
-                }
+            let inserted =
+                std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| m.insert(missing, 0)));
+            assert!(inserted.is_err());
+            m.assert_invariants();
 
-                for j in i + 1..1001 {
-                    assert!(m.contains(j));
-                }
+            // removing from the front shifts the whole window, the last slot stays free
+            for &k in &keys {
+                assert_eq!(m.remove(k), Some(k));
+                m.assert_invariants();
             }
+            assert!(m.is_empty());
+        }
+    }
 
-            for i in 1..1001 {
-                assert!(!m.contains(i));
+    #[test]
+    fn fingerprint_int_map() {
+        let mut m = FingerprintIntMap::with_capacity(64);
+        // same low half, the high half decides the fingerprint
+        let keys = (0..40u128).map(|i| (i << 64) | 0xabcd).collect::<Vec<_>>();
+        for &k in &keys {
+            assert_eq!(m.insert(k, k as u64 ^ (k >> 64) as u64), None);
+        }
+        assert_eq!(m.insert(keys[3], 0), Some(3 ^ 0xabcd));
+        assert_eq!(m.len(), 40);
+        assert!(m.memory_usage() < IntMap::<u128, u64>::with_capacity(64).memory_usage());
+
+        // two keys with the same fingerprint
+        let a = 5u128;
+        let b = (1u128 << 64) | (5 ^ 0x9e37_79b9_7f4a_7c15);
+        assert_eq!(a.fingerprint(), b.fingerprint());
+        m.insert(a, 1);
+        m.insert(b, 2);
+        assert_eq!((m.get(a), m.get(b)), (Some(&1), Some(&2)));
+
+        // removals in every chain position, with the last entry moving into the hole
+        assert_eq!(m.remove(a), Some(1));
+        assert_eq!((m.get(a), m.get(b)), (None, Some(&2)));
+        m.insert(a, 1);
+        assert_eq!(m.remove(b), Some(2));
+        assert_eq!((m.get(a), m.get(b)), (Some(&1), None));
+        for &k in keys.iter().step_by(2) {
+            assert!(m.remove(k).is_some());
+        }
+        assert!(!m.contains(keys[0]) && m.remove(keys[0]).is_none());
+        *m.get_mut(keys[1]).unwrap() += 1;
+        for (i, &k) in keys.iter().enumerate().skip(1).step_by(2) {
+            let expected = match i {
+                1 => (0xabcd ^ 1) + 1,
+                3 => 0,
+                _ => 0xabcd ^ i as u64,
+            };
+            assert_eq!(m.get(k).copied(), Some(expected), "key {i}");
+        }
+        assert_eq!(m.len(), 21);
+        assert_eq!(m.iter().count(), 21);
+
+        let mut signed = FingerprintIntMap::with_capacity(8);
+        signed.insert(-1i128, "minus one");
+        signed.insert(i128::MIN, "min");
+        assert_eq!(
+            (signed.get(-1), signed.get(i128::MIN), signed.get(1)),
+            (Some(&"minus one"), Some(&"min"), None)
+        );
+    }
+
+    #[test]
+    fn fingerprint_composite_keys() {
+        let mut m = FingerprintIntMap::with_capacity(256);
+        // keys differing only in the high bits of the second word
+        for i in 0..200u64 {
+            assert_eq!(m.insert([7, i << 48], i), None);
+        }
+        assert_eq!(m.get([7, 5 << 48]), Some(&5));
+        assert_eq!(m.get([7, 5]), None);
+        let homes: std::collections::HashSet<_> =
+            (0..200u64).map(|i| [7, i << 48].fingerprint() & 255).collect();
+        assert!(homes.len() > 100, "{} distinct home slots", homes.len());
+
+        let mut pairs = FingerprintIntMap::with_capacity(16);
+        pairs.insert((1u64, 2u32), 'a');
+        pairs.insert((2, 1), 'b');
+        assert_eq!(
+            (pairs.get((1, 2)), pairs.get((2, 1)), pairs.get((1, 1))),
+            (Some(&'a'), Some(&'b'), None)
+        );
+        assert_eq!(pairs.remove((1, 2)), Some('a'));
+        assert_eq!(pairs.iter().collect::<Vec<_>>(), [((2, 1), &'b')]);
+
+        let mut triples = FingerprintIntMap::with_capacity(16);
+        triples.insert([1u32, 2, 3], ());
+        assert!(triples.contains([1, 2, 3]) && !triples.contains([3, 2, 1]));
+    }
+
+    #[test]
+    fn adapted_int_map() {
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        struct Port(u16);
+
+        impl KeyAdapter for Port {
+            type Key = u32;
+
+            fn to_key(self) -> u32 {
+                self.0.into()
             }
 
-            for i in 1..1001 {
-                m.insert(i, i);
+            fn from_key(key: u32) -> Self {
+                Port(key as u16)
             }
+        }
 
-            // remove backwards
-            for i in (1..1001).rev() {
-                assert!(m.remove(i).is_some());
+        let mut m = AdaptedIntMap::with_capacity(8);
+        assert_eq!(m.insert(Port(80), "http"), None);
+        assert_eq!(m.insert(Port(443), "https"), None);
+        assert_eq!(m.insert(Port(80), "www"), Some("http"));
+        assert_eq!((m.get(Port(80)), m.len()), (Some(&"www"), 2));
+        assert_eq!(m.remove(Port(443)), Some("https"));
+        assert!(!m.contains(Port(443)));
+        assert_eq!(m.iter().collect::<Vec<_>>(), [(Port(80), &"www")]);
+        assert_eq!(m.as_inner().get(80), Some(&"www"));
+    }
 
-                for j in i..1001 {
-                    assert!(!m.contains(j));
-                }
+    #[test]
+    fn checksum() {
+        let mut a = IntMap::with_capacity(64);
+        let mut b = IntMap::with_exact_capacity(100);
+        for k in 0..50u64 {
+            a.insert(k * 64, k);
+            b.insert((49 - k) * 64, 49 - k);
+        }
+        assert_eq!(a.checksum(), b.checksum());
+        // pinned, so a change of the algorithm doesn't go unnoticed
+        let mut pinned = IntMap::with_capacity(4);
+        pinned.insert(1u32, 2u64);
+        pinned.insert(3, 4);
+        assert_eq!(pinned.checksum(), 8139454814806643558);
 
-                for j in 1..i {
-                    assert!(m.contains(j));
+        let c = a.checksum();
+        a.insert(64, 2);
+        assert_ne!(a.checksum(), c);
+        a.insert(64, 1);
+        assert_eq!(a.checksum(), c);
+        // swapping values between keys changes the digest
+        a.insert(0, 1);
+        a.insert(64, 0);
+        assert_ne!(a.checksum(), c);
+        a.remove(0);
+        assert_ne!(a.checksum(), c);
+        let mut strings = IntMap::with_capacity(4);
+        strings.insert(1u32, "ab".to_string());
+        let c = strings.checksum();
+        strings.insert(1, "ba".to_string());
+        assert_ne!(strings.checksum(), c);
+
+        // pinned for each kind of value the stability claim covers
+        fn one<V: Hash>(value: V) -> u64 {
+            let mut m = IntMap::with_capacity(4);
+            m.insert(7u32, value);
+            m.checksum()
+        }
+        let pins = [
+            one(0xabu8),
+            one(-2i16),
+            one(u32::MAX),
+            one(-1i64),
+            one(1u128 << 100),
+            one(40_000usize),
+            one(true),
+            one('ß'),
+            one("intmap"),
+            one(String::from("a longer string spanning words")),
+            one((1u8, -3i64, 'x')),
+        ];
+        assert_eq!(
+            pins,
+            [
+                7320187240221565543,
+                1445135159285613174,
+                10442415104602065377,
+                6442161595689905080,
+                14409971516690684157,
+                14117246573132790293,
+                4330789371753852489,
+                16347173703985401456,
+                6448341333629318895,
+                2736375337699053720,
+                17944949855158385602,
+            ]
+        );
+    }
+
+    #[test]
+    fn apply_ops() {
+        let mut m = IntMap::with_capacity(8);
+        assert_eq!(m.apply(MapOp::Insert(1u32, 'a')), None);
+        assert_eq!(m.apply(MapOp::Insert(1, 'b')), Some('a'));
+        assert_eq!(m.apply(MapOp::Insert(2, 'c')), None);
+        assert_eq!(m.apply(MapOp::Remove(1)), Some('b'));
+        assert_eq!(m.apply(MapOp::Remove(1)), None);
+        assert_eq!((m.len(), m.get(2)), (1, Some(&'c')));
+        assert_eq!(m.apply(MapOp::Clear), None);
+        assert!(m.is_empty());
+    }
+
+    #[test]
+    fn sharded_builder() {
+        assert!((0..100u64).all(|k| shard_for(k, 1) == 0 && shard_for(k, 0) == 0));
+        let mut builder = ShardedBuilder::with_config(
+            4,
+            &Config::new(300).capacity_policy(CapacityPolicy::Exact),
+        );
+        for k in 0..1000u64 {
+            builder.insert(k, k);
+        }
+        assert_eq!(builder.insert(7, 70), Some(7));
+        assert_eq!(builder.shard(builder.shard_for(7)).get(7), Some(&70));
+        let shards = builder.build();
+        assert_eq!(shards.len(), 4);
+        for (i, shard) in shards.iter().enumerate() {
+            assert!(shard.keys().all(|&k| shard_for(k, 4) as usize == i));
+            assert!((200..300).contains(&shard.len()), "shard {i} has {} keys", shard.len());
+            // the keys of a shard still spread over all of its home slots, routing by the
+            // map's own mixer seed would overflow the probe limit here
+            assert!(shard.avg_probes_count() < 4.0);
+        }
+    }
+
+    #[test]
+    fn capacity_types() {
+        assert_eq!(Capacity::for_entries(1000, 0.8), Capacity::new(1250));
+        assert_eq!(Capacity::new(1250).entries_at(0.8), 1000);
+        assert_eq!(Capacity::for_entries(0, 0.5).get(), 0);
+        assert_eq!(Capacity::for_entries(usize::MAX, 0.5).get(), u32::MAX);
+        let m: IntMap<u32, ()> = IntMap::with_capacity(Capacity::for_entries(100, 0.5));
+        assert_eq!(m.capacity(), 256);
+        assert_eq!(m.slots().get(), 256 + OVERFLOW_SLOTS);
+        let m: IntMap<u32, ()> = IntMap::with_probe_limit(Capacity::new(10), 4);
+        assert_eq!((m.capacity(), m.slots()), (16, Slots::new(20)));
+    }
+
+    #[test]
+    fn remove_sorted() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+        let mut rng = StdRng::seed_from_u64(7);
+        for round in 0..20 {
+            let mut m = if round % 2 == 0 {
+                IntMap::with_capacity(256)
+            } else {
+                IntMap::with_exact_capacity(200)
+            };
+            m.set_lazy_clear(round % 4 == 1);
+            let mut truth = HashMap::new();
+            // keys crowding a few home slots, so long clusters shift
+            while truth.len() < 150 {
+                let key = rng.gen_range(0..64u32) * 256 + rng.gen_range(0..48);
+                m.insert(key, key);
+                truth.insert(key, key);
+            }
+            let mut batch: Vec<u32> = truth.keys().copied().filter(|_| rng.gen_bool(0.4)).collect();
+            batch.extend([100_000, 100_001]);
+            batch.push(batch[0]);
+            batch.sort_unstable();
+            let expected = batch.iter().filter(|k| truth.remove(k).is_some()).count();
+            assert_eq!(m.remove_sorted(&batch), expected);
+            assert_eq!(m.len(), truth.len());
+            assert!(truth.iter().all(|(k, v)| m.get(*k) == Some(v)));
+            assert!(batch.iter().all(|k| !m.contains(*k)));
+            m.assert_invariants();
+        }
+        let mut m = IntMap::with_capacity(4);
+        assert_eq!(m.remove_sorted(&[]), 0);
+        m.insert(1u64, String::from("one"));
+        assert_eq!(m.remove_sorted(&[0, 1, 2]), 1);
+        assert!(m.is_empty());
+    }
+
+    #[test]
+    fn column_index() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut index = ColumnIndex::with_capacity(128);
+        let mut column = Vec::new();
+        let mut truth = HashMap::new();
+        for _ in 0..2000 {
+            let key = rng.gen_range(0..200u32);
+            if rng.gen_bool(0.5) && truth.len() < 100 {
+                match index.insert(key) {
+                    Ok(row) => {
+                        assert_eq!(row, column.len());
+                        column.push(key * 10);
+                        assert!(truth.insert(key, key * 10).is_none());
+                    }
+                    Err(row) => assert_eq!(column[row], truth[&key]),
                 }
+            } else if let Some(row) = index.remove(key) {
+                assert_eq!(column.swap_remove(row), truth.remove(&key).unwrap());
+            } else {
+                assert!(!truth.contains_key(&key));
+            }
+        }
+        assert_eq!(index.len(), column.len());
+        assert!(truth.iter().all(|(&k, v)| index.get(&column, k) == Some(v)));
+        assert!(index.keys().iter().enumerate().all(|(row, &k)| index.row(k) == Some(row)));
+        index.clear();
+        assert!(index.is_empty() && index.key(0).is_none());
+    }
+
+    #[test]
+    fn version() {
+        let mut m = IntMap::with_capacity(8);
+        let v0 = m.version();
+        m.insert(1u32, 1);
+        let v1 = m.version();
+        assert_ne!(v0, v1);
+        *m.get_mut(1).unwrap() = 2;
+        m.insert(1, 3);
+        m.remove(5);
+        assert_eq!(m.version(), v1);
+        m.remove(1);
+        assert_ne!(m.version(), v1);
+        let v2 = m.version();
+        m.clear();
+        assert_ne!(m.version(), v2);
+    }
+
+    // bumps the version under a live iterator the way a mutation through a raw pointer would,
+    // the undefined behavior the check is there to catch
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "map was modified during iteration")]
+    fn modified_during_iteration() {
+        let mut m = IntMap::with_capacity(8);
+        m.insert(1u32, 1);
+        m.insert(2, 2);
+        let mut iter = m.iter();
+        iter.next();
+        m.table.touch();
+        iter.next();
+    }
+
+    #[test]
+    fn int_map_cell() {
+        let cell = IntMapCell::with_capacity(16);
+        // shared borrows only, as deep in a call stack
+        let declare = |id: u32, name: &str| cell.insert(id, name.to_string());
+        assert_eq!(declare(1, "a"), None);
+        assert_eq!(declare(1, "b"), Some("a".to_string()));
+        declare(2, "c");
+        assert_eq!(cell.update(2, |name| name.push('!')), Some(()));
+        assert_eq!(cell.update(3, |name| name.push('!')), None);
+        assert_eq!(cell.get_cloned(2).as_deref(), Some("c!"));
+        assert_eq!(cell.with(|map| map.keys().count()), 2);
+        assert_eq!(cell.remove(1).as_deref(), Some("b"));
+        assert!(!cell.contains(1) && cell.len() == 1);
+
+        // a panicking closure releases the cell
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            cell.update(2, |_| panic!("in update"));
+        }));
+        assert!(result.is_err());
+        cell.clear();
+        assert!(cell.is_empty());
+        cell.into_inner().assert_invariants();
+    }
+
+    #[test]
+    #[should_panic(expected = "IntMapCell::insert called while IntMapCell::update is running")]
+    fn int_map_cell_reentry() {
+        let cell = IntMapCell::with_capacity(4);
+        cell.insert(1u32, 1);
+        cell.update(1, |v| cell.insert(2, *v));
+    }
+
+    #[test]
+    fn merge_iter() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+        use std::collections::BTreeMap;
+        let mut rng = StdRng::seed_from_u64(5);
+        // same layout, a different capacity and a different indexing
+        let others = [
+            IntMap::with_capacity(2048),
+            IntMap::with_capacity(4096),
+            IntMap::with_exact_capacity(3000),
+        ];
+        for mut b in others {
+            let mut a = IntMap::with_capacity(2048);
+            let mut truth = BTreeMap::new();
+            for _ in 0..1500 {
+                let key = rng.gen_range(0..4000u32);
+                a.insert(key, key);
+                truth.entry(key).or_insert((None, None)).0 = Some(key);
+            }
+            for _ in 0..1500 {
+                let key = rng.gen_range(0..4000u32);
+                b.insert(key, key as u64 * 2);
+                truth.entry(key).or_insert((None, None)).1 = Some(key as u64 * 2);
+            }
+            let iter = a.merge_iter(&b);
+            assert_eq!(iter.size_hint(), (a.len(), Some(a.len() + b.len())));
+            let mut merged: Vec<_> = iter.map(|(k, x, y)| (k, (x.copied(), y.copied()))).collect();
+            merged.sort_unstable();
+            assert!(merged.into_iter().eq(truth));
+        }
+        let empty = IntMap::<u32, ()>::with_capacity(4);
+        assert_eq!(empty.merge_iter(&empty).count(), 0);
+    }
+
+    #[test]
+    fn migrating_map() {
+        let mut old = IntMap::with_capacity(256);
+        for k in 0..200u32 {
+            old.insert(k * 7, k as u16);
+        }
+        let mut map = MigratingMap::new(old, |k, v| format!("{k}:{v}"));
+        assert_eq!((map.len(), map.remaining()), (200, 200));
+        assert_eq!(map.get(14).map(String::as_str), Some("14:2"));
+        map.get_mut(21).unwrap().push('!');
+        assert_eq!(map.insert(28, "new".into()).as_deref(), Some("28:4"));
+        assert_eq!(map.insert(5, "fresh".into()), None);
+        assert_eq!(map.remove(35).as_deref(), Some("35:5"));
+        assert_eq!(map.remove(21).as_deref(), Some("21:3!"));
+        assert!(map.contains(42) && !map.contains(35));
+        assert_eq!((map.len(), map.remaining()), (199, 196));
+        assert_eq!(map.migrate(50), 50);
+        assert_eq!(map.remaining(), 146);
+        // removals in front of the cursor shift entries back past it
+        for k in (0..200).step_by(3) {
+            map.remove(k * 7);
+        }
+        while map.migrate(10) > 0 {}
+        assert!(map.is_migrated() && map.old_map().is_empty());
+        let new = map.finish();
+        assert_eq!(new.get(28).map(String::as_str), Some("new"));
+        assert_eq!(new.get(7).map(String::as_str), Some("7:1"));
+        assert_eq!(new.get(5).map(String::as_str), Some("fresh"));
+        assert!(
+            new.len() == 133 && new.keys().all(|&k| k == 5 || (k % 7 == 0 && (k / 7) % 3 != 0))
+        );
+        new.assert_invariants();
+
+        let (mut old, mut new) = (IntMap::with_capacity(8), IntMap::with_exact_capacity(8));
+        old.insert(1u64, 1u8);
+        old.insert(2, 2);
+        new.insert(2, 20u32);
+        let map = MigratingMap::with_new_map(old, new, |_, v| v as u32);
+        assert_eq!((map.len(), map.remaining()), (2, 1));
+    }
+
+    #[test]
+    fn max_displacement() {
+        let mut m = IntMap::with_capacity(64);
+        assert_eq!(m.max_displacement(), 0);
+        for k in 0..8u32 {
+            m.insert(k * 64, ());
+        }
+        m.insert(3, ());
+        assert_eq!(m.max_displacement(), 7);
+        assert_eq!(m.max_displacement(), m.probes().into_iter().max().unwrap());
+    }
+
+    #[test]
+    fn monitored_map() {
+        let mut map = MonitoredIntMap::with_capacity(256);
+        let stats = map.stats_handle();
+        let inserts = if cfg!(miri) { 64 } else { 200 };
+        let monitor = std::thread::spawn(move || {
+            let mut last = 0;
+            while last < inserts {
+                let sample = stats.sample();
+                assert!(sample.len >= last && sample.capacity == 256);
+                last = sample.len;
             }
+        });
+        for k in 0..inserts as u32 {
+            map.insert(k, k);
+        }
+        monitor.join().unwrap();
+
+        // the displacement is rescanned every 16 mutations here
+        let stats = map.stats_handle();
+        map.clear();
+        assert_eq!(stats.sample(), TableStats { len: 0, capacity: 256, max_displacement: 0 });
+        for k in 0..10u32 {
+            map.insert(k * 256, k);
         }
+        map.remove(42);
+        for k in 100..105 {
+            map.insert(k, k);
+        }
+        assert_eq!((stats.len(), stats.sample().max_displacement), (15, 0));
+        map.insert(105, 105);
+        assert_eq!(stats.sample().max_displacement, 9);
+        map.with_mut(|m| m.retain(|k, _| k < 256));
+        assert_eq!(stats.sample(), TableStats { len: 7, capacity: 256, max_displacement: 0 });
+        assert!(stats.load_factor() == 7.0 / 256.0);
+    }
+
+    #[test]
+    fn remove_if() {
+        let mut m = IntMap::<i32, u32>::with_capacity(8);
+        m.insert(-1, 5);
+        m.insert(7, 6);
+        assert_eq!(m.remove_if(-1, |&v| v > 5), RemoveIf::Kept);
+        assert_eq!(m.remove_if(-1, |&v| v == 5), RemoveIf::Removed(5));
+        assert_eq!(m.remove_if(-1, |_| true), RemoveIf::Missing);
+        assert_eq!(m.len(), 1);
+        m.assert_invariants();
+    }
+
+    #[test]
+    fn reserved_key() {
+        let mut m = IntMap::<u64, u32>::with_capacity(4);
+        m.insert(u64::MAX, 0);
+        m.set_reserved_key(Some(u64::MAX));
+        assert_eq!(m.reserved_key(), Some(u64::MAX));
+        assert_eq!(m.try_insert(u64::MAX, 1), Err(InsertError::ReservedKey(u64::MAX, 1)));
+        assert_eq!(m.try_insert(1, 1), Ok(None));
+        assert_eq!(m.try_insert(1, 2), Ok(Some(1)));
+        // entries stored before the key was reserved stay reachable
+        assert_eq!(m.remove(u64::MAX), Some(0));
+
+        let err = m.try_insert(u64::MAX, 3).unwrap_err();
+        assert_eq!(err.to_string(), format!("key {} is reserved", u64::MAX));
+        assert_eq!(err.into_entry(), (u64::MAX, 3));
+        m.set_reserved_key(None);
+        assert_eq!(m.try_insert(u64::MAX, 4), Ok(None));
+    }
+
+    #[test]
+    #[should_panic(expected = "key 7 is reserved")]
+    fn reserved_key_insert_panics() {
+        let mut m = IntMap::<u32, u32>::with_capacity(4);
+        m.set_reserved_key(Some(7));
+        m.probe(7).or_insert(1);
     }
 
     #[test]
@@ -380,6 +5353,20 @@ mod tests {
         assert_eq!(Arc::strong_count(&is_dropped), 1);
     }
 
+    #[test]
+    fn drop_check_clear() {
+        use std::sync::Arc;
+        let is_dropped = Arc::new(1);
+        let mut m = IntMap::<u32, Arc<i32>>::with_capacity(2);
+        m.insert(0, Arc::clone(&is_dropped));
+        m.insert(1, Arc::clone(&is_dropped));
+        m.clear();
+        assert_eq!(Arc::strong_count(&is_dropped), 1);
+        m.insert(1, Arc::clone(&is_dropped));
+        drop(m);
+        assert_eq!(Arc::strong_count(&is_dropped), 1);
+    }
+
     #[test]
     fn drop_check2() {
         let mut m = IntMap::<u32, String>::with_capacity(5);
@@ -0,0 +1,79 @@
+use crate::error::LoadError;
+use crate::{Capacity, IntKey, IntMap};
+
+/// What `IntMapLoader` does when the input repeats a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicatePolicy {
+    /// Stop loading with `LoadError::DuplicateKey`.
+    Fail,
+    /// Keep the value seen first.
+    FirstWins,
+    /// Keep the value seen last, same as inserting the entries one by one.
+    #[default]
+    LastWins,
+}
+
+/// Builds a map from an entry stream, e.g. records read from a file, with explicit handling
+/// of duplicate keys.
+#[derive(Debug, Clone)]
+pub struct IntMapLoader {
+    capacity: u32,
+    on_duplicate: DuplicatePolicy,
+}
+
+impl IntMapLoader {
+    /// A loader building maps of `capacity`, repeated keys are `DuplicatePolicy::LastWins` by
+    /// default.
+    ///
+    /// ```
+    /// use intmap_rs::IntMapLoader;
+    ///
+    /// let map = IntMapLoader::new(16).load([(1u32, 'a'), (1, 'b')]).unwrap();
+    /// assert_eq!(map.get(1), Some(&'b'));
+    /// ```
+    pub fn new(capacity: impl Into<Capacity>) -> Self {
+        Self { capacity: capacity.into().get(), on_duplicate: DuplicatePolicy::default() }
+    }
+
+    /// Sets what happens when the input repeats a key.
+    ///
+    /// ```
+    /// use intmap_rs::{DuplicatePolicy, IntMapLoader};
+    ///
+    /// let loader = IntMapLoader::new(16).on_duplicate(DuplicatePolicy::FirstWins);
+    /// assert_eq!(loader.load([(1u32, 'a'), (1, 'b')]).unwrap().get(1), Some(&'a'));
+    /// ```
+    pub fn on_duplicate(mut self, policy: DuplicatePolicy) -> Self {
+        self.on_duplicate = policy;
+        self
+    }
+
+    /// Inserts `entries` in order into a new map, applying the duplicate policy.
+    ///
+    /// ```
+    /// use intmap_rs::{DuplicatePolicy, IntMapLoader, LoadError};
+    ///
+    /// let loader = IntMapLoader::new(16).on_duplicate(DuplicatePolicy::Fail);
+    /// assert_eq!(loader.load([(1u32, 'a'), (2, 'b')]).unwrap().len(), 2);
+    /// assert!(matches!(loader.load([(1u32, 'a'), (1, 'b')]), Err(LoadError::DuplicateKey(1))));
+    /// ```
+    pub fn load<K: IntKey, V>(
+        &self,
+        entries: impl IntoIterator<Item = (K, V)>,
+    ) -> Result<IntMap<K, V>, LoadError<K>> {
+        let mut map = IntMap::with_capacity(self.capacity);
+        for (key, value) in entries {
+            let probe = map.probe(key);
+            match self.on_duplicate {
+                DuplicatePolicy::Fail if probe.is_found() => {
+                    return Err(LoadError::DuplicateKey(key))
+                }
+                DuplicatePolicy::FirstWins if probe.is_found() => {}
+                _ => {
+                    probe.insert(value);
+                }
+            }
+        }
+        Ok(map)
+    }
+}
@@ -1,24 +1,131 @@
 use std::{
     borrow::Borrow,
     cmp::Ordering,
-    marker::PhantomData,
+    iter::FusedIterator,
     mem::{self, MaybeUninit},
     ops::{Index, IndexMut},
+    panic::UnwindSafe,
 };
 
 pub type Distance = i8;
 pub const FREE: Distance = -1;
 
+// bumps of 'Table::touch' on this thread, added to every table's version
+#[cfg(test)]
+thread_local!(static TOUCHES: std::cell::Cell<u64> = const { std::cell::Cell::new(0) });
+
+// slots an insert's displacement chain walks inline before continuing out of line
+const INLINE_DISPLACEMENTS: usize = 3;
+
+// free slots between two entries 'fill_sorted' streams zeros into rather than skipping
+const STREAM_GAP: usize = 16;
+
 pub struct Table<K, V> {
     distances: Buffer<Distance>,
     keys: Buffer<MaybeUninit<K>>,
     values: Buffer<MaybeUninit<V>>,
+    stamps: Option<Stamps>,
+    tags: Tags,
+    // per-slot recency ages of an 'AgingIntMap', moved along with their entries like tags
+    ages: Option<Buffer<u8>>,
+    // refill the distances lane with streaming stores
+    nontemporal: bool,
+    // every key slot holds an initialized key, free ones a zeroed or stale one, so a lookup can
+    // compare a whole probe window without branching on occupancy, see 'find_constant_time'
+    constant_time: bool,
+    // entries are stored less than 'probe_limit' slots away from their home slot
+    probe_limit: Distance,
+    // lanes are borrowed from the caller and must not be freed, see 'from_raw_parts'
+    owned: bool,
     capacity: usize,
     len: usize,
+    // bumped by every change to the set of occupied slots, see 'OccupiedSlotIter::check'
+    version: u64,
+}
+
+// Per-slot generation stamps for O(1) clearing: a slot is occupied only if its distance isn't
+// FREE and its stamp matches the current epoch, so bumping the epoch empties the whole table.
+struct Stamps {
+    lane: Buffer<u8>,
+    epoch: u8,
+}
+
+// The bytes an entry has in the optional lanes, carried along a displacement chain.
+#[derive(Clone, Copy, Default)]
+struct Meta {
+    tag: u8,
+    age: u8,
+}
+
+// Per-slot user tag bytes under the 'tags' feature, swapped along with their entries so a tag
+// stays with its key. Without the feature this is a zero-sized no-op and every tag reads 0.
+struct Tags {
+    #[cfg(feature = "tags")]
+    lane: Buffer<u8>,
+}
+
+#[cfg(feature = "tags")]
+impl Tags {
+    fn with_capacity(capacity: usize) -> Self {
+        Self { lane: Buffer::with_capacity_filled(capacity, 0) }
+    }
+
+    #[inline]
+    fn get(&self, index: usize) -> u8 {
+        self.lane[index]
+    }
+
+    #[inline]
+    fn set(&mut self, index: usize, tag: u8) {
+        self.lane[index] = tag;
+    }
+
+    #[inline]
+    fn swap_in(&mut self, index: usize, tag: &mut u8) {
+        mem::swap(&mut self.lane[index], tag);
+    }
+
+    #[inline]
+    fn swap(&mut self, i: usize, j: usize) {
+        unsafe { self.lane.swap_indices(i, j) };
+    }
+
+    // SAFETY: 'capacity' must be the one the lane was allocated with
+    unsafe fn free(&mut self, capacity: usize) {
+        let _ = mem::replace(&mut self.lane, Buffer::with_capacity(0)).into_inner(0, capacity);
+    }
+}
+
+#[cfg(not(feature = "tags"))]
+impl Tags {
+    #[inline(always)]
+    fn with_capacity(_capacity: usize) -> Self {
+        Self {}
+    }
+
+    #[inline(always)]
+    fn get(&self, _index: usize) -> u8 {
+        0
+    }
+
+    #[inline(always)]
+    fn set(&mut self, _index: usize, _tag: u8) {}
+
+    #[inline(always)]
+    fn swap_in(&mut self, _index: usize, _tag: &mut u8) {}
+
+    #[inline(always)]
+    fn swap(&mut self, _i: usize, _j: usize) {}
+
+    #[inline(always)]
+    unsafe fn free(&mut self, _capacity: usize) {}
 }
 
 unsafe impl<K, V: Send> Send for Table<K, V> {}
 unsafe impl<K, V: Sync> Sync for Table<K, V> {}
+// the lanes own their entries like a Vec does, as raw pointers they'd need RefUnwindSafe values;
+// a panic in user code never leaves a slot half written, see the 'unwind_safety' test
+impl<K: UnwindSafe, V: UnwindSafe> UnwindSafe for Table<K, V> {}
 
 impl<K, V> Table<K, V> {
     pub fn with_capacity(capacity: usize) -> Self {
@@ -26,41 +133,693 @@ impl<K, V> Table<K, V> {
             keys: Buffer::with_capacity(capacity),
             values: Buffer::with_capacity(capacity),
             distances: Buffer::with_capacity_filled(capacity, FREE),
+            stamps: None,
+            tags: Tags::with_capacity(capacity),
+            ages: None,
+            nontemporal: false,
+            constant_time: false,
+            probe_limit: Distance::MAX,
+            owned: true,
             len: 0,
+            version: 0,
             capacity,
         }
     }
 
+    pub fn with_capacity_nontemporal(capacity: usize) -> Self {
+        let mut distances = Buffer::with_capacity(capacity);
+        distances.fill_nontemporal(FREE, capacity);
+        Self {
+            keys: Buffer::with_capacity(capacity),
+            values: Buffer::with_capacity(capacity),
+            distances,
+            stamps: None,
+            tags: Tags::with_capacity(capacity),
+            ages: None,
+            nontemporal: true,
+            constant_time: false,
+            probe_limit: Distance::MAX,
+            owned: true,
+            len: 0,
+            version: 0,
+            capacity,
+        }
+    }
+
+    // SAFETY:
+    // the lanes must be valid for 'capacity' elements and outlive the table, which never frees them
+    pub unsafe fn from_raw_parts(
+        distances: *mut Distance,
+        keys: *mut MaybeUninit<K>,
+        values: *mut MaybeUninit<V>,
+        capacity: usize,
+        probe_limit: Distance,
+    ) -> Self {
+        let mut distances = Buffer(distances);
+        distances.fill(FREE, capacity);
+        Self {
+            keys: Buffer(keys),
+            values: Buffer(values),
+            distances,
+            stamps: None,
+            tags: Tags::with_capacity(capacity),
+            ages: None,
+            nontemporal: false,
+            constant_time: false,
+            probe_limit,
+            owned: false,
+            len: 0,
+            version: 0,
+            capacity,
+        }
+    }
+
+    // an empty table with the same capacity and settings, always owning its lanes
+    pub fn empty_like(&self) -> Self {
+        self.empty_like_for()
+    }
+
+    pub fn empty_like_for<U>(&self) -> Table<K, U> {
+        let mut table = if self.nontemporal {
+            Table::with_capacity_nontemporal(self.capacity)
+        } else {
+            Table::with_capacity(self.capacity)
+        };
+        table.probe_limit = self.probe_limit;
+        if self.stamps.is_some() {
+            table.enable_stamps();
+        }
+        if self.constant_time {
+            table.enable_constant_time();
+        }
+        if self.ages.is_some() {
+            table.enable_ages();
+        }
+        table
+    }
+
     #[inline]
-    pub(crate) fn keys(&self) -> Keys<'_, K> {
-        unsafe {
-            Keys {
-                keys: self.keys.offset_mut(0),
-                keys_end: self.keys.offset_mut(self.capacity),
-                distances: self.distances.offset_mut(0),
-                _marker: PhantomData,
+    pub fn probe_limit(&self) -> Distance {
+        self.probe_limit
+    }
+
+    // the limit must be in '2..=Distance::MAX', see 'search', and can only be lowered while empty
+    pub fn set_probe_limit(&mut self, limit: Distance) {
+        debug_assert!((2..=Distance::MAX).contains(&limit));
+        debug_assert!(self.len == 0 || limit >= self.probe_limit);
+        self.probe_limit = limit;
+    }
+
+    #[inline]
+    pub fn is_nontemporal(&self) -> bool {
+        self.nontemporal
+    }
+
+    pub fn set_nontemporal(&mut self, enabled: bool) {
+        self.nontemporal = enabled;
+    }
+
+    fn mark_all_free(&mut self) {
+        if self.nontemporal {
+            self.distances.fill_nontemporal(FREE, self.capacity);
+        } else {
+            self.distances.fill(FREE, self.capacity);
+        }
+    }
+
+    #[inline]
+    pub fn has_stamps(&self) -> bool {
+        self.stamps.is_some()
+    }
+
+    // every slot starts stamped with the current epoch, so existing entries stay visible
+    pub fn enable_stamps(&mut self) {
+        if self.stamps.is_none() {
+            self.stamps =
+                Some(Stamps { lane: Buffer::with_capacity_filled(self.capacity, 0), epoch: 0 });
+        }
+    }
+
+    // ages start at 0, for new entries too
+    pub fn enable_ages(&mut self) {
+        if self.ages.is_none() {
+            self.ages = Some(Buffer::with_capacity_filled(self.capacity, 0));
+        }
+    }
+
+    // 0 without the ages lane
+    #[inline]
+    pub fn age(&self, index: usize) -> u8 {
+        self.ages.as_ref().map_or(0, |ages| ages[index])
+    }
+
+    #[inline]
+    pub fn set_age(&mut self, index: usize, age: u8) {
+        if let Some(ages) = &mut self.ages {
+            ages[index] = age;
+        }
+    }
+
+    // the whole lane, free slots included; their bytes are overwritten by the next entry
+    pub fn ages_mut(&mut self) -> Option<&mut [u8]> {
+        let capacity = self.capacity;
+        self.ages.as_mut().map(|ages| ages.as_slice_mut(capacity))
+    }
+
+    pub fn disable_stamps(&mut self) {
+        if self.stamps.is_some() {
+            // stale slots have to be marked free before the epoch check goes away
+            for index in 0..self.capacity {
+                if self.distance(index) == FREE {
+                    self.distances[index] = FREE;
+                }
             }
+            let stamps = self.stamps.take().unwrap();
+            let _ = unsafe { stamps.lane.into_inner(self.capacity, self.capacity) };
+        }
+    }
+
+    pub fn enable_constant_time(&mut self) {
+        if !self.constant_time {
+            self.zero_free_keys();
+            self.constant_time = true;
         }
     }
 
+    // slots are never de-initialized again: entries move out with a copy and scrubbing writes
+    // bytes, so the flag can simply be dropped
+    pub fn disable_constant_time(&mut self) {
+        self.constant_time = false;
+    }
+
+    #[inline]
+    pub fn is_constant_time(&self) -> bool {
+        self.constant_time
+    }
+
+    fn zero_free_keys(&mut self) {
+        for index in 0..self.capacity {
+            if self.distance(index) == FREE {
+                unsafe { std::ptr::write_bytes(self.keys.offset_mut(index), 0, 1) };
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn distance(&self, index: usize) -> Distance {
+        match &self.stamps {
+            Some(stamps) if stamps.lane[index] != stamps.epoch => FREE,
+            _ => self.distances[index],
+        }
+    }
+
+    #[inline]
+    pub fn version(&self) -> u64 {
+        // volatile, so a live iterator's check isn't hoisted out of its loop on the strength of
+        // the shared borrow
+        let version = unsafe { std::ptr::read_volatile(&self.version) };
+        #[cfg(test)]
+        let version = version.wrapping_add(TOUCHES.with(std::cell::Cell::get));
+        version
+    }
+
+    // what a mutation behind a live iterator's back does to the version, without the UB; it
+    // bumps the version of every table on the thread
+    #[cfg(test)]
+    pub fn touch(&self) {
+        TOUCHES.with(|touches| touches.set(touches.get() + 1));
+    }
+
+    #[inline]
+    fn bump_version(&mut self) {
+        self.version = self.version.wrapping_add(1);
+    }
+
+    #[inline]
+    pub(crate) fn slots(&self) -> OccupiedSlotIter<'_, K, V> {
+        let (end, remaining, version) = (self.capacity, self.len, self.version());
+        OccupiedSlotIter { table: self, index: 0, end, remaining, version }
+    }
+
+    // occupied slots of 'start..end', counting them first keeps the iterators exact-sized
+    pub(crate) fn slots_range(&self, start: usize, end: usize) -> OccupiedSlotIter<'_, K, V> {
+        let remaining = (start..end).filter(|&i| self.distance(i) != FREE).count();
+        OccupiedSlotIter { table: self, index: start, end, remaining, version: self.version() }
+    }
+
+    pub(crate) fn iter_range(&self, start: usize, end: usize) -> Iter<'_, K, V> {
+        Iter { slots: self.slots_range(start, end) }
+    }
+
+    pub(crate) fn keys_range(&self, start: usize, end: usize) -> Keys<'_, K, V> {
+        Keys { slots: self.slots_range(start, end) }
+    }
+
+    pub(crate) fn values_range(&self, start: usize, end: usize) -> Values<'_, K, V> {
+        Values { slots: self.slots_range(start, end) }
+    }
+
+    #[inline]
+    pub(crate) fn keys(&self) -> Keys<'_, K, V> {
+        Keys { slots: self.slots() }
+    }
+
+    #[inline]
+    pub(crate) fn values(&self) -> Values<'_, K, V> {
+        Values { slots: self.slots() }
+    }
+
+    #[inline]
+    pub(crate) fn iter(&self) -> Iter<'_, K, V> {
+        Iter { slots: self.slots() }
+    }
+
     #[inline]
     pub fn len(&self) -> usize {
         self.len
     }
 
+    pub fn keys_to_vec(&self) -> Vec<K>
+    where
+        K: Copy,
+    {
+        self.export_lane(&self.keys)
+    }
+
+    pub fn values_to_vec(&self) -> Vec<V>
+    where
+        V: Copy,
+    {
+        self.export_lane(&self.values)
+    }
+
+    // same layout with every value replaced by 'f(key, value)': the keys lane is copied as is,
+    // no slot is probed again; a slot is marked occupied only once its value is written, so a
+    // panicking 'f' leaves a valid table behind
+    pub fn map_values_to<U>(&self, mut f: impl FnMut(&K, &V) -> U) -> Table<K, U>
+    where
+        K: Copy,
+    {
+        let mut table = self.empty_like_for::<U>();
+        unsafe {
+            std::ptr::copy_nonoverlapping(self.keys.0, table.keys.0, self.capacity);
+        }
+        for index in 0..self.capacity {
+            let distance = self.distance(index);
+            if distance != FREE {
+                let (key, value) = unsafe {
+                    (self.keys[index].assume_init_ref(), self.values[index].assume_init_ref())
+                };
+                table.values[index].write(f(key, value));
+                table.set_occupied(index, distance);
+                table.tags.set(index, self.tags.get(index));
+                table.set_age(index, self.age(index));
+                table.len += 1;
+            }
+        }
+        table
+    }
+
+    // moves the distances, values and stamps lanes into a table of 'K2' keys, only the keys lane
+    // is reallocated; 'f' must keep every key's home slot and its order among the other keys
+    pub fn convert_keys<K2>(self, mut f: impl FnMut(&K) -> K2) -> Table<K2, V>
+    where
+        K: Copy,
+    {
+        debug_assert!(self.owned, "borrowed lanes can't change owner");
+        let mut this = mem::ManuallyDrop::new(self);
+        let mut table = Table {
+            distances: Buffer(this.distances.0),
+            keys: Buffer::with_capacity(this.capacity),
+            values: Buffer(this.values.0),
+            stamps: this.stamps.take(),
+            tags: mem::replace(&mut this.tags, Tags::with_capacity(0)),
+            ages: this.ages.take(),
+            nontemporal: this.nontemporal,
+            constant_time: this.constant_time,
+            probe_limit: this.probe_limit,
+            owned: this.owned,
+            capacity: this.capacity,
+            len: this.len,
+            version: 0,
+        };
+        for index in 0..table.capacity {
+            // the stamps moved along, so occupancy is read through the new table
+            if table.distance(index) != FREE {
+                table.keys[index].write(f(unsafe { this.keys[index].assume_init_ref() }));
+            }
+        }
+        if table.constant_time {
+            table.zero_free_keys();
+        }
+        let _ = unsafe {
+            mem::replace(&mut this.keys, Buffer::with_capacity(0)).into_inner(0, table.capacity)
+        };
+        table
+    }
+
+    // predicate scans straight over the distances and values lanes, no per-item iterator state
+    pub fn count_values(&self, mut f: impl FnMut(&V) -> bool) -> usize {
+        (0..self.capacity)
+            .filter(|&i| self.distance(i) != FREE && f(unsafe { self.values[i].assume_init_ref() }))
+            .count()
+    }
+
+    pub fn any_value(&self, mut f: impl FnMut(&V) -> bool) -> bool {
+        (0..self.capacity)
+            .any(|i| self.distance(i) != FREE && f(unsafe { self.values[i].assume_init_ref() }))
+    }
+
+    // copies occupied slots of 'lane' in storage order, each run of adjacent occupied
+    // slots with a single memcpy
+    fn export_lane<T: Copy>(&self, lane: &Buffer<MaybeUninit<T>>) -> Vec<T> {
+        let mut out = Vec::<T>::with_capacity(self.len);
+        let mut index = 0;
+        while index < self.capacity {
+            if self.distance(index) == FREE {
+                index += 1;
+                continue;
+            }
+            let start = index;
+            while index < self.capacity && self.distance(index) != FREE {
+                index += 1;
+            }
+            // SAFETY:
+            // occupied slots are initialized and there are exactly 'len' of them, so the
+            // runs fit the preallocated capacity
+            unsafe {
+                let run = index - start;
+                let dst = out.as_mut_ptr().add(out.len());
+                std::ptr::copy_nonoverlapping(lane.offset_mut(start).cast::<T>(), dst, run);
+                out.set_len(out.len() + run);
+            }
+        }
+        out
+    }
+
+    #[inline]
+    pub fn memory_usage(&self) -> usize {
+        let stamps = if self.stamps.is_some() { mem::size_of::<u8>() } else { 0 };
+        let tags = if cfg!(feature = "tags") { mem::size_of::<u8>() } else { 0 };
+        let ages = if self.ages.is_some() { mem::size_of::<u8>() } else { 0 };
+        self.capacity
+            * (mem::size_of::<Distance>()
+                + mem::size_of::<K>()
+                + mem::size_of::<V>()
+                + stamps
+                + tags
+                + ages)
+    }
+
+    // largest distance of a stored entry, FREE for an empty table
+    pub fn max_distance(&self) -> Distance {
+        (0..self.capacity).map(|index| self.distance(index)).max().unwrap_or(FREE)
+    }
+
+    // occupied slots per cache line of the distances lane, lines are taken by address
+    pub fn occupancy_profile(&self) -> Vec<u8> {
+        const LINE: usize = 64;
+        let offset = self.distances.0 as usize % LINE;
+        let mut profile = vec![0; (offset + self.capacity).div_ceil(LINE)];
+        for (index, ..) in self.slots() {
+            profile[(offset + index) / LINE] += 1;
+        }
+        profile
+    }
+
+    // distance and entry stored at 'index', none for an empty slot
+    #[inline]
+    pub fn slot(&self, index: usize) -> Option<(Distance, &K, &V)> {
+        match self.distance(index) {
+            FREE => None,
+            distance => Some(unsafe {
+                (distance, self.keys[index].assume_init_ref(), self.values[index].assume_init_ref())
+            }),
+        }
+    }
+
+    // bounds-checked entry lookup by slot index
+    pub fn get_by_slot(&self, index: usize) -> Option<(&K, &V)> {
+        if index < self.capacity && self.distance(index) != FREE {
+            Some(unsafe {
+                (self.keys[index].assume_init_ref(), self.values[index].assume_init_ref())
+            })
+        } else {
+            None
+        }
+    }
+
+    pub fn get_by_slot_mut(&mut self, index: usize) -> Option<(&K, &mut V)> {
+        if index < self.capacity && self.distance(index) != FREE {
+            Some(unsafe {
+                (self.keys[index].assume_init_ref(), self.values[index].assume_init_mut())
+            })
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    pub(crate) fn iter_slots(&self) -> SlotIter<'_, K, V> {
+        SlotIter { slots: self.slots() }
+    }
+
+    pub fn occupied(&self) -> impl Iterator<Item = (usize, &K)> + '_ {
+        self.slots().map(|(i, k, _)| (i, k))
+    }
+
+    // overwrites 'n' released slots from 'offset': zeroes values for 'secure-wipe', then fills
+    // keys and values with 'POISON' for 'poison', no-op without either feature
+    #[inline(always)]
+    fn scrub(&mut self, _offset: usize, _n: usize) {
+        #[cfg(feature = "secure-wipe")]
+        self.values.fill_bytes(_offset, _n, 0);
+        #[cfg(feature = "poison")]
+        {
+            self.keys.fill_bytes(_offset, _n, POISON);
+            self.values.fill_bytes(_offset, _n, POISON);
+        }
+    }
+
+    // raw bytes of the values lane, occupied or not
+    #[cfg(all(test, any(feature = "secure-wipe", feature = "poison")))]
+    pub fn value_bytes(&self) -> &[u8] {
+        unsafe {
+            std::slice::from_raw_parts(self.values.0.cast(), self.capacity * mem::size_of::<V>())
+        }
+    }
+
+    #[cfg(any(test, debug_assertions))]
+    pub fn assert_invariants(&self, home_index: impl Fn(&K) -> usize) {
+        let mut len = 0;
+        for index in 0..self.capacity {
+            let distance = self.distance(index);
+            if distance == FREE {
+                continue;
+            }
+            len += 1;
+
+            assert!(distance >= 0, "slot {index}: invalid distance {distance}");
+            let home = home_index(unsafe { self.keys[index].assume_init_ref() });
+            assert!(home <= index, "slot {index}: stored before its home slot {home}");
+            assert_eq!(
+                index - home,
+                distance as usize,
+                "slot {index}: distance doesn't match home slot {home}"
+            );
+
+            // a displaced entry must be preceded by an occupied slot whose home is not further away,
+            // i.e. there are no holes inside a probe cluster and the cluster is ordered by home slot
+            if distance > 0 {
+                let prev = self.distance(index - 1);
+                assert!(prev != FREE, "slot {index}: hole inside a probe cluster");
+                assert!(distance <= prev + 1, "slot {index}: cluster is not ordered by home slot");
+            }
+        }
+        assert_eq!(len, self.len, "occupied slots count doesn't match length");
+    }
+
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
     #[inline]
     pub fn clear(&mut self) {
-        self.distances.fill(FREE, self.capacity);
+        self.drop_values();
+        match &mut self.stamps {
+            Some(stamps) if stamps.epoch < u8::MAX => stamps.epoch += 1,
+            // epoch wrapped around, stale stamps could match again
+            Some(stamps) => {
+                stamps.epoch = 0;
+                stamps.lane.fill(0, self.capacity);
+                self.mark_all_free();
+            }
+            None => self.mark_all_free(),
+        }
         self.len = 0;
+        self.bump_version();
+    }
+
+    // 'clear' in runs of 'chunk' slots from the back, 'between' runs after every run but the
+    // last. Entries in front of the cleared runs keep their whole probe path, so the table stays
+    // consistent at every call of 'between', even if it unwinds.
+    pub fn clear_chunked(
+        &mut self,
+        chunk: usize,
+        mut released: impl FnMut(&K),
+        mut between: impl FnMut(),
+    ) {
+        let chunk = chunk.max(1);
+        self.bump_version();
+        let mut end = self.capacity;
+        while end > 0 {
+            let start = end.saturating_sub(chunk);
+            for index in (start..end).rev() {
+                if self.distance(index) != FREE {
+                    self.distances[index] = FREE;
+                    self.len -= 1;
+                    released(unsafe { self.keys[index].assume_init_ref() });
+                    unsafe { self.values[index].assume_init_drop() };
+                }
+            }
+            self.scrub(start, end - start);
+            end = start;
+            if end > 0 {
+                between();
+            }
+        }
+    }
+
+    // call individual Value destructors, every slot is released before its value is dropped;
+    // if a destructor panics the guard still drops the remaining values, like a Vec does, so
+    // the table ends up empty either way
+    fn drop_values(&mut self) {
+        struct Guard<'a, K, V> {
+            table: &'a mut Table<K, V>,
+            index: usize,
+        }
+
+        impl<K, V> Guard<'_, K, V> {
+            // 'index' moves past a slot before its value is dropped, so a rerun after a panic
+            // resumes with the next slot
+            fn run(&mut self) {
+                let table = &mut *self.table;
+                if mem::needs_drop::<V>() {
+                    while self.index < table.capacity {
+                        let index = self.index;
+                        self.index += 1;
+                        if table.distance(index) != FREE {
+                            table.distances[index] = FREE;
+                            table.len -= 1;
+                            unsafe { table.values[index].assume_init_drop() };
+                        }
+                    }
+                }
+            }
+        }
+
+        impl<K, V> Drop for Guard<'_, K, V> {
+            fn drop(&mut self) {
+                self.run();
+                self.table.scrub(0, self.table.capacity);
+            }
+        }
+
+        let mut guard = Guard { table: self, index: 0 };
+        guard.run();
+    }
+
+    pub fn for_each_mut(&mut self, mut f: impl FnMut(&K, &mut V)) {
+        for index in 0..self.capacity {
+            if self.distance(index) != FREE {
+                unsafe {
+                    f(self.keys[index].assume_init_ref(), self.values[index].assume_init_mut())
+                };
+            }
+        }
+    }
+
+    // moves every entry out of the table in slot order along with its tag, leaving it empty;
+    // slots are released before 'f' is called, so a panicking 'f' drops the rest with the table
+    pub fn drain_with(&mut self, mut f: impl FnMut(K, V, u8)) {
+        self.bump_version();
+        for index in 0..self.capacity {
+            if self.distance(index) != FREE {
+                self.distances[index] = FREE;
+                self.len -= 1;
+                let (key, value) = unsafe {
+                    (self.keys[index].assume_init_read(), self.values[index].assume_init_read())
+                };
+                f(key, value, self.tags.get(index));
+            }
+        }
+        self.scrub(0, self.capacity);
     }
 }
 
 impl<K, V> Table<K, V> {
+    // always 0 without the 'tags' feature, where setting one does nothing
+    #[inline]
+    pub fn tag(&self, index: usize) -> u8 {
+        self.tags.get(index)
+    }
+
+    #[inline]
+    pub fn set_tag(&mut self, index: usize, tag: u8) {
+        self.tags.set(index, tag);
+    }
+
+    // starts loading the slot's distance, key and value into the cache
+    #[inline]
+    pub fn prefetch(&self, index: usize) {
+        (self.prefetcher())(index)
+    }
+
+    // 'prefetch' holding only the lanes' addresses, for use while the table is borrowed mutably
+    #[inline]
+    pub fn prefetcher(&self) -> impl Fn(usize) + Copy {
+        let (distances, keys, values) = (self.distances.0, self.keys.0, self.values.0);
+        move |index| {
+            crate::cache::prefetch(distances.wrapping_add(index));
+            crate::cache::prefetch(keys.wrapping_add(index));
+            crate::cache::prefetch(values.wrapping_add(index));
+        }
+    }
+
+    // compares all 'probe_limit' slots from the home slot 'index' and folds the matches with
+    // masks, so the work doesn't depend on where or whether the key is stored; only valid with
+    // 'constant_time' set. The table never holds duplicates, at most one slot matches.
+    #[inline]
+    pub fn find_constant_time(&self, key: &K, index: usize) -> Option<usize>
+    where
+        K: PartialEq,
+    {
+        debug_assert!(self.constant_time, "key slots aren't all initialized");
+        self.check_window(index);
+        let mut found = 0;
+        let mut hit = 0;
+        for i in index..index + self.probe_limit as usize {
+            // SAFETY: every key slot is initialized in constant time mode
+            let equal = unsafe { self.keys[i].assume_init_ref() } == key;
+            let matched = std::hint::black_box((self.distance(i) != FREE) & equal) as usize;
+            let mask = matched.wrapping_neg();
+            found |= i & mask;
+            hit |= matched;
+        }
+        (hit != 0).then_some(found)
+    }
+
+    // Probes at most the 'probe_limit' slots from the home slot 'index', which lie inside the
+    // table for every home slot: the overflow region after the last one is 'probe_limit' slots
+    // long. A key missing from a full window yields 'NotFound' with distance 'probe_limit',
+    // just past the window, which no insert accepts.
     pub fn search<Q>(&self, key: &Q, mut index: usize) -> SearchResult
     where
         K: Borrow<Q>,
         Q: ?Sized + Ord,
     {
+        self.check_window(index);
         index = match self.distance_key_cmp(index, 0, key) {
             Ordering::Less => return SearchResult::NotFound(index, 0),
             Ordering::Equal => return SearchResult::Found(index),
@@ -73,24 +832,51 @@ impl<K, V> Table<K, V> {
             Ordering::Greater => index + 1,
         };
 
-        for distance in 2..Distance::MAX {
+        for distance in 2..self.probe_limit {
             index = match self.distance_key_cmp(index, distance, key) {
                 Ordering::Less => return SearchResult::NotFound(index, distance),
                 Ordering::Equal => return SearchResult::Found(index),
                 Ordering::Greater => index + 1,
             }
         }
-        panic!("maximum probes count reached, you might want to increase capacity");
+        SearchResult::NotFound(index, self.probe_limit)
+    }
+
+    // one check up front instead of one per probed slot
+    #[inline(always)]
+    fn check_window(&self, home: usize) {
+        assert!(home + self.probe_limit as usize <= self.capacity, "home slot {home} out of range");
+    }
+
+    // SAFETY:
+    // the key must be present in the table, the probe never checks for the end of the cluster
+    pub unsafe fn search_unchecked<Q>(&self, key: &Q, mut index: usize) -> usize
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Ord,
+    {
+        let home = index;
+        loop {
+            debug_assert!(self.distance(index) != FREE, "probe reached an empty slot");
+            debug_assert!(
+                self.distance(index) as usize >= index - home,
+                "probe passed the key's slot"
+            );
+            if key.eq(self.keys[index].assume_init_ref().borrow()) {
+                return index;
+            }
+            index += 1;
+        }
     }
 
-    fn distance_key_cmp<Q: ?Sized>(&self, index: usize, distance: Distance, key: &Q) -> Ordering
+    fn distance_key_cmp<Q>(&self, index: usize, distance: Distance, key: &Q) -> Ordering
     where
-        Q: Ord,
+        Q: ?Sized + Ord,
         K: Borrow<Q>,
     {
         // any 'distance' initiated by 'search' routine is greater than 'distance' of an empty 'slot',
         // thus, subsequent 'key' read is always valid since slot is non-empty
-        if distance > self.distances[index] {
+        if distance > self.distance(index) {
             Ordering::Less
         } else if key.eq(unsafe { self.keys[index].assume_init_ref().borrow() }) {
             Ordering::Equal
@@ -102,51 +888,251 @@ impl<K, V> Table<K, V> {
 
 impl<K, V> Table<K, V> {
     pub fn insert(&mut self, index: usize, mut key: K, mut value: V, mut distance: Distance) {
-        if self.distances[index] == FREE {
-            self.write(index, key, value, distance);
+        // 'search' found no free slot in the key's whole probe window
+        assert!(
+            distance < self.probe_limit,
+            "maximum probes count reached, you might want to increase capacity"
+        );
+        // new entries start untagged and at age 0
+        let mut meta = Meta::default();
+        if self.distance(index) == FREE {
+            self.write(index, key, value, distance, meta);
         } else {
+            self.check_displacement(index, distance);
             // Safety:
             // to this point we know the slot is non-empty and thus it's memory is initialized
-            unsafe { self.swap_at(index, &mut key, &mut value, &mut distance) };
-            self.emplace(index + 1, key, value, distance + 1);
+            unsafe { self.swap_at(index, &mut key, &mut value, &mut distance, &mut meta) };
+            self.emplace(index + 1, key, value, distance + 1, meta);
         }
 
         self.len += 1;
+        self.bump_version();
+    }
+
+    // stores entries with distinct keys, sorted by home slot, into an empty table in a single
+    // front-to-back sweep: each goes to the first free slot at or after its home, which is where
+    // Robin Hood inserts in that order put it, so nothing is displaced and no slot is read. In
+    // nontemporal mode keys and values go out with streaming stores. 'stored' sees every key
+    // once it's in; an entry past the probe limit panics, the ones before it stay stored
+    pub fn fill_sorted(
+        &mut self,
+        entries: impl IntoIterator<Item = (usize, K, V)>,
+        mut stored: impl FnMut(K),
+    ) where
+        K: Copy,
+        V: Copy,
+    {
+        debug_assert_eq!(self.len, 0, "fill_sorted needs an empty table");
+        self.bump_version();
+        let stream = self.nontemporal;
+        let mut next = 0;
+        for (home, key, value) in entries {
+            let index = home.max(next);
+            let distance = index - home;
+            if distance >= self.probe_limit as usize {
+                crate::cache::store_fence();
+                panic!("maximum probes count reached, you might want to increase capacity");
+            }
+            if stream && index - next < STREAM_GAP {
+                // the free slots between two entries go out as zeros, so the lines around
+                // them are written whole instead of in parts, which cost a read of the line
+                for gap in next..index {
+                    unsafe {
+                        crate::cache::stream_store(
+                            self.keys.offset_mut(gap),
+                            &MaybeUninit::zeroed(),
+                        );
+                        crate::cache::stream_store(
+                            self.values.offset_mut(gap),
+                            &MaybeUninit::zeroed(),
+                        );
+                    }
+                }
+            }
+            let (key_slot, value_slot) = (&mut self.keys[index], &mut self.values[index]);
+            if stream {
+                unsafe {
+                    crate::cache::stream_store(key_slot, &MaybeUninit::new(key));
+                    crate::cache::stream_store(value_slot, &MaybeUninit::new(value));
+                }
+            } else {
+                key_slot.write(key);
+                value_slot.write(value);
+            }
+            self.tags.set(index, 0);
+            self.set_age(index, 0);
+            self.set_occupied(index, distance as Distance);
+            self.len += 1;
+            next = index + 1;
+            stored(key);
+        }
+        if stream {
+            crate::cache::store_fence();
+        }
     }
 
     pub fn remove(&mut self, index: usize) -> V {
         self.len -= 1;
+        self.bump_version();
         self.distances[index] = FREE;
 
         let ret = unsafe { self.values[index].assume_init_read() };
 
-        self.shift_up(index);
+        // the bytes of 'ret' travel with the released slot to the end of the cluster
+        let released = self.shift_up(index);
+        self.scrub(released, 1);
 
         ret
     }
 
+    // removes the entries whose slots are set in 'marked', a bitmap of occupied slots, in a
+    // single sweep instead of a backward shift per entry: a kept entry behind removed ones moves
+    // back once, as far as its home slot allows, so each slot of the affected clusters is
+    // visited once. Returns the number removed; values that need dropping are pushed to
+    // 'removed', so their drop code only runs after the sweep.
+    pub fn remove_marked(&mut self, marked: &[u64], removed: &mut Vec<V>) -> usize {
+        let is_marked = |index: usize| marked[index / 64] & (1 << (index % 64)) != 0;
+        let next_marked = |from: usize| {
+            let mut word = from / 64;
+            let mut bits = marked.get(word)? & (!0 << (from % 64));
+            while bits == 0 {
+                word += 1;
+                bits = *marked.get(word)?;
+            }
+            Some(word * 64 + bits.trailing_zeros() as usize)
+        };
+        let Some(mut read) = next_marked(0) else { return 0 };
+        self.bump_version();
+        let len = self.len;
+        // first free slot of the gap opened by the removals so far, if one is open
+        let mut gap = None;
+        while read < self.capacity {
+            if is_marked(read) {
+                self.len -= 1;
+                self.distances[read] = FREE;
+                if std::mem::needs_drop::<V>() {
+                    removed.push(unsafe { self.values[read].assume_init_read() });
+                }
+                self.scrub(read, 1);
+                gap = gap.or(Some(read));
+            } else if let (Some(write), distance @ 1..) = (gap, self.distance(read)) {
+                let home = read - distance as usize;
+                let to = write.max(home);
+                // 'to' is free, its released bytes move to 'read'
+                unsafe { self.swap_indices(to, read) };
+                self.set_occupied(to, (to - home) as Distance);
+                self.distances[read] = FREE;
+                gap = Some(to + 1);
+            } else {
+                // an empty slot or an entry in its home slot closes the gap, nothing behind it
+                // moves until the next removal
+                gap = None;
+                match next_marked(read + 1) {
+                    Some(index) => {
+                        read = index;
+                        continue;
+                    }
+                    None => break,
+                }
+            }
+            read += 1;
+        }
+        len - self.len
+    }
+
+    // dry run of 'emplace' over the distances lane: an insert that would push some entry past
+    // the probe limit panics here, before any entry moved, instead of halfway through the
+    // chain with a displaced entry in hand that would be lost. It's the only check of the
+    // bound in release builds, the 'emplace' loops rely on it and only assert it in debug
+    fn check_displacement(&self, index: usize, distance: Distance) {
+        assert!(
+            self.displacement_fits(index, distance),
+            "probes count overflow, increase initial capacity"
+        );
+    }
+
+    fn displacement_fits(&self, mut index: usize, mut distance: Distance) -> bool {
+        loop {
+            let occupant = self.distance(index);
+            if occupant == FREE {
+                return true;
+            } else if distance > occupant {
+                distance = occupant;
+            }
+            distance += 1;
+            if distance >= self.probe_limit {
+                return false;
+            }
+            index += 1;
+        }
+    }
+
+    // whether 'insert' at the insertion point 'search' returned stays within the probe limit
+    // instead of panicking
+    pub fn can_insert(&self, index: usize, distance: Distance) -> bool {
+        distance < self.probe_limit && self.displacement_fits(index, distance)
+    }
+
+    // the chain started by an insert's first swap rarely displaces more than a few entries, so
+    // the first steps stay inline (and unrolled) in 'insert', longer chains finish out of line
+    #[inline]
+    fn emplace(
+        &mut self,
+        mut index: usize,
+        mut key: K,
+        mut value: V,
+        mut distance: Distance,
+        mut meta: Meta,
+    ) {
+        for _ in 0..INLINE_DISPLACEMENTS {
+            let occupant = self.distance(index);
+            if occupant == FREE {
+                return self.write(index, key, value, distance, meta);
+            } else if distance > occupant {
+                unsafe { self.swap_at(index, &mut key, &mut value, &mut distance, &mut meta) };
+            }
+
+            distance += 1;
+            // 'insert' ran 'check_displacement' over this chain
+            debug_assert!(distance < self.probe_limit, "entry displaced past the probe limit");
+            index += 1;
+        }
+        self.emplace_long(index, key, value, distance, meta);
+    }
+
     #[inline(never)]
-    fn emplace(&mut self, mut index: usize, mut key: K, mut value: V, mut distance: Distance) {
+    fn emplace_long(
+        &mut self,
+        mut index: usize,
+        mut key: K,
+        mut value: V,
+        mut distance: Distance,
+        mut meta: Meta,
+    ) {
         loop {
-            if self.distances[index] == FREE {
-                self.write(index, key, value, distance);
+            let occupant = self.distance(index);
+            if occupant == FREE {
+                self.write(index, key, value, distance, meta);
                 break;
-            } else if distance > self.distances[index] {
-                unsafe { self.swap_at(index, &mut key, &mut value, &mut distance) };
+            } else if distance > occupant {
+                unsafe { self.swap_at(index, &mut key, &mut value, &mut distance, &mut meta) };
             }
 
-            assert!(distance < Distance::MAX, "probes count overflow, increase initial capacity");
             distance += 1;
+            // 'insert' ran 'check_displacement' over this chain
+            debug_assert!(distance < self.probe_limit, "entry displaced past the probe limit");
             index += 1;
         }
     }
 
-    fn shift_up(&mut self, mut index: usize) {
+    // returns the slot left free at the end of the shift; the walk stops at the last slot at
+    // the latest, which is never occupied as no entry is stored 'probe_limit' slots from home
+    fn shift_up(&mut self, mut index: usize) -> usize {
         loop {
-            index = index + 1;
+            index += 1;
 
-            if self.distances[index] < 1 {
-                break;
+            if self.distance(index) < 1 {
+                break index - 1;
             }
 
             self.distances[index] -= 1;
@@ -155,59 +1141,93 @@ impl<K, V> Table<K, V> {
         }
     }
 
-    fn write(&mut self, index: usize, key: K, value: V, distance: Distance) {
+    fn write(&mut self, index: usize, key: K, value: V, distance: Distance, meta: Meta) {
         self.keys[index].write(key);
         self.values[index].write(value);
+        self.tags.set(index, meta.tag);
+        self.set_age(index, meta.age);
+        self.set_occupied(index, distance);
+    }
+
+    #[inline]
+    fn set_occupied(&mut self, index: usize, distance: Distance) {
         self.distances[index] = distance;
+        if let Some(stamps) = &mut self.stamps {
+            stamps.lane[index] = stamps.epoch;
+        }
     }
 
     // SAFETY:
     // ensure that memory at 'index' is properly initialized
-    unsafe fn swap_at(&mut self, i: usize, key: &mut K, value: &mut V, distance: &mut Distance) {
+    unsafe fn swap_at(
+        &mut self,
+        i: usize,
+        key: &mut K,
+        value: &mut V,
+        distance: &mut Distance,
+        meta: &mut Meta,
+    ) {
         use std::ptr::swap_nonoverlapping;
         swap_nonoverlapping(self.distances.offset_mut(i), distance as *mut _, 1);
         swap_nonoverlapping(self.keys.offset_mut(i), key as *mut _ as *mut _, 1);
         swap_nonoverlapping(self.values.offset_mut(i), value as *mut _ as *mut _, 1);
+        self.tags.swap_in(i, &mut meta.tag);
+        if let Some(ages) = &mut self.ages {
+            mem::swap(&mut ages[i], &mut meta.age);
+        }
     }
 
     // SAFETY:
     // ensure that memory at both indices is properly initialized
     unsafe fn swap_indices(&mut self, i: usize, j: usize) {
         self.distances.swap_indices(i, j);
+        // the destination may be a freed slot carrying a stale stamp
+        if let Some(stamps) = &mut self.stamps {
+            stamps.lane.swap_indices(i, j);
+        }
         self.keys.swap_indices(i, j);
         self.values.swap_indices(i, j);
+        self.tags.swap(i, j);
+        if let Some(ages) = &mut self.ages {
+            ages.swap_indices(i, j);
+        }
     }
 }
 
-impl<K, V: Clone> Clone for Table<K, V> {
+impl<K: Clone, V: Clone> Clone for Table<K, V> {
     #[inline]
     fn clone(&self) -> Self {
-        let mut distances = Buffer::with_capacity_filled(self.capacity, FREE);
-        let mut keys = Buffer::with_capacity(self.capacity);
-        let mut values = Buffer::with_capacity(self.capacity);
-        unsafe {
-            self.distances.copy_to(&mut distances, self.capacity);
-            self.keys.copy_to(&mut keys, self.capacity);
-            self.values.copy_to(&mut values, self.capacity);
+        let mut table = self.empty_like();
+        // entries are copied one by one, a panicking V::clone leaves 'table' valid
+        for index in 0..self.capacity {
+            let distance = self.distance(index);
+            if distance != FREE {
+                let (key, value) = unsafe {
+                    (self.keys[index].assume_init_ref(), self.values[index].assume_init_ref())
+                };
+                let meta = Meta { tag: self.tags.get(index), age: self.age(index) };
+                table.write(index, key.clone(), value.clone(), distance, meta);
+                table.len += 1;
+            }
         }
-        Self { distances, keys, values, capacity: self.capacity, len: self.len }
+        table
     }
 }
 
 impl<K, V> Drop for Table<K, V> {
     #[inline]
     fn drop(&mut self) {
-        // call individual Value destructors
-        if std::mem::needs_drop::<V>() {
-            self.distances
-                .as_slice_mut(self.capacity)
-                .iter()
-                .enumerate()
-                .filter_map(|(i, d)| FREE.ne(d).then_some(i))
-                .for_each(|i| unsafe {
-                    let p = self.values.0.add(i).cast::<V>();
-                    std::ptr::drop_in_place(p);
-                });
+        self.drop_values();
+        if let Some(stamps) = self.stamps.take() {
+            let _ = unsafe { stamps.lane.into_inner(self.capacity, self.capacity) };
+        }
+        // the tags and ages lanes are always owned, borrowed tables included
+        unsafe { self.tags.free(self.capacity) };
+        if let Some(ages) = self.ages.take() {
+            let _ = unsafe { ages.into_inner(self.capacity, self.capacity) };
+        }
+        if !self.owned {
+            return;
         }
 
         // free the buffers
@@ -230,6 +1250,8 @@ impl<K, V> Index<usize> for Table<K, V> {
 
     #[inline]
     fn index(&self, index: usize) -> &Self::Output {
+        #[cfg(feature = "poison")]
+        assert!(self.distance(index) != FREE, "read of released (poisoned) slot {index}");
         unsafe { self.values[index].assume_init_ref() }
     }
 }
@@ -237,35 +1259,211 @@ impl<K, V> Index<usize> for Table<K, V> {
 impl<K, V> IndexMut<usize> for Table<K, V> {
     #[inline]
     fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        #[cfg(feature = "poison")]
+        assert!(self.distance(index) != FREE, "read of released (poisoned) slot {index}");
         unsafe { self.values[index].assume_init_mut() }
     }
 }
 
-pub struct Keys<'a, K> {
-    keys: *mut MaybeUninit<K>,
-    keys_end: *mut MaybeUninit<K>,
-    distances: *mut Distance,
-    _marker: PhantomData<&'a K>,
+// Walks a slot range of the table coupling the occupancy lanes with the data lanes, the only
+// place the iterators read keys and values; it never yields a reference to an empty slot.
+pub(crate) struct OccupiedSlotIter<'a, K, V> {
+    table: &'a Table<K, V>,
+    index: usize,
+    end: usize,
+    // occupied slots left in 'index..end'
+    remaining: usize,
+    // the table's version when the iterator was made
+    version: u64,
 }
 
-impl<'a, K> Iterator for Keys<'a, K> {
-    type Item = &'a K;
+impl<'a, K, V> OccupiedSlotIter<'a, K, V> {
+    // SAFETY: the slot at 'index' must be occupied
+    #[inline]
+    unsafe fn entry(&self, index: usize) -> (usize, &'a K, &'a V) {
+        let table = self.table;
+        (index, table.keys[index].assume_init_ref(), table.values[index].assume_init_ref())
+    }
+
+    // The borrow rules keep the table unchanged while the iterator lives, unless it's mutated
+    // behind them through raw pointers or foreign code. That is caught here, like the
+    // modification checks of std collections: a debug build panics, a release build ends the
+    // iteration.
+    #[inline]
+    fn check(&mut self) -> bool {
+        if self.table.version() == self.version {
+            return true;
+        }
+        if cfg!(debug_assertions) {
+            panic!("map was modified during iteration");
+        }
+        (self.index, self.remaining) = (self.end, 0);
+        false
+    }
+}
 
+impl<'a, K, V> Iterator for OccupiedSlotIter<'a, K, V> {
+    type Item = (usize, &'a K, &'a V);
+
+    #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        unsafe {
-            while self.keys < self.keys_end {
-                self.keys = self.keys.add(1);
-                self.distances = self.distances.add(1);
+        if !self.check() {
+            return None;
+        }
+        while self.index < self.end {
+            let index = self.index;
+            self.index += 1;
 
-                if *self.distances.offset(-1) != FREE {
-                    return Some((*self.keys.offset(-1)).assume_init_ref());
-                }
+            if self.table.distance(index) != FREE {
+                self.remaining -= 1;
+                // SAFETY:
+                // keys and values of an occupied slot are always initialized
+                return Some(unsafe { self.entry(index) });
+            }
+        }
+        None
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for OccupiedSlotIter<'a, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if !self.check() {
+            return None;
+        }
+        while self.index < self.end {
+            self.end -= 1;
+            if self.table.distance(self.end) != FREE {
+                self.remaining -= 1;
+                // SAFETY: the slot is occupied
+                return Some(unsafe { self.entry(self.end) });
             }
-            None
         }
+        None
     }
 }
 
+impl<'a, K, V> ExactSizeIterator for OccupiedSlotIter<'a, K, V> {}
+impl<'a, K, V> FusedIterator for OccupiedSlotIter<'a, K, V> {}
+
+pub struct Keys<'a, K, V> {
+    slots: OccupiedSlotIter<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator for Keys<'a, K, V> {
+    type Item = &'a K;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.slots.next().map(|(_, k, _)| k)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.slots.size_hint()
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for Keys<'a, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.slots.next_back().map(|(_, k, _)| k)
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for Keys<'a, K, V> {}
+impl<'a, K, V> FusedIterator for Keys<'a, K, V> {}
+
+pub struct Values<'a, K, V> {
+    slots: OccupiedSlotIter<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator for Values<'a, K, V> {
+    type Item = &'a V;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.slots.next().map(|(_, _, v)| v)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.slots.size_hint()
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for Values<'a, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.slots.next_back().map(|(_, _, v)| v)
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for Values<'a, K, V> {}
+impl<'a, K, V> FusedIterator for Values<'a, K, V> {}
+
+pub struct Iter<'a, K, V> {
+    slots: OccupiedSlotIter<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.slots.next().map(|(_, k, v)| (k, v))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.slots.size_hint()
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for Iter<'a, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.slots.next_back().map(|(_, k, v)| (k, v))
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for Iter<'a, K, V> {}
+impl<'a, K, V> FusedIterator for Iter<'a, K, V> {}
+
+pub struct SlotIter<'a, K, V> {
+    slots: OccupiedSlotIter<'a, K, V>,
+}
+
+impl<'a, K: Copy, V> Iterator for SlotIter<'a, K, V> {
+    type Item = (usize, K, &'a V);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.slots.next().map(|(i, k, v)| (i, *k, v))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.slots.size_hint()
+    }
+}
+
+impl<'a, K: Copy, V> DoubleEndedIterator for SlotIter<'a, K, V> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.slots.next_back().map(|(i, k, v)| (i, *k, v))
+    }
+}
+
+impl<'a, K: Copy, V> ExactSizeIterator for SlotIter<'a, K, V> {}
+impl<'a, K: Copy, V> FusedIterator for SlotIter<'a, K, V> {}
+
+#[derive(Clone, Copy)]
 pub enum SearchResult {
     Found(usize),
     NotFound(usize, Distance),
@@ -302,11 +1500,6 @@ impl<T> Buffer<T> {
         self.0.add(offset)
     }
 
-    #[inline]
-    pub unsafe fn copy_to(&self, dst: &mut Buffer<T>, len: usize) {
-        std::ptr::copy_nonoverlapping(self.0, dst.0, len);
-    }
-
     #[inline]
     pub unsafe fn swap_indices(&mut self, i: usize, j: usize) {
         std::ptr::swap_nonoverlapping(self.offset_mut(i), self.offset_mut(j), 1);
@@ -325,6 +1518,29 @@ impl<T: Copy> Buffer<T> {
     }
 }
 
+// byte pattern of released slots under the 'poison' feature
+#[cfg(feature = "poison")]
+pub const POISON: u8 = 0xDE;
+
+#[cfg(any(feature = "secure-wipe", feature = "poison"))]
+impl<T> Buffer<MaybeUninit<T>> {
+    // fills 'n' slots from 'offset' with volatile writes the optimizer can't elide as dead stores
+    pub fn fill_bytes(&mut self, offset: usize, n: usize, byte: u8) {
+        let bytes = unsafe { self.offset_mut(offset) }.cast::<u8>();
+        for i in 0..n * mem::size_of::<T>() {
+            unsafe { std::ptr::write_volatile(bytes.add(i), byte) };
+        }
+        std::sync::atomic::compiler_fence(std::sync::atomic::Ordering::SeqCst);
+    }
+}
+
+impl Buffer<Distance> {
+    // same as 'fill', but the stores bypass the cache, see 'cache::stream_fill'
+    pub fn fill_nontemporal(&mut self, value: Distance, n: usize) {
+        crate::cache::stream_fill(self.as_slice_mut(n), value);
+    }
+}
+
 impl<T> Index<usize> for Buffer<T> {
     type Output = T;
 
@@ -0,0 +1,312 @@
+use crate::{IntKey, IntMap};
+
+/// A map in the middle of a value schema change: entries of the old map are converted into
+/// the new one when they're accessed, or in batches with `migrate`, while the facade reads
+/// and writes as a single map of new values. A key is in at most one of the two maps. The old
+/// value is moved into `convert`, an unwinding `convert` drops the entry.
+///
+/// ```
+/// use intmap_rs::{IntMap, MigratingMap};
+///
+/// let mut old = IntMap::with_capacity(16);
+/// old.insert(1u32, 7u16);
+/// old.insert(2, 9);
+/// let mut map = MigratingMap::new(old, |_, v| u64::from(v) * 1000);
+/// assert_eq!(map.get(1), Some(&7000));
+/// assert_eq!(map.remaining(), 1);
+/// let new: IntMap<u32, u64> = map.finish();
+/// assert_eq!(new.get(2), Some(&9000));
+/// ```
+pub struct MigratingMap<K, VOld, VNew> {
+    old: IntMap<K, VOld>,
+    new: IntMap<K, VNew>,
+    convert: fn(K, VOld) -> VNew,
+    // slot of 'old' the next 'migrate' batch starts at, only a hint: removals may shift an
+    // entry below it, a pass that reaches the end with entries left starts over
+    cursor: usize,
+}
+
+impl<K: IntKey, VOld, VNew> MigratingMap<K, VOld, VNew> {
+    /// Migrates `old` into a new map of the same capacity.
+    ///
+    /// ```
+    /// use intmap_rs::{IntMap, MigratingMap};
+    ///
+    /// # let mut old = IntMap::with_capacity(16);
+    /// # old.insert(1u32, 7u16);
+    /// # old.insert(2, 9);
+    /// let map = MigratingMap::new(old, |_, v| u64::from(v) * 1000);
+    /// assert_eq!((map.remaining(), map.new_map().capacity()), (2, 16));
+    /// ```
+    pub fn new(old: IntMap<K, VOld>, convert: fn(K, VOld) -> VNew) -> Self {
+        let new = IntMap::with_capacity(old.capacity() as u32);
+        Self::with_new_map(old, new, convert)
+    }
+
+    /// Migrates `old` into `new`, e.g. one built with another `Config`. Keys of `old` that are
+    /// also in `new` are dropped from `old`, the new value wins.
+    ///
+    /// ```
+    /// use intmap_rs::{IntMap, MigratingMap};
+    ///
+    /// let mut old = IntMap::with_capacity(16);
+    /// old.insert(1u32, 7u16);
+    /// old.insert(2, 9);
+    /// let mut new = IntMap::with_capacity(32);
+    /// new.insert(1, 1u64);
+    /// let mut map = MigratingMap::with_new_map(old, new, |_, v| u64::from(v) * 1000);
+    /// assert_eq!(map.get(1), Some(&1));
+    /// assert_eq!(map.remaining(), 1);
+    /// ```
+    pub fn with_new_map(
+        mut old: IntMap<K, VOld>,
+        new: IntMap<K, VNew>,
+        convert: fn(K, VOld) -> VNew,
+    ) -> Self {
+        old.retain(|key, _| !new.contains(key));
+        Self { old, new, convert, cursor: 0 }
+    }
+
+    // moves the key's old entry over, if it has one
+    fn migrate_key(&mut self, key: K) {
+        if let Some(value) = self.old.remove(key) {
+            self.new.insert(key, (self.convert)(key, value));
+        }
+    }
+
+    /// The value stored for `key`, migrating its old entry first.
+    ///
+    /// ```
+    /// use intmap_rs::{IntMap, MigratingMap};
+    ///
+    /// # let mut old = IntMap::with_capacity(16);
+    /// # old.insert(1u32, 7u16);
+    /// # old.insert(2, 9);
+    /// let mut map = MigratingMap::new(old, |_, v| u64::from(v) * 1000);
+    /// assert_eq!(map.get(1), Some(&7000));
+    /// assert_eq!(map.get(3), None);
+    /// assert_eq!(map.remaining(), 1);
+    /// ```
+    pub fn get(&mut self, key: K) -> Option<&VNew> {
+        self.get_mut(key).map(|v| &*v)
+    }
+
+    /// The value stored for `key`, mutably, migrating its old entry first.
+    ///
+    /// ```
+    /// use intmap_rs::{IntMap, MigratingMap};
+    ///
+    /// # let mut old = IntMap::with_capacity(16);
+    /// # old.insert(1u32, 7u16);
+    /// # old.insert(2, 9);
+    /// let mut map = MigratingMap::new(old, |_, v| u64::from(v) * 1000);
+    /// *map.get_mut(1).unwrap() += 1;
+    /// assert_eq!(map.get(1), Some(&7001));
+    /// ```
+    pub fn get_mut(&mut self, key: K) -> Option<&mut VNew> {
+        self.migrate_key(key);
+        self.new.get_mut(key)
+    }
+
+    /// Inserts into the new map, returns the previous value, converted if it was still old.
+    ///
+    /// ```
+    /// use intmap_rs::{IntMap, MigratingMap};
+    ///
+    /// # let mut old = IntMap::with_capacity(16);
+    /// # old.insert(1u32, 7u16);
+    /// # old.insert(2, 9);
+    /// let mut map = MigratingMap::new(old, |_, v| u64::from(v) * 1000);
+    /// assert_eq!(map.insert(1, 5), Some(7000));
+    /// assert_eq!((map.insert(3, 6), map.remaining()), (None, 1));
+    /// ```
+    pub fn insert(&mut self, key: K, value: VNew) -> Option<VNew> {
+        let old = self.old.remove(key).map(|v| (self.convert)(key, v));
+        self.new.insert(key, value).or(old)
+    }
+
+    /// Removes the entry, returns its value, converted if it was still old.
+    ///
+    /// ```
+    /// use intmap_rs::{IntMap, MigratingMap};
+    ///
+    /// # let mut old = IntMap::with_capacity(16);
+    /// # old.insert(1u32, 7u16);
+    /// # old.insert(2, 9);
+    /// let mut map = MigratingMap::new(old, |_, v| u64::from(v) * 1000);
+    /// assert_eq!((map.remove(1), map.remove(1)), (Some(7000), None));
+    /// assert_eq!(map.len(), 1);
+    /// ```
+    pub fn remove(&mut self, key: K) -> Option<VNew> {
+        match self.old.remove(key) {
+            Some(value) => Some((self.convert)(key, value)),
+            None => self.new.remove(key),
+        }
+    }
+
+    /// Doesn't migrate the entry.
+    ///
+    /// ```
+    /// use intmap_rs::{IntMap, MigratingMap};
+    ///
+    /// # let mut old = IntMap::with_capacity(16);
+    /// # old.insert(1u32, 7u16);
+    /// # old.insert(2, 9);
+    /// let map = MigratingMap::new(old, |_, v| u64::from(v) * 1000);
+    /// assert!(map.contains(1) && !map.contains(3));
+    /// assert_eq!(map.remaining(), 2);
+    /// ```
+    pub fn contains(&self, key: K) -> bool {
+        self.new.contains(key) || self.old.contains(key)
+    }
+
+    /// Number of entries in both maps.
+    ///
+    /// ```
+    /// use intmap_rs::{IntMap, MigratingMap};
+    ///
+    /// # let mut old = IntMap::with_capacity(16);
+    /// # old.insert(1u32, 7u16);
+    /// # old.insert(2, 9);
+    /// let mut map = MigratingMap::new(old, |_, v| u64::from(v) * 1000);
+    /// map.get(1);
+    /// assert_eq!(map.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.new.len() + self.old.len()
+    }
+
+    /// Whether both maps are empty.
+    ///
+    /// ```
+    /// use intmap_rs::{IntMap, MigratingMap};
+    ///
+    /// # let mut old = IntMap::with_capacity(16);
+    /// # old.insert(1u32, 7u16);
+    /// # old.insert(2, 9);
+    /// let mut map = MigratingMap::new(old, |_, v| u64::from(v) * 1000);
+    /// assert!(!map.is_empty());
+    /// map.remove(1);
+    /// map.remove(2);
+    /// assert!(map.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Entries still in the old schema.
+    ///
+    /// ```
+    /// use intmap_rs::{IntMap, MigratingMap};
+    ///
+    /// # let mut old = IntMap::with_capacity(16);
+    /// # old.insert(1u32, 7u16);
+    /// # old.insert(2, 9);
+    /// let mut map = MigratingMap::new(old, |_, v| u64::from(v) * 1000);
+    /// map.get(2);
+    /// assert_eq!(map.remaining(), 1);
+    /// ```
+    pub fn remaining(&self) -> usize {
+        self.old.len()
+    }
+
+    /// Whether every old entry has been converted.
+    ///
+    /// ```
+    /// use intmap_rs::{IntMap, MigratingMap};
+    ///
+    /// # let mut old = IntMap::with_capacity(16);
+    /// # old.insert(1u32, 7u16);
+    /// # old.insert(2, 9);
+    /// let mut map = MigratingMap::new(old, |_, v| u64::from(v) * 1000);
+    /// assert!(!map.is_migrated());
+    /// map.migrate(2);
+    /// assert!(map.is_migrated());
+    /// ```
+    pub fn is_migrated(&self) -> bool {
+        self.old.is_empty()
+    }
+
+    /// Migrates up to `n` entries, e.g. a batch per tick of a long-running service, returns
+    /// how many were migrated.
+    ///
+    /// ```
+    /// use intmap_rs::{IntMap, MigratingMap};
+    ///
+    /// # let mut old = IntMap::with_capacity(16);
+    /// # old.insert(1u32, 7u16);
+    /// # old.insert(2, 9);
+    /// let mut map = MigratingMap::new(old, |_, v| u64::from(v) * 1000);
+    /// assert_eq!((map.migrate(1), map.remaining()), (1, 1));
+    /// assert_eq!(map.migrate(10), 1);
+    /// ```
+    pub fn migrate(&mut self, n: usize) -> usize {
+        let mut migrated = 0;
+        let mut restarted = false;
+        while migrated < n && !self.old.is_empty() {
+            match self.old.get_by_slot(self.cursor) {
+                // the removal shifts the next entry of the cluster into this slot
+                Some((key, _)) => {
+                    self.migrate_key(key);
+                    migrated += 1;
+                }
+                None if self.cursor < self.old.slots().get() => self.cursor += 1,
+                None => {
+                    debug_assert!(!restarted, "old map has entries but none in its slots");
+                    restarted = true;
+                    self.cursor = 0;
+                }
+            }
+        }
+        migrated
+    }
+
+    /// Migrates what's left and returns the new map.
+    ///
+    /// ```
+    /// use intmap_rs::{IntMap, MigratingMap};
+    ///
+    /// # let mut old = IntMap::with_capacity(16);
+    /// # old.insert(1u32, 7u16);
+    /// # old.insert(2, 9);
+    /// let map = MigratingMap::new(old, |_, v| u64::from(v) * 1000);
+    /// let new = map.finish();
+    /// assert_eq!((new.get(1), new.get(2)), (Some(&7000), Some(&9000)));
+    /// ```
+    pub fn finish(mut self) -> IntMap<K, VNew> {
+        self.migrate(usize::MAX);
+        self.new
+    }
+
+    /// The entries still in the old schema.
+    ///
+    /// ```
+    /// use intmap_rs::{IntMap, MigratingMap};
+    ///
+    /// # let mut old = IntMap::with_capacity(16);
+    /// # old.insert(1u32, 7u16);
+    /// # old.insert(2, 9);
+    /// let mut map = MigratingMap::new(old, |_, v| u64::from(v) * 1000);
+    /// map.get(1);
+    /// assert_eq!(map.old_map().keys().collect::<Vec<_>>(), [&2]);
+    /// ```
+    pub fn old_map(&self) -> &IntMap<K, VOld> {
+        &self.old
+    }
+
+    /// The entries already converted.
+    ///
+    /// ```
+    /// use intmap_rs::{IntMap, MigratingMap};
+    ///
+    /// # let mut old = IntMap::with_capacity(16);
+    /// # old.insert(1u32, 7u16);
+    /// # old.insert(2, 9);
+    /// let mut map = MigratingMap::new(old, |_, v| u64::from(v) * 1000);
+    /// map.get(1);
+    /// assert_eq!(map.new_map().get(1), Some(&7000));
+    /// ```
+    pub fn new_map(&self) -> &IntMap<K, VNew> {
+        &self.new
+    }
+}
@@ -0,0 +1,361 @@
+use crate::{Capacity, IntKey, IntMap};
+use std::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+use std::sync::Arc;
+
+/// Table health as last published by the map's owner, see `StatsHandle::sample`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TableStats {
+    pub len: usize,
+    pub capacity: usize,
+    /// As `IntMap::max_displacement`, as of the last rescan.
+    pub max_displacement: usize,
+}
+
+// written by the owning thread only, so plain stores suffice
+#[derive(Default)]
+struct Mirror {
+    len: AtomicUsize,
+    capacity: AtomicUsize,
+    max_displacement: AtomicUsize,
+}
+
+/// Reads the stats a `MonitoredIntMap` publishes, from any thread: each read is a relaxed
+/// atomic load, with no lock and nothing the writer waits on. Fields are loaded one by one, so
+/// a sample taken during a mutation can mix values from before and after it.
+#[derive(Clone)]
+pub struct StatsHandle {
+    mirror: Arc<Mirror>,
+}
+
+impl StatsHandle {
+    /// The stats as last published, each field loaded separately.
+    ///
+    /// ```
+    /// use intmap_rs::{MonitoredIntMap, TableStats};
+    ///
+    /// let mut map = MonitoredIntMap::with_capacity(16);
+    /// let stats = map.stats_handle();
+    /// map.insert(1u32, ());
+    /// map.insert(17, ());
+    /// assert_eq!(stats.sample(), TableStats { len: 2, capacity: 16, max_displacement: 1 });
+    /// ```
+    pub fn sample(&self) -> TableStats {
+        TableStats {
+            len: self.len(),
+            capacity: self.mirror.capacity.load(Relaxed),
+            max_displacement: self.mirror.max_displacement.load(Relaxed),
+        }
+    }
+
+    /// The published number of entries.
+    ///
+    /// ```
+    /// use intmap_rs::MonitoredIntMap;
+    ///
+    /// let mut map = MonitoredIntMap::with_capacity(16);
+    /// let stats = map.stats_handle();
+    /// map.insert(1u32, ());
+    /// assert_eq!(stats.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.mirror.len.load(Relaxed)
+    }
+
+    /// Whether the published number of entries is zero.
+    ///
+    /// ```
+    /// use intmap_rs::MonitoredIntMap;
+    ///
+    /// let mut map = MonitoredIntMap::with_capacity(16);
+    /// let stats = map.stats_handle();
+    /// assert!(stats.is_empty());
+    /// map.insert(1u32, ());
+    /// assert!(!stats.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Published entries per slot of capacity.
+    ///
+    /// ```
+    /// use intmap_rs::MonitoredIntMap;
+    ///
+    /// let mut map = MonitoredIntMap::with_capacity(16);
+    /// let stats = map.stats_handle();
+    /// for key in 0..4u32 {
+    ///     map.insert(key, ());
+    /// }
+    /// assert_eq!(stats.load_factor(), 0.25);
+    /// ```
+    pub fn load_factor(&self) -> f32 {
+        let stats = self.sample();
+        stats.len as f32 / stats.capacity.max(1) as f32
+    }
+}
+
+// mutations between rescans of the displacement, as a share of the capacity
+const RESCAN_SHARE: usize = 16;
+
+/// An `IntMap` that mirrors its `len`, capacity and max displacement into atomics, so
+/// monitoring threads holding a `StatsHandle` can sample table health without synchronizing
+/// with the writer.
+///
+/// `len` is published after every mutation. The max displacement takes a scan of the table,
+/// so it's rescanned once per `capacity / 16` mutations, a couple of bytes read per mutation,
+/// and lags by up to that many; `publish` rescans right away.
+///
+/// ```
+/// use intmap_rs::MonitoredIntMap;
+///
+/// let mut map = MonitoredIntMap::with_capacity(64);
+/// let stats = map.stats_handle();
+/// let monitor = std::thread::spawn(move || stats.sample().capacity);
+/// map.insert(1u32, 'a');
+/// assert_eq!(monitor.join().unwrap(), 64);
+/// assert_eq!(map.stats_handle().len(), 1);
+/// ```
+pub struct MonitoredIntMap<K, V> {
+    map: IntMap<K, V>,
+    mirror: Arc<Mirror>,
+    // mutations left until the next displacement rescan
+    until_rescan: usize,
+}
+
+impl<K: IntKey, V> MonitoredIntMap<K, V> {
+    /// Wraps `map` and publishes its stats.
+    ///
+    /// ```
+    /// use intmap_rs::{IntMap, MonitoredIntMap};
+    ///
+    /// let mut inner = IntMap::with_capacity(16);
+    /// inner.insert(1u32, ());
+    /// let map = MonitoredIntMap::new(inner);
+    /// assert_eq!(map.stats_handle().len(), 1);
+    /// ```
+    pub fn new(map: IntMap<K, V>) -> Self {
+        let mut map = Self { map, mirror: Arc::default(), until_rescan: 0 };
+        map.publish();
+        map
+    }
+
+    /// Monitors a new, empty map of `capacity`.
+    ///
+    /// ```
+    /// use intmap_rs::MonitoredIntMap;
+    ///
+    /// let mut map = MonitoredIntMap::with_capacity(16);
+    /// map.insert(1u32, ());
+    /// assert_eq!(map.stats_handle().sample().capacity, 16);
+    /// ```
+    pub fn with_capacity(capacity: impl Into<Capacity>) -> Self {
+        Self::new(IntMap::with_capacity(capacity))
+    }
+
+    /// A handle for monitoring threads, all handles read the same mirror.
+    ///
+    /// ```
+    /// use intmap_rs::MonitoredIntMap;
+    ///
+    /// let mut map = MonitoredIntMap::with_capacity(16);
+    /// let (a, b) = (map.stats_handle(), map.stats_handle());
+    /// map.insert(1u32, ());
+    /// assert_eq!(a.sample(), b.sample());
+    /// ```
+    pub fn stats_handle(&self) -> StatsHandle {
+        StatsHandle { mirror: Arc::clone(&self.mirror) }
+    }
+
+    /// Publishes every stat now, including a displacement rescan.
+    ///
+    /// ```
+    /// use intmap_rs::MonitoredIntMap;
+    ///
+    /// let mut map = MonitoredIntMap::with_capacity(1024);
+    /// let stats = map.stats_handle();
+    /// map.insert(1u32, ());
+    /// map.insert(1025, ());
+    /// assert_eq!(stats.sample().max_displacement, 0);
+    /// map.publish();
+    /// assert_eq!(stats.sample().max_displacement, 1);
+    /// ```
+    pub fn publish(&mut self) {
+        self.mirror.capacity.store(self.map.capacity(), Relaxed);
+        self.mirror.max_displacement.store(self.map.max_displacement(), Relaxed);
+        self.until_rescan = (self.map.capacity() / RESCAN_SHARE).max(1);
+        self.mirror.len.store(self.map.len(), Relaxed);
+    }
+
+    // after a mutation
+    #[inline]
+    fn mutated(&mut self) {
+        self.mirror.len.store(self.map.len(), Relaxed);
+        self.until_rescan -= 1;
+        if self.until_rescan == 0 {
+            self.publish();
+        }
+    }
+
+    /// Inserts the entry and publishes the new `len`, returns the value it replaced, if any.
+    ///
+    /// ```
+    /// use intmap_rs::MonitoredIntMap;
+    ///
+    /// let mut map = MonitoredIntMap::with_capacity(16);
+    /// let stats = map.stats_handle();
+    /// assert_eq!(map.insert(1u32, 'a'), None);
+    /// assert_eq!((map.insert(1, 'b'), stats.len()), (Some('a'), 1));
+    /// ```
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let old = self.map.insert(key, value);
+        self.mutated();
+        old
+    }
+
+    /// Removes the entry and publishes the new `len`, returns its value if the key was present.
+    ///
+    /// ```
+    /// use intmap_rs::MonitoredIntMap;
+    ///
+    /// let mut map = MonitoredIntMap::with_capacity(16);
+    /// let stats = map.stats_handle();
+    /// map.insert(1u32, 'a');
+    /// assert_eq!((map.remove(1), map.remove(1)), (Some('a'), None));
+    /// assert!(stats.is_empty());
+    /// ```
+    pub fn remove(&mut self, key: K) -> Option<V> {
+        let old = self.map.remove(key)?;
+        self.mutated();
+        Some(old)
+    }
+
+    /// The value stored for `key`, if any.
+    ///
+    /// ```
+    /// use intmap_rs::MonitoredIntMap;
+    ///
+    /// let mut map = MonitoredIntMap::with_capacity(16);
+    /// map.insert(1u32, 'a');
+    /// assert_eq!((map.get(1), map.get(2)), (Some(&'a'), None));
+    /// ```
+    pub fn get(&self, key: K) -> Option<&V> {
+        self.map.get(key)
+    }
+
+    /// The value stored for `key`, mutably, if any. Publishes nothing, values aren't monitored.
+    ///
+    /// ```
+    /// use intmap_rs::MonitoredIntMap;
+    ///
+    /// let mut map = MonitoredIntMap::with_capacity(16);
+    /// map.insert(1u32, 10);
+    /// *map.get_mut(1).unwrap() += 1;
+    /// assert_eq!(map.get(1), Some(&11));
+    /// ```
+    pub fn get_mut(&mut self, key: K) -> Option<&mut V> {
+        self.map.get_mut(key)
+    }
+
+    /// Whether an entry is stored for `key`.
+    ///
+    /// ```
+    /// use intmap_rs::MonitoredIntMap;
+    ///
+    /// let mut map = MonitoredIntMap::with_capacity(16);
+    /// map.insert(1u32, ());
+    /// assert!(map.contains(1) && !map.contains(2));
+    /// ```
+    pub fn contains(&self, key: K) -> bool {
+        self.map.contains(key)
+    }
+
+    /// Number of entries stored.
+    ///
+    /// ```
+    /// use intmap_rs::MonitoredIntMap;
+    ///
+    /// let mut map = MonitoredIntMap::with_capacity(16);
+    /// map.insert(1u32, ());
+    /// assert_eq!(map.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Whether the map holds no entries.
+    ///
+    /// ```
+    /// use intmap_rs::MonitoredIntMap;
+    ///
+    /// let mut map = MonitoredIntMap::with_capacity(16);
+    /// assert!(map.is_empty());
+    /// map.insert(1u32, ());
+    /// assert!(!map.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Removes every entry and publishes.
+    ///
+    /// ```
+    /// use intmap_rs::MonitoredIntMap;
+    ///
+    /// let mut map = MonitoredIntMap::with_capacity(16);
+    /// let stats = map.stats_handle();
+    /// map.insert(1u32, ());
+    /// map.clear();
+    /// assert!(map.is_empty() && stats.is_empty());
+    /// ```
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.publish();
+    }
+
+    /// Runs `f` on the map, for the APIs this wrapper doesn't forward, then publishes.
+    ///
+    /// ```
+    /// use intmap_rs::MonitoredIntMap;
+    ///
+    /// let mut map = MonitoredIntMap::with_capacity(16);
+    /// let stats = map.stats_handle();
+    /// map.with_mut(|m| {
+    ///     m.insert(1u32, ());
+    ///     m.insert(2, ());
+    /// });
+    /// assert_eq!(stats.len(), 2);
+    /// ```
+    pub fn with_mut<R>(&mut self, f: impl FnOnce(&mut IntMap<K, V>) -> R) -> R {
+        let result = f(&mut self.map);
+        self.publish();
+        result
+    }
+
+    /// The wrapped map.
+    ///
+    /// ```
+    /// use intmap_rs::MonitoredIntMap;
+    ///
+    /// let mut map = MonitoredIntMap::with_capacity(16);
+    /// map.insert(1u32, ());
+    /// assert_eq!(map.as_inner().keys_to_vec(), [1]);
+    /// ```
+    pub fn as_inner(&self) -> &IntMap<K, V> {
+        &self.map
+    }
+
+    /// Unwraps the map, handles keep the last published stats.
+    ///
+    /// ```
+    /// use intmap_rs::MonitoredIntMap;
+    ///
+    /// let mut map = MonitoredIntMap::with_capacity(16);
+    /// let stats = map.stats_handle();
+    /// map.insert(1u32, ());
+    /// let inner = map.into_inner();
+    /// assert_eq!((inner.len(), stats.len()), (1, 1));
+    /// ```
+    pub fn into_inner(self) -> IntMap<K, V> {
+        self.map
+    }
+}
@@ -0,0 +1,101 @@
+use crate::map::{Iter, Keys, Table, Values};
+use crate::{IntKey, IntMap};
+
+/// A disjoint slot range of a map, see `IntMap::partitions`.
+pub struct Partition<'a, K, V> {
+    table: &'a Table<K, V>,
+    start: usize,
+    end: usize,
+}
+
+impl<'a, K, V> Partition<'a, K, V> {
+    /// Range of table slots covered by the partition.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(64);
+    /// m.insert(1u32, 'a');
+    /// m.insert(40, 'b');
+    /// let parts: Vec<_> = m.partitions(2).collect();
+    /// assert_eq!(parts[0].slots().start, 0);
+    /// assert_eq!(parts[0].slots().end, parts[1].slots().start);
+    /// ```
+    pub fn slots(&self) -> std::ops::Range<usize> {
+        self.start..self.end
+    }
+
+    /// Entries stored in the partition's slots, in table order.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(64);
+    /// m.insert(1u32, 'a');
+    /// m.insert(40, 'b');
+    /// let entries: Vec<_> = m.partitions(2).flat_map(|p| p.iter()).collect();
+    /// assert_eq!(entries, [(&1, &'a'), (&40, &'b')]);
+    /// ```
+    pub fn iter(&self) -> Iter<'a, K, V> {
+        self.table.iter_range(self.start, self.end)
+    }
+
+    /// Keys stored in the partition's slots, in table order.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(64);
+    /// m.insert(1u32, 'a');
+    /// m.insert(40, 'b');
+    /// let keys: Vec<_> = m.partitions(2).flat_map(|p| p.keys()).collect();
+    /// assert_eq!(keys, [&1, &40]);
+    /// ```
+    pub fn keys(&self) -> Keys<'a, K, V> {
+        self.table.keys_range(self.start, self.end)
+    }
+
+    /// Values stored in the partition's slots, in table order.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(64);
+    /// m.insert(1u32, 'a');
+    /// m.insert(40, 'b');
+    /// let values: Vec<_> = m.partitions(2).flat_map(|p| p.values()).collect();
+    /// assert_eq!(values, [&'a', &'b']);
+    /// ```
+    pub fn values(&self) -> Values<'a, K, V> {
+        self.table.values_range(self.start, self.end)
+    }
+}
+
+impl<K: IntKey, V> IntMap<K, V> {
+    /// Splits the table into `n` disjoint slot ranges of about equal size, every entry belongs
+    /// to exactly one of them. Partitions are `Send` if the values are `Sync`, so they can be
+    /// handed to worker threads to scan the map in parallel. `n` is at least 1.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(64);
+    /// for k in 0..40u32 {
+    ///     m.insert(k, k);
+    /// }
+    /// let sums = std::thread::scope(|s| {
+    ///     let parts = m.partitions(4).map(|p| s.spawn(move || p.values().sum::<u32>()));
+    ///     parts.collect::<Vec<_>>().into_iter().map(|h| h.join().unwrap()).collect::<Vec<_>>()
+    /// });
+    /// assert_eq!(sums.iter().sum::<u32>(), (0..40).sum());
+    /// ```
+    pub fn partitions(&self, n: usize) -> impl ExactSizeIterator<Item = Partition<'_, K, V>> {
+        let slots = self.table.capacity();
+        let n = n.clamp(1, slots);
+        (0..n).map(move |i| Partition {
+            table: &self.table,
+            start: slots * i / n,
+            end: slots * (i + 1) / n,
+        })
+    }
+}
@@ -0,0 +1,145 @@
+use crate::profile::Profile;
+use crate::{Capacity, Indexing, IntKey, IntMap, SaturationPolicy, OVERFLOW_SLOTS};
+
+/// Recycles cleared maps by capacity class, so short-lived maps don't hit the allocator
+/// and fault in fresh pages every time.
+pub struct IntMapPool<K, V> {
+    // indexed by log2 of the map's capacity
+    classes: Vec<Vec<IntMap<K, V>>>,
+    max_per_class: usize,
+}
+
+impl<K: IntKey, V> IntMapPool<K, V> {
+    /// A pool keeping at most `max_per_class` idle maps for every capacity.
+    ///
+    /// ```
+    /// use intmap_rs::IntMapPool;
+    ///
+    /// let mut pool = IntMapPool::<u32, u32>::new(2);
+    /// for _ in 0..3 {
+    ///     let map = pool.acquire(16);
+    ///     pool.release(map);
+    /// }
+    /// assert_eq!(pool.len(), 1);
+    /// ```
+    pub fn new(max_per_class: usize) -> Self {
+        Self { classes: Vec::new(), max_per_class }
+    }
+
+    /// Hands out an empty map of the requested capacity, reusing a released one if available.
+    ///
+    /// ```
+    /// use intmap_rs::IntMapPool;
+    ///
+    /// let mut pool = IntMapPool::<u32, u32>::new(2);
+    /// let mut map = pool.acquire(16);
+    /// map.insert(1, 1);
+    /// pool.release(map);
+    /// let map = pool.acquire(10);
+    /// assert!(map.is_empty() && map.capacity() == 16 && pool.is_empty());
+    /// ```
+    pub fn acquire(&mut self, capacity: impl Into<Capacity>) -> IntMap<K, V> {
+        let capacity = capacity.into().get();
+        let class = crate::round_capacity(capacity).trailing_zeros() as usize;
+        self.classes
+            .get_mut(class)
+            .and_then(Vec::pop)
+            .unwrap_or_else(|| IntMap::with_capacity(capacity))
+    }
+
+    /// Clears the map and keeps it for later `acquire` calls, maps over the class limit are dropped.
+    /// The map's settings go back to those of `with_capacity`; maps whose table layout differs,
+    /// exact capacity or a lowered probe limit, are dropped too.
+    ///
+    /// ```
+    /// use intmap_rs::{CapacityPolicy, Config, IntMap, IntMapPool};
+    ///
+    /// let mut pool = IntMapPool::<u32, u32>::new(1);
+    /// let map = pool.acquire(16);
+    /// pool.release(map);
+    /// let map = pool.acquire(16);
+    /// pool.release(map);
+    /// pool.release(IntMap::with_config(&Config::new(10).capacity_policy(CapacityPolicy::Exact)));
+    /// assert_eq!(pool.len(), 1);
+    /// ```
+    pub fn release(&mut self, mut map: IntMap<K, V>) {
+        if !map.has_default_layout() {
+            return;
+        }
+        let class = map.capacity().trailing_zeros() as usize;
+        if self.classes.len() <= class {
+            self.classes.resize_with(class + 1, Vec::new);
+        }
+        if self.classes[class].len() < self.max_per_class {
+            map.clear();
+            map.reset_config();
+            self.classes[class].push(map);
+        }
+    }
+
+    /// Number of idle maps held by the pool.
+    ///
+    /// ```
+    /// use intmap_rs::IntMapPool;
+    ///
+    /// let mut pool = IntMapPool::<u32, u32>::new(2);
+    /// let map = pool.acquire(16);
+    /// pool.release(map);
+    /// let map = pool.acquire(64);
+    /// pool.release(map);
+    /// assert_eq!(pool.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.classes.iter().map(Vec::len).sum()
+    }
+
+    /// Whether the pool holds no idle maps.
+    ///
+    /// ```
+    /// use intmap_rs::IntMapPool;
+    ///
+    /// let mut pool = IntMapPool::<u32, u32>::new(2);
+    /// assert!(pool.is_empty());
+    /// let map = pool.acquire(16);
+    /// pool.release(map);
+    /// assert!(!pool.is_empty());
+    /// ```
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drops every idle map.
+    ///
+    /// ```
+    /// use intmap_rs::IntMapPool;
+    ///
+    /// let mut pool = IntMapPool::<u32, u32>::new(2);
+    /// let map = pool.acquire(16);
+    /// pool.release(map);
+    /// pool.shrink();
+    /// assert!(pool.is_empty());
+    /// ```
+    pub fn shrink(&mut self) {
+        self.classes.clear();
+    }
+}
+
+impl<K: IntKey, V> IntMap<K, V> {
+    // whether the table is laid out as by 'with_capacity', which settings can't change
+    fn has_default_layout(&self) -> bool {
+        matches!(self.indexing, Indexing::Mask(_))
+            && self.table.capacity() == self.capacity() + OVERFLOW_SLOTS
+    }
+
+    // restores the settings of a 'with_capacity' map, on an empty one
+    fn reset_config(&mut self) {
+        self.reserved = None;
+        self.saturation = SaturationPolicy::Overflow;
+        self.canonicalize = None;
+        self.profile = Profile::new();
+        self.set_lazy_clear(false);
+        self.set_nontemporal_clear(false);
+        self.set_constant_time_lookup(false);
+    }
+}
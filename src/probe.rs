@@ -0,0 +1,214 @@
+use crate::map::SearchResult;
+use crate::{IntKey, IntMap};
+
+/// Result of a single search for a key, holds either the position of the entry or the
+/// insertion point of the missing key.
+pub struct Probe<'a, K, V> {
+    map: &'a mut IntMap<K, V>,
+    key: K,
+    result: SearchResult,
+}
+
+impl<'a, K: IntKey, V> Probe<'a, K, V> {
+    #[inline]
+    pub(crate) fn new(map: &'a mut IntMap<K, V>, key: K, result: SearchResult) -> Self {
+        Self { map, key, result }
+    }
+
+    /// The probed key.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(16);
+    /// m.insert(1u32, 10);
+    /// assert_eq!(m.probe(7).key(), 7);
+    /// ```
+    #[inline]
+    pub fn key(&self) -> K {
+        self.key
+    }
+
+    /// Whether the probed key is present.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(16);
+    /// m.insert(1u32, 10);
+    /// assert!(m.probe(1).is_found() && !m.probe(2).is_found());
+    /// ```
+    #[inline]
+    pub fn is_found(&self) -> bool {
+        self.result.is_found()
+    }
+
+    /// The value of the probed key, if present.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(16);
+    /// m.insert(1u32, 10);
+    /// assert_eq!(m.probe(1).get(), Some(&10));
+    /// assert_eq!(m.probe(2).get(), None);
+    /// ```
+    #[inline]
+    pub fn get(&self) -> Option<&V> {
+        match self.result {
+            SearchResult::Found(index) => Some(&self.map.table[index]),
+            SearchResult::NotFound(..) => None,
+        }
+    }
+
+    /// The value of the probed key, mutably, if present.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(16);
+    /// m.insert(1u32, 10);
+    /// let mut probe = m.probe(1);
+    /// *probe.get_mut().unwrap() += 1;
+    /// assert_eq!(probe.get(), Some(&11));
+    /// ```
+    #[inline]
+    pub fn get_mut(&mut self) -> Option<&mut V> {
+        match self.result {
+            SearchResult::Found(index) => Some(&mut self.map.table[index]),
+            SearchResult::NotFound(..) => None,
+        }
+    }
+
+    /// The value of the probed key with the map's lifetime, if present.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(16);
+    /// m.insert(1u32, 10);
+    /// let value = m.probe(1).into_mut().unwrap();
+    /// *value = 20;
+    /// assert_eq!(m.get(1), Some(&20));
+    /// assert!(m.probe(2).into_mut().is_none());
+    /// ```
+    #[inline]
+    pub fn into_mut(self) -> Option<&'a mut V> {
+        match self.result {
+            SearchResult::Found(index) => Some(&mut self.map.table[index]),
+            SearchResult::NotFound(..) => None,
+        }
+    }
+
+    /// Writes `value` for the probed key, returns the previous value if the key was present.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(16);
+    /// m.insert(1u32, 10);
+    /// assert_eq!(m.probe(1).insert(11), Some(10));
+    /// assert_eq!(m.probe(2).insert(20), None);
+    /// assert_eq!(m.get(2), Some(&20));
+    /// ```
+    #[inline]
+    pub fn insert(self, value: V) -> Option<V> {
+        match self.result {
+            SearchResult::Found(index) => {
+                Some(std::mem::replace(&mut self.map.table[index], value))
+            }
+            SearchResult::NotFound(index, distance) => {
+                self.map.assert_not_reserved(self.key);
+                let (index, distance) = self.map.admit_or_panic(self.key, index, distance);
+                self.map.insert_at(index, self.key, value, distance);
+                None
+            }
+        }
+    }
+
+    /// The value of the probed key, inserting `value` first if it's missing.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(16);
+    /// m.insert(1u32, 10);
+    /// *m.probe(1).or_insert(0) += 1;
+    /// *m.probe(2).or_insert(0) += 1;
+    /// assert_eq!((m.get(1), m.get(2)), (Some(&11), Some(&1)));
+    /// ```
+    #[inline]
+    pub fn or_insert(self, value: V) -> &'a mut V {
+        self.or_insert_with(|| value)
+    }
+
+    /// The value of the probed key, inserting the result of `f` first if it's missing. `f` only
+    /// runs for a missing key.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(16);
+    /// m.insert(1u32, 10);
+    /// m.probe(1).or_insert_with(|| unreachable!());
+    /// assert_eq!(*m.probe(2).or_insert_with(|| 20), 20);
+    /// ```
+    #[inline]
+    pub fn or_insert_with<F: FnOnce() -> V>(self, f: F) -> &'a mut V {
+        match self.result {
+            SearchResult::Found(index) => &mut self.map.table[index],
+            SearchResult::NotFound(index, distance) => {
+                self.map.assert_not_reserved(self.key);
+                // before an evicting admit, so a panicking 'f' leaves the map unchanged
+                let value = f();
+                let (index, distance) = self.map.admit_or_panic(self.key, index, distance);
+                // the new entry always lands at the insertion point, displaced ones move further
+                self.map.insert_at(index, self.key, value, distance);
+                &mut self.map.table[index]
+            }
+        }
+    }
+
+    /// Same as `or_insert_with` for a fallible `f`, an error leaves the map unchanged.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(16);
+    /// m.insert(1u32, 10);
+    /// assert_eq!(m.probe(2).or_try_insert_with(|| Err::<u32, _>("no value")), Err("no value"));
+    /// assert!(!m.contains(2));
+    /// assert_eq!(m.probe(2).or_try_insert_with(|| Ok::<_, ()>(20)), Ok(&mut 20));
+    /// ```
+    #[inline]
+    pub fn or_try_insert_with<E, F: FnOnce() -> Result<V, E>>(self, f: F) -> Result<&'a mut V, E> {
+        match self.result {
+            SearchResult::Found(index) => Ok(&mut self.map.table[index]),
+            SearchResult::NotFound(index, distance) => {
+                self.map.assert_not_reserved(self.key);
+                let value = f()?;
+                let (index, distance) = self.map.admit_or_panic(self.key, index, distance);
+                self.map.insert_at(index, self.key, value, distance);
+                Ok(&mut self.map.table[index])
+            }
+        }
+    }
+
+    /// Removes the probed key, returns its value if it was present.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut m = IntMap::with_capacity(16);
+    /// m.insert(1u32, 10);
+    /// assert_eq!(m.probe(1).remove(), Some(10));
+    /// assert_eq!(m.probe(1).remove(), None);
+    /// ```
+    #[inline]
+    pub fn remove(self) -> Option<V> {
+        match self.result {
+            SearchResult::Found(index) => Some(self.map.remove_at(index, self.key)),
+            SearchResult::NotFound(..) => None,
+        }
+    }
+}
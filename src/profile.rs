@@ -0,0 +1,269 @@
+// Profiling mode: with the 'profiling' feature every map times its operations with the CPU's
+// cycle counter and keeps a log2 histogram per operation class, see `IntMap::profile`. Without
+// the feature this is a zero-sized no-op, like the shadow.
+#[cfg(feature = "profiling")]
+use std::sync::atomic::{AtomicU64, Ordering::Relaxed};
+
+/// The operations a map profiles. Calls made from inside another operation count for both,
+/// e.g. the `remove` of an evicting insert.
+#[cfg(feature = "profiling")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OpClass {
+    /// `get`, `get_mut` and `contains`
+    Lookup,
+    /// `insert` and `try_insert`
+    Insert,
+    Remove,
+    Clear,
+}
+
+#[cfg(not(feature = "profiling"))]
+#[derive(Clone, Copy)]
+pub(crate) enum OpClass {
+    Lookup,
+    Insert,
+    Remove,
+    Clear,
+}
+
+// one per bit length of a tick count, 0 to 64
+#[cfg(feature = "profiling")]
+const BUCKETS: usize = 65;
+
+/// The ticks spent in one class of operations, read with `IntMap::profile`.
+///
+/// Ticks are the target's cycle counter: `rdtsc` on x86-64, `cntvct_el0` on AArch64, elsewhere
+/// nanoseconds. They include the counter reads themselves, a few dozen cycles on x86-64.
+/// Durations are kept in power-of-two buckets, so percentiles are upper bounds within a factor
+/// of two.
+#[cfg(feature = "profiling")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpProfile {
+    count: u64,
+    total: u64,
+    // operations by the bit length of their tick count
+    buckets: [u64; BUCKETS],
+}
+
+#[cfg(feature = "profiling")]
+impl OpProfile {
+    /// Number of operations timed.
+    ///
+    /// ```
+    /// use intmap_rs::{IntMap, OpClass};
+    ///
+    /// let mut m = IntMap::with_capacity(16);
+    /// m.insert(1u32, ());
+    /// m.get(1);
+    /// m.get(2);
+    /// let lookups = m.profile(OpClass::Lookup);
+    /// assert_eq!(lookups.count(), 2);
+    /// assert_eq!(m.profile(OpClass::Remove).count(), 0);
+    /// ```
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Ticks spent in all of the operations.
+    ///
+    /// ```
+    /// use intmap_rs::{IntMap, OpClass};
+    ///
+    /// let mut m = IntMap::with_capacity(16);
+    /// m.insert(1u32, ());
+    /// m.get(1);
+    /// m.get(2);
+    /// let lookups = m.profile(OpClass::Lookup);
+    /// assert!(lookups.total_ticks() >= lookups.percentile(0.0));
+    /// ```
+    pub fn total_ticks(&self) -> u64 {
+        self.total
+    }
+
+    /// Ticks per operation, 0 if none ran.
+    ///
+    /// ```
+    /// use intmap_rs::{IntMap, OpClass};
+    ///
+    /// let mut m = IntMap::with_capacity(16);
+    /// m.insert(1u32, ());
+    /// m.get(1);
+    /// m.get(2);
+    /// let lookups = m.profile(OpClass::Lookup);
+    /// assert_eq!(lookups.mean_ticks(), lookups.total_ticks() as f64 / 2.0);
+    /// assert_eq!(m.profile(OpClass::Clear).mean_ticks(), 0.0);
+    /// ```
+    pub fn mean_ticks(&self) -> f64 {
+        self.total as f64 / self.count.max(1) as f64
+    }
+
+    /// Ticks that a `p` share of the operations took at most, `p` in `[0, 1]`; 0 if none ran.
+    ///
+    /// ```
+    /// use intmap_rs::{IntMap, OpClass};
+    ///
+    /// let mut m = IntMap::with_capacity(16);
+    /// m.insert(1u32, ());
+    /// m.get(1);
+    /// m.get(2);
+    /// let lookups = m.profile(OpClass::Lookup);
+    /// assert!(lookups.percentile(0.5) <= lookups.percentile(1.0));
+    /// assert_eq!(m.profile(OpClass::Clear).percentile(0.99), 0);
+    /// ```
+    pub fn percentile(&self, p: f64) -> u64 {
+        let rank = (p.clamp(0.0, 1.0) * self.count as f64).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (bits, &n) in self.buckets.iter().enumerate() {
+            seen += n;
+            if seen >= rank {
+                return if bits == 0 { 0 } else { u64::MAX >> (64 - bits) };
+            }
+        }
+        0
+    }
+}
+
+#[cfg(feature = "profiling")]
+impl std::fmt::Display for OpProfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ops, mean {:.0}, p50 <= {}, p99 <= {}, p99.9 <= {} ticks",
+            self.count,
+            self.mean_ticks(),
+            self.percentile(0.5),
+            self.percentile(0.99),
+            self.percentile(0.999),
+        )
+    }
+}
+
+// Counters are bumped with a relaxed load and store instead of a locked add, which would cost
+// more than the lookups being timed; readers sharing a map, e.g. through `SyncIntMap`, can lose
+// counts.
+#[cfg(feature = "profiling")]
+struct Counters {
+    count: AtomicU64,
+    total: AtomicU64,
+    buckets: [AtomicU64; BUCKETS],
+}
+
+#[cfg(feature = "profiling")]
+impl Default for Counters {
+    fn default() -> Self {
+        Self {
+            count: AtomicU64::new(0),
+            total: AtomicU64::new(0),
+            buckets: std::array::from_fn(|_| AtomicU64::new(0)),
+        }
+    }
+}
+
+#[cfg(feature = "profiling")]
+impl Counters {
+    #[inline]
+    fn record(&self, ticks: u64) {
+        let bump = |counter: &AtomicU64, n: u64| counter.store(counter.load(Relaxed) + n, Relaxed);
+        bump(&self.count, 1);
+        bump(&self.total, ticks);
+        bump(&self.buckets[(u64::BITS - ticks.leading_zeros()) as usize], 1);
+    }
+
+    fn snapshot(&self) -> OpProfile {
+        OpProfile {
+            count: self.count.load(Relaxed),
+            total: self.total.load(Relaxed),
+            buckets: std::array::from_fn(|bits| self.buckets[bits].load(Relaxed)),
+        }
+    }
+
+    fn reset(&self) {
+        self.count.store(0, Relaxed);
+        self.total.store(0, Relaxed);
+        self.buckets.iter().for_each(|n| n.store(0, Relaxed));
+    }
+}
+
+#[cfg(feature = "profiling")]
+#[derive(Default)]
+pub(crate) struct Profile {
+    classes: [Counters; 4],
+}
+
+// A started operation, handed back to 'Profile::stop'. It doesn't borrow the profile, so the
+// map can be mutated in between; an operation that unwinds isn't counted.
+#[cfg(feature = "profiling")]
+pub(crate) struct Timer {
+    class: OpClass,
+    start: u64,
+}
+
+#[cfg(feature = "profiling")]
+impl Profile {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn start(&self, class: OpClass) -> Timer {
+        Timer { class, start: ticks() }
+    }
+
+    #[inline]
+    pub fn stop(&self, timer: Timer) {
+        self.classes[timer.class as usize].record(ticks().wrapping_sub(timer.start));
+    }
+
+    pub fn get(&self, class: OpClass) -> OpProfile {
+        self.classes[class as usize].snapshot()
+    }
+
+    pub fn reset(&self) {
+        self.classes.iter().for_each(Counters::reset);
+    }
+}
+
+#[cfg(feature = "profiling")]
+#[inline(always)]
+fn ticks() -> u64 {
+    #[cfg(all(target_arch = "x86_64", not(miri)))]
+    {
+        // SAFETY: rdtsc is part of the x86-64 baseline
+        unsafe { core::arch::x86_64::_rdtsc() }
+    }
+    #[cfg(all(target_arch = "aarch64", not(miri)))]
+    {
+        let ticks: u64;
+        // SAFETY: the virtual counter is readable from user space on every AArch64 OS we run on
+        unsafe { core::arch::asm!("mrs {}, cntvct_el0", out(reg) ticks, options(nomem, nostack)) };
+        ticks
+    }
+    // Miri runs neither instruction
+    #[cfg(any(not(any(target_arch = "x86_64", target_arch = "aarch64")), miri))]
+    {
+        static EPOCH: std::sync::OnceLock<std::time::Instant> = std::sync::OnceLock::new();
+        EPOCH.get_or_init(std::time::Instant::now).elapsed().as_nanos() as u64
+    }
+}
+
+#[cfg(not(feature = "profiling"))]
+pub(crate) struct Profile;
+
+#[cfg(not(feature = "profiling"))]
+pub(crate) struct Timer;
+
+#[cfg(not(feature = "profiling"))]
+impl Profile {
+    #[inline(always)]
+    pub fn new() -> Self {
+        Profile
+    }
+
+    #[inline(always)]
+    pub fn start(&self, _class: OpClass) -> Timer {
+        Timer
+    }
+
+    #[inline(always)]
+    pub fn stop(&self, _timer: Timer) {}
+}
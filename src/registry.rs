@@ -0,0 +1,223 @@
+use crate::{Capacity, IntKey, IntMap};
+
+/// An id → value registry that hands out the keys itself: `insert_new` takes a key from the free
+/// list of removed ones or else the next never used one, starting at zero.
+///
+/// Keys stay below the capacity, so every entry sits in its home slot and lookups never probe.
+/// Removed keys are reused, an id kept after `remove` may later name a different value.
+pub struct IdRegistry<K, V> {
+    map: IntMap<K, V>,
+    next: K,
+    free: Vec<K>,
+}
+
+impl<K: IntKey, V> IdRegistry<K, V> {
+    /// An empty registry handing out keys below `capacity` rounded up to a power of two.
+    ///
+    /// ```
+    /// use intmap_rs::IdRegistry;
+    ///
+    /// let ids = IdRegistry::<u32, &str>::with_capacity(4);
+    /// assert_eq!((ids.capacity(), ids.len()), (4, 0));
+    /// ```
+    pub fn with_capacity(capacity: impl Into<Capacity>) -> Self {
+        Self { map: IntMap::with_capacity(capacity), next: K::zero(), free: Vec::new() }
+    }
+
+    /// Stores `value` under a fresh key and returns the key, panics if the registry is full.
+    ///
+    /// ```
+    /// use intmap_rs::IdRegistry;
+    ///
+    /// let mut ids = IdRegistry::<u32, &str>::with_capacity(4);
+    /// assert_eq!((ids.insert_new("a"), ids.insert_new("b")), (0, 1));
+    /// assert_eq!(ids.get(1), Some(&"b"));
+    /// ```
+    pub fn insert_new(&mut self, value: V) -> K {
+        self.try_insert_new(value).unwrap_or_else(|_| panic!("registry is full"))
+    }
+
+    /// Like `insert_new`, hands the value back if the registry is full.
+    ///
+    /// ```
+    /// use intmap_rs::IdRegistry;
+    ///
+    /// let mut ids = IdRegistry::<u32, &str>::with_capacity(4);
+    /// for name in ["a", "b", "c", "d"] {
+    ///     ids.insert_new(name);
+    /// }
+    /// assert_eq!(ids.try_insert_new("e"), Err("e"));
+    /// ```
+    pub fn try_insert_new(&mut self, value: V) -> Result<K, V> {
+        let key = match self.free.pop() {
+            Some(key) => key,
+            None if self.map.len() < self.map.capacity() => {
+                let key = self.next;
+                self.next = key + K::one();
+                key
+            }
+            None => return Err(value),
+        };
+        self.map.insert(key, value);
+        Ok(key)
+    }
+
+    /// Removes the entry and puts its key on the free list.
+    ///
+    /// ```
+    /// use intmap_rs::IdRegistry;
+    ///
+    /// let mut ids = IdRegistry::<u32, &str>::with_capacity(4);
+    /// let a = ids.insert_new("a");
+    /// ids.insert_new("b");
+    /// assert_eq!((ids.remove(a), ids.remove(a)), (Some("a"), None));
+    /// assert_eq!(ids.insert_new("c"), a);
+    /// ```
+    pub fn remove(&mut self, key: K) -> Option<V> {
+        let value = self.map.remove(key)?;
+        self.free.push(key);
+        Some(value)
+    }
+
+    /// The value registered under `key`, if any.
+    ///
+    /// ```
+    /// use intmap_rs::IdRegistry;
+    ///
+    /// let mut ids = IdRegistry::<u32, &str>::with_capacity(4);
+    /// let a = ids.insert_new("a");
+    /// assert_eq!((ids.get(a), ids.get(3)), (Some(&"a"), None));
+    /// ```
+    pub fn get(&self, key: K) -> Option<&V> {
+        self.map.get(key)
+    }
+
+    /// The value registered under `key`, mutably, if any.
+    ///
+    /// ```
+    /// use intmap_rs::IdRegistry;
+    ///
+    /// let mut ids = IdRegistry::<u32, &str>::with_capacity(4);
+    /// let a = ids.insert_new("a");
+    /// *ids.get_mut(a).unwrap() = "b";
+    /// assert_eq!(ids.get(a), Some(&"b"));
+    /// ```
+    pub fn get_mut(&mut self, key: K) -> Option<&mut V> {
+        self.map.get_mut(key)
+    }
+
+    /// Whether a value is registered under `key`.
+    ///
+    /// ```
+    /// use intmap_rs::IdRegistry;
+    ///
+    /// let mut ids = IdRegistry::<u32, &str>::with_capacity(4);
+    /// let a = ids.insert_new("a");
+    /// assert!(ids.contains(a) && !ids.contains(a + 1));
+    /// ```
+    pub fn contains(&self, key: K) -> bool {
+        self.map.contains(key)
+    }
+
+    /// Every registered entry, in key order.
+    ///
+    /// ```
+    /// use intmap_rs::IdRegistry;
+    ///
+    /// let mut ids = IdRegistry::<u32, &str>::with_capacity(4);
+    /// ids.insert_new("a");
+    /// ids.insert_new("b");
+    /// assert_eq!(ids.iter().collect::<Vec<_>>(), [(0, &"a"), (1, &"b")]);
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = (K, &V)> {
+        self.map.iter().map(|(k, v)| (*k, v))
+    }
+
+    /// Removes every entry and starts allocating from zero again.
+    ///
+    /// ```
+    /// use intmap_rs::IdRegistry;
+    ///
+    /// let mut ids = IdRegistry::<u32, &str>::with_capacity(4);
+    /// ids.insert_new("a");
+    /// let b = ids.insert_new("b");
+    /// ids.remove(b);
+    /// ids.clear();
+    /// assert_eq!((ids.insert_new("c"), ids.insert_new("d")), (0, 1));
+    /// ```
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.free.clear();
+        self.next = K::zero();
+    }
+
+    /// Number of registered values.
+    ///
+    /// ```
+    /// use intmap_rs::IdRegistry;
+    ///
+    /// let mut ids = IdRegistry::<u32, &str>::with_capacity(4);
+    /// ids.insert_new("a");
+    /// assert_eq!(ids.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Whether no value is registered.
+    ///
+    /// ```
+    /// use intmap_rs::IdRegistry;
+    ///
+    /// let mut ids = IdRegistry::<u32, &str>::with_capacity(4);
+    /// assert!(ids.is_empty());
+    /// ids.insert_new("a");
+    /// assert!(!ids.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Whether every key below the capacity is taken, so `insert_new` would panic.
+    ///
+    /// ```
+    /// use intmap_rs::IdRegistry;
+    ///
+    /// let mut ids = IdRegistry::<u32, &str>::with_capacity(4);
+    /// for name in ["a", "b", "c"] {
+    ///     ids.insert_new(name);
+    /// }
+    /// assert!(!ids.is_full());
+    /// ids.insert_new("d");
+    /// assert!(ids.is_full());
+    /// ```
+    pub fn is_full(&self) -> bool {
+        self.map.len() == self.map.capacity()
+    }
+
+    /// Number of keys the registry hands out, at most.
+    ///
+    /// ```
+    /// use intmap_rs::IdRegistry;
+    ///
+    /// let ids = IdRegistry::<u32, &str>::with_capacity(4);
+    /// assert_eq!(IdRegistry::<u32, ()>::with_capacity(5).capacity(), 8);
+    /// assert_eq!(ids.capacity(), 4);
+    /// ```
+    pub fn capacity(&self) -> usize {
+        self.map.capacity()
+    }
+
+    /// The underlying map, key → value.
+    ///
+    /// ```
+    /// use intmap_rs::IdRegistry;
+    ///
+    /// let mut ids = IdRegistry::<u32, &str>::with_capacity(4);
+    /// let a = ids.insert_new("a");
+    /// assert_eq!(ids.as_inner().get(a), Some(&"a"));
+    /// ```
+    pub fn as_inner(&self) -> &IntMap<K, V> {
+        &self.map
+    }
+}
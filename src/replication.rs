@@ -0,0 +1,281 @@
+use crate::{IntKey, IntMap};
+
+/// A map mutation, e.g. streamed from a primary to replicas that `apply` it to their own
+/// maps. Replicas built with the same `Config` end up with identical tables, including which
+/// entry a `SaturationPolicy::Evict` insert evicts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MapOp<K, V> {
+    Insert(K, V),
+    Remove(K),
+    Clear,
+}
+
+impl<K: IntKey, V> IntMap<K, V> {
+    /// Performs `op`, returns the value it replaced or removed.
+    ///
+    /// ```
+    /// use intmap_rs::{IntMap, MapOp};
+    ///
+    /// let mut replica = IntMap::with_capacity(8);
+    /// for op in [MapOp::Insert(1u32, 'a'), MapOp::Insert(2, 'b'), MapOp::Remove(1)] {
+    ///     replica.apply(op);
+    /// }
+    /// assert_eq!((replica.len(), replica.get(2)), (1, Some(&'b')));
+    /// ```
+    pub fn apply(&mut self, op: MapOp<K, V>) -> Option<V> {
+        match op {
+            MapOp::Insert(key, value) => self.insert(key, value),
+            MapOp::Remove(key) => self.remove(key),
+            MapOp::Clear => {
+                self.clear();
+                None
+            }
+        }
+    }
+}
+
+/// An `IntMap` that records each mutation as a `MapOp`, so a primary can ship them to its
+/// replicas with `take_ops`. Only mutations that change the map are recorded; there's no
+/// `get_mut` since in-place changes couldn't be recorded, insert the new value instead.
+///
+/// ```
+/// use intmap_rs::{IntMap, RecordingIntMap};
+///
+/// let mut primary = RecordingIntMap::with_capacity(8);
+/// let mut replica = IntMap::with_capacity(8);
+/// primary.insert(1u32, 'a');
+/// primary.remove(1);
+/// primary.insert(2, 'b');
+/// for op in primary.take_ops() {
+///     replica.apply(op);
+/// }
+/// assert!(primary.as_inner() == &replica);
+/// ```
+#[cfg(feature = "oplog")]
+pub struct RecordingIntMap<K, V> {
+    map: IntMap<K, V>,
+    ops: Vec<MapOp<K, V>>,
+}
+
+#[cfg(feature = "oplog")]
+impl<K: IntKey, V: Clone> RecordingIntMap<K, V> {
+    /// An empty map of `capacity` recording from the start.
+    ///
+    /// ```
+    /// use intmap_rs::RecordingIntMap;
+    ///
+    /// let mut map = RecordingIntMap::with_capacity(8);
+    /// map.insert(1u32, 'a');
+    /// assert_eq!(map.ops().len(), 1);
+    /// ```
+    pub fn with_capacity(capacity: impl Into<crate::Capacity>) -> Self {
+        Self::from_inner(IntMap::with_capacity(capacity))
+    }
+
+    /// Records from here on, replicas have to start from a copy of `map`.
+    ///
+    /// ```
+    /// use intmap_rs::{IntMap, RecordingIntMap};
+    ///
+    /// let mut inner = IntMap::with_capacity(8);
+    /// inner.insert(1u32, 'a');
+    /// let map = RecordingIntMap::from_inner(inner.clone());
+    /// assert!(map.ops().is_empty() && map.as_inner() == &inner);
+    /// ```
+    pub fn from_inner(map: IntMap<K, V>) -> Self {
+        Self { map, ops: Vec::new() }
+    }
+
+    /// Inserts the entry and records a `MapOp::Insert`, returns the value it replaced, if any.
+    ///
+    /// ```
+    /// use intmap_rs::{MapOp, RecordingIntMap};
+    ///
+    /// let mut map = RecordingIntMap::with_capacity(8);
+    /// assert_eq!((map.insert(1u32, 'a'), map.insert(1, 'b')), (None, Some('a')));
+    /// assert_eq!(map.ops(), [MapOp::Insert(1, 'a'), MapOp::Insert(1, 'b')]);
+    /// ```
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let old = self.map.insert(key, value.clone());
+        self.ops.push(MapOp::Insert(key, value));
+        old
+    }
+
+    /// Removes the entry, returns its value if the key was present. Only a removal that happened is
+    /// recorded.
+    ///
+    /// ```
+    /// use intmap_rs::{MapOp, RecordingIntMap};
+    ///
+    /// let mut map = RecordingIntMap::with_capacity(8);
+    /// map.insert(1u32, 'a');
+    /// assert_eq!((map.remove(1), map.remove(1)), (Some('a'), None));
+    /// assert_eq!(map.ops(), [MapOp::Insert(1, 'a'), MapOp::Remove(1)]);
+    /// ```
+    pub fn remove(&mut self, key: K) -> Option<V> {
+        let old = self.map.remove(key)?;
+        self.ops.push(MapOp::Remove(key));
+        Some(old)
+    }
+
+    /// Removes every entry and records a `MapOp::Clear`.
+    ///
+    /// ```
+    /// use intmap_rs::{MapOp, RecordingIntMap};
+    ///
+    /// let mut map = RecordingIntMap::with_capacity(8);
+    /// map.insert(1u32, 'a');
+    /// map.clear();
+    /// assert_eq!(map.ops().last(), Some(&MapOp::Clear));
+    /// assert!(map.is_empty());
+    /// ```
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.ops.push(MapOp::Clear);
+    }
+
+    /// As `IntMap::retain`, recording a `Remove` per dropped entry.
+    ///
+    /// ```
+    /// use intmap_rs::{MapOp, RecordingIntMap};
+    ///
+    /// let mut map = RecordingIntMap::with_capacity(8);
+    /// map.insert(1u32, 'a');
+    /// map.insert(2, 'b');
+    /// map.take_ops();
+    /// map.retain(|key, _| key == 2);
+    /// assert_eq!(map.ops(), [MapOp::Remove(1)]);
+    /// ```
+    pub fn retain(&mut self, mut f: impl FnMut(K, &V) -> bool) {
+        let ops = &mut self.ops;
+        self.map.retain(|key, value| {
+            let keep = f(key, value);
+            if !keep {
+                ops.push(MapOp::Remove(key));
+            }
+            keep
+        });
+    }
+
+    /// The value stored for `key`, if any.
+    ///
+    /// ```
+    /// use intmap_rs::RecordingIntMap;
+    ///
+    /// let mut map = RecordingIntMap::with_capacity(8);
+    /// map.insert(1u32, 'a');
+    /// assert_eq!((map.get(1), map.get(2)), (Some(&'a'), None));
+    /// ```
+    pub fn get(&self, key: K) -> Option<&V> {
+        self.map.get(key)
+    }
+
+    /// Whether an entry is stored for `key`.
+    ///
+    /// ```
+    /// use intmap_rs::RecordingIntMap;
+    ///
+    /// let mut map = RecordingIntMap::with_capacity(8);
+    /// map.insert(1u32, 'a');
+    /// assert!(map.contains(1) && !map.contains(2));
+    /// ```
+    pub fn contains(&self, key: K) -> bool {
+        self.map.contains(key)
+    }
+
+    /// Every entry, in table order.
+    ///
+    /// ```
+    /// use intmap_rs::RecordingIntMap;
+    ///
+    /// let mut map = RecordingIntMap::with_capacity(8);
+    /// map.insert(2u32, 'b');
+    /// map.insert(1, 'a');
+    /// assert_eq!(map.iter().collect::<Vec<_>>(), [(&1, &'a'), (&2, &'b')]);
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> + '_ {
+        self.map.iter()
+    }
+
+    /// Number of entries stored.
+    ///
+    /// ```
+    /// use intmap_rs::RecordingIntMap;
+    ///
+    /// let mut map = RecordingIntMap::with_capacity(8);
+    /// map.insert(1u32, 'a');
+    /// map.insert(1, 'b');
+    /// assert_eq!(map.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Whether the map holds no entries.
+    ///
+    /// ```
+    /// use intmap_rs::RecordingIntMap;
+    ///
+    /// let mut map = RecordingIntMap::with_capacity(8);
+    /// assert!(map.is_empty());
+    /// map.insert(1u32, 'a');
+    /// assert!(!map.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Ops recorded since the last `take_ops`, oldest first.
+    ///
+    /// ```
+    /// use intmap_rs::{MapOp, RecordingIntMap};
+    ///
+    /// let mut map = RecordingIntMap::with_capacity(8);
+    /// map.insert(1u32, 'a');
+    /// map.insert(2, 'b');
+    /// assert_eq!(map.ops(), [MapOp::Insert(1, 'a'), MapOp::Insert(2, 'b')]);
+    /// ```
+    pub fn ops(&self) -> &[MapOp<K, V>] {
+        &self.ops
+    }
+
+    /// Hands out the ops recorded since the last call, oldest first, and starts a new batch.
+    ///
+    /// ```
+    /// use intmap_rs::RecordingIntMap;
+    ///
+    /// let mut map = RecordingIntMap::with_capacity(8);
+    /// map.insert(1u32, 'a');
+    /// assert_eq!(map.take_ops().len(), 1);
+    /// assert!(map.take_ops().is_empty());
+    /// ```
+    pub fn take_ops(&mut self) -> Vec<MapOp<K, V>> {
+        std::mem::take(&mut self.ops)
+    }
+
+    /// The recorded map.
+    ///
+    /// ```
+    /// use intmap_rs::RecordingIntMap;
+    ///
+    /// let mut map = RecordingIntMap::with_capacity(8);
+    /// map.insert(1u32, 'a');
+    /// assert_eq!(map.as_inner().get(1), Some(&'a'));
+    /// ```
+    pub fn as_inner(&self) -> &IntMap<K, V> {
+        &self.map
+    }
+
+    /// Unwraps the map, dropping the ops not taken yet.
+    ///
+    /// ```
+    /// use intmap_rs::RecordingIntMap;
+    ///
+    /// let mut map = RecordingIntMap::with_capacity(8);
+    /// map.insert(1u32, 'a');
+    /// assert_eq!(map.into_inner().len(), 1);
+    /// ```
+    pub fn into_inner(self) -> IntMap<K, V> {
+        self.map
+    }
+}
@@ -0,0 +1,113 @@
+use crate::{IntKey, IntMap};
+
+// how many slots ahead of the scan the other map's home slots are prefetched, as in 'merge'
+const PROBE_PREFETCH: usize = 16;
+
+// Relations between the key sets of two maps, values are ignored.
+impl<K: IntKey, V> IntMap<K, V> {
+    // whether some key of 'self' is in 'other' ('present') or missing from it, scanning the
+    // slots of 'self' and prefetching the lookups into 'other' when the maps index keys
+    // differently and those land at random
+    fn any_key<V2>(&self, other: &IntMap<K, V2>, present: bool) -> bool {
+        let prefetch = self.indexing != other.indexing;
+        (0..self.table.capacity()).any(|index| {
+            if prefetch {
+                if let Some((&k, _)) = self.table.get_by_slot(index + PROBE_PREFETCH) {
+                    other.prefetch(k);
+                }
+            }
+            self.table.get_by_slot(index).is_some_and(|(&k, _)| other.contains(k) == present)
+        })
+    }
+
+    /// Whether some key is present in both maps. Probes the larger map with the keys of the
+    /// smaller one, prefetched a few keys ahead, and stops at the first common key.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut a = IntMap::with_capacity(16);
+    /// let mut b = IntMap::with_capacity(64);
+    /// a.insert(1u32, 'a');
+    /// b.insert(1, 1.0);
+    /// b.insert(2, 2.0);
+    /// assert!(a.intersects(&b) && b.intersects(&a));
+    /// b.remove(1);
+    /// assert!(!a.intersects(&b));
+    /// ```
+    pub fn intersects<V2>(&self, other: &IntMap<K, V2>) -> bool {
+        if self.len() <= other.len() {
+            self.any_key(other, true)
+        } else {
+            other.any_key(self, true)
+        }
+    }
+
+    /// Whether no key is present in both maps.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut a = IntMap::with_capacity(16);
+    /// let mut b = IntMap::with_capacity(64);
+    /// a.insert(1u32, 'a');
+    /// b.insert(1, 1.0);
+    /// b.insert(2, 2.0);
+    /// assert!(!a.is_disjoint(&b));
+    /// b.remove(1);
+    /// assert!(a.is_disjoint(&b));
+    /// ```
+    pub fn is_disjoint<V2>(&self, other: &IntMap<K, V2>) -> bool {
+        !self.intersects(other)
+    }
+
+    /// Whether every key of `self` is present in `other`.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut a = IntMap::with_capacity(16);
+    /// let mut b = IntMap::with_capacity(64);
+    /// a.insert(1u32, 'a');
+    /// b.insert(1, 1.0);
+    /// b.insert(2, 2.0);
+    /// assert!(a.keys_subset_of(&b) && !b.keys_subset_of(&a));
+    /// ```
+    pub fn keys_subset_of<V2>(&self, other: &IntMap<K, V2>) -> bool {
+        self.len() <= other.len() && !self.any_key(other, false)
+    }
+
+    /// Whether every key of `other` is present in `self`.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut a = IntMap::with_capacity(16);
+    /// let mut b = IntMap::with_capacity(64);
+    /// a.insert(1u32, 'a');
+    /// b.insert(1, 1.0);
+    /// b.insert(2, 2.0);
+    /// assert!(b.keys_superset_of(&a) && !a.keys_superset_of(&b));
+    /// ```
+    pub fn keys_superset_of<V2>(&self, other: &IntMap<K, V2>) -> bool {
+        other.keys_subset_of(self)
+    }
+
+    /// Whether both maps hold the same keys.
+    ///
+    /// ```
+    /// use intmap_rs::IntMap;
+    ///
+    /// let mut a = IntMap::with_capacity(16);
+    /// let mut b = IntMap::with_capacity(64);
+    /// a.insert(1u32, 'a');
+    /// b.insert(1, 1.0);
+    /// b.insert(2, 2.0);
+    /// assert!(!a.keys_equal(&b));
+    /// a.insert(2, 'b');
+    /// assert!(a.keys_equal(&b));
+    /// ```
+    pub fn keys_equal<V2>(&self, other: &IntMap<K, V2>) -> bool {
+        self.len() == other.len() && self.keys_subset_of(other)
+    }
+}
@@ -0,0 +1,49 @@
+// Shadow mode: with the 'shadow' feature every map mirrors its key set into a std HashSet and
+// asserts on each search that the table agrees with it. Values aren't mirrored, they carry no
+// bounds to compare them by. Without the feature this is a zero-sized no-op.
+#[cfg(feature = "shadow")]
+use std::collections::HashSet;
+
+#[derive(Clone, Default)]
+pub(crate) struct Shadow<K> {
+    #[cfg(feature = "shadow")]
+    keys: HashSet<K>,
+    #[cfg(not(feature = "shadow"))]
+    keys: std::marker::PhantomData<K>,
+}
+
+#[cfg(feature = "shadow")]
+impl<K: crate::IntKey> Shadow<K> {
+    #[track_caller]
+    pub fn check(&self, key: K, found: bool, len: usize) {
+        assert_eq!(self.keys.contains(&key), found, "shadow: presence of key {key} diverged");
+        assert_eq!(self.keys.len(), len, "shadow: length diverged");
+    }
+
+    pub fn insert(&mut self, key: K) {
+        assert!(self.keys.insert(key), "shadow: key {key} inserted twice");
+    }
+
+    pub fn remove(&mut self, key: K) {
+        assert!(self.keys.remove(&key), "shadow: removed missing key {key}");
+    }
+
+    pub fn clear(&mut self) {
+        self.keys.clear();
+    }
+}
+
+#[cfg(not(feature = "shadow"))]
+impl<K> Shadow<K> {
+    #[inline(always)]
+    pub fn check(&self, _key: K, _found: bool, _len: usize) {}
+
+    #[inline(always)]
+    pub fn insert(&mut self, _key: K) {}
+
+    #[inline(always)]
+    pub fn remove(&mut self, _key: K) {}
+
+    #[inline(always)]
+    pub fn clear(&mut self) {}
+}
@@ -0,0 +1,127 @@
+use crate::{mix, Capacity, Config, IntKey, IntMap};
+
+// Shards are picked from the map's key mixer under a seed of their own: with the seed of
+// `with_exact_capacity` maps, the keys of one shard would share the high mixer bits those
+// maps index by and crowd a slice of every shard's home slots.
+const SHARD_SEED: u64 = 0x2545_f491_4f6c_dd1d;
+
+/// The shard of `n_shards` that owns `key`, the same for every call and process. `n_shards`
+/// is at least 1.
+///
+/// ```
+/// use intmap_rs::{shard_for, ShardedBuilder};
+///
+/// let mut builder = ShardedBuilder::new(4, 64);
+/// builder.insert(42u64, "answer");
+/// let shards = builder.build();
+/// assert_eq!(shards[shard_for(42u64, 4) as usize].get(42), Some(&"answer"));
+/// ```
+#[inline]
+pub fn shard_for<K: IntKey>(key: K, n_shards: u32) -> u32 {
+    ((mix(key, SHARD_SEED) as u64 * n_shards.max(1) as u64) >> 32) as u32
+}
+
+/// Builds one map per shard from a stream of entries routed with `shard_for`, e.g. to hand
+/// each worker thread the map of the keys it owns.
+pub struct ShardedBuilder<K, V> {
+    shards: Vec<IntMap<K, V>>,
+}
+
+impl<K: IntKey, V> ShardedBuilder<K, V> {
+    /// `n_shards` maps of `shard_capacity` each, `n_shards` is at least 1.
+    ///
+    /// ```
+    /// use intmap_rs::ShardedBuilder;
+    ///
+    /// let mut builder = ShardedBuilder::new(4, 64);
+    /// builder.insert(1u32, 'a');
+    /// assert_eq!(builder.n_shards(), 4);
+    /// assert_eq!(ShardedBuilder::<u32, ()>::new(0, 64).n_shards(), 1);
+    /// ```
+    pub fn new(n_shards: u32, shard_capacity: impl Into<Capacity>) -> Self {
+        Self::with_config(n_shards, &Config::new(shard_capacity))
+    }
+
+    /// `n_shards` maps built from `config`, see `IntMap::with_config`.
+    ///
+    /// ```
+    /// use intmap_rs::{Config, ShardedBuilder};
+    ///
+    /// let builder = ShardedBuilder::<u32, ()>::with_config(2, &Config::new(32).probe_limit(8));
+    /// assert_eq!((builder.shard(1).capacity(), builder.shard(1).probe_limit()), (32, 8));
+    /// ```
+    pub fn with_config(n_shards: u32, config: &Config) -> Self {
+        Self { shards: (0..n_shards.max(1)).map(|_| IntMap::with_config(config)).collect() }
+    }
+
+    /// Number of shards.
+    ///
+    /// ```
+    /// use intmap_rs::ShardedBuilder;
+    ///
+    /// let mut builder = ShardedBuilder::new(4, 64);
+    /// builder.insert(1u32, 'a');
+    /// assert_eq!(builder.n_shards(), 4);
+    /// ```
+    pub fn n_shards(&self) -> u32 {
+        self.shards.len() as u32
+    }
+
+    /// The shard that owns `key`, as `shard_for` with this builder's shard count.
+    ///
+    /// ```
+    /// use intmap_rs::{shard_for, ShardedBuilder};
+    ///
+    /// let builder = ShardedBuilder::<u32, ()>::new(4, 64);
+    /// assert_eq!(builder.shard_for(42), shard_for(42u32, 4) as usize);
+    /// ```
+    #[inline]
+    pub fn shard_for(&self, key: K) -> usize {
+        shard_for(key, self.n_shards()) as usize
+    }
+
+    /// Inserts into the key's shard, returns the previous value.
+    ///
+    /// ```
+    /// use intmap_rs::ShardedBuilder;
+    ///
+    /// let mut builder = ShardedBuilder::new(4, 64);
+    /// assert_eq!(builder.insert(1u32, 'a'), None);
+    /// assert_eq!(builder.insert(1, 'b'), Some('a'));
+    /// assert_eq!(builder.shard(builder.shard_for(1)).get(1), Some(&'b'));
+    /// ```
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let shard = self.shard_for(key);
+        self.shards[shard].insert(key, value)
+    }
+
+    /// The map of one shard, panics if `shard` is out of range.
+    ///
+    /// ```
+    /// use intmap_rs::ShardedBuilder;
+    ///
+    /// let mut builder = ShardedBuilder::new(4, 64);
+    /// builder.insert(1u32, 'a');
+    /// let total: usize = (0..4).map(|shard| builder.shard(shard).len()).sum();
+    /// assert_eq!(total, 1);
+    /// ```
+    pub fn shard(&self, shard: usize) -> &IntMap<K, V> {
+        &self.shards[shard]
+    }
+
+    /// The maps, indexed by shard.
+    ///
+    /// ```
+    /// use intmap_rs::ShardedBuilder;
+    ///
+    /// let mut builder = ShardedBuilder::new(4, 64);
+    /// for key in 0..100u32 {
+    ///     builder.insert(key, key);
+    /// }
+    /// let shards = builder.build();
+    /// assert_eq!(shards.iter().map(|shard| shard.len()).sum::<usize>(), 100);
+    /// ```
+    pub fn build(self) -> Vec<IntMap<K, V>> {
+        self.shards
+    }
+}
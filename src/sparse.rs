@@ -0,0 +1,284 @@
+use crate::{Capacity, IntMap};
+use std::ops::{Add, Mul};
+
+/// A sparse vector of `V` indexed by `u32`, missing entries read as `V::default()`.
+pub struct SparseVec<V> {
+    map: IntMap<u32, V>,
+}
+
+impl<V> SparseVec<V> {
+    /// An empty vector with room for `capacity` stored entries.
+    ///
+    /// ```
+    /// use intmap_rs::SparseVec;
+    ///
+    /// let mut v = SparseVec::with_capacity(16);
+    /// v.set(3, 2.0);
+    /// assert_eq!((v.nnz(), v.get(7)), (1, 0.0));
+    /// ```
+    pub fn with_capacity(capacity: impl Into<Capacity>) -> Self {
+        Self::from_inner(IntMap::with_capacity(capacity))
+    }
+
+    /// Wraps an index → value map.
+    ///
+    /// ```
+    /// use intmap_rs::{IntMap, SparseVec};
+    ///
+    /// let mut m = IntMap::with_capacity(16);
+    /// m.insert(3, 2.0);
+    /// assert_eq!(SparseVec::from_inner(m).get(3), 2.0);
+    /// ```
+    pub fn from_inner(map: IntMap<u32, V>) -> Self {
+        Self { map }
+    }
+
+    /// The underlying index → value map.
+    ///
+    /// ```
+    /// use intmap_rs::SparseVec;
+    ///
+    /// let mut v = SparseVec::with_capacity(16);
+    /// v.set(3, 2.0);
+    /// assert_eq!(v.into_inner().get(3), Some(&2.0));
+    /// ```
+    pub fn into_inner(self) -> IntMap<u32, V> {
+        self.map
+    }
+
+    /// The underlying index → value map.
+    ///
+    /// ```
+    /// use intmap_rs::SparseVec;
+    ///
+    /// let mut v = SparseVec::with_capacity(16);
+    /// v.set(3, 2.0);
+    /// assert_eq!(v.as_inner().keys_to_vec(), [3]);
+    /// ```
+    pub fn as_inner(&self) -> &IntMap<u32, V> {
+        &self.map
+    }
+
+    /// Stores `value` at `index`, returns the previous one.
+    ///
+    /// ```
+    /// use intmap_rs::SparseVec;
+    ///
+    /// let mut v = SparseVec::with_capacity(16);
+    /// v.set(3, 2.0);
+    /// assert_eq!((v.set(3, 5.0), v.set(4, 1.0)), (Some(2.0), None));
+    /// assert_eq!(v.get(3), 5.0);
+    /// ```
+    pub fn set(&mut self, index: u32, value: V) -> Option<V> {
+        self.map.insert(index, value)
+    }
+
+    /// Drops the stored entry at `index`, returns it if there was one. It reads as `V::default()`
+    /// afterwards.
+    ///
+    /// ```
+    /// use intmap_rs::SparseVec;
+    ///
+    /// let mut v = SparseVec::with_capacity(16);
+    /// v.set(3, 2.0);
+    /// assert_eq!((v.remove(3), v.remove(3)), (Some(2.0), None));
+    /// assert_eq!(v.get(3), 0.0);
+    /// ```
+    pub fn remove(&mut self, index: u32) -> Option<V> {
+        self.map.remove(index)
+    }
+
+    /// Number of stored (non-implicit) entries.
+    ///
+    /// ```
+    /// use intmap_rs::SparseVec;
+    ///
+    /// let mut v = SparseVec::with_capacity(16);
+    /// v.set(3, 2.0);
+    /// v.set(5, 0.0);
+    /// assert_eq!(v.nnz(), 2);
+    /// ```
+    pub fn nnz(&self) -> usize {
+        self.map.len()
+    }
+
+    /// The stored entries, in table order.
+    ///
+    /// ```
+    /// use intmap_rs::SparseVec;
+    ///
+    /// let mut v = SparseVec::with_capacity(16);
+    /// v.set(3, 2.0);
+    /// v.set(1, 4.0);
+    /// assert_eq!(v.iter().collect::<Vec<_>>(), [(1, &4.0), (3, &2.0)]);
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &V)> {
+        self.map.iter().map(|(i, v)| (*i, v))
+    }
+
+    /// Drops every stored entry, keeping the capacity.
+    ///
+    /// ```
+    /// use intmap_rs::SparseVec;
+    ///
+    /// let mut v = SparseVec::with_capacity(16);
+    /// v.set(3, 2.0);
+    /// v.clear();
+    /// assert_eq!((v.nnz(), v.get(3)), (0, 0.0));
+    /// ```
+    pub fn clear(&mut self) {
+        self.map.clear()
+    }
+}
+
+impl<V: Copy + Default + Add<Output = V> + Mul<Output = V>> SparseVec<V> {
+    /// Reads the entry at `index`, `V::default()` if it's not stored.
+    ///
+    /// ```
+    /// use intmap_rs::SparseVec;
+    ///
+    /// let mut v = SparseVec::with_capacity(16);
+    /// v.set(3, 2.0);
+    /// assert_eq!((v.get(3), v.get(4)), (2.0, 0.0));
+    /// ```
+    pub fn get(&self, index: u32) -> V {
+        self.map.get(index).copied().unwrap_or_default()
+    }
+
+    /// Adds `value` to the entry at `index`, creating it if needed.
+    ///
+    /// ```
+    /// use intmap_rs::SparseVec;
+    ///
+    /// let mut v = SparseVec::with_capacity(16);
+    /// v.set(3, 2.0);
+    /// v.add_at(3, 1.0);
+    /// v.add_at(4, 1.0);
+    /// assert_eq!((v.get(3), v.get(4)), (3.0, 1.0));
+    /// ```
+    pub fn add_at(&mut self, index: u32, value: V) {
+        self.map.merge_insert(index, value, |old, new| *old = *old + new);
+    }
+
+    /// Dot product of two sparse vectors, probes the denser one for the entries of the other.
+    ///
+    /// ```
+    /// use intmap_rs::SparseVec;
+    ///
+    /// let mut v = SparseVec::with_capacity(16);
+    /// v.set(3, 2.0);
+    /// let mut w = SparseVec::with_capacity(16);
+    /// w.set(3, 4.0);
+    /// w.set(9, 1.0);
+    /// assert_eq!(v.dot(&w), 8.0);
+    /// ```
+    pub fn dot(&self, other: &Self) -> V {
+        self.map.inner_join(&other.map).fold(V::default(), |acc, (_, a, b)| acc + *a * *b)
+    }
+
+    /// Dot product with a dense vector, entries past the end of `dense` count as zero.
+    ///
+    /// ```
+    /// use intmap_rs::SparseVec;
+    ///
+    /// let mut v = SparseVec::with_capacity(16);
+    /// v.set(3, 2.0);
+    /// v.set(10, 1.0);
+    /// assert_eq!(v.dot_dense(&[1.0, 1.0, 1.0, 3.0]), 6.0);
+    /// ```
+    pub fn dot_dense(&self, dense: &[V]) -> V {
+        self.iter()
+            .filter_map(|(i, v)| dense.get(i as usize).map(|d| *v * *d))
+            .fold(V::default(), |acc, x| acc + x)
+    }
+
+    /// `self += a * x`, entries of `x` missing from `self` are inserted.
+    ///
+    /// ```
+    /// use intmap_rs::SparseVec;
+    ///
+    /// let mut v = SparseVec::with_capacity(16);
+    /// v.set(3, 2.0);
+    /// let mut x = SparseVec::with_capacity(16);
+    /// x.set(3, 1.0);
+    /// x.set(4, 1.0);
+    /// v.axpy(2.0, &x);
+    /// assert_eq!((v.get(3), v.get(4)), (4.0, 2.0));
+    /// ```
+    pub fn axpy(&mut self, a: V, x: &Self) {
+        for (i, v) in x.iter() {
+            self.add_at(i, a * *v);
+        }
+    }
+
+    /// Multiplies every stored entry by `a` in a single pass over the values lane.
+    ///
+    /// ```
+    /// use intmap_rs::SparseVec;
+    ///
+    /// let mut v = SparseVec::with_capacity(16);
+    /// v.set(3, 2.0);
+    /// v.set(4, 1.0);
+    /// v.scale(3.0);
+    /// assert_eq!((v.get(3), v.get(4)), (6.0, 3.0));
+    /// ```
+    pub fn scale(&mut self, a: V) {
+        self.map.table.for_each_mut(|_, v| *v = *v * a);
+    }
+
+    /// Adds `values[n]` to the entry at `indices[n]` for every `n`, repeated indices accumulate.
+    ///
+    /// ```
+    /// use intmap_rs::SparseVec;
+    ///
+    /// let mut v = SparseVec::with_capacity(16);
+    /// v.set(3, 2.0);
+    /// v.scatter_add(&[3, 4, 4], &[1.0, 1.0, 1.0]);
+    /// assert_eq!((v.get(3), v.get(4)), (3.0, 2.0));
+    /// ```
+    pub fn scatter_add(&mut self, indices: &[u32], values: &[V]) {
+        assert_eq!(indices.len(), values.len(), "indices and values lengths differ");
+        for (&i, &v) in indices.iter().zip(values) {
+            self.add_at(i, v);
+        }
+    }
+
+    /// Adds every stored entry onto `dense[index]`; panics if an index is out of `dense` bounds.
+    ///
+    /// ```
+    /// use intmap_rs::SparseVec;
+    ///
+    /// let mut v = SparseVec::with_capacity(16);
+    /// v.set(3, 2.0);
+    /// let mut dense = [1.0; 4];
+    /// v.scatter_into(&mut dense);
+    /// assert_eq!(dense, [1.0, 1.0, 1.0, 3.0]);
+    /// ```
+    pub fn scatter_into(&self, dense: &mut [V]) {
+        for (i, v) in self.iter() {
+            dense[i as usize] = dense[i as usize] + *v;
+        }
+    }
+
+    /// Collects the entries at `indices` of `dense` into a sparse vector, skipping `V::default()`.
+    ///
+    /// ```
+    /// use intmap_rs::SparseVec;
+    ///
+    /// let v = SparseVec::gather(&[0.0, 5.0, 0.0, 7.0], &[1, 2, 3]);
+    /// assert_eq!((v.nnz(), v.get(1), v.get(3)), (2, 5.0, 7.0));
+    /// ```
+    pub fn gather(dense: &[V], indices: &[u32]) -> Self
+    where
+        V: PartialEq,
+    {
+        let mut vec = Self::with_capacity((indices.len() as u32).saturating_mul(2).max(1));
+        for &i in indices {
+            let v = dense[i as usize];
+            if v != V::default() {
+                vec.set(i, v);
+            }
+        }
+        vec
+    }
+}
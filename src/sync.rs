@@ -0,0 +1,230 @@
+use crate::{Capacity, IntKey, IntMap};
+use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// An `IntMap` behind a `RwLock`, guards never escape the helpers.
+///
+/// A panic while the lock is held poisons it; the map stays memory safe, so later calls
+/// recover the lock instead of propagating the poison. A panicking `with_write` closure can
+/// leave its own changes half done, `is_poisoned` tells whether that happened.
+pub struct SyncIntMap<K, V> {
+    lock: RwLock<IntMap<K, V>>,
+}
+
+impl<K: IntKey, V> SyncIntMap<K, V> {
+    /// Puts `map` behind the lock.
+    ///
+    /// ```
+    /// use intmap_rs::{IntMap, SyncIntMap};
+    ///
+    /// let mut inner = IntMap::with_capacity(16);
+    /// inner.insert(1u32, 'a');
+    /// assert_eq!(SyncIntMap::new(inner).get_cloned(1), Some('a'));
+    /// ```
+    pub fn new(map: IntMap<K, V>) -> Self {
+        Self { lock: RwLock::new(map) }
+    }
+
+    /// A new, empty map of `capacity` behind the lock.
+    ///
+    /// ```
+    /// use intmap_rs::SyncIntMap;
+    ///
+    /// let map = SyncIntMap::with_capacity(16);
+    /// map.insert(1u32, 'a');
+    /// assert_eq!(map.with_read(|m| m.capacity()), 16);
+    /// ```
+    pub fn with_capacity(capacity: impl Into<Capacity>) -> Self {
+        Self::new(IntMap::with_capacity(capacity))
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, IntMap<K, V>> {
+        self.lock.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, IntMap<K, V>> {
+        self.lock.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// A clone of the value stored for `key`, if any; a reference couldn't outlive the lock.
+    ///
+    /// ```
+    /// use intmap_rs::SyncIntMap;
+    ///
+    /// let map = SyncIntMap::with_capacity(16);
+    /// map.insert(1u32, String::from("a"));
+    /// assert_eq!((map.get_cloned(1).as_deref(), map.get_cloned(2)), (Some("a"), None));
+    /// ```
+    pub fn get_cloned(&self, key: K) -> Option<V>
+    where
+        V: Clone,
+    {
+        self.read().get(key).cloned()
+    }
+
+    /// Whether an entry is stored for `key`.
+    ///
+    /// ```
+    /// use intmap_rs::SyncIntMap;
+    ///
+    /// let map = SyncIntMap::with_capacity(16);
+    /// map.insert(1u32, ());
+    /// assert!(map.contains(1) && !map.contains(2));
+    /// ```
+    pub fn contains(&self, key: K) -> bool {
+        self.read().contains(key)
+    }
+
+    /// Inserts the entry, returns the value it replaced, if any.
+    ///
+    /// ```
+    /// use intmap_rs::SyncIntMap;
+    ///
+    /// let map = SyncIntMap::with_capacity(16);
+    /// std::thread::scope(|s| {
+    ///     for t in 0..4u32 {
+    ///         let map = &map;
+    ///         s.spawn(move || map.insert(t, t));
+    ///     }
+    /// });
+    /// assert_eq!(map.len(), 4);
+    /// ```
+    pub fn insert(&self, key: K, value: V) -> Option<V> {
+        self.write().insert(key, value)
+    }
+
+    /// Removes the entry, returns its value if the key was present.
+    ///
+    /// ```
+    /// use intmap_rs::SyncIntMap;
+    ///
+    /// let map = SyncIntMap::with_capacity(16);
+    /// map.insert(1u32, 'a');
+    /// assert_eq!((map.remove(1), map.remove(1)), (Some('a'), None));
+    /// ```
+    pub fn remove(&self, key: K) -> Option<V> {
+        self.write().remove(key)
+    }
+
+    /// Replaces the value with `new` if it equals `current`, returns the replaced value. A
+    /// mismatch hands back a clone of the actual value, a missing key `Err(None)`.
+    ///
+    /// ```
+    /// use intmap_rs::SyncIntMap;
+    ///
+    /// let map = SyncIntMap::with_capacity(16);
+    /// map.insert(1u32, 10);
+    /// assert_eq!(map.compare_exchange(1, &10, 11), Ok(10));
+    /// assert_eq!(map.compare_exchange(1, &10, 12), Err(Some(11)));
+    /// assert_eq!(map.compare_exchange(2, &10, 12), Err(None));
+    /// ```
+    pub fn compare_exchange(&self, key: K, current: &V, new: V) -> Result<V, Option<V>>
+    where
+        V: PartialEq + Clone,
+    {
+        match self.write().get_mut(key) {
+            Some(value) if value == current => Ok(std::mem::replace(value, new)),
+            Some(value) => Err(Some(value.clone())),
+            None => Err(None),
+        }
+    }
+
+    /// Number of entries stored.
+    ///
+    /// ```
+    /// use intmap_rs::SyncIntMap;
+    ///
+    /// let map = SyncIntMap::with_capacity(16);
+    /// map.insert(1u32, ());
+    /// assert_eq!(map.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.read().len()
+    }
+
+    /// Whether the map holds no entries.
+    ///
+    /// ```
+    /// use intmap_rs::SyncIntMap;
+    ///
+    /// let map = SyncIntMap::with_capacity(16);
+    /// assert!(map.is_empty());
+    /// map.insert(1u32, ());
+    /// assert!(!map.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Runs `f` under the shared lock.
+    ///
+    /// ```
+    /// use intmap_rs::SyncIntMap;
+    ///
+    /// let map = SyncIntMap::with_capacity(16);
+    /// map.insert(1u32, 10);
+    /// map.insert(2, 20);
+    /// assert_eq!(map.with_read(|m| m.values().sum::<u32>()), 30);
+    /// ```
+    pub fn with_read<R>(&self, f: impl FnOnce(&IntMap<K, V>) -> R) -> R {
+        f(&self.read())
+    }
+
+    /// Runs `f` under the exclusive lock.
+    ///
+    /// ```
+    /// use intmap_rs::SyncIntMap;
+    ///
+    /// let map = SyncIntMap::with_capacity(16);
+    /// map.with_write(|m| {
+    ///     m.insert(1u32, 10);
+    ///     m.insert(2, 20);
+    /// });
+    /// assert_eq!(map.len(), 2);
+    /// ```
+    pub fn with_write<R>(&self, f: impl FnOnce(&mut IntMap<K, V>) -> R) -> R {
+        f(&mut self.write())
+    }
+
+    /// Whether a panic happened while the lock was held, since the last `clear_poison`.
+    ///
+    /// ```
+    /// use intmap_rs::SyncIntMap;
+    /// use std::panic::{catch_unwind, AssertUnwindSafe};
+    ///
+    /// let map = SyncIntMap::<u32, u32>::with_capacity(16);
+    /// let _ = catch_unwind(AssertUnwindSafe(|| map.with_write(|_| panic!("oops"))));
+    /// assert!(map.is_poisoned());
+    /// map.insert(1, 1);
+    /// ```
+    pub fn is_poisoned(&self) -> bool {
+        self.lock.is_poisoned()
+    }
+
+    /// Marks the lock as healthy again, e.g. after checking the map following a panic.
+    ///
+    /// ```
+    /// use intmap_rs::SyncIntMap;
+    /// use std::panic::{catch_unwind, AssertUnwindSafe};
+    ///
+    /// let map = SyncIntMap::<u32, u32>::with_capacity(16);
+    /// let _ = catch_unwind(AssertUnwindSafe(|| map.with_write(|_| panic!("oops"))));
+    /// map.clear_poison();
+    /// assert!(!map.is_poisoned());
+    /// ```
+    pub fn clear_poison(&self) {
+        self.lock.clear_poison();
+    }
+
+    /// Unwraps the map, whether or not the lock is poisoned.
+    ///
+    /// ```
+    /// use intmap_rs::SyncIntMap;
+    ///
+    /// let map = SyncIntMap::with_capacity(16);
+    /// map.insert(1u32, 'a');
+    /// assert_eq!(map.into_inner().get(1), Some(&'a'));
+    /// ```
+    pub fn into_inner(self) -> IntMap<K, V> {
+        self.lock.into_inner().unwrap_or_else(PoisonError::into_inner)
+    }
+}
@@ -0,0 +1,280 @@
+use crate::{Capacity, IntKey, IntMap};
+use std::fmt::{self, Debug};
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+
+/// A key tagged with the key space it belongs to, e.g. `Id<Order, u64>` vs `Id<Trade, u64>`.
+/// The tag is a zero-sized marker type and never has to implement anything.
+pub struct Id<Tag, K> {
+    raw: K,
+    _tag: PhantomData<fn() -> Tag>,
+}
+
+impl<Tag, K> Id<Tag, K> {
+    /// Tags `raw` with the key space `Tag`.
+    ///
+    /// ```
+    /// use intmap_rs::Id;
+    ///
+    /// struct Order;
+    /// const FIRST: Id<Order, u64> = Id::new(1);
+    /// assert_eq!(FIRST.raw(), 1);
+    /// ```
+    pub const fn new(raw: K) -> Self {
+        Self { raw, _tag: PhantomData }
+    }
+
+    /// The untagged key.
+    ///
+    /// ```
+    /// use intmap_rs::Id;
+    ///
+    /// struct Trade;
+    /// assert_eq!(Id::<Trade, u32>::new(9).raw(), 9);
+    /// ```
+    pub fn raw(self) -> K {
+        self.raw
+    }
+}
+
+// derives would require the bounds on 'Tag' too
+
+impl<Tag, K: Copy> Clone for Id<Tag, K> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<Tag, K: Copy> Copy for Id<Tag, K> {}
+
+impl<Tag, K: PartialEq> PartialEq for Id<Tag, K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.raw == other.raw
+    }
+}
+
+impl<Tag, K: Eq> Eq for Id<Tag, K> {}
+
+impl<Tag, K: Hash> Hash for Id<Tag, K> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.raw.hash(state)
+    }
+}
+
+impl<Tag, K: Debug> Debug for Id<Tag, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Id({:?})", self.raw)
+    }
+}
+
+/// An `IntMap` that only accepts keys of one key space, see `Id`. Zero-cost over the plain map.
+///
+/// ```compile_fail
+/// use intmap_rs::{Id, TypedIntMap};
+///
+/// struct Order;
+/// struct Trade;
+/// let orders = TypedIntMap::<Order, u64, ()>::with_capacity(8);
+/// orders.get(Id::<Trade, u64>::new(7));
+/// ```
+pub struct TypedIntMap<Tag, K, V> {
+    map: IntMap<K, V>,
+    _tag: PhantomData<fn() -> Tag>,
+}
+
+impl<Tag, K: IntKey, V> TypedIntMap<Tag, K, V> {
+    /// An empty map of `capacity` for keys of the `Tag` key space.
+    ///
+    /// ```
+    /// use intmap_rs::{Id, TypedIntMap};
+    ///
+    /// struct Order;
+    /// let mut orders = TypedIntMap::<Order, u64, &str>::with_capacity(16);
+    /// let id = Id::new(7);
+    /// orders.insert(id, "open");
+    /// assert_eq!(orders.capacity(), 16);
+    /// ```
+    pub fn with_capacity(capacity: impl Into<Capacity>) -> Self {
+        Self::from_inner(IntMap::with_capacity(capacity))
+    }
+
+    /// Types an existing map, its keys become ids of the `Tag` key space.
+    ///
+    /// ```
+    /// use intmap_rs::{Id, IntMap, TypedIntMap};
+    ///
+    /// struct Order;
+    /// let mut inner = IntMap::with_capacity(16);
+    /// inner.insert(7u64, "open");
+    /// let orders = TypedIntMap::<Order, _, _>::from_inner(inner);
+    /// assert_eq!(orders.get(Id::new(7)), Some(&"open"));
+    /// ```
+    pub fn from_inner(map: IntMap<K, V>) -> Self {
+        Self { map, _tag: PhantomData }
+    }
+
+    /// Inserts the entry, returns the value it replaced, if any.
+    ///
+    /// ```
+    /// use intmap_rs::{Id, TypedIntMap};
+    ///
+    /// struct Order;
+    /// let mut orders = TypedIntMap::<Order, u64, &str>::with_capacity(16);
+    /// let id = Id::new(7);
+    /// assert_eq!(orders.insert(id, "open"), None);
+    /// assert_eq!(orders.insert(id, "filled"), Some("open"));
+    /// ```
+    pub fn insert(&mut self, key: Id<Tag, K>, value: V) -> Option<V> {
+        self.map.insert(key.raw, value)
+    }
+
+    /// Removes the entry, returns its value if the key was present.
+    ///
+    /// ```
+    /// use intmap_rs::{Id, TypedIntMap};
+    ///
+    /// struct Order;
+    /// let mut orders = TypedIntMap::<Order, u64, &str>::with_capacity(16);
+    /// let id = Id::new(7);
+    /// orders.insert(id, "open");
+    /// assert_eq!((orders.remove(id), orders.remove(id)), (Some("open"), None));
+    /// ```
+    pub fn remove(&mut self, key: Id<Tag, K>) -> Option<V> {
+        self.map.remove(key.raw)
+    }
+
+    /// The value stored for `key`, if any.
+    ///
+    /// ```
+    /// use intmap_rs::{Id, TypedIntMap};
+    ///
+    /// struct Order;
+    /// let mut orders = TypedIntMap::<Order, u64, &str>::with_capacity(16);
+    /// let id = Id::new(7);
+    /// orders.insert(id, "open");
+    /// assert_eq!((orders.get(id), orders.get(Id::new(8))), (Some(&"open"), None));
+    /// ```
+    pub fn get(&self, key: Id<Tag, K>) -> Option<&V> {
+        self.map.get(key.raw)
+    }
+
+    /// The value stored for `key`, mutably, if any.
+    ///
+    /// ```
+    /// use intmap_rs::{Id, TypedIntMap};
+    ///
+    /// struct Order;
+    /// let mut orders = TypedIntMap::<Order, u64, &str>::with_capacity(16);
+    /// let id = Id::new(7);
+    /// orders.insert(id, "open");
+    /// *orders.get_mut(id).unwrap() = "filled";
+    /// assert_eq!(orders.get(id), Some(&"filled"));
+    /// ```
+    pub fn get_mut(&mut self, key: Id<Tag, K>) -> Option<&mut V> {
+        self.map.get_mut(key.raw)
+    }
+
+    /// Whether an entry is stored for `key`.
+    ///
+    /// ```
+    /// use intmap_rs::{Id, TypedIntMap};
+    ///
+    /// struct Order;
+    /// let mut orders = TypedIntMap::<Order, u64, &str>::with_capacity(16);
+    /// let id = Id::new(7);
+    /// orders.insert(id, "open");
+    /// assert!(orders.contains(id) && !orders.contains(Id::new(8)));
+    /// ```
+    pub fn contains(&self, key: Id<Tag, K>) -> bool {
+        self.map.contains(key.raw)
+    }
+
+    /// Every entry with its typed key, in table order.
+    ///
+    /// ```
+    /// use intmap_rs::{Id, TypedIntMap};
+    ///
+    /// struct Order;
+    /// let mut orders = TypedIntMap::<Order, u64, &str>::with_capacity(16);
+    /// let id = Id::new(7);
+    /// orders.insert(id, "open");
+    /// assert_eq!(orders.iter().collect::<Vec<_>>(), [(id, &"open")]);
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = (Id<Tag, K>, &V)> + '_ {
+        self.map.iter().map(|(k, v)| (Id::new(*k), v))
+    }
+
+    /// Number of entries stored.
+    ///
+    /// ```
+    /// use intmap_rs::{Id, TypedIntMap};
+    ///
+    /// struct Order;
+    /// let mut orders = TypedIntMap::<Order, u64, &str>::with_capacity(16);
+    /// let id = Id::new(7);
+    /// orders.insert(id, "open");
+    /// assert_eq!(orders.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Whether the map holds no entries.
+    ///
+    /// ```
+    /// use intmap_rs::{Id, TypedIntMap};
+    ///
+    /// struct Order;
+    /// let mut orders = TypedIntMap::<Order, u64, &str>::with_capacity(16);
+    /// let id = Id::new(7);
+    /// assert!(orders.is_empty());
+    /// orders.insert(id, "open");
+    /// assert!(!orders.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Number of home slots, see `IntMap::capacity`.
+    ///
+    /// ```
+    /// use intmap_rs::TypedIntMap;
+    ///
+    /// struct Order;
+    /// let orders = TypedIntMap::<Order, u64, &str>::with_capacity(10);
+    /// assert_eq!(orders.capacity(), 16);
+    /// ```
+    pub fn capacity(&self) -> usize {
+        self.map.capacity()
+    }
+
+    /// The untyped map, for the APIs this wrapper doesn't forward.
+    ///
+    /// ```
+    /// use intmap_rs::{Id, TypedIntMap};
+    ///
+    /// struct Order;
+    /// let mut orders = TypedIntMap::<Order, u64, &str>::with_capacity(16);
+    /// let id = Id::new(7);
+    /// orders.insert(id, "open");
+    /// assert_eq!(orders.as_inner().keys_to_vec(), [7]);
+    /// ```
+    pub fn as_inner(&self) -> &IntMap<K, V> {
+        &self.map
+    }
+
+    /// Drops the key types, returns the untyped map.
+    ///
+    /// ```
+    /// use intmap_rs::{Id, TypedIntMap};
+    ///
+    /// struct Order;
+    /// let mut orders = TypedIntMap::<Order, u64, &str>::with_capacity(16);
+    /// let id = Id::new(7);
+    /// orders.insert(id, "open");
+    /// assert_eq!(orders.into_inner().get(7), Some(&"open"));
+    /// ```
+    pub fn into_inner(self) -> IntMap<K, V> {
+        self.map
+    }
+}
@@ -0,0 +1,273 @@
+use crate::{Capacity, IntKey, IntMap};
+use std::panic::{self, AssertUnwindSafe};
+
+/// An `IntMap` that tracks the aggregate `weight(value)` of its values, e.g. the heap bytes of
+/// `Vec`/`String` payloads, and keeps it within a budget.
+///
+/// An insert that pushes the total over the budget evicts other entries until it fits again;
+/// victims are picked by a clock hand sweeping the table slots, so every entry gets evicted
+/// about as often, regardless of its key. Evicted entries go to the eviction hook, if any.
+/// Values are only reachable through methods that weigh them again after a change.
+pub struct WeightedIntMap<K, V> {
+    map: IntMap<K, V>,
+    weight: fn(&V) -> usize,
+    total: usize,
+    budget: usize,
+    // next slot the clock hand looks at
+    hand: usize,
+    on_evict: Option<Box<dyn FnMut(K, V)>>,
+}
+
+impl<K: IntKey, V> WeightedIntMap<K, V> {
+    /// An empty map of `capacity` whose values may weigh `budget` in total, by `weight`.
+    ///
+    /// ```
+    /// use intmap_rs::WeightedIntMap;
+    ///
+    /// let mut map = WeightedIntMap::with_budget(16, 8, String::len);
+    /// map.insert(1u32, String::from("abc"));
+    /// assert_eq!((map.budget(), map.total_weight()), (8, 3));
+    /// ```
+    pub fn with_budget(
+        capacity: impl Into<Capacity>,
+        budget: usize,
+        weight: fn(&V) -> usize,
+    ) -> Self {
+        Self {
+            map: IntMap::with_capacity(capacity),
+            weight,
+            total: 0,
+            budget,
+            hand: 0,
+            on_evict: None,
+        }
+    }
+
+    /// Called with every entry evicted to stay within the budget, instead of dropping it.
+    ///
+    /// ```
+    /// use intmap_rs::WeightedIntMap;
+    /// use std::{cell::RefCell, rc::Rc};
+    ///
+    /// let mut map = WeightedIntMap::with_budget(16, 4, String::len);
+    /// let evicted = Rc::new(RefCell::new(Vec::new()));
+    /// let sink = Rc::clone(&evicted);
+    /// map.set_eviction_hook(move |key, _| sink.borrow_mut().push(key));
+    /// map.insert(1u32, String::from("abc"));
+    /// map.insert(2, String::from("de"));
+    /// assert_eq!(*evicted.borrow(), [1]);
+    /// ```
+    pub fn set_eviction_hook(&mut self, hook: impl FnMut(K, V) + 'static) {
+        self.on_evict = Some(Box::new(hook));
+    }
+
+    /// Inserts the entry, then evicts other entries while the total weight exceeds the budget.
+    /// An entry heavier than the whole budget is kept alone. Returns the previous value.
+    ///
+    /// ```
+    /// use intmap_rs::WeightedIntMap;
+    ///
+    /// let mut map = WeightedIntMap::with_budget(16, 8, String::len);
+    /// map.insert(1u32, String::from("abc"));
+    /// map.insert(2, String::from("defg"));
+    /// assert_eq!(map.total_weight(), 7);
+    /// map.insert(3, String::from("hi"));
+    /// assert!(map.get(1).is_none() && map.total_weight() == 6);
+    /// map.insert(4, String::from("0123456789"));
+    /// assert_eq!((map.len(), map.total_weight()), (1, 10));
+    /// ```
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        // counted only once the insert returns, it panics when the table is full
+        let weight = (self.weight)(&value);
+        let old = self.map.insert(key, value);
+        self.total = self.total + weight - old.as_ref().map_or(0, self.weight);
+        self.enforce_budget(key);
+        old
+    }
+
+    /// Removes the entry, returns its value if the key was present.
+    ///
+    /// ```
+    /// use intmap_rs::WeightedIntMap;
+    ///
+    /// let mut map = WeightedIntMap::with_budget(16, 8, String::len);
+    /// map.insert(1u32, String::from("abc"));
+    /// assert_eq!(map.remove(1).as_deref(), Some("abc"));
+    /// assert_eq!((map.remove(1), map.total_weight()), (None, 0));
+    /// ```
+    pub fn remove(&mut self, key: K) -> Option<V> {
+        let value = self.map.remove(key)?;
+        self.total -= (self.weight)(&value);
+        Some(value)
+    }
+
+    /// The value stored for `key`, if any.
+    ///
+    /// ```
+    /// use intmap_rs::WeightedIntMap;
+    ///
+    /// let mut map = WeightedIntMap::with_budget(16, 8, String::len);
+    /// map.insert(1u32, String::from("abc"));
+    /// assert_eq!(map.get(1).map(String::as_str), Some("abc"));
+    /// assert!(map.get(2).is_none());
+    /// ```
+    pub fn get(&self, key: K) -> Option<&V> {
+        self.map.get(key)
+    }
+
+    /// Runs `f` on the value and weighs it again, which may evict other entries. Returns false
+    /// if the key is missing.
+    ///
+    /// ```
+    /// use intmap_rs::WeightedIntMap;
+    ///
+    /// let mut map = WeightedIntMap::with_budget(16, 8, String::len);
+    /// map.insert(1u32, String::from("abc"));
+    /// assert!(map.update(1, |s| s.push_str("de")));
+    /// assert_eq!(map.total_weight(), 5);
+    /// assert!(!map.update(2, |s| s.clear()));
+    /// ```
+    pub fn update(&mut self, key: K, f: impl FnOnce(&mut V)) -> bool {
+        let Some(value) = self.map.get_mut(key) else {
+            return false;
+        };
+        let before = (self.weight)(value);
+        // weighed again even if 'f' unwinds, so the total stays right
+        let result = panic::catch_unwind(AssertUnwindSafe(|| f(value)));
+        self.total = self.total - before + (self.weight)(value);
+        if let Err(payload) = result {
+            panic::resume_unwind(payload);
+        }
+        self.enforce_budget(key);
+        true
+    }
+
+    /// Sets a new budget and evicts entries until it's met.
+    ///
+    /// ```
+    /// use intmap_rs::WeightedIntMap;
+    ///
+    /// let mut map = WeightedIntMap::with_budget(16, 8, String::len);
+    /// map.insert(1u32, String::from("abc"));
+    /// map.insert(2, String::from("de"));
+    /// map.set_budget(2);
+    /// assert_eq!((map.len(), map.total_weight()), (1, 2));
+    /// ```
+    pub fn set_budget(&mut self, budget: usize) {
+        self.budget = budget;
+        while self.total > self.budget && self.evict_one(None) {}
+    }
+
+    /// The total weight the values may have.
+    ///
+    /// ```
+    /// use intmap_rs::WeightedIntMap;
+    ///
+    /// let mut map = WeightedIntMap::with_budget(16, 8, String::len);
+    /// map.insert(1u32, String::from("abc"));
+    /// map.set_budget(100);
+    /// assert_eq!(map.budget(), 100);
+    /// ```
+    pub fn budget(&self) -> usize {
+        self.budget
+    }
+
+    /// Sum of the weights of all values.
+    ///
+    /// ```
+    /// use intmap_rs::WeightedIntMap;
+    ///
+    /// let mut map = WeightedIntMap::with_budget(16, 8, String::len);
+    /// map.insert(1u32, String::from("abc"));
+    /// map.insert(2, String::from("de"));
+    /// assert_eq!(map.total_weight(), 5);
+    /// ```
+    pub fn total_weight(&self) -> usize {
+        self.total
+    }
+
+    /// Number of entries stored.
+    ///
+    /// ```
+    /// use intmap_rs::WeightedIntMap;
+    ///
+    /// let mut map = WeightedIntMap::with_budget(16, 8, String::len);
+    /// map.insert(1u32, String::from("abc"));
+    /// map.insert(2, String::new());
+    /// assert_eq!(map.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Whether the map holds no entries.
+    ///
+    /// ```
+    /// use intmap_rs::WeightedIntMap;
+    ///
+    /// let mut map = WeightedIntMap::with_budget(16, 8, String::len);
+    /// map.insert(1u32, String::from("abc"));
+    /// assert!(!map.is_empty());
+    /// map.remove(1);
+    /// assert!(map.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Every entry, in table order.
+    ///
+    /// ```
+    /// use intmap_rs::WeightedIntMap;
+    ///
+    /// let mut map = WeightedIntMap::with_budget(16, 8, String::len);
+    /// map.insert(1u32, String::from("abc"));
+    /// map.insert(2, String::from("de"));
+    /// let keys: Vec<_> = map.iter().map(|(key, value)| (key, value.len())).collect();
+    /// assert_eq!(keys, [(1, 3), (2, 2)]);
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = (K, &V)> {
+        self.map.iter().map(|(k, v)| (*k, v))
+    }
+
+    /// Unwraps the map, dropping the eviction hook.
+    ///
+    /// ```
+    /// use intmap_rs::WeightedIntMap;
+    ///
+    /// let mut map = WeightedIntMap::with_budget(16, 8, String::len);
+    /// map.insert(1u32, String::from("abc"));
+    /// assert_eq!(map.into_inner().get(1).map(String::len), Some(3));
+    /// ```
+    pub fn into_inner(self) -> IntMap<K, V> {
+        self.map
+    }
+
+    fn enforce_budget(&mut self, keep: K) {
+        while self.total > self.budget && self.evict_one(Some(keep)) {}
+    }
+
+    // evicts the next entry under the clock hand other than 'keep', false if there's none
+    fn evict_one(&mut self, keep: Option<K>) -> bool {
+        let slots = self.map.table.capacity();
+        for _ in 0..slots {
+            let index = self.hand;
+            self.hand = (self.hand + 1) % slots;
+            let Some(key) = self.map.get_by_slot(index).map(|(k, _)| k) else {
+                continue;
+            };
+            if Some(key) == keep {
+                continue;
+            }
+            // the backward shift may move an unvisited entry into 'index', look at it again
+            self.hand = index;
+            let value = self.map.remove(key).unwrap();
+            self.total -= (self.weight)(&value);
+            if let Some(hook) = &mut self.on_evict {
+                hook(key, value);
+            }
+            return true;
+        }
+        false
+    }
+}
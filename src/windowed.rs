@@ -0,0 +1,224 @@
+use crate::{Capacity, IntKey, IntMap};
+
+/// Keeps only the keys in `[max_key - window, max_key]`, where `max_key` is the largest key
+/// inserted so far; entries that fall out of the window are dropped on insert. Meant for data
+/// keyed by a mostly increasing sequence number, e.g. ticks.
+pub struct WindowedIntMap<K, V> {
+    map: IntMap<K, V>,
+    window: K,
+    max_key: Option<K>,
+    // every stored key is at least 'low'
+    low: K,
+}
+
+impl<K: IntKey, V> WindowedIntMap<K, V> {
+    /// A map keeping keys at most `window` below the largest one, `window` must not be negative.
+    /// `capacity` should leave room for `window + 1` keys.
+    ///
+    /// ```
+    /// use intmap_rs::WindowedIntMap;
+    ///
+    /// let mut ticks = WindowedIntMap::with_capacity(16, 10u64);
+    /// ticks.insert(100, 'a').unwrap();
+    /// assert_eq!((ticks.window(), ticks.window_start()), (10, 90));
+    /// ```
+    pub fn with_capacity(capacity: impl Into<Capacity>, window: K) -> Self {
+        assert!(window >= K::zero(), "window must not be negative");
+        Self { map: IntMap::with_capacity(capacity), window, max_key: None, low: K::min_value() }
+    }
+
+    /// Inserts the entry and evicts the keys that fell out of the window, returns the previous
+    /// value. A key already below the window is not stored and its value is handed back.
+    ///
+    /// ```
+    /// use intmap_rs::WindowedIntMap;
+    ///
+    /// let mut ticks = WindowedIntMap::with_capacity(16, 10u64);
+    /// ticks.insert(100, 'a').unwrap();
+    /// assert_eq!(ticks.insert(100, 'b'), Ok(Some('a')));
+    /// assert_eq!(ticks.insert(111, 'c'), Ok(None));
+    /// assert!(!ticks.contains(100));
+    /// assert_eq!(ticks.insert(95, 'd'), Err('d'));
+    /// ```
+    pub fn insert(&mut self, key: K, value: V) -> Result<Option<V>, V> {
+        if key < self.low {
+            return Err(value);
+        }
+        if self.max_key.is_none_or(|max| key > max) {
+            self.max_key = Some(key);
+            self.advance(key.checked_sub(&self.window).unwrap_or(K::min_value()));
+        }
+        Ok(self.map.insert(key, value))
+    }
+
+    // evicts the keys below 'low', one probe per skipped key or a full scan if that's cheaper
+    fn advance(&mut self, low: K) {
+        if low <= self.low {
+            return;
+        }
+        let skipped = low.checked_sub(&self.low).and_then(|n| n.to_usize());
+        match skipped {
+            Some(n) if n <= self.map.len() => {
+                let mut key = self.low;
+                while key < low {
+                    self.map.remove(key);
+                    key = key + K::one();
+                }
+            }
+            _ => self.map.retain(|key, _| key >= low),
+        }
+        self.low = low;
+    }
+
+    /// The value stored for `key`, if any.
+    ///
+    /// ```
+    /// use intmap_rs::WindowedIntMap;
+    ///
+    /// let mut ticks = WindowedIntMap::with_capacity(16, 10u64);
+    /// ticks.insert(100, 'a').unwrap();
+    /// assert_eq!((ticks.get(100), ticks.get(101)), (Some(&'a'), None));
+    /// ```
+    pub fn get(&self, key: K) -> Option<&V> {
+        self.map.get(key)
+    }
+
+    /// The value stored for `key`, mutably, if any.
+    ///
+    /// ```
+    /// use intmap_rs::WindowedIntMap;
+    ///
+    /// let mut ticks = WindowedIntMap::with_capacity(16, 10u64);
+    /// ticks.insert(100, 'a').unwrap();
+    /// *ticks.get_mut(100).unwrap() = 'b';
+    /// assert_eq!(ticks.get(100), Some(&'b'));
+    /// ```
+    pub fn get_mut(&mut self, key: K) -> Option<&mut V> {
+        self.map.get_mut(key)
+    }
+
+    /// Removes the entry, returns its value if the key was present. The window doesn't move.
+    ///
+    /// ```
+    /// use intmap_rs::WindowedIntMap;
+    ///
+    /// let mut ticks = WindowedIntMap::with_capacity(16, 10u64);
+    /// ticks.insert(100, 'a').unwrap();
+    /// assert_eq!((ticks.remove(100), ticks.remove(100)), (Some('a'), None));
+    /// assert_eq!(ticks.max_key(), Some(100));
+    /// ```
+    pub fn remove(&mut self, key: K) -> Option<V> {
+        self.map.remove(key)
+    }
+
+    /// Whether an entry is stored for `key`.
+    ///
+    /// ```
+    /// use intmap_rs::WindowedIntMap;
+    ///
+    /// let mut ticks = WindowedIntMap::with_capacity(16, 10u64);
+    /// ticks.insert(100, 'a').unwrap();
+    /// assert!(ticks.contains(100) && !ticks.contains(99));
+    /// ```
+    pub fn contains(&self, key: K) -> bool {
+        self.map.contains(key)
+    }
+
+    /// Largest key ever inserted.
+    ///
+    /// ```
+    /// use intmap_rs::WindowedIntMap;
+    ///
+    /// let mut ticks = WindowedIntMap::with_capacity(16, 10u64);
+    /// ticks.insert(100, 'a').unwrap();
+    /// ticks.insert(95, 'b').unwrap();
+    /// assert_eq!(ticks.max_key(), Some(100));
+    /// ```
+    pub fn max_key(&self) -> Option<K> {
+        self.max_key
+    }
+
+    /// Smallest key the window currently accepts.
+    ///
+    /// ```
+    /// use intmap_rs::WindowedIntMap;
+    ///
+    /// let mut ticks = WindowedIntMap::with_capacity(16, 10u64);
+    /// ticks.insert(100, 'a').unwrap();
+    /// ticks.insert(105, 'b').unwrap();
+    /// assert_eq!(ticks.window_start(), 95);
+    /// ```
+    pub fn window_start(&self) -> K {
+        self.low
+    }
+
+    /// How far below the largest key the window reaches.
+    ///
+    /// ```
+    /// use intmap_rs::WindowedIntMap;
+    ///
+    /// let mut ticks = WindowedIntMap::with_capacity(16, 10u64);
+    /// ticks.insert(100, 'a').unwrap();
+    /// assert_eq!(ticks.window(), 10);
+    /// ```
+    pub fn window(&self) -> K {
+        self.window
+    }
+
+    /// Every entry, in table order.
+    ///
+    /// ```
+    /// use intmap_rs::WindowedIntMap;
+    ///
+    /// let mut ticks = WindowedIntMap::with_capacity(16, 10u64);
+    /// ticks.insert(100, 'a').unwrap();
+    /// ticks.insert(101, 'b').unwrap();
+    /// assert_eq!(ticks.iter().collect::<Vec<_>>(), [(100, &'a'), (101, &'b')]);
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = (K, &V)> {
+        self.map.iter().map(|(k, v)| (*k, v))
+    }
+
+    /// Number of entries stored.
+    ///
+    /// ```
+    /// use intmap_rs::WindowedIntMap;
+    ///
+    /// let mut ticks = WindowedIntMap::with_capacity(16, 10u64);
+    /// ticks.insert(100, 'a').unwrap();
+    /// ticks.insert(105, 'b').unwrap();
+    /// ticks.insert(120, 'c').unwrap();
+    /// assert_eq!(ticks.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Whether the map holds no entries.
+    ///
+    /// ```
+    /// use intmap_rs::WindowedIntMap;
+    ///
+    /// let mut ticks = WindowedIntMap::with_capacity(16, 10u64);
+    /// ticks.insert(100, 'a').unwrap();
+    /// assert!(!ticks.is_empty());
+    /// ticks.remove(100);
+    /// assert!(ticks.is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Number of home slots, see `IntMap::capacity`.
+    ///
+    /// ```
+    /// use intmap_rs::WindowedIntMap;
+    ///
+    /// let mut ticks = WindowedIntMap::with_capacity(16, 10u64);
+    /// ticks.insert(100, 'a').unwrap();
+    /// assert_eq!(ticks.capacity(), 16);
+    /// ```
+    pub fn capacity(&self) -> usize {
+        self.map.capacity()
+    }
+}
@@ -0,0 +1,109 @@
+// Differential tests against BTreeMap over random op sequences.
+//
+// These complement the libfuzzer target in `fuzz/` and run as part of plain `cargo test`,
+// every case is driven by a fixed seed so failures are reproducible.
+use intmap_rs::IntMap;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::BTreeMap;
+
+// far fewer under Miri, which runs the suite a few orders of magnitude slower
+const SEEDS: u64 = if cfg!(miri) { 2 } else { 32 };
+const OPS: usize = if cfg!(miri) { 200 } else { 2_000 };
+
+#[derive(Debug)]
+enum Op<K> {
+    Insert(K, u32),
+    Get(K),
+    GetMut(K, u32),
+    Remove(K),
+    Contains(K),
+    Clear,
+}
+
+macro_rules! differential {
+    ($name:ident, $key:ty) => {
+        #[test]
+        fn $name() {
+            for seed in 0..SEEDS {
+                let mut rng = StdRng::seed_from_u64(seed);
+                let capacity = 1u32 << rng.gen_range(1..10);
+
+                // a pool of at most capacity/2 distinct keys keeps probe chains well below the limit
+                // while still colliding heavily: extremes, keys sharing the low bits, random keys
+                let mut pool: Vec<$key> = vec![<$key>::MIN, <$key>::MAX, 0, 1, <$key>::MAX - 1];
+                if <$key>::MIN != 0 {
+                    pool.push(<$key>::MIN + 1);
+                    pool.push((0 as $key).wrapping_sub(1));
+                }
+                while pool.len() < capacity as usize / 2 {
+                    let key: $key = match rng.gen_range(0..3) {
+                        0 => rng.gen(),
+                        1 => rng.gen::<$key>() & !(capacity as $key - 1),
+                        _ => rng.gen_range(0..capacity as $key * 2),
+                    };
+                    pool.push(key);
+                }
+                pool.sort_unstable();
+                pool.dedup();
+                pool.truncate((capacity as usize / 2).max(1));
+
+                // every fourth seed reduces keys with fastrange onto a non-power-of-two capacity
+                let mut map: IntMap<$key, u32> = if seed % 4 == 3 {
+                    IntMap::with_exact_capacity(capacity - capacity / 4)
+                } else {
+                    IntMap::with_capacity(capacity)
+                };
+                let mut truth: BTreeMap<$key, u32> = BTreeMap::new();
+                // odd seeds exercise generation-stamp clearing
+                map.set_lazy_clear(seed % 2 == 1);
+
+                for _ in 0..OPS {
+                    let key = pool[rng.gen_range(0..pool.len())];
+                    let op = match rng.gen_range(0..100) {
+                        0..=39 => Op::Insert(key, rng.gen()),
+                        40..=59 => Op::Get(key),
+                        60..=69 => Op::GetMut(key, rng.gen()),
+                        70..=89 => Op::Remove(key),
+                        90..=98 => Op::Contains(key),
+                        _ => Op::Clear,
+                    };
+                    match op {
+                        Op::Insert(k, v) => {
+                            assert_eq!(map.insert(k, v), truth.insert(k, v), "{op:?}")
+                        }
+                        Op::Get(k) => assert_eq!(map.get(k), truth.get(&k), "{op:?}"),
+                        Op::GetMut(k, v) => {
+                            let a = map.get_mut(k).map(|x| std::mem::replace(x, v));
+                            let b = truth.get_mut(&k).map(|x| std::mem::replace(x, v));
+                            assert_eq!(a, b, "{op:?}");
+                        }
+                        Op::Remove(k) => assert_eq!(map.remove(k), truth.remove(&k), "{op:?}"),
+                        Op::Contains(k) => {
+                            assert_eq!(map.contains(k), truth.contains_key(&k), "{op:?}")
+                        }
+                        Op::Clear => {
+                            map.clear();
+                            truth.clear();
+                        }
+                    }
+                    assert_eq!(map.len(), truth.len());
+                    #[cfg(debug_assertions)]
+                    map.assert_invariants();
+                }
+
+                let mut keys = map.keys().copied().collect::<Vec<_>>();
+                keys.sort_unstable();
+                assert_eq!(keys, truth.keys().copied().collect::<Vec<_>>(), "seed {seed}");
+            }
+        }
+    };
+}
+
+differential!(differential_i32, i32);
+differential!(differential_u32, u32);
+differential!(differential_i64, i64);
+differential!(differential_u64, u64);
+differential!(differential_usize, usize);
+differential!(differential_i128, i128);
+differential!(differential_u128, u128);
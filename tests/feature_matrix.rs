@@ -0,0 +1,162 @@
+// Builds against whatever features are enabled, so `scripts/test-features.sh` runs it under
+// every combination: the shared scenario must give the same results in every build, and each
+// gated API is exercised as a user of the crate would call it.
+//
+// Only the crate's actual features are covered; e.g. serde, rayon or no_std get their
+// sections here once they exist.
+use intmap_rs::{InsertError, IntMap, SaturationPolicy};
+use std::collections::BTreeMap;
+
+// a fixed op sequence over colliding keys with removes and updates, checked against a
+// BTreeMap; returns the final map
+fn scenario() -> IntMap<u64, u64> {
+    let mut map = IntMap::with_capacity(64);
+    let mut truth = BTreeMap::new();
+    for i in 0..400u64 {
+        // 256 keys over 64 home slots, so clusters form and shift
+        let key = (i * 37) % 256;
+        match i % 5 {
+            0..=2 => assert_eq!(map.insert(key, i), truth.insert(key, i), "insert {key}"),
+            3 => assert_eq!(map.remove(key), truth.remove(&key), "remove {key}"),
+            _ => assert_eq!(map.get(key), truth.get(&key), "get {key}"),
+        }
+        if map.len() > 48 {
+            let &oldest = truth.keys().next().unwrap();
+            assert_eq!(map.remove(oldest), truth.remove(&oldest));
+        }
+    }
+    assert_eq!(map.len(), truth.len());
+    assert!(truth.iter().all(|(k, v)| map.get(*k) == Some(v)));
+    map
+}
+
+#[test]
+fn same_results_in_every_build() {
+    let map = scenario();
+    map.assert_invariants();
+    // pinned: the features only add lanes, checks or scrubbing, never change the contents
+    assert_eq!((map.len(), map.checksum()), (48, 8499385166419447704));
+}
+
+#[test]
+fn saturation_policies() {
+    let mut map = IntMap::with_capacity(4);
+    map.set_saturation_policy(SaturationPolicy::Evict);
+    for k in 0..8u32 {
+        map.insert(k, k);
+    }
+    assert_eq!(map.len(), 4);
+    map.set_saturation_policy(SaturationPolicy::SuggestEviction { max_load_percent: 50 });
+    map.clear();
+    map.insert(0, 0);
+    map.insert(4, 4);
+    let Err(InsertError::EvictionNeeded(1, 1, candidates)) = map.try_insert(1, 1) else {
+        panic!("insert past the load limit went through");
+    };
+    assert_eq!(candidates, [4, 0]);
+    map.assert_invariants();
+}
+
+#[test]
+#[cfg(feature = "tags")]
+fn tags() {
+    let mut map = scenario();
+    let keys: Vec<u64> = map.keys().copied().collect();
+    for &k in &keys {
+        assert!(map.set_tag(k, (k % 4) as u8));
+    }
+    for k in keys.iter().step_by(3) {
+        map.remove(*k);
+    }
+    assert!(map.iter().all(|(k, _)| map.tag(*k) == Some((k % 4) as u8)));
+    assert!(map.iter_tagged(1).all(|(k, _)| k % 4 == 1));
+}
+
+#[test]
+#[cfg(feature = "env")]
+fn config_from_env() {
+    use intmap_rs::Config;
+
+    std::env::set_var("FEATURE_MATRIX_CAPACITY", "32");
+    std::env::set_var("FEATURE_MATRIX_SATURATION", "reject");
+    let config = Config::new(8).override_from_env("FEATURE_MATRIX").unwrap();
+    let mut map = IntMap::with_config(&config);
+    for k in 0..32u32 {
+        map.insert(k, ());
+    }
+    assert!(map.try_insert(32, ()).is_err());
+}
+
+#[test]
+#[cfg(feature = "net")]
+fn socket_addr_keys() {
+    use intmap_rs::AdaptedIntMap;
+    use std::net::{Ipv4Addr, SocketAddrV4};
+
+    let mut peers = AdaptedIntMap::with_capacity(16);
+    for port in 8000..8010 {
+        peers.insert(SocketAddrV4::new(Ipv4Addr::LOCALHOST, port), port);
+    }
+    assert_eq!(peers.get(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 8004)), Some(&8004));
+    assert!(peers.iter().all(|(addr, &port)| addr.port() == port));
+}
+
+#[test]
+#[cfg(feature = "duration")]
+fn duration_keys() {
+    use intmap_rs::AdaptedIntMap;
+    use std::time::Duration;
+
+    let mut timers = AdaptedIntMap::with_capacity(16);
+    for ms in [5, 10, 250] {
+        timers.insert(Duration::from_millis(ms), ms);
+    }
+    assert_eq!(timers.remove(Duration::from_millis(10)), Some(10));
+    assert_eq!(timers.get(Duration::from_micros(250_000)), Some(&250));
+}
+
+#[test]
+#[cfg(feature = "oplog")]
+fn replicated_scenario() {
+    use intmap_rs::RecordingIntMap;
+
+    let mut primary = RecordingIntMap::from_inner(scenario());
+    let mut replica = scenario();
+    primary.retain(|k, _| k % 2 == 0);
+    primary.insert(1000, 1);
+    for op in primary.take_ops() {
+        replica.apply(op);
+    }
+    assert_eq!(primary.as_inner().checksum(), replica.checksum());
+}
+
+#[test]
+#[cfg(feature = "profiling")]
+fn profiled_scenario() {
+    use intmap_rs::OpClass;
+
+    let map = scenario();
+    let lookups = map.profile(OpClass::Lookup);
+    // the scenario's gets, plus the final check of every entry
+    assert_eq!(lookups.count(), 80 + 48);
+    assert!(lookups.percentile(0.5) <= lookups.percentile(0.99));
+    map.reset_profile();
+    assert_eq!(map.profile(OpClass::Insert).count(), 0);
+}
+
+#[test]
+#[cfg(feature = "bench-utils")]
+fn bench_workloads() {
+    use intmap_rs::bench::{self, KeyDistribution};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    let mut rng = StdRng::seed_from_u64(1);
+    let ops = bench::ops_with_keys::<u64, u64>(KeyDistribution::Clustered, &mut rng, 500);
+    let mut map = IntMap::with_capacity(1024);
+    let mut std_map = std::collections::HashMap::new();
+    bench::run(&mut map, &ops);
+    bench::run(&mut std_map, &ops);
+    assert_eq!(map.len(), std_map.len());
+    assert!(std_map.iter().all(|(k, v)| map.get(*k) == Some(v)));
+}